@@ -0,0 +1,12 @@
+-- Per-cycle problem volume, used for anomaly detection
+CREATE TABLE IF NOT EXISTS poll_cycles (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    polled_at INTEGER NOT NULL,
+    fetched_count INTEGER NOT NULL,
+    new_count INTEGER NOT NULL,
+    status_change_count INTEGER NOT NULL,
+    baseline REAL,
+    anomaly INTEGER NOT NULL DEFAULT 0
+);
+
+CREATE INDEX IF NOT EXISTS idx_poll_cycles_polled_at ON poll_cycles(polled_at);
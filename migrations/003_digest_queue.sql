@@ -0,0 +1,10 @@
+-- Problems waiting to be sent in a connector's next digest
+CREATE TABLE IF NOT EXISTS digest_queue (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    connector_name TEXT NOT NULL,
+    problem_id TEXT NOT NULL,
+    problem_json TEXT NOT NULL,
+    queued_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_digest_queue_connector ON digest_queue(connector_name);
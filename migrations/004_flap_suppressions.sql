@@ -0,0 +1,8 @@
+-- Status changes withheld because the problem is flapping
+CREATE TABLE IF NOT EXISTS flap_suppressions (
+    problem_id TEXT PRIMARY KEY,
+    forwarded_status TEXT NOT NULL, -- status receivers last got
+    pending_status TEXT,            -- current status not yet forwarded, if any
+    suppressed_count INTEGER NOT NULL DEFAULT 0,
+    last_suppressed_at INTEGER NOT NULL
+);
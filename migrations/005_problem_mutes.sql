@@ -0,0 +1,7 @@
+-- Problems muted from the CLI; their updates are not forwarded until muted_until
+CREATE TABLE IF NOT EXISTS problem_mutes (
+    problem_id TEXT PRIMARY KEY,
+    muted_until INTEGER NOT NULL,
+    reason TEXT,
+    muted_at INTEGER NOT NULL
+);
@@ -0,0 +1,6 @@
+-- Connectors disabled from the CLI; they receive nothing until enabled again
+CREATE TABLE IF NOT EXISTS disabled_connectors (
+    connector_name TEXT PRIMARY KEY,
+    reason TEXT,
+    disabled_at INTEGER NOT NULL
+);
@@ -0,0 +1,17 @@
+-- Occurrences of recurring problems per connector, for recurrence sampling
+CREATE TABLE IF NOT EXISTS recurrence_counters (
+    connector_name TEXT NOT NULL,
+    recurrence_key TEXT NOT NULL,  -- title and/or entity the occurrences share
+    occurrences INTEGER NOT NULL,  -- since the counter was last reset
+    skipped INTEGER NOT NULL,      -- occurrences skipped since the last forwarded one
+    last_seen_at INTEGER NOT NULL,
+    PRIMARY KEY (connector_name, recurrence_key)
+);
+
+-- Problems a connector skipped by sampling; their later updates are skipped too
+CREATE TABLE IF NOT EXISTS recurrence_skipped (
+    connector_name TEXT NOT NULL,
+    problem_id TEXT NOT NULL,
+    skipped_at INTEGER NOT NULL,
+    PRIMARY KEY (connector_name, problem_id)
+);
@@ -0,0 +1,8 @@
+-- Monitored entities fetched for entity enrichment, reused until they expire
+CREATE TABLE IF NOT EXISTS entity_cache (
+    environment TEXT NOT NULL,
+    entity_id TEXT NOT NULL,
+    details TEXT NOT NULL,  -- JSON: displayName, properties, tags
+    fetched_at INTEGER NOT NULL,
+    PRIMARY KEY (environment, entity_id)
+);
@@ -0,0 +1,9 @@
+-- Security problems (Application Security vulnerabilities) forwarded to connectors
+CREATE TABLE IF NOT EXISTS forwarded_security_problems (
+    security_problem_id TEXT PRIMARY KEY,
+    display_id TEXT NOT NULL,
+    status TEXT NOT NULL,  -- OPEN, RESOLVED or MUTED
+    risk_level TEXT,       -- CRITICAL, HIGH, MEDIUM, LOW or NONE
+    first_forwarded_at INTEGER NOT NULL,
+    last_forwarded_at INTEGER NOT NULL
+);
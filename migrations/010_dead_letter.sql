@@ -0,0 +1,13 @@
+-- Deliveries that failed after all retries, kept with their payload until replayed
+CREATE TABLE IF NOT EXISTS dead_letter (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    connector_name TEXT NOT NULL,
+    problem_ids TEXT NOT NULL,     -- JSON array
+    problems_json TEXT NOT NULL,   -- JSON array of the problems as sent
+    error_message TEXT NOT NULL,
+    failed_at INTEGER NOT NULL,
+    replay_count INTEGER NOT NULL DEFAULT 0,
+    last_replayed_at INTEGER
+);
+
+CREATE INDEX IF NOT EXISTS idx_dead_letter_connector ON dead_letter(connector_name);
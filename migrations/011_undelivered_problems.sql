@@ -0,0 +1,11 @@
+-- Latest state of each problem a connector has not received yet, retried on later polls
+CREATE TABLE IF NOT EXISTS undelivered_problems (
+    connector_name TEXT NOT NULL,
+    problem_id TEXT NOT NULL,
+    problem_status TEXT NOT NULL,
+    problem_json TEXT NOT NULL,
+    first_failed_at INTEGER NOT NULL,
+    attempts INTEGER NOT NULL DEFAULT 1,
+    last_error TEXT,
+    PRIMARY KEY (connector_name, problem_id)
+);
@@ -0,0 +1,35 @@
+-- Latest delivery state of each problem per connector, so a batch that reached
+-- one connector and failed on another can be told apart
+CREATE TABLE IF NOT EXISTS problem_deliveries (
+    problem_id TEXT NOT NULL,
+    connector_name TEXT NOT NULL,
+    problem_status TEXT, -- unknown for deliveries recorded before this table
+    state TEXT NOT NULL, -- 'delivered' or 'failed'
+    attempts INTEGER NOT NULL DEFAULT 1,
+    last_response_code INTEGER,
+    last_error TEXT,
+    last_attempt_at INTEGER NOT NULL,
+    delivered_at INTEGER,
+    PRIMARY KEY (problem_id, connector_name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_problem_deliveries_connector ON problem_deliveries(connector_name, state);
+
+-- Seed from the latest delivery attempt per problem and connector in forward history
+INSERT OR IGNORE INTO problem_deliveries
+    (problem_id, connector_name, state, attempts, last_response_code, last_error, last_attempt_at, delivered_at)
+SELECT h.problem_id,
+       h.connector_name,
+       CASE WHEN h.status = 'success' THEN 'delivered' ELSE 'failed' END,
+       (SELECT COUNT(*) FROM forward_history a
+        WHERE a.problem_id = h.problem_id AND a.connector_name = h.connector_name
+          AND a.status IN ('success', 'failed', 'shadow_failed')),
+       h.response_code,
+       h.error_message,
+       h.forwarded_at,
+       (SELECT MAX(s.forwarded_at) FROM forward_history s
+        WHERE s.problem_id = h.problem_id AND s.connector_name = h.connector_name AND s.status = 'success')
+FROM forward_history h
+WHERE h.id = (SELECT MAX(l.id) FROM forward_history l
+              WHERE l.problem_id = h.problem_id AND l.connector_name = h.connector_name
+                AND l.status IN ('success', 'failed', 'shadow_failed'));
@@ -0,0 +1,10 @@
+-- Every status a tracked problem was seen in, for lifecycle timelines
+CREATE TABLE IF NOT EXISTS problem_status_changes (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    problem_id TEXT NOT NULL,
+    from_status TEXT, -- NULL when the problem was first seen
+    to_status TEXT NOT NULL,
+    changed_at INTEGER NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_problem_status_changes_problem ON problem_status_changes(problem_id);
@@ -0,0 +1,2 @@
+-- How long each delivery took, retries included
+ALTER TABLE forward_history ADD COLUMN duration_ms INTEGER;
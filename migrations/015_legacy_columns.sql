@@ -0,0 +1,7 @@
+-- Columns earlier versions added at startup (management_zones, tenant and
+-- snapshot of forwarded_problems, problem_status and instance_id of
+-- forward_history) are listed with this migration in MIGRATIONS, which adds
+-- each unless the database has it already
+
+CREATE INDEX IF NOT EXISTS idx_forward_history_delivery
+    ON forward_history(problem_id, connector_name, problem_status, status);
@@ -0,0 +1,30 @@
+-- Forward history is an audit log that outlives the problem cache, so its
+-- problem_id no longer references forwarded_problems. SQLite cannot drop a
+-- foreign key, so the table is rebuilt.
+CREATE TABLE forward_history_new (
+    id INTEGER PRIMARY KEY AUTOINCREMENT,
+    problem_id TEXT NOT NULL,
+    problem_status TEXT,
+    instance_id TEXT,
+    connector_name TEXT NOT NULL,
+    status TEXT NOT NULL,
+    response_code INTEGER,
+    error_message TEXT,
+    forwarded_at INTEGER NOT NULL,
+    duration_ms INTEGER
+);
+
+INSERT INTO forward_history_new
+    (id, problem_id, problem_status, instance_id, connector_name, status, response_code, error_message,
+     forwarded_at, duration_ms)
+SELECT id, problem_id, problem_status, instance_id, connector_name, status, response_code, error_message,
+       forwarded_at, duration_ms
+FROM forward_history;
+
+DROP TABLE forward_history;
+ALTER TABLE forward_history_new RENAME TO forward_history;
+
+CREATE INDEX IF NOT EXISTS idx_forward_history_problem_id ON forward_history(problem_id);
+CREATE INDEX IF NOT EXISTS idx_forward_history_connector ON forward_history(connector_name);
+CREATE INDEX IF NOT EXISTS idx_forward_history_delivery
+    ON forward_history(problem_id, connector_name, problem_status, status);
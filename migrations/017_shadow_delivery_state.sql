@@ -0,0 +1,7 @@
+-- Failures of shadow connectors get their own delivery state instead of counting as failing
+UPDATE problem_deliveries SET state = 'shadow_failed'
+WHERE state = 'failed'
+  AND (SELECT h.status FROM forward_history h
+       WHERE h.problem_id = problem_deliveries.problem_id AND h.connector_name = problem_deliveries.connector_name
+         AND h.status IN ('success', 'failed', 'shadow_failed')
+       ORDER BY h.id DESC LIMIT 1) = 'shadow_failed';
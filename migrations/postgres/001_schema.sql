@@ -0,0 +1,180 @@
+-- Postgres equivalent of the SQLite schema as of SQLite migration 017
+-- (017_shadow_delivery_state), used by `dtpf db migrate-to`. Timestamps stay
+-- Unix seconds to keep values identical. A new SQLite migration needs its
+-- counterpart here and this header updated.
+CREATE TABLE IF NOT EXISTS forwarded_problems (
+    id BIGSERIAL PRIMARY KEY,
+    problem_id TEXT NOT NULL UNIQUE,
+    status TEXT NOT NULL,
+    severity_level TEXT,
+    title TEXT NOT NULL,
+    first_seen_at BIGINT NOT NULL,
+    last_forwarded_at BIGINT NOT NULL,
+    last_status_change_at BIGINT NOT NULL,
+    forward_count BIGINT DEFAULT 1,
+    created_at BIGINT NOT NULL,
+    updated_at BIGINT NOT NULL,
+    management_zones TEXT NOT NULL DEFAULT '[]',
+    tenant TEXT
+);
+
+ALTER TABLE forwarded_problems ADD COLUMN IF NOT EXISTS tenant TEXT;
+ALTER TABLE forwarded_problems ADD COLUMN IF NOT EXISTS snapshot BYTEA;
+
+CREATE INDEX IF NOT EXISTS idx_status ON forwarded_problems(status);
+CREATE INDEX IF NOT EXISTS idx_last_forwarded_at ON forwarded_problems(last_forwarded_at);
+
+-- No foreign key to forwarded_problems: history outlives cleared problems
+CREATE TABLE IF NOT EXISTS forward_history (
+    id BIGSERIAL PRIMARY KEY,
+    problem_id TEXT NOT NULL,
+    problem_status TEXT,
+    instance_id TEXT,
+    connector_name TEXT NOT NULL,
+    status TEXT NOT NULL,
+    response_code BIGINT,
+    error_message TEXT,
+    forwarded_at BIGINT NOT NULL,
+    duration_ms BIGINT
+);
+
+CREATE INDEX IF NOT EXISTS idx_forward_history_problem_id ON forward_history(problem_id);
+CREATE INDEX IF NOT EXISTS idx_forward_history_connector ON forward_history(connector_name);
+-- Added after the first release of this schema
+ALTER TABLE forward_history ADD COLUMN IF NOT EXISTS problem_status TEXT;
+ALTER TABLE forward_history ADD COLUMN IF NOT EXISTS instance_id TEXT;
+ALTER TABLE forward_history ADD COLUMN IF NOT EXISTS duration_ms BIGINT;
+CREATE INDEX IF NOT EXISTS idx_forward_history_delivery
+    ON forward_history(problem_id, connector_name, problem_status, status);
+
+CREATE TABLE IF NOT EXISTS app_state (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL,
+    updated_at BIGINT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS poll_cycles (
+    id BIGSERIAL PRIMARY KEY,
+    polled_at BIGINT NOT NULL,
+    fetched_count BIGINT NOT NULL,
+    new_count BIGINT NOT NULL,
+    status_change_count BIGINT NOT NULL,
+    baseline DOUBLE PRECISION,
+    anomaly BIGINT NOT NULL DEFAULT 0
+);
+
+CREATE INDEX IF NOT EXISTS idx_poll_cycles_polled_at ON poll_cycles(polled_at);
+
+CREATE TABLE IF NOT EXISTS digest_queue (
+    id BIGSERIAL PRIMARY KEY,
+    connector_name TEXT NOT NULL,
+    problem_id TEXT NOT NULL,
+    problem_json TEXT NOT NULL,
+    queued_at BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_digest_queue_connector ON digest_queue(connector_name);
+
+CREATE TABLE IF NOT EXISTS flap_suppressions (
+    problem_id TEXT PRIMARY KEY,
+    forwarded_status TEXT NOT NULL,
+    pending_status TEXT,
+    suppressed_count BIGINT NOT NULL DEFAULT 0,
+    last_suppressed_at BIGINT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS problem_mutes (
+    problem_id TEXT PRIMARY KEY,
+    muted_until BIGINT NOT NULL,
+    reason TEXT,
+    muted_at BIGINT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS disabled_connectors (
+    connector_name TEXT PRIMARY KEY,
+    reason TEXT,
+    disabled_at BIGINT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS recurrence_counters (
+    connector_name TEXT NOT NULL,
+    recurrence_key TEXT NOT NULL,
+    occurrences BIGINT NOT NULL,
+    skipped BIGINT NOT NULL,
+    last_seen_at BIGINT NOT NULL,
+    PRIMARY KEY (connector_name, recurrence_key)
+);
+
+CREATE TABLE IF NOT EXISTS recurrence_skipped (
+    connector_name TEXT NOT NULL,
+    problem_id TEXT NOT NULL,
+    skipped_at BIGINT NOT NULL,
+    PRIMARY KEY (connector_name, problem_id)
+);
+
+CREATE TABLE IF NOT EXISTS entity_cache (
+    environment TEXT NOT NULL,
+    entity_id TEXT NOT NULL,
+    details TEXT NOT NULL,
+    fetched_at BIGINT NOT NULL,
+    PRIMARY KEY (environment, entity_id)
+);
+
+CREATE TABLE IF NOT EXISTS forwarded_security_problems (
+    security_problem_id TEXT PRIMARY KEY,
+    display_id TEXT NOT NULL,
+    status TEXT NOT NULL,
+    risk_level TEXT,
+    first_forwarded_at BIGINT NOT NULL,
+    last_forwarded_at BIGINT NOT NULL
+);
+
+CREATE TABLE IF NOT EXISTS dead_letter (
+    id BIGSERIAL PRIMARY KEY,
+    connector_name TEXT NOT NULL,
+    problem_ids TEXT NOT NULL,
+    problems_json TEXT NOT NULL,
+    error_message TEXT NOT NULL,
+    failed_at BIGINT NOT NULL,
+    replay_count BIGINT NOT NULL DEFAULT 0,
+    last_replayed_at BIGINT
+);
+
+CREATE INDEX IF NOT EXISTS idx_dead_letter_connector ON dead_letter(connector_name);
+
+CREATE TABLE IF NOT EXISTS undelivered_problems (
+    connector_name TEXT NOT NULL,
+    problem_id TEXT NOT NULL,
+    problem_status TEXT NOT NULL,
+    problem_json TEXT NOT NULL,
+    first_failed_at BIGINT NOT NULL,
+    attempts BIGINT NOT NULL DEFAULT 1,
+    last_error TEXT,
+    PRIMARY KEY (connector_name, problem_id)
+);
+
+CREATE TABLE IF NOT EXISTS problem_deliveries (
+    problem_id TEXT NOT NULL,
+    connector_name TEXT NOT NULL,
+    problem_status TEXT,
+    -- 'delivered', 'failed' or 'shadow_failed' (SQLite migration 017)
+    state TEXT NOT NULL,
+    attempts BIGINT NOT NULL DEFAULT 1,
+    last_response_code BIGINT,
+    last_error TEXT,
+    last_attempt_at BIGINT NOT NULL,
+    delivered_at BIGINT,
+    PRIMARY KEY (problem_id, connector_name)
+);
+
+CREATE INDEX IF NOT EXISTS idx_problem_deliveries_connector ON problem_deliveries(connector_name, state);
+
+CREATE TABLE IF NOT EXISTS problem_status_changes (
+    id BIGSERIAL PRIMARY KEY,
+    problem_id TEXT NOT NULL,
+    from_status TEXT,
+    to_status TEXT NOT NULL,
+    changed_at BIGINT NOT NULL
+);
+
+CREATE INDEX IF NOT EXISTS idx_problem_status_changes_problem ON problem_status_changes(problem_id);
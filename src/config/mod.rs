@@ -1,3 +1,3 @@
 pub mod settings;
 
-pub use settings::{Settings, DynatraceConfig, PollingConfig, ConnectorConfig, HttpMethod};
+pub use settings::{Settings, AcknowledgmentConfig, ActiveHoursConfig, AnomalyDetectionConfig, AuditLogsConfig, CloudEventsConfig, CloudEventsMode, CloseEndpointConfig, ComputedFieldsConfig, CorrelationConfig, CycleReportConfig, DatabaseConfig, DatabaseDriver, DuplicateGuardConfig, DynatraceCommentConfig, DynatraceConfig, EntityEnrichmentConfig, EncryptionConfig, EnvelopeConfig, EnvironmentConfig, EventsConfig, PollingConfig, ConnectorConfig, ConnectorType, DigestConfig, ElasticsearchConfig, EventHubsAuth, EventHubsConfig, ExecConfig, FieldMapConfig, FileCompression, FileSinkConfig, FlapSuppressionConfig, FormConfig, FormField, GzipConfig, HealthConfig, HeldProblems, HttpMethod, JournalMode, LabelsConfig, MaintenanceWindowsConfig, OutboundConfig, OversizeStrategy, PayloadFormat, PriorityConfig, PriorityKey, PubSubAuth, PushConfig, PubSubConfig, RecurrenceKey, RecurrenceSamplingConfig, RedisConfig, RenotifyConfig, RetryConfig, RoutingConfig, RoutingRule, RunbookRule, SecurityProblemsConfig, SeverityOverride, SigningConfig, Synchronous, TemplateConfig, XMattersConfig, XmlConfig, XmlField, ZoneFilterConfig};
@@ -1,16 +1,72 @@
 use serde::{Deserialize, Serialize};
-use std::collections::HashMap;
+use chrono::{DateTime, Datelike, NaiveTime, TimeZone, Weekday};
+use chrono_tz::Tz;
+use rand::Rng;
+use std::collections::{HashMap, HashSet};
 use std::path::PathBuf;
+use crate::dynatrace::{Problem, ProblemChange, ProblemStatus};
 use crate::error::{ForwarderError, Result};
 
+/// Impact levels Dynatrace assigns to problems
+const IMPACT_LEVELS: [&str; 4] = ["INFRASTRUCTURE", "SERVICES", "APPLICATION", "ENVIRONMENT"];
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct Settings {
+    /// Name of this forwarder instance, stored with every forward history row
+    /// (default: the hostname)
+    pub instance_id: Option<String>,
     pub dynatrace: DynatraceConfig,
     pub polling: PollingConfig,
     pub database: DatabaseConfig,
     pub connectors: Vec<ConnectorConfig>,
     #[serde(default)]
     pub logging: LoggingConfig,
+    #[serde(default)]
+    pub anomaly_detection: AnomalyDetectionConfig,
+    #[serde(default)]
+    pub health: HealthConfig,
+    #[serde(default)]
+    pub retry: RetryConfig,
+    #[serde(default)]
+    pub correlation: CorrelationConfig,
+    #[serde(default)]
+    pub cycle_reports: CycleReportConfig,
+    #[serde(default)]
+    pub duplicate_guard: DuplicateGuardConfig,
+    /// Failed deliveries retried on later polls
+    #[serde(default)]
+    pub redelivery: RedeliveryConfig,
+    #[serde(default)]
+    pub maintenance_windows: MaintenanceWindowsConfig,
+    #[serde(default)]
+    pub entity_enrichment: EntityEnrichmentConfig,
+    /// HTTP endpoint receiving Dynatrace problem notifications
+    #[serde(default)]
+    pub push: PushConfig,
+    /// Dynatrace events forwarded alongside problems
+    #[serde(default)]
+    pub events: EventsConfig,
+    /// Dynatrace security problems (Application Security vulnerabilities) forwarded alongside problems
+    #[serde(default)]
+    pub security_problems: SecurityProblemsConfig,
+    /// Dynatrace audit log entries forwarded alongside problems
+    #[serde(default)]
+    pub audit_logs: AuditLogsConfig,
+    /// Proxy for the Dynatrace API and every HTTP connector without its own
+    pub proxy: Option<ProxyConfig>,
+    #[serde(default)]
+    pub flap_suppression: FlapSuppressionConfig,
+    #[serde(default)]
+    pub outbound: OutboundConfig,
+    #[serde(default)]
+    pub renotify: RenotifyConfig,
+    #[serde(default)]
+    pub priority: PriorityConfig,
+    /// Runbook links attached to matching problems in every connector payload
+    #[serde(default)]
+    pub runbooks: Vec<RunbookRule>,
+    /// Ordered rules choosing connectors per problem (all connectors when not set)
+    pub routing: Option<RoutingConfig>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
@@ -18,19 +74,474 @@ pub struct DynatraceConfig {
     pub base_url: String,
     pub tenant: String,
     pub problem_selector: Option<String>,
+    /// User-Agent for Dynatrace API calls (default: dynatrace-problem-forwarder/<version> (<hostname>))
+    pub user_agent: Option<String>,
+    /// Send a unique X-Request-Id header with every Dynatrace API call
+    #[serde(default)]
+    pub send_request_id: bool,
+    /// Proxy for Dynatrace API calls (default: the global `proxy`)
+    pub proxy: Option<ProxyConfig>,
+    /// Custom CA certificates and client certificate for Dynatrace API calls
+    pub tls: Option<TlsConfig>,
+    /// Retries of a Dynatrace API call answered with 429 Too Many Requests
+    #[serde(default = "default_rate_limit_retries")]
+    pub rate_limit_retries: u32,
+    /// Longest wait for the rate limit to reset before the call fails
+    #[serde(default = "default_rate_limit_max_wait")]
+    pub rate_limit_max_wait_seconds: u64,
+    /// Attempts of a problem list page request failing with a 5xx or timeout
+    /// (1 disables retries)
+    #[serde(default = "default_dynatrace_retry_attempts")]
+    pub retry_attempts: u32,
+    /// Upper bound for the exponential delay between two attempts
+    #[serde(default = "default_dynatrace_retry_max_delay")]
+    pub retry_max_delay_seconds: u64,
+    /// Request evidence details with the problem list (used for chat evidence summaries)
+    #[serde(default)]
+    pub fetch_evidence: bool,
+    /// Extra fields of the problem list, e.g. `+evidenceDetails,+impactAnalysis`
+    pub fields: Option<String>,
+    /// Fetch each new problem from the problem details endpoint, adding evidence,
+    /// recent comments and impact analysis to the payload
+    #[serde(default)]
+    pub fetch_details: bool,
+    /// Only ask for problems active since the last successful poll of each
+    /// environment (sent as `from`/`to`, persisted in the database)
+    #[serde(default)]
+    pub since_last_poll: bool,
+    /// `from` of the first poll with `since_last_poll`, e.g. `now-24h` (default: the API's `now-2h`)
+    pub initial_from: Option<String>,
+    /// Problems per page of the problem list, at most 500 (default: the API's 10)
+    pub page_size: Option<u32>,
+    /// Split a problem list of several pages into this many timeframe slices
+    /// fetched concurrently (default: 1, pages are fetched one after another)
+    #[serde(default = "default_parallel_pages")]
+    pub parallel_pages: usize,
+    #[serde(skip)]
+    pub api_token: Option<String>,
+    /// Name of this environment when `environments` lists more (default: "default")
+    pub name: Option<String>,
+    /// Further environments polled alongside this one; their problems are
+    /// forwarded with the environment name as `tenant`
+    #[serde(default)]
+    pub environments: Vec<EnvironmentConfig>,
+}
+
+/// HTTP(S) proxy for outgoing requests
+#[derive(Debug, Deserialize, Clone)]
+pub struct ProxyConfig {
+    /// Proxy URL, e.g. `http://proxy.example.com:3128`
+    pub url: String,
+    pub username: Option<String>,
+    /// Proxy password; `${ENV_VAR}` placeholders are expanded
+    pub password: Option<String>,
+    /// Hosts reached without the proxy: host names, `.domain` suffixes, IP
+    /// addresses or CIDR ranges
+    #[serde(default)]
+    pub no_proxy: Vec<String>,
+}
+
+impl ProxyConfig {
+    /// The proxy for a reqwest client, used for HTTP and HTTPS requests
+    pub fn to_proxy(&self) -> Result<reqwest::Proxy> {
+        let mut proxy = reqwest::Proxy::all(&self.url)
+            .map_err(|e| ForwarderError::Config(format!("Invalid proxy URL '{}': {}", self.url, e)))?;
+
+        if let Some(username) = &self.username {
+            proxy = proxy.basic_auth(username, self.password.as_deref().unwrap_or_default());
+        }
+
+        if !self.no_proxy.is_empty() {
+            proxy = proxy.no_proxy(reqwest::NoProxy::from_string(&self.no_proxy.join(",")));
+        }
+
+        Ok(proxy)
+    }
+}
+
+/// Certificates for TLS connections: extra trusted CAs and a client certificate
+/// for mutual TLS, all PEM files
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct TlsConfig {
+    /// CA certificates trusted in addition to the built-in roots
+    pub ca_file: Option<PathBuf>,
+    /// Client certificate (chain), optionally followed by its private key
+    pub client_cert: Option<PathBuf>,
+    /// Private key of the client certificate, when not in `client_cert`
+    pub client_key: Option<PathBuf>,
+}
+
+impl TlsConfig {
+    /// Contents of `ca_file`
+    pub fn ca_pem(&self) -> Result<Option<Vec<u8>>> {
+        self.ca_file.as_ref().map(|path| read_pem(path, "ca_file")).transpose()
+    }
+
+    /// Contents of `client_cert` and of `client_key` (empty when the key is in
+    /// the certificate file)
+    pub fn identity_pem(&self) -> Result<Option<(Vec<u8>, Vec<u8>)>> {
+        let Some(cert) = &self.client_cert else {
+            return Ok(None);
+        };
+
+        if cert
+            .extension()
+            .and_then(|extension| extension.to_str())
+            .is_some_and(|extension| ["p12", "pfx"].contains(&extension.to_ascii_lowercase().as_str()))
+        {
+            return Err(ForwarderError::Config(format!(
+                "tls.client_cert '{}' is PKCS#12, which is not supported; convert it to PEM with \
+                 `openssl pkcs12 -in {} -out client.pem -nodes`",
+                cert.display(),
+                cert.display()
+            )));
+        }
+
+        let key = match &self.client_key {
+            Some(key) => read_pem(key, "client_key")?,
+            None => Vec::new(),
+        };
+        Ok(Some((read_pem(cert, "client_cert")?, key)))
+    }
+
+    /// Add the CA certificates and client identity to a reqwest client
+    pub fn apply(&self, mut builder: reqwest::ClientBuilder) -> Result<reqwest::ClientBuilder> {
+        let invalid = |field: &str, e: reqwest::Error| ForwarderError::Config(format!("Invalid tls.{}: {}", field, e));
+
+        if let Some(pem) = self.ca_pem()? {
+            for certificate in reqwest::Certificate::from_pem_bundle(&pem).map_err(|e| invalid("ca_file", e))? {
+                builder = builder.add_root_certificate(certificate);
+            }
+        }
+
+        if let Some((cert, key)) = self.identity_pem()? {
+            let identity = reqwest::Identity::from_pem(&[cert, key].concat()).map_err(|e| invalid("client_cert", e))?;
+            builder = builder.identity(identity);
+        }
+
+        Ok(builder)
+    }
+}
+
+fn read_pem(path: &PathBuf, field: &str) -> Result<Vec<u8>> {
+    std::fs::read(path).map_err(|e| ForwarderError::Config(format!("Failed to read tls.{} '{}': {}", field, path.display(), e)))
+}
+
+fn default_parallel_pages() -> usize {
+    1
+}
+
+fn default_rate_limit_retries() -> u32 {
+    3
+}
+
+fn default_rate_limit_max_wait() -> u64 {
+    60
+}
+
+fn default_dynatrace_retry_attempts() -> u32 {
+    3
+}
+
+fn default_dynatrace_retry_max_delay() -> u64 {
+    10
+}
+
+/// Optional problem fields the problems API returns when asked for
+pub const PROBLEM_FIELDS: [&str; 3] = ["evidenceDetails", "impactAnalysis", "recentComments"];
+
+impl DynatraceConfig {
+    /// Extra fields requested with the problem list: `fields` (without the
+    /// leading `+`) plus `evidenceDetails` when `fetch_evidence` is set
+    pub fn problem_fields(&self) -> Vec<String> {
+        let mut fields: Vec<String> = self
+            .fields
+            .iter()
+            .flat_map(|fields| fields.split(','))
+            .map(|field| field.trim().trim_start_matches('+').to_string())
+            .filter(|field| !field.is_empty())
+            .collect();
+
+        if self.fetch_evidence {
+            fields.push("evidenceDetails".to_string());
+        }
+
+        let mut seen = HashSet::new();
+        fields.retain(|field| seen.insert(field.clone()));
+        fields
+    }
+}
+
+/// A Dynatrace environment polled in addition to the main one
+#[derive(Debug, Deserialize, Clone)]
+pub struct EnvironmentConfig {
+    pub name: String,
+    pub base_url: String,
+    pub tenant: String,
+    /// Environment variable holding the API token
+    pub token_env: String,
+    /// Problem selector for this environment (default: `dynatrace.problem_selector`)
+    pub problem_selector: Option<String>,
     #[serde(skip)]
     pub api_token: Option<String>,
 }
 
+impl EnvironmentConfig {
+    /// Get the Dynatrace UI link prefix for a problem (append the problem ID)
+    pub fn problem_url_base(&self) -> String {
+        format!(
+            "{}/e/{}/#problems/problemdetails;pid=",
+            self.base_url.trim_end_matches('/'),
+            self.tenant
+        )
+    }
+
+    /// Get the full API URL for the problems endpoint; `fetch_evidence` is shared
+    /// by all environments
+    pub fn problems_url(&self, dynatrace: &DynatraceConfig) -> String {
+        let mut url = format!(
+            "{}/e/{}/api/v2/problems",
+            self.base_url.trim_end_matches('/'),
+            self.tenant
+        );
+
+        let mut params = Vec::new();
+
+        if let Some(selector) = &self.problem_selector {
+            params.push(format!("problemSelector={}", selector));
+            params.push("sort=-startTime".to_string());
+        }
+
+        if let Some(page_size) = dynatrace.page_size {
+            params.push(format!("pageSize={}", page_size));
+        }
+
+        let fields = dynatrace.problem_fields();
+        if !fields.is_empty() {
+            let fields: Vec<String> = fields.iter().map(|field| format!("%2B{}", field)).collect();
+            params.push(format!("fields={}", fields.join(",")));
+        }
+
+        if !params.is_empty() {
+            url.push('?');
+            url.push_str(&params.join("&"));
+        }
+
+        url
+    }
+
+    /// Get the full API URL for the monitored entities endpoint
+    pub fn entities_url(&self) -> String {
+        format!(
+            "{}/e/{}/api/v2/entities",
+            self.base_url.trim_end_matches('/'),
+            self.tenant
+        )
+    }
+
+    /// Get the full API URL for the events endpoint
+    pub fn events_url(&self) -> String {
+        format!(
+            "{}/e/{}/api/v2/events",
+            self.base_url.trim_end_matches('/'),
+            self.tenant
+        )
+    }
+
+    /// Get the full API URL for the security problems endpoint
+    pub fn security_problems_url(&self) -> String {
+        format!(
+            "{}/e/{}/api/v2/securityProblems",
+            self.base_url.trim_end_matches('/'),
+            self.tenant
+        )
+    }
+
+    /// Get the full API URL for the audit logs endpoint
+    pub fn audit_logs_url(&self) -> String {
+        format!(
+            "{}/e/{}/api/v2/auditlogs",
+            self.base_url.trim_end_matches('/'),
+            self.tenant
+        )
+    }
+
+    /// Get the full API URL for API token lookups
+    pub fn token_lookup_url(&self) -> String {
+        format!(
+            "{}/e/{}/api/v2/apiTokens/lookup",
+            self.base_url.trim_end_matches('/'),
+            self.tenant
+        )
+    }
+
+    /// Get the full API URL for the maintenance window settings objects
+    pub fn maintenance_windows_url(&self) -> String {
+        format!(
+            "{}/e/{}/api/v2/settings/objects?schemaIds={}&fields=objectId,value&pageSize=500",
+            self.base_url.trim_end_matches('/'),
+            self.tenant,
+            urlencoding::encode(crate::dynatrace::maintenance::MAINTENANCE_WINDOW_SCHEMA)
+        )
+    }
+}
+
+fn default_environment_name() -> String {
+    "default".to_string()
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct PollingConfig {
     pub interval_seconds: u64,
     pub batch_size: Option<usize>,
+    /// Wait this long after startup before the first poll
+    #[serde(default)]
+    pub initial_delay_seconds: u64,
+    /// Add a random 0..=splay_seconds to the initial delay so restarted fleets spread out
+    #[serde(default)]
+    pub splay_seconds: u64,
+    /// Problems first seen more than this many hours after they started are
+    /// recorded as skipped instead of forwarded, e.g. on first startup
+    pub max_problem_age_hours: Option<u64>,
+    /// Check and forward each page of the problem list as soon as it arrives
+    /// instead of after the last one
+    #[serde(default)]
+    pub stream_pages: bool,
+}
+
+impl PollingConfig {
+    /// Delay before the first poll: the initial delay plus a random splay
+    pub fn startup_delay(&self) -> std::time::Duration {
+        let splay = if self.splay_seconds > 0 {
+            rand::thread_rng().gen_range(0..=self.splay_seconds * 1000)
+        } else {
+            0
+        };
+
+        std::time::Duration::from_millis(self.initial_delay_seconds * 1000 + splay)
+    }
 }
 
 #[derive(Debug, Deserialize, Clone)]
 pub struct DatabaseConfig {
+    #[serde(default)]
+    pub driver: DatabaseDriver,
+    /// SQLite database file; not used by the `memory` driver
+    #[serde(default)]
     pub path: PathBuf,
+    /// Redis holding the state of the `redis` driver
+    pub redis: Option<RedisConfig>,
+    /// At-rest encryption of the SQLite database with SQLCipher
+    pub encryption: Option<EncryptionConfig>,
+    /// SQLite journal mode; WAL lets readers and the writer work concurrently
+    #[serde(default)]
+    pub journal_mode: JournalMode,
+    /// How long a write waits for a locked database before failing with `database is locked`
+    #[serde(default = "default_busy_timeout_ms")]
+    pub busy_timeout_ms: u64,
+    #[serde(default)]
+    pub synchronous: Synchronous,
+}
+
+fn default_busy_timeout_ms() -> u64 {
+    5000
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum DatabaseDriver {
+    #[default]
+    Sqlite,
+    /// In-memory SQLite, lost on exit: for ephemeral runs without a volume
+    Memory,
+    /// In-memory SQLite loaded from and written through to Redis
+    Redis,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct EncryptionConfig {
+    /// Environment variable holding the passphrase
+    pub key_env: Option<String>,
+    /// File holding the passphrase (trailing line breaks are ignored)
+    pub key_file: Option<PathBuf>,
+}
+
+impl EncryptionConfig {
+    pub fn key(&self) -> Result<String> {
+        let key = match (&self.key_env, &self.key_file) {
+            (Some(name), _) => std::env::var(name)
+                .map_err(|_| ForwarderError::Config(format!("database encryption key variable '{}' is not set", name)))?,
+            (None, Some(path)) => std::fs::read_to_string(path)
+                .map_err(|e| {
+                    ForwarderError::Config(format!("cannot read database key file '{}': {}", path.display(), e))
+                })?
+                .trim_end_matches(['\r', '\n'])
+                .to_string(),
+            (None, None) => {
+                return Err(ForwarderError::Config(
+                    "database.encryption needs key_env or key_file".to_string(),
+                ))
+            }
+        };
+        if key.is_empty() {
+            return Err(ForwarderError::Config("database encryption key is empty".to_string()));
+        }
+        Ok(key)
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct RedisConfig {
+    /// e.g. `redis://:password@redis:6379/0` (default: the `DTPF_REDIS_URL` variable)
+    pub url: Option<String>,
+    /// Prefix of the keys the forwarder writes
+    #[serde(default = "default_redis_key_prefix")]
+    pub key_prefix: String,
+    /// Approximate number of forward history rows kept in the history stream
+    #[serde(default = "default_redis_history_max_len")]
+    pub history_max_len: usize,
+}
+
+impl RedisConfig {
+    pub fn url(&self) -> Result<String> {
+        match &self.url {
+            Some(url) => Ok(url.clone()),
+            None => std::env::var("DTPF_REDIS_URL").map_err(|_| {
+                ForwarderError::Config("database.redis.url or DTPF_REDIS_URL must be set".to_string())
+            }),
+        }
+    }
+}
+
+fn default_redis_key_prefix() -> String {
+    "dtpf".to_string()
+}
+
+fn default_redis_history_max_len() -> usize {
+    100_000
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum JournalMode {
+    Delete,
+    Truncate,
+    Persist,
+    Memory,
+    #[default]
+    Wal,
+    Off,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum Synchronous {
+    Off,
+    /// Safe with WAL: a power loss may only roll back the latest commits
+    #[default]
+    Normal,
+    Full,
+    Extra,
 }
 
 #[derive(Debug, Deserialize, Clone, Serialize)]
@@ -42,9 +553,35 @@ pub enum HttpMethod {
     Patch,
 }
 
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum ConnectorType {
+    #[default]
+    Webhook,
+    Pubsub,
+    Elasticsearch,
+    Eventhubs,
+    /// Cisco Webex incoming webhook (markdown message)
+    Webex,
+    /// Google Chat incoming webhook (cards)
+    #[serde(rename = "google_chat")]
+    GoogleChat,
+    /// xMatters flow HTTP trigger / inbound integration
+    Xmatters,
+    /// Local NDJSON file
+    File,
+    /// Local command receiving the problem JSON on stdin
+    Exec,
+    /// gRPC service implementing proto/problem_forward.proto
+    Grpc,
+}
+
 #[derive(Debug, Deserialize, Clone)]
 pub struct ConnectorConfig {
     pub name: String,
+    #[serde(rename = "type", default)]
+    pub connector_type: ConnectorType,
+    #[serde(default)]
     pub url: String,
     #[serde(default = "default_method")]
     pub method: HttpMethod,
@@ -53,109 +590,2147 @@ pub struct ConnectorConfig {
     pub retry_attempts: Option<u32>,
     #[serde(default = "default_verify_ssl")]
     pub verify_ssl: bool,
+    /// Proxy for this connector's requests (default: the global `proxy`)
+    pub proxy: Option<ProxyConfig>,
+    /// Custom CA certificates and client certificate for this connector
+    pub tls: Option<TlsConfig>,
     #[serde(default = "default_batch_mode")]
     pub batch_mode: bool,
+    /// Most problems per batch request; larger batches are sent as several requests
+    pub batch_max_items: Option<usize>,
+    /// Largest batch body in bytes (webhook connectors); larger batches are split
+    pub batch_max_bytes: Option<usize>,
+    pub pubsub: Option<PubSubConfig>,
+    pub elasticsearch: Option<ElasticsearchConfig>,
+    pub eventhubs: Option<EventHubsConfig>,
+    pub xmatters: Option<XMattersConfig>,
+    pub file: Option<FileSinkConfig>,
+    pub exec: Option<ExecConfig>,
+    pub labels: Option<LabelsConfig>,
+    /// Per-severity overrides for retry_attempts and timeout_seconds (keyed by severityLevel)
+    #[serde(default)]
+    pub severity_overrides: HashMap<String, SeverityOverride>,
+    pub digest: Option<DigestConfig>,
+    /// Only notify within these hours; problems outside them are held
+    pub active_hours: Option<ActiveHoursConfig>,
+    /// Poll the receiver's status URL after a 202 Accepted before recording the result
+    pub acknowledgment: Option<AcknowledgmentConfig>,
+    /// Only forward problems in (or not in) these management zones
+    pub management_zones: Option<ZoneFilterConfig>,
+    /// Only forward problems whose entity tags match this expression,
+    /// e.g. `env:prod AND team:payments`
+    pub tag_filter: Option<String>,
+    /// Only forward problems with one of these impact levels, e.g. `INFRASTRUCTURE`
+    #[serde(default)]
+    pub impact_levels: Vec<String>,
+    /// Only forward problems affecting an entity of one of these types, e.g. `HOST`
+    #[serde(default)]
+    pub entity_types: Vec<String>,
+    /// Problem updates the connector receives (default: all)
+    #[serde(default = "default_forward_on")]
+    pub forward_on: Vec<ProblemTransition>,
+    /// Connectors sharing a failover group receive each problem only once,
+    /// trying members in order until one succeeds
+    pub failover_group: Option<String>,
+    /// IANA timezone (e.g. Europe/Berlin) for digest schedules and rendered
+    /// timestamps; defaults to UTC
+    pub timezone: Option<Tz>,
+    /// Fraction (0.0-1.0) of problems sent to this connector. Problems are
+    /// picked by ID, so every update of a sampled problem is sent.
+    pub sample_rate: Option<f64>,
+    /// Forward only every Nth occurrence of a recurring problem
+    pub recurrence_sampling: Option<RecurrenceSamplingConfig>,
+    /// Send traffic but ignore failures, for canarying a new connector
+    #[serde(default)]
+    pub shadow: bool,
+    /// Sign request bodies with HMAC-SHA256
+    pub signing: Option<SigningConfig>,
+    /// Gzip request bodies above a size threshold (`Content-Encoding: gzip`)
+    pub gzip: Option<GzipConfig>,
+    /// Largest request body (before compression) the receiver accepts
+    pub max_payload_bytes: Option<usize>,
+    /// What to do with a body over `max_payload_bytes`
+    #[serde(default)]
+    pub oversize_strategy: OversizeStrategy,
+    /// Wrap webhook payloads in CloudEvents 1.0 envelopes
+    pub cloudevents: Option<CloudEventsConfig>,
+    /// Wrap each problem in an envelope with forwarder metadata and the event type
+    pub envelope: Option<EnvelopeConfig>,
+    /// Body format for webhook, file and exec connectors
+    #[serde(default)]
+    pub format: PayloadFormat,
+    /// Element names and field mapping for the `xml` format
+    pub xml: Option<XmlConfig>,
+    /// Fields for `format: form` and `format: query`
+    pub form: Option<FormConfig>,
+    /// Handlebars templates for the webhook body
+    pub template: Option<TemplateConfig>,
+    /// Send problems that are no longer open to a different URL or with a different method
+    pub close_endpoint: Option<CloseEndpointConfig>,
+    /// Comment on the Dynatrace problem after each successful forward
+    pub dynatrace_comment: Option<DynatraceCommentConfig>,
+    /// Add derived fields (local times, duration, Dynatrace link) to the problem JSON
+    pub computed_fields: Option<ComputedFieldsConfig>,
+    /// Only send these top-level fields of the problem JSON
+    #[serde(default)]
+    pub include_fields: Vec<String>,
+    /// Remove these top-level fields from the problem JSON, e.g. `impactedEntities`
+    #[serde(default)]
+    pub exclude_fields: Vec<String>,
+    /// Rename or drop top-level fields of the problem JSON
+    pub field_map: Option<FieldMapConfig>,
+    /// jq expression applied to each problem's JSON, after `field_map`
+    pub transform: Option<String>,
 }
 
-fn default_method() -> HttpMethod {
-    HttpMethod::Post
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum PayloadFormat {
+    /// Problem JSON (default)
+    #[default]
+    Json,
+    /// ArcSight Common Event Format, one line per problem
+    Cef,
+    /// QRadar Log Event Extended Format, one line per problem
+    Leef,
+    /// XML document for legacy ticketing systems (webhook connectors only)
+    Xml,
+    /// `application/x-www-form-urlencoded` body built from `form.fields`
+    Form,
+    /// `form.fields` as query parameters on the URL, without a body
+    Query,
 }
 
-fn default_verify_ssl() -> bool {
-    true
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+pub struct ZoneFilterConfig {
+    /// Zone names or ids; a problem must be in at least one (all problems when empty)
+    #[serde(default)]
+    pub include: Vec<String>,
+    /// Zone names or ids; a problem in any of them is not forwarded
+    #[serde(default)]
+    pub exclude: Vec<String>,
 }
 
-fn default_batch_mode() -> bool {
-    true
+impl ZoneFilterConfig {
+    /// Whether a problem in these zones (names and ids) passes the filter
+    pub fn matches(&self, zones: &[&str]) -> bool {
+        let listed = |list: &[String]| list.iter().any(|zone| zones.contains(&zone.as_str()));
+        (self.include.is_empty() || listed(&self.include)) && !listed(&self.exclude)
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum OversizeStrategy {
+    /// Drop the request with an error instead of sending it
+    #[default]
+    Fail,
+    /// Shorten every problem's affected and impacted entity lists until the body fits
+    TruncateEntities,
+    /// Send the batch as several smaller requests (batch mode only)
+    Split,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct FieldMapConfig {
+    /// Dynatrace field name -> outgoing name, e.g. `problemId: alert_id`
+    #[serde(default)]
+    pub rename: HashMap<String, String>,
+    /// Fields removed from the payload, e.g. `entityTags`
+    #[serde(default)]
+    pub drop: Vec<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct TemplateConfig {
+    /// Body for one problem (`batch_mode: false`); the problem fields are the context
+    pub single: Option<String>,
+    /// Body for one problem that is no longer open (`batch_mode: false`);
+    /// `single` is used when not set
+    pub close: Option<String>,
+    /// Body for a batch; the context has `problems` and `count`
+    pub batch: Option<String>,
 }
 
 #[derive(Debug, Deserialize, Clone)]
-pub struct LoggingConfig {
-    #[serde(default = "default_log_level")]
-    pub level: String,
-    #[serde(default = "default_log_format")]
-    pub format: String,
+pub struct ComputedFieldsConfig {
+    /// strftime format of `startTimeLocal` and `endTimeLocal`, in the connector's timezone
+    #[serde(default = "default_computed_time_format")]
+    pub time_format: String,
 }
 
-impl Default for LoggingConfig {
+impl Default for ComputedFieldsConfig {
     fn default() -> Self {
         Self {
-            level: "info".to_string(),
-            format: "pretty".to_string(),
+            time_format: default_computed_time_format(),
         }
     }
 }
 
-fn default_log_level() -> String {
-    "info".to_string()
+fn default_computed_time_format() -> String {
+    "%Y-%m-%d %H:%M:%S %Z".to_string()
 }
 
-fn default_log_format() -> String {
-    "pretty".to_string()
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct CloseEndpointConfig {
+    /// URL for closed problems; `{problemId}` and `{displayId}` are replaced
+    pub url: Option<String>,
+    /// Method for closed problems (default: the connector's method)
+    pub method: Option<HttpMethod>,
 }
 
-impl Settings {
-    /// Load settings from a YAML file
-    pub fn load(config_path: &PathBuf) -> Result<Self> {
-        // Check if config file exists
-        if !config_path.exists() {
-            return Err(ForwarderError::Config(format!(
-                "Configuration file not found: {}\n\nPlease create a config.yaml file or specify the path with --config.\nYou can use config.yaml.example as a template.",
-                config_path.display()
-            )));
-        }
+#[derive(Debug, Deserialize, Clone)]
+pub struct DynatraceCommentConfig {
+    /// Comment text; `{connector}`, `{reference}` and `{displayId}` are replaced
+    /// (default: "Forwarded to {connector}", with " as {reference}" when one was captured)
+    pub message: Option<String>,
+    /// JSON pointer to the receiver's reference in the response body, e.g. `/result/number`
+    pub reference_field: Option<String>,
+    /// Comment context shown in Dynatrace
+    #[serde(default = "default_comment_context")]
+    pub context: String,
+}
 
-        let config_content = std::fs::read_to_string(config_path)
-            .map_err(|e| ForwarderError::Config(format!(
-                "Failed to read config file '{}': {}",
-                config_path.display(),
-                e
-            )))?;
+fn default_comment_context() -> String {
+    "dynatrace-problem-forwarder".to_string()
+}
 
-        let mut settings: Settings = serde_yaml::from_str(&config_content)?;
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct FormConfig {
+    /// Fields in the order they are sent
+    pub fields: Vec<FormField>,
+}
 
-        // Load API token from environment variable
-        settings.dynatrace.api_token = std::env::var("DYNATRACE_API_TOKEN")
-            .ok()
-            .or_else(|| {
-                // Try from .env file if not in environment
-                dotenv::dotenv().ok();
-                std::env::var("DYNATRACE_API_TOKEN").ok()
-            });
+#[derive(Debug, Deserialize, Clone)]
+pub struct FormField {
+    pub name: String,
+    /// Handlebars template with the problem fields as context, e.g. `"{{displayId}}: {{title}}"`
+    pub value: String,
+}
 
-        // Replace environment variable placeholders in connector headers
-        if let Some(connectors) = Some(&mut settings.connectors) {
-            for connector in connectors.iter_mut() {
-                if let Some(headers) = &mut connector.headers {
-                    for (_, value) in headers.iter_mut() {
-                        if value.starts_with("${") && value.ends_with("}") {
-                            let env_var = &value[2..value.len() - 1];
-                            if let Ok(env_value) = std::env::var(env_var) {
-                                *value = env_value;
-                            }
-                        }
-                    }
-                }
-            }
+#[derive(Debug, Deserialize, Clone)]
+pub struct XmlConfig {
+    /// Document element wrapping all problems
+    #[serde(default = "default_xml_root_element")]
+    pub root_element: String,
+    /// Element written for each problem
+    #[serde(default = "default_xml_item_element")]
+    pub item_element: String,
+    /// Child elements of each problem, in order. Without a mapping the whole
+    /// problem JSON is converted to elements.
+    #[serde(default)]
+    pub fields: Vec<XmlField>,
+    /// Wrap the document in a SOAP 1.1 envelope and send this `SOAPAction`
+    pub soap_action: Option<String>,
+}
+
+impl Default for XmlConfig {
+    fn default() -> Self {
+        Self {
+            root_element: default_xml_root_element(),
+            item_element: default_xml_item_element(),
+            fields: Vec::new(),
+            soap_action: None,
         }
+    }
+}
 
-        settings.validate()?;
+#[derive(Debug, Deserialize, Clone)]
+pub struct XmlField {
+    /// Element name
+    pub element: String,
+    /// JSON pointer into the problem payload, e.g. `/rootCauseEntity/name`
+    pub pointer: String,
+}
 
-        Ok(settings)
-    }
+fn default_xml_root_element() -> String {
+    "problems".to_string()
+}
 
-    /// Validate the configuration
-    fn validate(&self) -> Result<()> {
-        // Validate Dynatrace config
-        if self.dynatrace.base_url.is_empty() {
-            return Err(ForwarderError::Validation(
-                "Dynatrace base_url cannot be empty".to_string(),
-            ));
-        }
+fn default_xml_item_element() -> String {
+    "problem".to_string()
+}
 
-        if self.dynatrace.tenant.is_empty() {
-            return Err(ForwarderError::Validation(
-                "Dynatrace tenant cannot be empty".to_string(),
+#[derive(Debug, Deserialize, Clone)]
+pub struct DigestConfig {
+    #[serde(default = "default_digest_enabled")]
+    pub enabled: bool,
+    /// Collect problems for this long before sending one summary
+    #[serde(default)]
+    pub window_seconds: u64,
+    /// Local times of day (HH:MM, connector timezone) to send the digest at,
+    /// instead of a rolling window
+    #[serde(default)]
+    pub send_at: Vec<String>,
+    /// Send one summary of each poll's problems instead of collecting over a window
+    #[serde(default)]
+    pub every_poll: bool,
+    /// Number of problems listed individually in the summary
+    #[serde(default = "default_digest_top_n")]
+    pub top_n: usize,
+}
+
+impl DigestConfig {
+    /// Parsed `send_at` times; invalid entries are rejected during validation
+    pub fn send_times(&self) -> Vec<NaiveTime> {
+        self.send_at
+            .iter()
+            .filter_map(|time| NaiveTime::parse_from_str(time, "%H:%M").ok())
+            .collect()
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct RecurrenceSamplingConfig {
+    /// Forward the first occurrence and then every `every`th one
+    pub every: u64,
+    /// What makes problems occurrences of the same issue (default: title and entity)
+    #[serde(default = "default_recurrence_match_on")]
+    pub match_on: Vec<RecurrenceKey>,
+    /// Only sample problems whose title matches (default: all problems)
+    pub title_regex: Option<String>,
+    /// Start counting again after this long without an occurrence
+    #[serde(default = "default_recurrence_reset_after_hours")]
+    pub reset_after_hours: u64,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RecurrenceKey {
+    /// Problem title
+    Title,
+    /// Root cause entity, or the first affected entity without one
+    Entity,
+}
+
+fn default_recurrence_match_on() -> Vec<RecurrenceKey> {
+    vec![RecurrenceKey::Title, RecurrenceKey::Entity]
+}
+
+fn default_recurrence_reset_after_hours() -> u64 {
+    24
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ActiveHoursConfig {
+    /// Days the window opens on (every day when empty), e.g. `[Mon, Tue, Wed, Thu, Fri]`
+    #[serde(default)]
+    pub days: Vec<Weekday>,
+    /// Local time (HH:MM, connector timezone) the window opens
+    pub start: String,
+    /// Local time the window closes; before `start` for a window past midnight
+    pub end: String,
+    /// What happens to problems outside the window
+    #[serde(default)]
+    pub held: HeldProblems,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ProblemTransition {
+    /// First time the forwarder sees the problem, whatever its status
+    New,
+    /// A known problem changed back to OPEN
+    Reopened,
+    /// A known problem changed to CLOSED
+    Closed,
+    /// A known problem changed to RESOLVED
+    Resolved,
+    /// A problem still open after `renotify.after_minutes` is sent again
+    Renotification,
+}
+
+fn default_forward_on() -> Vec<ProblemTransition> {
+    vec![
+        ProblemTransition::New,
+        ProblemTransition::Reopened,
+        ProblemTransition::Closed,
+        ProblemTransition::Resolved,
+        ProblemTransition::Renotification,
+    ]
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum HeldProblems {
+    /// Send one digest of the held problems when the window opens
+    #[default]
+    Digest,
+    /// Deliver the held problems as usual when the window opens
+    Forward,
+    /// Discard them, recording each as `suppressed` in forward history
+    Drop,
+}
+
+impl ActiveHoursConfig {
+    /// Parsed `start` and `end`; invalid times are rejected during validation
+    pub fn times(&self) -> Option<(NaiveTime, NaiveTime)> {
+        let start = NaiveTime::parse_from_str(&self.start, "%H:%M").ok()?;
+        let end = NaiveTime::parse_from_str(&self.end, "%H:%M").ok()?;
+        Some((start, end))
+    }
+
+    /// Whether `now` (in the connector's timezone) falls inside the window. A
+    /// window past midnight belongs to the day it opens on.
+    pub fn is_active<T: TimeZone>(&self, now: &DateTime<T>) -> bool {
+        let Some((start, end)) = self.times() else {
+            return true;
+        };
+        let time = now.time();
+        let today = now.weekday();
+        let opens_on = |day: Weekday| self.days.is_empty() || self.days.contains(&day);
+
+        if start <= end {
+            opens_on(today) && time >= start && time < end
+        } else {
+            (opens_on(today) && time >= start) || (opens_on(today.pred()) && time < end)
+        }
+    }
+}
+
+fn default_digest_enabled() -> bool {
+    true
+}
+
+fn default_digest_top_n() -> usize {
+    10
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct AcknowledgmentConfig {
+    /// Seconds between status checks
+    #[serde(default = "default_ack_interval")]
+    pub interval_seconds: u64,
+    /// Give up (and fail the forward) after this many seconds
+    #[serde(default = "default_ack_timeout")]
+    pub timeout_seconds: u64,
+    /// JSON pointer to the status URL in the 202 body (default: the Location header)
+    pub status_url_field: Option<String>,
+    /// JSON pointer to the state in the status response. Without it any 2xx
+    /// other than 202 counts as accepted.
+    pub status_field: Option<String>,
+    #[serde(default = "default_ack_success_values")]
+    pub success_values: Vec<String>,
+    #[serde(default = "default_ack_failure_values")]
+    pub failure_values: Vec<String>,
+}
+
+fn default_ack_interval() -> u64 {
+    5
+}
+
+fn default_ack_timeout() -> u64 {
+    120
+}
+
+fn default_ack_success_values() -> Vec<String> {
+    ["success", "succeeded", "completed", "created"].map(String::from).to_vec()
+}
+
+fn default_ack_failure_values() -> Vec<String> {
+    ["failed", "failure", "error", "rejected"].map(String::from).to_vec()
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct SeverityOverride {
+    pub retry_attempts: Option<u32>,
+    pub timeout_seconds: Option<u64>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ElasticsearchConfig {
+    /// Target index, may contain strftime placeholders (e.g. "dt-problems-%Y.%m")
+    #[serde(default = "default_elasticsearch_index")]
+    pub index: String,
+    pub username: Option<String>,
+    pub password: Option<String>,
+    pub api_key: Option<String>,
+}
+
+fn default_elasticsearch_index() -> String {
+    "dt-problems".to_string()
+}
+
+impl Default for ElasticsearchConfig {
+    fn default() -> Self {
+        Self {
+            index: default_elasticsearch_index(),
+            username: None,
+            password: None,
+            api_key: None,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum EventHubsAuth {
+    /// Shared access signature from a key name and key
+    #[default]
+    Sas,
+    /// Azure AD client credentials (service principal)
+    Aad,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct EventHubsConfig {
+    /// Namespace name (the `<namespace>` in `<namespace>.servicebus.windows.net`)
+    pub namespace: String,
+    pub event_hub: String,
+    #[serde(default)]
+    pub auth: EventHubsAuth,
+    pub sas_key_name: Option<String>,
+    pub sas_key: Option<String>,
+    pub tenant_id: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+}
+
+/// xMatters REST API access, used to terminate events when problems close
+#[derive(Debug, Deserialize, Clone)]
+pub struct XMattersConfig {
+    /// Instance URL, e.g. https://acme.xmatters.com
+    pub base_url: String,
+    pub username: String,
+    pub password: String,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct FileSinkConfig {
+    pub path: PathBuf,
+    /// Rotate when the file would grow beyond this size
+    #[serde(default = "default_file_max_size_mb")]
+    pub max_size_mb: u64,
+    /// Number of rotated files (`<path>.1` ... `<path>.N`) to keep
+    #[serde(default = "default_file_max_files")]
+    pub max_files: usize,
+    /// Compress files as they are rotated (`<path>.1.zst`)
+    pub compression: Option<FileCompression>,
+    /// Encrypt files as they are rotated to these age public keys (`<path>.1.age`)
+    #[serde(default)]
+    pub age_recipients: Vec<String>,
+}
+
+impl FileSinkConfig {
+    pub fn max_size_bytes(&self) -> u64 {
+        self.max_size_mb * 1024 * 1024
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum FileCompression {
+    Zstd,
+}
+
+fn default_file_max_size_mb() -> u64 {
+    100
+}
+
+fn default_file_max_files() -> usize {
+    5
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct ExecConfig {
+    /// Program to run, looked up on PATH unless it is a path
+    pub command: String,
+    #[serde(default)]
+    pub args: Vec<String>,
+    /// Extra environment variables for the command
+    #[serde(default)]
+    pub env: HashMap<String, String>,
+    pub working_dir: Option<PathBuf>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct SigningConfig {
+    /// Shared secret (supports ${ENV_VAR})
+    pub secret: String,
+    /// Header carrying `sha256=<hex digest>` of the request body
+    #[serde(default = "default_signing_header")]
+    pub header: String,
+}
+
+fn default_signing_header() -> String {
+    "X-DTPF-Signature".to_string()
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct GzipConfig {
+    /// Bodies smaller than this are sent uncompressed
+    #[serde(default = "default_gzip_min_bytes")]
+    pub min_bytes: usize,
+    /// Compression level, 1 (fastest) to 9 (smallest)
+    #[serde(default = "default_gzip_level")]
+    pub level: u32,
+}
+
+impl Default for GzipConfig {
+    fn default() -> Self {
+        Self {
+            min_bytes: default_gzip_min_bytes(),
+            level: default_gzip_level(),
+        }
+    }
+}
+
+fn default_gzip_min_bytes() -> usize {
+    1024
+}
+
+fn default_gzip_level() -> u32 {
+    6
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct CloudEventsConfig {
+    #[serde(default)]
+    pub mode: CloudEventsMode,
+    /// `source` attribute (default: the Dynatrace environment URL)
+    pub source: Option<String>,
+    /// `type` is this prefix plus the lowercase problem status, e.g. `com.dynatrace.problem.open`
+    #[serde(default = "default_cloudevents_type_prefix")]
+    pub type_prefix: String,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct EnvelopeConfig {
+    /// Environment label, e.g. `production` (omitted when not set)
+    pub environment: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "lowercase")]
+pub enum CloudEventsMode {
+    /// Attributes and data in one JSON envelope (a JSON array of envelopes in batch mode)
+    #[default]
+    Structured,
+    /// Problem JSON as the body, attributes in `ce-*` headers
+    Binary,
+}
+
+fn default_cloudevents_type_prefix() -> String {
+    "com.dynatrace.problem".to_string()
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct LabelsConfig {
+    /// Entity tag keys to copy into labels (empty = none)
+    #[serde(default)]
+    pub tags: Vec<String>,
+    /// Add a `management_zone` label with comma-separated zone names
+    #[serde(default = "default_label_management_zones")]
+    pub management_zones: bool,
+    /// Rename label keys (original key -> new key)
+    #[serde(default)]
+    pub rename: HashMap<String, String>,
+}
+
+fn default_label_management_zones() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize, Clone, Default, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum PubSubAuth {
+    /// Sign tokens with a service account JSON key file
+    #[default]
+    ServiceAccount,
+    /// Fetch tokens from the GCE/GKE metadata server
+    WorkloadIdentity,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct PubSubConfig {
+    pub project_id: String,
+    pub topic: String,
+    #[serde(default)]
+    pub auth: PubSubAuth,
+    /// Path to the service account key file (falls back to GOOGLE_APPLICATION_CREDENTIALS)
+    pub credentials_file: Option<PathBuf>,
+}
+
+impl ConnectorConfig {
+    /// Timezone used for schedules and rendered timestamps
+    pub fn timezone(&self) -> Tz {
+        self.timezone.unwrap_or(Tz::UTC)
+    }
+
+    /// A batch split into requests of at most `batch_max_items` problems
+    pub fn batch_chunks<'a, T>(&self, problems: &'a [T]) -> std::slice::Chunks<'a, T> {
+        problems.chunks(self.batch_max_items.unwrap_or(problems.len()).max(1))
+    }
+
+    /// Retry attempts and timeout for a set of problems. With mixed severities
+    /// (batch mode) the most generous policy wins, counting severities without
+    /// an override at the connector's own settings.
+    pub fn delivery_policy<'a, I>(&self, severities: I) -> (u32, u64)
+    where
+        I: IntoIterator<Item = &'a str>,
+    {
+        let default_attempts = self.retry_attempts.unwrap_or(3);
+        let default_timeout = self.timeout_seconds.unwrap_or(30);
+        let mut policy: Option<(u32, u64)> = None;
+
+        for severity in severities {
+            let severity_override = self.severity_overrides.get(severity);
+            let attempts = severity_override.and_then(|o| o.retry_attempts).unwrap_or(default_attempts);
+            let timeout = severity_override.and_then(|o| o.timeout_seconds).unwrap_or(default_timeout);
+
+            policy = Some(match policy {
+                Some((most_attempts, longest_timeout)) => (most_attempts.max(attempts), longest_timeout.max(timeout)),
+                None => (attempts, timeout),
+            });
+        }
+
+        policy.unwrap_or((default_attempts, default_timeout))
+    }
+
+    /// Whether the connector's `forward_on` includes the problem's update. Problems
+    /// not coming from a poll (e.g. volume anomaly alerts) are always forwarded.
+    pub fn forwards(&self, problem: &Problem) -> bool {
+        let transition = match (&problem.change, &problem.status) {
+            (None, _) => return true,
+            (Some(ProblemChange::New), _) => ProblemTransition::New,
+            (Some(ProblemChange::StatusChange), ProblemStatus::Open) => ProblemTransition::Reopened,
+            (Some(ProblemChange::StatusChange), ProblemStatus::Closed) => ProblemTransition::Closed,
+            (Some(ProblemChange::StatusChange), ProblemStatus::Resolved) => ProblemTransition::Resolved,
+            (Some(ProblemChange::Renotification), _) => ProblemTransition::Renotification,
+        };
+        self.forward_on.contains(&transition)
+    }
+
+    /// Whether a problem falls into this connector's `sample_rate`
+    pub fn samples(&self, problem_id: &str) -> bool {
+        let Some(rate) = self.sample_rate else {
+            return true;
+        };
+
+        // FNV-1a, stable across restarts unlike the std hasher
+        let hash = problem_id.bytes().fold(0xcbf2_9ce4_8422_2325_u64, |hash, byte| {
+            (hash ^ byte as u64).wrapping_mul(0x0100_0000_01b3)
+        });
+
+        ((hash % 10_000) as f64) < rate * 10_000.0
+    }
+
+    /// Validate the connector on its own; `Settings::validate` checks it against the others
+    fn validate(&self) -> Result<()> {
+        if self.name.is_empty() {
+            return Err(ForwarderError::Validation(
+                "Connector name cannot be empty".to_string(),
+            ));
+        }
+
+        if let Some(rate) = self.sample_rate {
+            if !(rate > 0.0 && rate <= 1.0) {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}' sample_rate must be greater than 0 and at most 1",
+                    self.name
+                )));
+            }
+        }
+
+        if let Some(sampling) = &self.recurrence_sampling {
+            if sampling.every < 2 || sampling.match_on.is_empty() || sampling.reset_after_hours == 0 {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}' recurrence_sampling needs every of at least 2, a match_on key and reset_after_hours greater than 0",
+                    self.name
+                )));
+            }
+
+            if let Some(pattern) = &sampling.title_regex {
+                regex::Regex::new(pattern).map_err(|e| {
+                    ForwarderError::Validation(format!(
+                        "Connector '{}' recurrence_sampling.title_regex is invalid: {}",
+                        self.name, e
+                    ))
+                })?;
+            }
+        }
+
+        if let Some(digest) = self.digest.as_ref().filter(|d| d.enabled) {
+            if self.shadow {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}' cannot use shadow with digest mode",
+                    self.name
+                )));
+            }
+
+            if !matches!(
+                self.connector_type,
+                ConnectorType::Webhook | ConnectorType::Webex | ConnectorType::GoogleChat
+            ) {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}': digest mode is only supported for webhook, webex and google_chat connectors",
+                    self.name
+                )));
+            }
+
+            if !digest.every_poll && digest.window_seconds == 0 && digest.send_at.is_empty() {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}' digest requires every_poll, window_seconds greater than 0 or send_at times",
+                    self.name
+                )));
+            }
+
+            if digest.every_poll && (digest.window_seconds > 0 || !digest.send_at.is_empty()) {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}' digest.every_poll cannot be combined with window_seconds or send_at",
+                    self.name
+                )));
+            }
+
+            if let Some(time) = digest
+                .send_at
+                .iter()
+                .find(|time| NaiveTime::parse_from_str(time, "%H:%M").is_err())
+            {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}' digest.send_at '{}' is not a valid HH:MM time",
+                    self.name, time
+                )));
+            }
+        }
+
+        if let Some(active_hours) = &self.active_hours {
+            if active_hours.times().is_none() {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}' active_hours.start and end must be HH:MM times",
+                    self.name
+                )));
+            }
+
+            if self.digest.as_ref().is_some_and(|d| d.enabled) || self.failover_group.is_some() {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}': active_hours cannot be combined with digest mode or a failover group",
+                    self.name
+                )));
+            }
+
+            if active_hours.held == HeldProblems::Digest
+                && (self.connector_type != ConnectorType::Webhook
+                    || self.format != PayloadFormat::Json
+                    || self.cloudevents.is_some()
+                    || self.template.is_some()
+                    || self.envelope.is_some())
+            {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}': active_hours.held: digest needs a plain JSON webhook connector; use forward or drop",
+                    self.name
+                )));
+            }
+        }
+
+        if let Some(ack) = &self.acknowledgment {
+            if self.connector_type != ConnectorType::Webhook {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}': acknowledgment polling is only supported for webhook connectors",
+                    self.name
+                )));
+            }
+
+            if ack.interval_seconds == 0 || ack.timeout_seconds < ack.interval_seconds {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}' acknowledgment.interval_seconds must be greater than 0 and not exceed timeout_seconds",
+                    self.name
+                )));
+            }
+        }
+
+        self.validate_payload()?;
+        self.validate_dynatrace_actions()?;
+        self.validate_filters()?;
+        self.validate_transport()?;
+        self.validate_target()
+    }
+
+    /// Payload format, reshaping options, template, XML, CloudEvents and envelope
+    fn validate_payload(&self) -> Result<()> {
+        if self.format != PayloadFormat::Json {
+            if !matches!(
+                self.connector_type,
+                ConnectorType::Webhook | ConnectorType::File | ConnectorType::Exec
+            ) {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}': format is only supported for webhook, file and exec connectors",
+                    self.name
+                )));
+            }
+
+            if self.cloudevents.is_some() || self.digest.as_ref().is_some_and(|d| d.enabled) {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}': CEF/LEEF/XML/form output cannot be combined with cloudevents or digest mode",
+                    self.name
+                )));
+            }
+        }
+
+        if self.format == PayloadFormat::Xml && self.connector_type != ConnectorType::Webhook {
+            return Err(ForwarderError::Validation(format!(
+                "Connector '{}': format xml is only supported for webhook connectors",
+                self.name
+            )));
+        }
+
+        let form_format = matches!(self.format, PayloadFormat::Form | PayloadFormat::Query);
+        if form_format {
+            if self.connector_type != ConnectorType::Webhook || self.batch_mode {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}': form and query formats require a webhook connector with batch_mode: false",
+                    self.name
+                )));
+            }
+
+            let fields = self.form.as_ref().map(|form| form.fields.as_slice()).unwrap_or_default();
+            if fields.is_empty() {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}': form and query formats need at least one form.fields entry",
+                    self.name
+                )));
+            }
+
+            for field in fields {
+                if field.name.is_empty() {
+                    return Err(ForwarderError::Validation(format!(
+                        "Connector '{}': form field names cannot be empty",
+                        self.name
+                    )));
+                }
+                handlebars::Template::compile(&field.value).map_err(|e| {
+                    ForwarderError::Validation(format!(
+                        "Connector '{}': invalid template for form field '{}': {}",
+                        self.name, field.name, e
+                    ))
+                })?;
+            }
+        } else if self.form.is_some() {
+            return Err(ForwarderError::Validation(format!(
+                "Connector '{}': form settings require format: form or format: query",
+                self.name
+            )));
+        }
+
+        // Options reshaping the problem JSON need a connector that sends it
+        let reshaping = [
+            ("computed_fields", self.computed_fields.is_some()),
+            ("include_fields", !self.include_fields.is_empty()),
+            ("exclude_fields", !self.exclude_fields.is_empty()),
+            ("field_map", self.field_map.is_some()),
+            ("transform", self.transform.is_some()),
+        ];
+        for (option, _) in reshaping.iter().filter(|(_, set)| *set) {
+            if matches!(
+                self.connector_type,
+                ConnectorType::Webex | ConnectorType::GoogleChat | ConnectorType::Grpc | ConnectorType::Xmatters
+            ) {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}': {} is not supported for {:?} connectors, which use a fixed message format",
+                    self.name, option, self.connector_type
+                )));
+            }
+
+            if matches!(self.format, PayloadFormat::Cef | PayloadFormat::Leef) || self.template.is_some() {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}': {} cannot be combined with CEF/LEEF output or a template",
+                    self.name, option
+                )));
+            }
+        }
+
+        if let Some(computed) = &self.computed_fields {
+            let invalid = chrono::format::StrftimeItems::new(&computed.time_format)
+                .any(|item| matches!(item, chrono::format::Item::Error));
+            if invalid {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}': invalid computed_fields.time_format '{}'",
+                    self.name, computed.time_format
+                )));
+            }
+        }
+
+        if !self.include_fields.is_empty() && !self.exclude_fields.is_empty() {
+            return Err(ForwarderError::Validation(format!(
+                "Connector '{}': use either include_fields or exclude_fields, not both",
+                self.name
+            )));
+        }
+
+        if let Some(transform) = &self.transform {
+            crate::forwarder::transform::validate(transform).map_err(|e| {
+                ForwarderError::Validation(format!("Connector '{}': invalid transform: {}", self.name, e))
+            })?;
+        }
+
+        if let Some(field_map) = &self.field_map {
+            if let Some(field) = field_map.drop.iter().find(|field| field_map.rename.contains_key(*field)) {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}': field_map cannot both rename and drop '{}'",
+                    self.name, field
+                )));
+            }
+
+            let mut targets: Vec<&String> = field_map.rename.values().collect();
+            targets.sort();
+            if let Some(pair) = targets.windows(2).find(|pair| pair[0] == pair[1]) {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}': field_map renames more than one field to '{}'",
+                    self.name, pair[0]
+                )));
+            }
+        }
+
+        if let Some(template) = &self.template {
+            if self.connector_type != ConnectorType::Webhook {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}': template is only supported for webhook connectors",
+                    self.name
+                )));
+            }
+
+            if self.format != PayloadFormat::Json
+                || self.cloudevents.is_some()
+                || self.digest.as_ref().is_some_and(|d| d.enabled)
+            {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}': template cannot be combined with format, cloudevents or digest mode",
+                    self.name
+                )));
+            }
+
+            let mode_template = if self.batch_mode { &template.batch } else { &template.single };
+            if mode_template.is_none() {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}': template.{} is required when batch_mode is {}",
+                    self.name,
+                    if self.batch_mode { "batch" } else { "single" },
+                    self.batch_mode
+                )));
+            }
+
+            if template.close.is_some() && self.batch_mode {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}': template.close requires batch_mode: false",
+                    self.name
+                )));
+            }
+
+            for (mode, source) in [
+                ("single", &template.single),
+                ("close", &template.close),
+                ("batch", &template.batch),
+            ] {
+                if let Some(source) = source {
+                    handlebars::Template::compile(source).map_err(|e| {
+                        ForwarderError::Validation(format!(
+                            "Connector '{}': invalid template.{}: {}",
+                            self.name, mode, e
+                        ))
+                    })?;
+                }
+            }
+        }
+
+        if let Some(xml) = &self.xml {
+            if self.format != PayloadFormat::Xml {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}': xml settings require format: xml",
+                    self.name
+                )));
+            }
+
+            let elements = [&xml.root_element, &xml.item_element]
+                .into_iter()
+                .chain(xml.fields.iter().map(|field| &field.element));
+            for element in elements {
+                if !is_xml_name(element) {
+                    return Err(ForwarderError::Validation(format!(
+                        "Connector '{}': '{}' is not a valid XML element name",
+                        self.name, element
+                    )));
+                }
+            }
+
+            if let Some(field) = xml.fields.iter().find(|field| !field.pointer.starts_with('/')) {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}': xml field '{}' pointer must start with '/'",
+                    self.name, field.element
+                )));
+            }
+        }
+
+        if let Some(cloudevents) = &self.cloudevents {
+            if self.connector_type != ConnectorType::Webhook {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}': cloudevents is only supported for webhook connectors",
+                    self.name
+                )));
+            }
+
+            if self.digest.as_ref().is_some_and(|d| d.enabled) {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}' cannot use cloudevents with digest mode",
+                    self.name
+                )));
+            }
+
+            if cloudevents.mode == CloudEventsMode::Binary && self.batch_mode {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}': binary CloudEvents carry one problem per request and require batch_mode: false",
+                    self.name
+                )));
+            }
+        }
+
+        if self.envelope.is_some() {
+            if self.connector_type != ConnectorType::Webhook {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}': envelope is only supported for webhook connectors",
+                    self.name
+                )));
+            }
+
+            if self.cloudevents.is_some()
+                || self.template.is_some()
+                || self.format != PayloadFormat::Json
+                || self.digest.as_ref().is_some_and(|d| d.enabled)
+            {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}': envelope cannot be combined with cloudevents, a template, format or digest mode",
+                    self.name
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Closing and commenting on problems in Dynatrace
+    fn validate_dynatrace_actions(&self) -> Result<()> {
+        if let Some(close) = &self.close_endpoint {
+            if self.connector_type != ConnectorType::Webhook {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}': close_endpoint is only supported for webhook connectors",
+                    self.name
+                )));
+            }
+
+            if self.batch_mode || self.digest.as_ref().is_some_and(|d| d.enabled) {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}': close_endpoint requires batch_mode: false and no digest mode",
+                    self.name
+                )));
+            }
+
+            if close.url.is_none() && close.method.is_none() {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}': close_endpoint needs a url or a method",
+                    self.name
+                )));
+            }
+        }
+
+        if let Some(comment) = &self.dynatrace_comment {
+            if let Some(field) = &comment.reference_field {
+                if self.connector_type != ConnectorType::Webhook {
+                    return Err(ForwarderError::Validation(format!(
+                        "Connector '{}': dynatrace_comment.reference_field is only supported for webhook connectors",
+                        self.name
+                    )));
+                }
+
+                if !field.starts_with('/') {
+                    return Err(ForwarderError::Validation(format!(
+                        "Connector '{}': dynatrace_comment.reference_field '{}' must be a JSON pointer starting with '/'",
+                        self.name, field
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Which problems the connector receives
+    fn validate_filters(&self) -> Result<()> {
+        if let Some(expression) = &self.tag_filter {
+            if let Err(e) = crate::forwarder::tag_filter::TagFilter::parse(expression) {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}' tag_filter is invalid: {}",
+                    self.name, e
+                )));
+            }
+        }
+
+        if self.forward_on.is_empty() {
+            return Err(ForwarderError::Validation(format!(
+                "Connector '{}' forward_on must list at least one of new, reopened, closed, resolved, renotification",
+                self.name
+            )));
+        }
+
+        if let Some(level) = self.impact_levels.iter().find(|level| !IMPACT_LEVELS.contains(&level.as_str())) {
+            return Err(ForwarderError::Validation(format!(
+                "Connector '{}' impact_levels contains unknown level '{}' (expected one of {})",
+                self.name,
+                level,
+                IMPACT_LEVELS.join(", ")
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Batch limits, payload size, compression, signing and retries
+    fn validate_transport(&self) -> Result<()> {
+        if self.batch_max_items == Some(0) || self.batch_max_bytes == Some(0) {
+            return Err(ForwarderError::Validation(format!(
+                "Connector '{}' batch_max_items and batch_max_bytes must be greater than 0",
+                self.name
+            )));
+        }
+
+        if self.batch_max_bytes.is_some() && self.connector_type != ConnectorType::Webhook {
+            return Err(ForwarderError::Validation(format!(
+                "Connector '{}': batch_max_bytes is only supported for webhook connectors",
+                self.name
+            )));
+        }
+
+        if let Some(max_bytes) = self.max_payload_bytes {
+            if self.connector_type != ConnectorType::Webhook {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}': max_payload_bytes is only supported for webhook connectors",
+                    self.name
+                )));
+            }
+
+            if max_bytes == 0 || self.format == PayloadFormat::Query {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}' max_payload_bytes must be greater than 0 and needs a request body (not format: query)",
+                    self.name
+                )));
+            }
+
+            if self.oversize_strategy == OversizeStrategy::Split && !self.batch_mode {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}': oversize_strategy split requires batch_mode: true",
+                    self.name
+                )));
+            }
+        }
+
+        if let Some(gzip) = &self.gzip {
+            if self.connector_type != ConnectorType::Webhook {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}': gzip is only supported for webhook connectors",
+                    self.name
+                )));
+            }
+
+            if self.format == PayloadFormat::Query {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}': gzip has no effect with format: query, which sends no body",
+                    self.name
+                )));
+            }
+
+            if !(1..=9).contains(&gzip.level) {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}' gzip.level must be between 1 and 9",
+                    self.name
+                )));
+            }
+        }
+
+        if let Some(signing) = &self.signing {
+            if !matches!(
+                self.connector_type,
+                ConnectorType::Webhook | ConnectorType::Webex | ConnectorType::GoogleChat
+            ) {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}': signing is only supported for webhook, webex and google_chat connectors",
+                    self.name
+                )));
+            }
+
+            if signing.secret.is_empty() || signing.secret.starts_with("${") {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}' signing.secret is empty or its environment variable is not set",
+                    self.name
+                )));
+            }
+
+            if reqwest::header::HeaderName::from_bytes(signing.header.as_bytes()).is_err() {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}' signing.header '{}' is not a valid header name",
+                    self.name, signing.header
+                )));
+            }
+        }
+
+        for (severity, severity_override) in &self.severity_overrides {
+            if severity_override.retry_attempts == Some(0) {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}' severity_overrides.{}.retry_attempts must be greater than 0",
+                    self.name, severity
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Target URL and the section of the connector type
+    fn validate_target(&self) -> Result<()> {
+        if matches!(
+            self.connector_type,
+            ConnectorType::Webhook
+                | ConnectorType::Elasticsearch
+                | ConnectorType::Webex
+                | ConnectorType::GoogleChat
+                | ConnectorType::Xmatters
+                | ConnectorType::Grpc
+        ) {
+            if self.url.is_empty() {
+                return Err(ForwarderError::Validation(
+                    format!("Connector '{}' URL cannot be empty", self.name),
+                ));
+            }
+
+            if !self.url.starts_with("http://") && !self.url.starts_with("https://") {
+                return Err(ForwarderError::Validation(
+                    format!("Connector '{}' URL must start with http:// or https://", self.name),
+                ));
+            }
+        }
+
+        match self.connector_type {
+            ConnectorType::Webhook
+            | ConnectorType::Webex
+            | ConnectorType::GoogleChat
+            | ConnectorType::Grpc => {}
+            ConnectorType::File => {
+                let file = self.file.as_ref().ok_or_else(|| {
+                    ForwarderError::Validation(format!(
+                        "Connector '{}' of type file requires a 'file' section",
+                        self.name
+                    ))
+                })?;
+
+                if file.path.as_os_str().is_empty() || file.max_size_mb == 0 {
+                    return Err(ForwarderError::Validation(format!(
+                        "Connector '{}' file requires a path and max_size_mb greater than 0",
+                        self.name
+                    )));
+                }
+
+                if let Some(recipient) = file
+                    .age_recipients
+                    .iter()
+                    .find(|recipient| recipient.parse::<age::x25519::Recipient>().is_err())
+                {
+                    return Err(ForwarderError::Validation(format!(
+                        "Connector '{}' file.age_recipients: '{}' is not an age public key (age1...)",
+                        self.name, recipient
+                    )));
+                }
+            }
+            ConnectorType::Exec => {
+                let exec = self.exec.as_ref().ok_or_else(|| {
+                    ForwarderError::Validation(format!(
+                        "Connector '{}' of type exec requires an 'exec' section",
+                        self.name
+                    ))
+                })?;
+
+                if exec.command.is_empty() {
+                    return Err(ForwarderError::Validation(format!(
+                        "Connector '{}' exec.command cannot be empty",
+                        self.name
+                    )));
+                }
+            }
+            ConnectorType::Xmatters => {
+                if let Some(xm) = &self.xmatters {
+                    if !xm.base_url.starts_with("http://") && !xm.base_url.starts_with("https://") {
+                        return Err(ForwarderError::Validation(format!(
+                            "Connector '{}' xmatters.base_url must start with http:// or https://",
+                            self.name
+                        )));
+                    }
+                }
+            }
+            ConnectorType::Elasticsearch => {
+                if let Some(es) = &self.elasticsearch {
+                    if es.api_key.is_some() && es.username.is_some() {
+                        return Err(ForwarderError::Validation(format!(
+                            "Connector '{}' must use either elasticsearch.api_key or username/password, not both",
+                            self.name
+                        )));
+                    }
+
+                    if chrono::format::StrftimeItems::new(&es.index)
+                        .any(|item| matches!(item, chrono::format::Item::Error))
+                    {
+                        return Err(ForwarderError::Validation(format!(
+                            "Connector '{}' elasticsearch.index '{}' is not a valid strftime pattern",
+                            self.name, es.index
+                        )));
+                    }
+                }
+            }
+            ConnectorType::Eventhubs => {
+                let eh = self.eventhubs.as_ref().ok_or_else(|| {
+                    ForwarderError::Validation(format!(
+                        "Connector '{}' of type eventhubs requires an 'eventhubs' section",
+                        self.name
+                    ))
+                })?;
+
+                let complete = match eh.auth {
+                    EventHubsAuth::Sas => eh.sas_key_name.is_some() && eh.sas_key.is_some(),
+                    EventHubsAuth::Aad => {
+                        eh.tenant_id.is_some() && eh.client_id.is_some() && eh.client_secret.is_some()
+                    }
+                };
+
+                if eh.namespace.is_empty() || eh.event_hub.is_empty() || !complete {
+                    return Err(ForwarderError::Validation(format!(
+                        "Connector '{}' eventhubs requires namespace, event_hub and credentials for the selected auth (sas: sas_key_name/sas_key, aad: tenant_id/client_id/client_secret)",
+                        self.name
+                    )));
+                }
+            }
+            ConnectorType::Pubsub => {
+                let pubsub = self.pubsub.as_ref().ok_or_else(|| {
+                    ForwarderError::Validation(format!(
+                        "Connector '{}' of type pubsub requires a 'pubsub' section",
+                        self.name
+                    ))
+                })?;
+
+                if pubsub.project_id.is_empty() || pubsub.topic.is_empty() {
+                    return Err(ForwarderError::Validation(format!(
+                        "Connector '{}' pubsub.project_id and pubsub.topic cannot be empty",
+                        self.name
+                    )));
+                }
+
+                if pubsub.auth == PubSubAuth::ServiceAccount
+                    && pubsub.credentials_file.is_none()
+                    && std::env::var("GOOGLE_APPLICATION_CREDENTIALS").is_err()
+                {
+                    return Err(ForwarderError::Validation(format!(
+                        "Connector '{}' uses service_account auth but neither pubsub.credentials_file nor GOOGLE_APPLICATION_CREDENTIALS is set",
+                        self.name
+                    )));
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Replace a whole-value `${VAR}` placeholder with the environment variable's value
+fn expand_env_placeholder(value: &mut String) {
+    if value.starts_with("${") && value.ends_with('}') {
+        let env_var = &value[2..value.len() - 1];
+        if let Ok(env_value) = std::env::var(env_var) {
+            *value = env_value;
+        }
+    }
+}
+
+fn default_method() -> HttpMethod {
+    HttpMethod::Post
+}
+
+fn default_verify_ssl() -> bool {
+    true
+}
+
+fn default_batch_mode() -> bool {
+    true
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct AnomalyDetectionConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Number of recent normal cycles averaged into the baseline
+    #[serde(default = "default_baseline_cycles")]
+    pub baseline_cycles: u32,
+    /// Flag a cycle when its volume exceeds baseline * multiplier
+    #[serde(default = "default_threshold_multiplier")]
+    pub threshold_multiplier: f64,
+    /// Ignore cycles with fewer new/changed problems than this
+    #[serde(default = "default_min_problems")]
+    pub min_problems: u32,
+    /// Forward a synthetic meta-alert problem describing the anomaly
+    #[serde(default)]
+    pub meta_alert: bool,
+    /// Send batch webhook connectors one digest instead of the full batch during a storm
+    #[serde(default)]
+    pub digest_during_storm: bool,
+}
+
+impl Default for AnomalyDetectionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            baseline_cycles: default_baseline_cycles(),
+            threshold_multiplier: default_threshold_multiplier(),
+            min_problems: default_min_problems(),
+            meta_alert: false,
+            digest_during_storm: false,
+        }
+    }
+}
+
+fn default_baseline_cycles() -> u32 {
+    20
+}
+
+fn default_threshold_multiplier() -> f64 {
+    5.0
+}
+
+fn default_min_problems() -> u32 {
+    20
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct HealthConfig {
+    /// Number of recent requests used for each connector's health score
+    #[serde(default = "default_health_window")]
+    pub window: usize,
+    /// Try failover group members healthiest-first instead of in configured order
+    #[serde(default)]
+    pub prefer_healthiest: bool,
+}
+
+impl Default for HealthConfig {
+    fn default() -> Self {
+        Self {
+            window: default_health_window(),
+            prefer_healthiest: false,
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct CorrelationConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Group problems of one poll cycle that share a root cause entity
+    #[serde(default = "default_correlate_by_root_cause")]
+    pub by_root_cause: bool,
+    /// Group problems sharing a management zone that started within `window_seconds`
+    #[serde(default)]
+    pub by_management_zone: bool,
+    #[serde(default = "default_correlation_window")]
+    pub window_seconds: u64,
+}
+
+impl Default for CorrelationConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            by_root_cause: default_correlate_by_root_cause(),
+            by_management_zone: false,
+            window_seconds: default_correlation_window(),
+        }
+    }
+}
+
+fn default_correlate_by_root_cause() -> bool {
+    true
+}
+
+fn default_correlation_window() -> u64 {
+    300
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct DuplicateGuardConfig {
+    /// Skip deliveries that forward history records as already successful for
+    /// the same problem, status and connector (or failover group)
+    #[serde(default)]
+    pub enabled: bool,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct RedeliveryConfig {
+    /// Send problems a connector failed to receive again after each poll
+    #[serde(default)]
+    pub enabled: bool,
+    /// Give up on a problem this long after its first failed delivery
+    #[serde(default = "default_redelivery_max_age_minutes")]
+    pub max_age_minutes: u64,
+}
+
+impl Default for RedeliveryConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            max_age_minutes: default_redelivery_max_age_minutes(),
+        }
+    }
+}
+
+fn default_redelivery_max_age_minutes() -> u64 {
+    60
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct RenotifyConfig {
+    /// Forward problems again while they stay open
+    #[serde(default)]
+    pub enabled: bool,
+    /// First reminder this long after the problem was forwarded as open
+    #[serde(default = "default_renotify_after_minutes")]
+    pub after_minutes: u64,
+    /// Further reminders at this interval (default: only one reminder)
+    pub repeat_minutes: Option<u64>,
+    /// Connectors receiving reminders instead of the ones routing selects
+    #[serde(default)]
+    pub connectors: Vec<String>,
+}
+
+impl Default for RenotifyConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            after_minutes: default_renotify_after_minutes(),
+            repeat_minutes: None,
+            connectors: Vec::new(),
+        }
+    }
+}
+
+fn default_renotify_after_minutes() -> u64 {
+    60
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct PriorityConfig {
+    /// Forward the problems of a poll most important first instead of in fetch order
+    #[serde(default)]
+    pub enabled: bool,
+    /// Sort keys, compared in order until one differs
+    #[serde(default = "default_priority_order")]
+    pub order_by: Vec<PriorityKey>,
+}
+
+impl Default for PriorityConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            order_by: default_priority_order(),
+        }
+    }
+}
+
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum PriorityKey {
+    /// AVAILABILITY first, then ERROR, PERFORMANCE, RESOURCE_CONTENTION, CUSTOM_ALERT, MONITORING_UNAVAILABLE
+    Severity,
+    /// APPLICATION first, then SERVICES, INFRASTRUCTURE, ENVIRONMENT
+    Impact,
+    /// Open problems before closed or resolved ones
+    Status,
+    /// Earliest start time first
+    Oldest,
+    /// Latest start time first
+    Newest,
+}
+
+fn default_priority_order() -> Vec<PriorityKey> {
+    vec![PriorityKey::Severity, PriorityKey::Impact, PriorityKey::Oldest]
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct OutboundConfig {
+    /// Connector requests in flight at once, across all connectors (default: unlimited)
+    pub max_concurrent_requests: Option<usize>,
+    /// Connector requests started per second, across all connectors (default: unlimited)
+    pub max_requests_per_second: Option<f64>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct FlapSuppressionConfig {
+    /// Withhold a status change when the same status of the problem was
+    /// forwarded less than `window_minutes` ago
+    #[serde(default)]
+    pub enabled: bool,
+    /// Also how long a withheld status must hold before it is forwarded
+    #[serde(default = "default_flap_window_minutes")]
+    pub window_minutes: u64,
+}
+
+impl Default for FlapSuppressionConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            window_minutes: default_flap_window_minutes(),
+        }
+    }
+}
+
+fn default_flap_window_minutes() -> u64 {
+    10
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct MaintenanceWindowsConfig {
+    /// Skip problems whose affected entities are inside an active Dynatrace
+    /// maintenance window (needs the settings.read token scope)
+    #[serde(default)]
+    pub enabled: bool,
+    /// How often the maintenance window list is refreshed
+    #[serde(default = "default_maintenance_refresh_seconds")]
+    pub refresh_seconds: u64,
+}
+
+impl Default for MaintenanceWindowsConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            refresh_seconds: default_maintenance_refresh_seconds(),
+        }
+    }
+}
+
+fn default_maintenance_refresh_seconds() -> u64 {
+    300
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct EntityEnrichmentConfig {
+    /// Add monitored entity properties and tags to the affected and impacted
+    /// entities of forwarded problems (needs the entities.read token scope)
+    #[serde(default)]
+    pub enabled: bool,
+    /// How long fetched entities are reused from the local cache
+    #[serde(default = "default_entity_cache_ttl_minutes")]
+    pub cache_ttl_minutes: u64,
+    /// Only keep these entity properties, e.g. `ipAddress` (default: all)
+    #[serde(default)]
+    pub properties: Vec<String>,
+}
+
+impl Default for EntityEnrichmentConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            cache_ttl_minutes: default_entity_cache_ttl_minutes(),
+            properties: Vec::new(),
+        }
+    }
+}
+
+fn default_entity_cache_ttl_minutes() -> u64 {
+    60
+}
+
+/// Dynatrace events (Events API v2) forwarded alongside problems
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct EventsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Events API selector, e.g. `eventType("CUSTOM_DEPLOYMENT","CUSTOM_INFO")`
+    /// (default: all events)
+    pub event_selector: Option<String>,
+    /// Seconds between event polls (default: `polling.interval_seconds`)
+    pub interval_seconds: Option<u64>,
+    /// Timeframe start of each poll, e.g. `now-10m` (default: the API's `now-2h`)
+    pub from: Option<String>,
+}
+
+/// Dynatrace security problems (Security Problems API v2) forwarded alongside problems
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct SecurityProblemsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Security problems API selector, e.g. `riskLevel("CRITICAL","HIGH")`
+    /// (default: all security problems)
+    pub selector: Option<String>,
+    /// Seconds between security problem polls (default: `polling.interval_seconds`)
+    pub interval_seconds: Option<u64>,
+}
+
+/// Dynatrace audit log entries (Audit Logs API v2) forwarded alongside problems
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct AuditLogsConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Audit logs API filter, e.g. `category("CONFIG","TOKEN")` (default: all entries)
+    pub filter: Option<String>,
+    /// Seconds between audit log polls (default: `polling.interval_seconds`)
+    pub interval_seconds: Option<u64>,
+    /// Timeframe start of the first poll, e.g. `now-1d` (default: `now-2h`); later
+    /// polls continue after the last forwarded entry
+    pub initial_from: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct PushConfig {
+    /// Also receive problem notifications while `dtpf run` polls (`dtpf serve` always does)
+    #[serde(default)]
+    pub enabled: bool,
+    /// Address the receiver listens on
+    #[serde(default = "default_push_listen")]
+    pub listen: String,
+    /// Path Dynatrace posts to; further environments use `<path>/<environment>`
+    #[serde(default = "default_push_path")]
+    pub path: String,
+    /// Bearer token Dynatrace must send, e.g. `${DTPF_PUSH_TOKEN}`
+    pub auth_token: Option<String>,
+}
+
+impl Default for PushConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            listen: default_push_listen(),
+            path: default_push_path(),
+            auth_token: None,
+        }
+    }
+}
+
+fn default_push_listen() -> String {
+    "0.0.0.0:8470".to_string()
+}
+
+fn default_push_path() -> String {
+    "/dynatrace/problems".to_string()
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct CycleReportConfig {
+    #[serde(default)]
+    pub enabled: bool,
+    /// Directory receiving one JSON report file per poll cycle
+    #[serde(default = "default_cycle_report_directory")]
+    pub directory: PathBuf,
+}
+
+impl Default for CycleReportConfig {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            directory: default_cycle_report_directory(),
+        }
+    }
+}
+
+fn default_cycle_report_directory() -> PathBuf {
+    PathBuf::from("./reports")
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct RetryConfig {
+    /// Upper bound for the exponential delay between two attempts
+    #[serde(default = "default_retry_max_delay")]
+    pub max_delay_seconds: u64,
+    /// Stop retrying a delivery once this much time has passed since its first attempt
+    #[serde(default = "default_retry_max_elapsed")]
+    pub max_elapsed_seconds: u64,
+}
+
+impl Default for RetryConfig {
+    fn default() -> Self {
+        Self {
+            max_delay_seconds: default_retry_max_delay(),
+            max_elapsed_seconds: default_retry_max_elapsed(),
+        }
+    }
+}
+
+fn default_retry_max_delay() -> u64 {
+    60
+}
+
+fn default_retry_max_elapsed() -> u64 {
+    300
+}
+
+fn default_health_window() -> usize {
+    crate::forwarder::health::DEFAULT_HEALTH_WINDOW
+}
+
+/// Attach `url` to problems matching every criterion that is set
+#[derive(Debug, Deserialize, Clone)]
+pub struct RunbookRule {
+    pub url: String,
+    /// Link text for chat messages
+    pub name: Option<String>,
+    /// Entity tag as `key` or `key:value`
+    pub tag: Option<String>,
+    pub management_zone: Option<String>,
+    /// Regular expression matched against the problem title
+    pub title_regex: Option<String>,
+}
+
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct RoutingConfig {
+    /// Evaluated in order; every matching rule adds its connectors until one with `stop`
+    #[serde(default)]
+    pub rules: Vec<RoutingRule>,
+    /// Connectors for problems no rule matches (unrouted when empty)
+    #[serde(default)]
+    pub default: Vec<String>,
+}
+
+/// Send problems matching every criterion that is set to `connectors`
+#[derive(Debug, Deserialize, Clone, Default)]
+pub struct RoutingRule {
+    /// Shown in logs
+    pub name: Option<String>,
+    /// Any of these severity levels, e.g. `AVAILABILITY`
+    #[serde(default)]
+    pub severity: Vec<String>,
+    /// Any of these impact levels, e.g. `SERVICES`
+    #[serde(default)]
+    pub impact_level: Vec<String>,
+    /// Any of these management zone names or ids
+    #[serde(default)]
+    pub management_zones: Vec<String>,
+    /// Entity tag expression, as in a connector's `tag_filter`
+    pub tag: Option<String>,
+    /// Regular expression matched against the problem title
+    pub title_regex: Option<String>,
+    /// Any of these root cause entity types, e.g. `PROCESS_GROUP_INSTANCE`
+    #[serde(default)]
+    pub root_cause_type: Vec<String>,
+    /// Regular expression matched against the root cause entity name
+    pub root_cause_name_regex: Option<String>,
+    /// Connector names
+    pub connectors: Vec<String>,
+    /// Do not evaluate later rules when this one matches
+    #[serde(default)]
+    pub stop: bool,
+}
+
+impl RoutingRule {
+    /// Rule name for messages, or its position
+    pub fn label(&self, index: usize) -> String {
+        self.name.clone().unwrap_or_else(|| format!("#{}", index + 1))
+    }
+}
+
+#[derive(Debug, Deserialize, Clone)]
+pub struct LoggingConfig {
+    #[serde(default = "default_log_level")]
+    pub level: String,
+    #[serde(default = "default_log_format")]
+    pub format: String,
+}
+
+impl Default for LoggingConfig {
+    fn default() -> Self {
+        Self {
+            level: "info".to_string(),
+            format: "pretty".to_string(),
+        }
+    }
+}
+
+fn default_log_level() -> String {
+    "info".to_string()
+}
+
+fn default_log_format() -> String {
+    "pretty".to_string()
+}
+
+/// Simple XML element names: a letter or `_`, then letters, digits, `_`, `-`, `.` or `:`
+fn is_xml_name(name: &str) -> bool {
+    let mut chars = name.chars();
+    chars.next().is_some_and(|c| c.is_ascii_alphabetic() || c == '_')
+        && chars.all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.' | ':'))
+        && !name.to_ascii_lowercase().starts_with("xml")
+}
+
+impl Settings {
+    /// Load settings from a YAML file
+    pub fn load(config_path: &PathBuf) -> Result<Self> {
+        // Check if config file exists
+        if !config_path.exists() {
+            return Err(ForwarderError::Config(format!(
+                "Configuration file not found: {}\n\nPlease create a config.yaml file or specify the path with --config.\nYou can use config.yaml.example as a template.",
+                config_path.display()
+            )));
+        }
+
+        let config_content = std::fs::read_to_string(config_path)
+            .map_err(|e| ForwarderError::Config(format!(
+                "Failed to read config file '{}': {}",
+                config_path.display(),
+                e
+            )))?;
+
+        let mut settings: Settings = serde_yaml::from_str(&config_content)?;
+
+        // Load API token from environment variable
+        settings.dynatrace.api_token = std::env::var("DYNATRACE_API_TOKEN")
+            .ok()
+            .or_else(|| {
+                // Try from .env file if not in environment
+                dotenv::dotenv().ok();
+                std::env::var("DYNATRACE_API_TOKEN").ok()
+            });
+
+        for environment in settings.dynatrace.environments.iter_mut() {
+            environment.api_token = std::env::var(&environment.token_env).ok().or_else(|| {
+                dotenv::dotenv().ok();
+                std::env::var(&environment.token_env).ok()
+            });
+        }
+
+        if let Some(token) = &mut settings.push.auth_token {
+            expand_env_placeholder(token);
+        }
+
+        if let Some(password) = settings.proxy.as_mut().and_then(|proxy| proxy.password.as_mut()) {
+            expand_env_placeholder(password);
+        }
+
+        if let Some(password) = settings.dynatrace.proxy.as_mut().and_then(|proxy| proxy.password.as_mut()) {
+            expand_env_placeholder(password);
+        }
+
+        if settings.dynatrace.proxy.is_none() {
+            settings.dynatrace.proxy = settings.proxy.clone();
+        }
+
+        // Replace environment variable placeholders in connector headers and credentials
+        for connector in settings.connectors.iter_mut() {
+            if let Some(headers) = &mut connector.headers {
+                for (_, value) in headers.iter_mut() {
+                    expand_env_placeholder(value);
+                }
+            }
+
+            if let Some(es) = &mut connector.elasticsearch {
+                for value in [&mut es.username, &mut es.password, &mut es.api_key].into_iter().flatten() {
+                    expand_env_placeholder(value);
+                }
+            }
+
+            if let Some(xm) = &mut connector.xmatters {
+                expand_env_placeholder(&mut xm.password);
+            }
+
+            if let Some(eh) = &mut connector.eventhubs {
+                for value in [&mut eh.sas_key, &mut eh.client_secret].into_iter().flatten() {
+                    expand_env_placeholder(value);
+                }
+            }
+
+            if let Some(exec) = &mut connector.exec {
+                for value in exec.env.values_mut() {
+                    expand_env_placeholder(value);
+                }
+            }
+
+            if let Some(signing) = &mut connector.signing {
+                expand_env_placeholder(&mut signing.secret);
+            }
+
+            match &mut connector.proxy {
+                Some(proxy) => {
+                    if let Some(password) = &mut proxy.password {
+                        expand_env_placeholder(password);
+                    }
+                }
+                None => connector.proxy = settings.proxy.clone(),
+            }
+        }
+
+        settings.validate()?;
+
+        Ok(settings)
+    }
+
+    /// Redis keeps tracked problems, app state and forward history only. Reject
+    /// features whose queues would be lost on every restart.
+    fn validate_redis_state(&self) -> Result<()> {
+        let unsupported = |feature: String| {
+            Err(ForwarderError::Validation(format!(
+                "{} is not supported with database.driver redis: its state is not kept in Redis",
+                feature
+            )))
+        };
+
+        if self.redelivery.enabled {
+            return unsupported("redelivery".to_string());
+        }
+        if self.flap_suppression.enabled {
+            return unsupported("flap_suppression".to_string());
+        }
+        if self.security_problems.enabled {
+            return unsupported("security_problems".to_string());
+        }
+        if self.anomaly_detection.enabled {
+            return unsupported("anomaly_detection".to_string());
+        }
+        for connector in &self.connectors {
+            if connector.recurrence_sampling.is_some() {
+                return unsupported(format!("Connector '{}' recurrence_sampling", connector.name));
+            }
+            if connector.digest.as_ref().is_some_and(|d| d.enabled) {
+                return unsupported(format!("Connector '{}' digest", connector.name));
+            }
+            if connector.active_hours.as_ref().is_some_and(|a| a.held != HeldProblems::Drop) {
+                return unsupported(format!("Connector '{}' active_hours with held problems", connector.name));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Validate the configuration
+    fn validate(&self) -> Result<()> {
+        if self.instance_id.as_deref().is_some_and(|id| id.trim().is_empty()) {
+            return Err(ForwarderError::Validation(
+                "instance_id cannot be empty".to_string(),
+            ));
+        }
+
+        self.validate_dynatrace()?;
+        self.validate_network()?;
+        self.validate_polling()?;
+        self.validate_database()?;
+        self.validate_delivery()?;
+        self.validate_push()?;
+        self.validate_enrichment()?;
+        self.validate_routing()?;
+        self.validate_failover_groups()?;
+
+        for connector in &self.connectors {
+            connector.validate()?;
+        }
+
+        Ok(())
+    }
+
+    /// Dynatrace connection, problem fields and environments
+    fn validate_dynatrace(&self) -> Result<()> {
+        if self.dynatrace.base_url.is_empty() {
+            return Err(ForwarderError::Validation(
+                "Dynatrace base_url cannot be empty".to_string(),
+            ));
+        }
+
+        if self.dynatrace.tenant.is_empty() {
+            return Err(ForwarderError::Validation(
+                "Dynatrace tenant cannot be empty".to_string(),
             ));
         }
 
@@ -165,56 +2740,692 @@ impl Settings {
             ));
         }
 
-        // Validate polling config
+        if self.dynatrace.retry_attempts == 0 {
+            return Err(ForwarderError::Validation(
+                "dynatrace.retry_attempts must be at least 1".to_string(),
+            ));
+        }
+
+        if self.dynatrace.page_size.is_some_and(|size| !(1..=500).contains(&size)) {
+            return Err(ForwarderError::Validation(
+                "dynatrace.page_size must be between 1 and 500".to_string(),
+            ));
+        }
+
+        if self.dynatrace.parallel_pages == 0 {
+            return Err(ForwarderError::Validation(
+                "dynatrace.parallel_pages must be at least 1".to_string(),
+            ));
+        }
+
+        if self.dynatrace.parallel_pages > 1 && self.polling.stream_pages {
+            return Err(ForwarderError::Validation(
+                "dynatrace.parallel_pages cannot be combined with polling.stream_pages".to_string(),
+            ));
+        }
+
+        if let Some(field) = self
+            .dynatrace
+            .problem_fields()
+            .into_iter()
+            .find(|field| !PROBLEM_FIELDS.contains(&field.as_str()))
+        {
+            return Err(ForwarderError::Validation(format!(
+                "Unknown Dynatrace problem field '{}' (supported: {})",
+                field,
+                PROBLEM_FIELDS.join(", ")
+            )));
+        }
+
+        let mut environment_names = HashSet::new();
+        for environment in self.dynatrace_environments() {
+            if environment.name.trim().is_empty() {
+                return Err(ForwarderError::Validation(
+                    "Dynatrace environment name cannot be empty".to_string(),
+                ));
+            }
+
+            if !environment_names.insert(environment.name.clone()) {
+                return Err(ForwarderError::Validation(format!(
+                    "Dynatrace environment name '{}' is used more than once",
+                    environment.name
+                )));
+            }
+
+            if environment.base_url.is_empty() || environment.tenant.is_empty() {
+                return Err(ForwarderError::Validation(format!(
+                    "Dynatrace environment '{}' needs base_url and tenant",
+                    environment.name
+                )));
+            }
+
+            if environment.api_token.is_none() {
+                return Err(ForwarderError::Validation(format!(
+                    "Dynatrace environment '{}': {} environment variable is required",
+                    environment.name, environment.token_env
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// TLS and proxy settings of Dynatrace and the connectors
+    fn validate_network(&self) -> Result<()> {
+        let tls_configs = self
+            .dynatrace
+            .tls
+            .iter()
+            .chain(self.connectors.iter().filter_map(|connector| connector.tls.as_ref()));
+        for tls in tls_configs {
+            if let Err(ForwarderError::Config(message)) = tls.apply(reqwest::Client::builder()) {
+                return Err(ForwarderError::Validation(message));
+            }
+        }
+
+        let proxies = self
+            .proxy
+            .iter()
+            .chain(&self.dynatrace.proxy)
+            .chain(self.connectors.iter().filter_map(|connector| connector.proxy.as_ref()));
+        for proxy in proxies {
+            if let Err(ForwarderError::Config(message)) = proxy.to_proxy() {
+                return Err(ForwarderError::Validation(message));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Polling and the per-cycle features: volume anomalies and cycle reports
+    fn validate_polling(&self) -> Result<()> {
         if self.polling.interval_seconds == 0 {
             return Err(ForwarderError::Validation(
                 "polling.interval_seconds must be greater than 0".to_string(),
             ));
         }
 
-        // Validate connectors
-        if self.connectors.is_empty() {
+        if self.polling.max_problem_age_hours == Some(0) {
             return Err(ForwarderError::Validation(
-                "At least one connector must be configured".to_string(),
+                "polling.max_problem_age_hours must be greater than 0".to_string(),
             ));
         }
 
-        for connector in &self.connectors {
-            if connector.name.is_empty() {
+        if self.anomaly_detection.enabled
+            && (self.anomaly_detection.baseline_cycles == 0 || self.anomaly_detection.threshold_multiplier <= 1.0)
+        {
+            return Err(ForwarderError::Validation(
+                "anomaly_detection.baseline_cycles must be > 0 and threshold_multiplier must be > 1".to_string(),
+            ));
+        }
+
+        if self.cycle_reports.enabled && self.cycle_reports.directory.as_os_str().is_empty() {
+            return Err(ForwarderError::Validation(
+                "cycle_reports.directory cannot be empty".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Storage driver, path and encryption
+    fn validate_database(&self) -> Result<()> {
+        if let Some(encryption) = &self.database.encryption {
+            if self.database.driver != DatabaseDriver::Sqlite {
                 return Err(ForwarderError::Validation(
-                    "Connector name cannot be empty".to_string(),
+                    "database.encryption only applies to the sqlite driver".to_string(),
                 ));
             }
-
-            if connector.url.is_empty() {
+            if encryption.key_env.is_some() == encryption.key_file.is_some() {
                 return Err(ForwarderError::Validation(
-                    format!("Connector '{}' URL cannot be empty", connector.name),
+                    "database.encryption needs exactly one of key_env and key_file".to_string(),
                 ));
             }
+        }
+
+        if self.database.driver == DatabaseDriver::Redis && self.database.redis.is_none() {
+            return Err(ForwarderError::Validation(
+                "database.redis is required when database.driver is redis".to_string(),
+            ));
+        }
+
+        if self.database.driver == DatabaseDriver::Redis {
+            self.validate_redis_state()?;
+        }
+
+        if self.database.driver == DatabaseDriver::Sqlite && self.database.path.as_os_str().is_empty() {
+            return Err(ForwarderError::Validation(
+                "database.path is required unless database.driver is memory".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Redelivery, renotification, outbound limits, priority, flap suppression and retries
+    fn validate_delivery(&self) -> Result<()> {
+        if self.redelivery.enabled && self.redelivery.max_age_minutes == 0 {
+            return Err(ForwarderError::Validation(
+                "redelivery.max_age_minutes must be greater than 0".to_string(),
+            ));
+        }
 
-            if !connector.url.starts_with("http://") && !connector.url.starts_with("https://") {
+        if self.renotify.enabled {
+            if self.renotify.after_minutes == 0 || self.renotify.repeat_minutes == Some(0) {
                 return Err(ForwarderError::Validation(
-                    format!("Connector '{}' URL must start with http:// or https://", connector.name),
+                    "renotify.after_minutes and repeat_minutes must be greater than 0".to_string(),
                 ));
             }
+
+            if let Some(name) = self
+                .renotify
+                .connectors
+                .iter()
+                .find(|name| !self.connectors.iter().any(|c| &c.name == *name))
+            {
+                return Err(ForwarderError::Validation(format!(
+                    "renotify.connectors references unknown connector '{}'",
+                    name
+                )));
+            }
+        }
+
+        if self.outbound.max_concurrent_requests == Some(0)
+            || self.outbound.max_requests_per_second.is_some_and(|rate| rate <= 0.0 || !rate.is_finite())
+        {
+            return Err(ForwarderError::Validation(
+                "outbound.max_concurrent_requests and max_requests_per_second must be greater than 0".to_string(),
+            ));
+        }
+
+        if self.priority.enabled && self.priority.order_by.is_empty() {
+            return Err(ForwarderError::Validation(
+                "priority.order_by must name at least one key".to_string(),
+            ));
+        }
+
+        if self.flap_suppression.enabled && self.flap_suppression.window_minutes == 0 {
+            return Err(ForwarderError::Validation(
+                "flap_suppression.window_minutes must be greater than 0".to_string(),
+            ));
+        }
+
+        if self.retry.max_delay_seconds == 0 || self.retry.max_elapsed_seconds == 0 {
+            return Err(ForwarderError::Validation(
+                "retry.max_delay_seconds and retry.max_elapsed_seconds must be greater than 0".to_string(),
+            ));
+        }
+
+        Ok(())
+    }
+
+    /// Push receiver address and path
+    fn validate_push(&self) -> Result<()> {
+        if self.push.listen.parse::<std::net::SocketAddr>().is_err() {
+            return Err(ForwarderError::Validation(format!(
+                "push.listen '{}' must be an address like 0.0.0.0:8470",
+                self.push.listen
+            )));
+        }
+
+        if !self.push.path.starts_with('/') || self.push.path.len() < 2 || self.push.path.ends_with('/') {
+            return Err(ForwarderError::Validation(format!(
+                "push.path '{}' must start with '/' and not end with one",
+                self.push.path
+            )));
+        }
+
+        Ok(())
+    }
+
+    /// Maintenance windows, correlation and runbook links
+    fn validate_enrichment(&self) -> Result<()> {
+        if self.maintenance_windows.enabled && self.maintenance_windows.refresh_seconds == 0 {
+            return Err(ForwarderError::Validation(
+                "maintenance_windows.refresh_seconds must be greater than 0".to_string(),
+            ));
+        }
+
+        if self.correlation.enabled && !self.correlation.by_root_cause && !self.correlation.by_management_zone {
+            return Err(ForwarderError::Validation(
+                "correlation needs by_root_cause or by_management_zone".to_string(),
+            ));
+        }
+
+        for rule in &self.runbooks {
+            if rule.tag.is_none() && rule.management_zone.is_none() && rule.title_regex.is_none() {
+                return Err(ForwarderError::Validation(format!(
+                    "Runbook '{}' needs at least one of tag, management_zone or title_regex",
+                    rule.url
+                )));
+            }
+
+            if let Some(pattern) = &rule.title_regex {
+                regex::Regex::new(pattern).map_err(|e| {
+                    ForwarderError::Validation(format!(
+                        "Runbook '{}' title_regex is invalid: {}",
+                        rule.url, e
+                    ))
+                })?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Connector list and routing rules
+    fn validate_routing(&self) -> Result<()> {
+        if self.connectors.is_empty() {
+            return Err(ForwarderError::Validation(
+                "At least one connector must be configured".to_string(),
+            ));
+        }
+
+        if let Some(routing) = &self.routing {
+            let known = |name: &String| self.connectors.iter().any(|connector| &connector.name == name);
+
+            for (index, rule) in routing.rules.iter().enumerate() {
+                if rule.connectors.is_empty() {
+                    return Err(ForwarderError::Validation(format!(
+                        "Routing rule '{}' needs at least one connector",
+                        rule.label(index)
+                    )));
+                }
+
+                if let Some(pattern) = &rule.title_regex {
+                    regex::Regex::new(pattern).map_err(|e| {
+                        ForwarderError::Validation(format!(
+                            "Routing rule '{}' title_regex is invalid: {}",
+                            rule.label(index), e
+                        ))
+                    })?;
+                }
+
+                if let Some(pattern) = &rule.root_cause_name_regex {
+                    regex::Regex::new(pattern).map_err(|e| {
+                        ForwarderError::Validation(format!(
+                            "Routing rule '{}' root_cause_name_regex is invalid: {}",
+                            rule.label(index), e
+                        ))
+                    })?;
+                }
+
+                if let Some(expression) = &rule.tag {
+                    crate::forwarder::tag_filter::TagFilter::parse(expression).map_err(|e| {
+                        ForwarderError::Validation(format!("Routing rule '{}' tag is invalid: {}", rule.label(index), e))
+                    })?;
+                }
+
+                if let Some(name) = rule.connectors.iter().find(|name| !known(name)) {
+                    return Err(ForwarderError::Validation(format!(
+                        "Routing rule '{}' references unknown connector '{}'",
+                        rule.label(index), name
+                    )));
+                }
+            }
+
+            if let Some(name) = routing.default.iter().find(|name| !known(name)) {
+                return Err(ForwarderError::Validation(format!(
+                    "routing.default references unknown connector '{}'",
+                    name
+                )));
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Connectors of a failover group must be interchangeable
+    fn validate_failover_groups(&self) -> Result<()> {
+        let mut failover_batch_modes: HashMap<&str, bool> = HashMap::new();
+        // Filters that decide which problems a connector receives
+        let mut failover_routing = HashMap::new();
+        for connector in &self.connectors {
+            let Some(group) = connector.failover_group.as_deref() else {
+                continue;
+            };
+
+            if connector.digest.as_ref().is_some_and(|d| d.enabled) {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}' cannot use digest mode inside failover group '{}'",
+                    connector.name, group
+                )));
+            }
+
+            if connector.shadow || connector.sample_rate.is_some() || connector.recurrence_sampling.is_some() {
+                return Err(ForwarderError::Validation(format!(
+                    "Connector '{}' cannot use shadow, sample_rate or recurrence_sampling inside failover group '{}'",
+                    connector.name, group
+                )));
+            }
+
+            if *failover_batch_modes.entry(group).or_insert(connector.batch_mode) != connector.batch_mode {
+                return Err(ForwarderError::Validation(format!(
+                    "All connectors in failover group '{}' must use the same batch_mode",
+                    group
+                )));
+            }
+
+            let routing = (
+                connector.management_zones.as_ref(),
+                connector.tag_filter.as_deref(),
+                connector.impact_levels.as_slice(),
+                connector.entity_types.as_slice(),
+                connector.forward_on.as_slice(),
+            );
+            if *failover_routing.entry(group).or_insert(routing) != routing {
+                return Err(ForwarderError::Validation(format!(
+                    "All connectors in failover group '{}' must use the same management_zones, tag_filter, impact_levels, entity_types and forward_on",
+                    group
+                )));
+            }
         }
 
         Ok(())
     }
 
+    /// Name recorded with forward history: `instance_id` or the hostname
+    pub fn instance_id(&self) -> String {
+        self.instance_id
+            .clone()
+            .unwrap_or_else(|| gethostname::gethostname().to_string_lossy().into_owned())
+    }
+
+    /// The main Dynatrace environment from the `dynatrace` section
+    pub fn primary_environment(&self) -> EnvironmentConfig {
+        EnvironmentConfig {
+            name: self.dynatrace.name.clone().unwrap_or_else(default_environment_name),
+            base_url: self.dynatrace.base_url.clone(),
+            tenant: self.dynatrace.tenant.clone(),
+            token_env: "DYNATRACE_API_TOKEN".to_string(),
+            problem_selector: self.dynatrace.problem_selector.clone(),
+            api_token: self.dynatrace.api_token.clone(),
+        }
+    }
+
+    /// Every polled environment, the main one first. Selectors default to the main one's.
+    pub fn dynatrace_environments(&self) -> Vec<EnvironmentConfig> {
+        let mut environments = vec![self.primary_environment()];
+        environments.extend(self.dynatrace.environments.iter().map(|environment| EnvironmentConfig {
+            problem_selector: environment
+                .problem_selector
+                .clone()
+                .or_else(|| self.dynatrace.problem_selector.clone()),
+            ..environment.clone()
+        }));
+        environments
+    }
+
+    /// Whether problems come from more than one environment and carry a `tenant`
+    pub fn is_multi_tenant(&self) -> bool {
+        !self.dynatrace.environments.is_empty()
+    }
+
+    /// API token scopes the enabled features need
+    pub fn required_token_scopes(&self) -> Vec<&'static str> {
+        let mut scopes = vec!["problems.read"];
+        if self.connectors.iter().any(|c| c.dynatrace_comment.is_some()) {
+            scopes.push("problems.write");
+        }
+        if self.entity_enrichment.enabled {
+            scopes.push("entities.read");
+        }
+        if self.maintenance_windows.enabled {
+            scopes.push("settings.read");
+        }
+        if self.events.enabled {
+            scopes.push("events.read");
+        }
+        if self.security_problems.enabled {
+            scopes.push("securityProblems.read");
+        }
+        if self.audit_logs.enabled {
+            scopes.push("auditLogs.read");
+        }
+        scopes
+    }
+
+    /// Get the Dynatrace UI link prefix for a problem (append the problem ID)
+    pub fn get_problem_url_base(&self) -> String {
+        self.primary_environment().problem_url_base()
+    }
+
     /// Get the full API URL for problems endpoint
     pub fn get_problems_url(&self) -> String {
-        let mut url = format!(
-            "{}/e/{}/api/v2/problems",
-            self.dynatrace.base_url.trim_end_matches('/'),
-            self.dynatrace.tenant
-        );
+        self.primary_environment().problems_url(&self.dynatrace)
+    }
+
+    /// Get the full API URL for the maintenance window settings objects
+    pub fn get_maintenance_windows_url(&self) -> String {
+        self.primary_environment().maintenance_windows_url()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
 
-        if let Some(selector) = &self.dynatrace.problem_selector {
-            url.push_str(&format!("?problemSelector={}", selector));
-            url.push_str("&sort=-startTime");
+    #[test]
+    fn test_startup_delay_within_splay() {
+        let polling = PollingConfig {
+            interval_seconds: 60,
+            batch_size: None,
+            initial_delay_seconds: 5,
+            splay_seconds: 10,
+            max_problem_age_hours: None,
+            stream_pages: false,
+        };
+
+        for _ in 0..100 {
+            let delay = polling.startup_delay();
+            assert!(delay >= std::time::Duration::from_secs(5));
+            assert!(delay <= std::time::Duration::from_secs(15));
         }
+    }
 
-        url
+    #[test]
+    fn test_problem_fields_merge_fetch_evidence() {
+        let dynatrace: DynatraceConfig = serde_yaml::from_str(
+            r#"
+base_url: https://dt.example.com/
+tenant: abc
+fields: "+impactAnalysis, +evidenceDetails,recentComments"
+fetch_evidence: true
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(
+            dynatrace.problem_fields(),
+            vec!["impactAnalysis", "evidenceDetails", "recentComments"]
+        );
+
+        let environment = EnvironmentConfig {
+            name: "default".to_string(),
+            base_url: dynatrace.base_url.clone(),
+            tenant: dynatrace.tenant.clone(),
+            token_env: String::new(),
+            problem_selector: None,
+            api_token: None,
+        };
+        assert_eq!(
+            environment.problems_url(&dynatrace),
+            "https://dt.example.com/e/abc/api/v2/problems?fields=%2BimpactAnalysis,%2BevidenceDetails,%2BrecentComments"
+        );
+    }
+
+    /// Settings with the redis driver and one connector, `connector` adding to
+    /// the connector and `extra` to the top level
+    fn redis_settings(connector: &str, extra: &str) -> Settings {
+        let yaml = format!(
+            r#"
+dynatrace:
+  base_url: "https://abc.live.dynatrace.com"
+  tenant: "abc"
+polling:
+  interval_seconds: 60
+database:
+  driver: redis
+  redis:
+    url: "redis://localhost:6379/0"
+connectors:
+  - name: hook
+    url: https://hooks.example.com
+{}
+{}
+"#,
+            connector, extra
+        );
+        let mut settings: Settings = serde_yaml::from_str(&yaml).unwrap();
+        settings.dynatrace.api_token = Some("test-token".to_string());
+        settings
+    }
+
+    #[test]
+    fn test_redis_driver_rejects_unpersisted_state() {
+        assert!(redis_settings("", "").validate().is_ok());
+        let error = redis_settings("", "redelivery:\n  enabled: true").validate().unwrap_err();
+        assert!(error.to_string().contains("redelivery is not supported with database.driver redis"));
+        assert!(redis_settings("", "flap_suppression:\n  enabled: true").validate().is_err());
+    }
+
+    #[test]
+    fn test_redis_driver_rejects_security_problems() {
+        let error = redis_settings("", "security_problems:\n  enabled: true").validate().unwrap_err();
+        assert!(error.to_string().contains("security_problems is not supported with database.driver redis"));
+    }
+
+    #[test]
+    fn test_redis_driver_rejects_anomaly_detection() {
+        let error = redis_settings("", "anomaly_detection:\n  enabled: true").validate().unwrap_err();
+        assert!(error.to_string().contains("anomaly_detection is not supported with database.driver redis"));
+    }
+
+    #[test]
+    fn test_redis_driver_rejects_recurrence_sampling() {
+        let error = redis_settings("    recurrence_sampling:\n      every: 5", "").validate().unwrap_err();
+        assert!(error
+            .to_string()
+            .contains("Connector 'hook' recurrence_sampling is not supported with database.driver redis"));
+    }
+
+    #[test]
+    fn test_connector_validates_alone() {
+        let mut connector: ConnectorConfig =
+            serde_yaml::from_str("name: hook\nurl: https://hooks.example.com\ngzip:\n  level: 12").unwrap();
+        assert!(connector.validate_payload().is_ok());
+        let error = connector.validate_transport().unwrap_err();
+        assert!(error.to_string().contains("Connector 'hook' gzip.level must be between 1 and 9"));
+
+        connector.gzip = None;
+        connector.url = "hooks.example.com".to_string();
+        assert!(connector.validate_transport().is_ok());
+        let error = connector.validate().unwrap_err();
+        assert!(error.to_string().contains("URL must start with http:// or https://"));
+    }
+
+    #[test]
+    fn test_sections_validate_alone() {
+        let mut settings = redis_settings("", "push:\n  path: /");
+        let error = settings.validate_push().unwrap_err();
+        assert!(error.to_string().contains("push.path '/'"));
+        assert!(settings.validate_delivery().is_ok());
+
+        settings.flap_suppression.enabled = true;
+        settings.flap_suppression.window_minutes = 0;
+        let error = settings.validate_delivery().unwrap_err();
+        assert!(error.to_string().contains("flap_suppression.window_minutes"));
+        // The redis driver rejects flap suppression on its own
+        assert!(settings.validate_database().unwrap_err().to_string().contains("flap_suppression is not supported"));
+    }
+
+    #[test]
+    fn test_delivery_policy_severity_overrides() {
+        let connector: ConnectorConfig = serde_yaml::from_str(
+            r#"
+name: pager
+url: https://pager.example.com
+retry_attempts: 3
+timeout_seconds: 30
+severity_overrides:
+  AVAILABILITY:
+    retry_attempts: 10
+    timeout_seconds: 60
+  CUSTOM_ALERT:
+    retry_attempts: 2
+"#,
+        )
+        .unwrap();
+
+        assert_eq!(connector.delivery_policy(["AVAILABILITY"]), (10, 60));
+        assert_eq!(connector.delivery_policy(["CUSTOM_ALERT"]), (2, 30));
+        assert_eq!(connector.delivery_policy(["PERFORMANCE"]), (3, 30));
+        assert_eq!(connector.delivery_policy(["CUSTOM_ALERT", "AVAILABILITY"]), (10, 60));
+        // Severities without an override keep the connector's own policy
+        assert_eq!(connector.delivery_policy(["PERFORMANCE", "CUSTOM_ALERT"]), (3, 30));
+    }
+
+    #[test]
+    fn test_sample_rate_is_stable_per_problem() {
+        let connector: ConnectorConfig =
+            serde_yaml::from_str("name: canary\nurl: https://canary.example.com\nsample_rate: 0.25").unwrap();
+
+        let ids: Vec<String> = (0..2000).map(|i| format!("{}_{}V2", i * 7919, i)).collect();
+        let sampled = ids.iter().filter(|id| connector.samples(id)).count();
+
+        assert!((350..650).contains(&sampled), "sampled {} of 2000", sampled);
+
+        let unsampled = ConnectorConfig { sample_rate: None, ..connector };
+        assert!(ids.iter().all(|id| unsampled.samples(id)));
+    }
+
+    #[test]
+    fn test_forward_on_transitions() {
+        let connector: ConnectorConfig =
+            serde_yaml::from_str("name: tickets\nurl: https://tickets.example.com\nforward_on: [new, closed]").unwrap();
+        let mut problem = crate::dynatrace::ProblemFixture::load(std::path::Path::new(
+            "tests/fixtures/problems/open_problem.json",
+        ))
+        .unwrap()
+        .remove(0);
+
+        problem.change = Some(ProblemChange::New);
+        assert!(connector.forwards(&problem));
+
+        problem.change = Some(ProblemChange::StatusChange);
+        problem.status = ProblemStatus::Resolved;
+        assert!(!connector.forwards(&problem));
+        problem.status = ProblemStatus::Closed;
+        assert!(connector.forwards(&problem));
+    }
+
+    #[test]
+    fn test_active_hours_windows() {
+        let at = |day: u32, hour: u32| Tz::UTC.with_ymd_and_hms(2026, 2, day, hour, 30, 0).unwrap();
+        let office: ActiveHoursConfig =
+            serde_yaml::from_str("{days: [Mon, Tue, Wed, Thu, Fri], start: '08:00', end: '20:00'}").unwrap();
+        let night: ActiveHoursConfig = serde_yaml::from_str("{days: [Fri], start: '22:00', end: '06:00'}").unwrap();
+
+        // 2026-02-06 is a Friday
+        assert!(office.is_active(&at(6, 8)));
+        assert!(!office.is_active(&at(6, 20)));
+        assert!(!office.is_active(&at(7, 12)));
+        assert!(night.is_active(&at(6, 23)));
+        assert!(night.is_active(&at(7, 5)));
+        assert!(!night.is_active(&at(7, 23)));
+    }
+
+    #[test]
+    fn test_zone_filter_include_and_exclude() {
+        let filter = ZoneFilterConfig {
+            include: vec!["payments".to_string(), "checkout".to_string()],
+            exclude: vec!["staging".to_string()],
+        };
+
+        assert!(filter.matches(&["payments", "-1234"]));
+        assert!(!filter.matches(&["payments", "staging"]));
+        assert!(!filter.matches(&["search"]));
+        assert!(!filter.matches(&[]));
+        assert!(ZoneFilterConfig::default().matches(&[]));
     }
 }
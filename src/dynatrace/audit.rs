@@ -0,0 +1,161 @@
+use serde::{Deserialize, Serialize};
+use crate::dynatrace::models::{Problem, ProblemStatus};
+
+/// One page of `GET /api/v2/auditlogs`
+#[derive(Debug, Deserialize)]
+pub struct AuditLogResponse {
+    #[serde(rename = "totalCount", default)]
+    pub total_count: i64,
+    #[serde(default)]
+    pub logs: Vec<AuditLogEntry>,
+    #[serde(rename = "nextPageKey")]
+    pub next_page_key: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditLogEntry {
+    pub log_id: String,
+    /// e.g. `CREATE`, `UPDATE`, `DELETE`, `LOGIN`
+    pub event_type: String,
+    /// e.g. `CONFIG`, `TOKEN`, `WEB_UI`
+    pub category: String,
+    pub entity_id: Option<String>,
+    pub user: Option<String>,
+    pub user_type: Option<String>,
+    pub user_origin: Option<String>,
+    pub timestamp: i64,
+    #[serde(default = "default_success")]
+    pub success: bool,
+    pub message: Option<String>,
+    /// JSON patch of a configuration change
+    pub patch: Option<serde_json::Value>,
+}
+
+fn default_success() -> bool {
+    true
+}
+
+/// What a forwarded audit log entry adds to its problem-shaped payload
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditInfo {
+    pub event_type: String,
+    pub category: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entity_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub user_origin: Option<String>,
+    pub success: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub patch: Option<serde_json::Value>,
+}
+
+impl AuditLogEntry {
+    /// The entry in the shape connectors forward: the log ID as problem ID,
+    /// `AUDIT_<category>` as display ID, severity `AUDIT` and status `CLOSED`,
+    /// with the user, event type and patch under `audit`
+    pub fn into_problem(self) -> Problem {
+        let title = self.message.clone().unwrap_or_else(|| match &self.entity_id {
+            Some(entity_id) => format!("{} {} {}", self.event_type, self.category, entity_id),
+            None => format!("{} {}", self.event_type, self.category),
+        });
+
+        Problem {
+            problem_id: self.log_id,
+            display_id: format!("AUDIT_{}", self.category),
+            title,
+            impact_level: "ENVIRONMENT".to_string(),
+            severity_level: "AUDIT".to_string(),
+            status: ProblemStatus::Closed,
+            affected_entities: vec![],
+            impacted_entities: vec![],
+            root_cause_entity: None,
+            management_zones: vec![],
+            entity_tags: vec![],
+            problem_filters: vec![],
+            start_time: self.timestamp,
+            end_time: self.timestamp,
+            evidence_details: None,
+            recent_comments: None,
+            impact_analysis: None,
+            event: None,
+            security: None,
+            audit: Some(AuditInfo {
+                event_type: self.event_type,
+                category: self.category,
+                entity_id: self.entity_id,
+                user: self.user,
+                user_type: self.user_type,
+                user_origin: self.user_origin,
+                success: self.success,
+                patch: self.patch,
+            }),
+            runbooks: vec![],
+            correlated_problems: vec![],
+            recurrence: None,
+            tenant: None,
+            tenant_url_base: None,
+            change: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_audit_log_entry_into_problem() {
+        let page: AuditLogResponse = serde_json::from_str(
+            r#"{
+                "totalCount": 2,
+                "pageSize": 1000,
+                "logs": [
+                    {
+                        "logId": "164812591400010000",
+                        "eventType": "UPDATE",
+                        "category": "CONFIG",
+                        "entityId": "ALERTING_PROFILE: 0f6bd7a5-8c7f-4c35-a1e0-9f6b1f5b2e11",
+                        "environmentId": "abc12345",
+                        "user": "jane.doe@example.com",
+                        "userType": "USER_NAME",
+                        "userOrigin": "webui (10.1.2.3)",
+                        "timestamp": 1648125914000,
+                        "success": true,
+                        "patch": [{"op": "replace", "path": "/name", "value": "Production", "oldValue": "Prod"}]
+                    },
+                    {
+                        "logId": "164812592100020000",
+                        "eventType": "LOGIN",
+                        "category": "WEB_UI",
+                        "user": "john.roe@example.com",
+                        "timestamp": 1648125921000,
+                        "success": false,
+                        "message": "Login failed"
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let mut logs = page.logs.into_iter();
+
+        let problem = logs.next().unwrap().into_problem();
+        assert_eq!(problem.problem_id, "164812591400010000");
+        assert_eq!(problem.display_id, "AUDIT_CONFIG");
+        assert_eq!(problem.title, "UPDATE CONFIG ALERTING_PROFILE: 0f6bd7a5-8c7f-4c35-a1e0-9f6b1f5b2e11");
+        assert_eq!(problem.start_time, 1648125914000);
+        let audit = serde_json::to_value(&problem).unwrap()["audit"].clone();
+        assert_eq!(audit["user"], "jane.doe@example.com");
+        assert_eq!(audit["patch"][0]["oldValue"], "Prod");
+
+        let problem = logs.next().unwrap().into_problem();
+        assert_eq!(problem.title, "Login failed");
+        assert!(!problem.audit.unwrap().success);
+    }
+}
@@ -1,102 +1,348 @@
-use reqwest::{Client, header};
-use crate::config::Settings;
-use crate::dynatrace::models::ProblemsResponse;
+use reqwest::header::HeaderMap;
+use reqwest::{Client, Method, RequestBuilder, Response, StatusCode, header};
+use crate::config::{EnvironmentConfig, Settings};
+use crate::dynatrace::audit::{AuditLogEntry, AuditLogResponse};
+use crate::dynatrace::events::{Event, EventsResponse};
+use crate::dynatrace::maintenance::{MaintenanceWindow, MaintenanceWindowPage};
+use crate::dynatrace::models::{ApiTokenMetadata, EntitiesResponse, EntityDetails, Problem, ProblemsResponse};
+use crate::dynatrace::security::{SecurityProblem, SecurityProblemsResponse};
 use crate::error::{ForwarderError, Result};
+use crate::forwarder::retry::{retry_with_backoff, RetryPolicy};
+use serde::Serialize;
+use std::sync::Mutex;
+use std::time::Duration;
 use tracing::{debug, info, warn};
 
+/// Properties the problem details endpoint only returns when asked for
+const PROBLEM_DETAIL_FIELDS: &str = "%2BevidenceDetails,%2BimpactAnalysis,%2BrecentComments";
+
+/// Entity IDs per entity selector, keeping request URLs well under common limits
+const ENTITIES_PER_REQUEST: usize = 50;
+
+/// Timeframe start the problems API uses when `from` is not given
+const DEFAULT_FROM: &str = "now-2h";
+
+/// Wait after a 429 response that names no time to retry at
+const DEFAULT_RATE_LIMIT_WAIT: Duration = Duration::from_secs(5);
+
+/// Response headers Dynatrace uses to identify a request for support cases
+const RESPONSE_REQUEST_ID_HEADERS: [&str; 2] = ["x-dt-request-id", "x-request-id"];
+
 pub struct DynatraceClient {
     client: Client,
+    environment: String,
     api_token: String,
     problems_url: String,
+    maintenance_windows_url: String,
+    entities_url: String,
+    events_url: String,
+    security_problems_url: String,
+    audit_logs_url: String,
+    token_lookup_url: String,
+    send_request_id: bool,
+    rate_limit_retries: u32,
+    rate_limit_max_wait: Duration,
+    quota: Mutex<ApiQuota>,
+    parallel_pages: usize,
+    /// Retries of problem list page requests
+    retry_policy: RetryPolicy,
+}
+
+/// Request quota reported by the Dynatrace API's rate-limit headers
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ApiQuota {
+    /// `X-RateLimit-Limit` of the latest response
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub limit: Option<u64>,
+    /// `X-RateLimit-Remaining` of the latest response
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub remaining: Option<u64>,
+    /// 429 responses received since the quota was last taken
+    pub rate_limited: u64,
 }
 
 impl DynatraceClient {
-    /// Create a new Dynatrace client
+    /// Create a new Dynatrace client for the main environment
     pub fn new(settings: &Settings) -> Result<Self> {
-        let api_token = settings
-            .dynatrace
+        Self::for_environment(settings, &settings.primary_environment())
+    }
+
+    /// Create a client for one of the configured environments
+    pub fn for_environment(settings: &Settings, environment: &EnvironmentConfig) -> Result<Self> {
+        let api_token = environment
             .api_token
             .clone()
-            .ok_or_else(|| ForwarderError::Config("Missing DYNATRACE_API_TOKEN".to_string()))?;
+            .ok_or_else(|| ForwarderError::Config(format!("Missing {}", environment.token_env)))?;
+
+        let user_agent = settings
+            .dynatrace
+            .user_agent
+            .clone()
+            .unwrap_or_else(default_user_agent);
 
-        let client = Client::builder()
+        let mut builder = Client::builder()
             .timeout(std::time::Duration::from_secs(30))
-            .build()?;
+            .user_agent(user_agent);
+
+        if let Some(proxy) = &settings.dynatrace.proxy {
+            builder = builder.proxy(proxy.to_proxy()?);
+        }
 
-        let problems_url = settings.get_problems_url();
+        if let Some(tls) = &settings.dynatrace.tls {
+            builder = tls.apply(builder)?;
+        }
+
+        let client = builder.build()?;
+
+        let problems_url = environment.problems_url(&settings.dynatrace);
 
         Ok(Self {
             client,
+            environment: environment.name.clone(),
             api_token,
             problems_url,
+            maintenance_windows_url: environment.maintenance_windows_url(),
+            entities_url: environment.entities_url(),
+            events_url: environment.events_url(),
+            security_problems_url: environment.security_problems_url(),
+            audit_logs_url: environment.audit_logs_url(),
+            token_lookup_url: environment.token_lookup_url(),
+            send_request_id: settings.dynatrace.send_request_id,
+            rate_limit_retries: settings.dynatrace.rate_limit_retries,
+            rate_limit_max_wait: Duration::from_secs(settings.dynatrace.rate_limit_max_wait_seconds),
+            quota: Mutex::new(ApiQuota::default()),
+            parallel_pages: settings.dynatrace.parallel_pages,
+            // Retrying past the next poll would only delay it
+            retry_policy: RetryPolicy::new(
+                settings.dynatrace.retry_attempts,
+                Duration::from_secs(settings.dynatrace.retry_max_delay_seconds),
+                Duration::from_secs(settings.polling.interval_seconds),
+            ),
         })
     }
 
-    /// Fetch problems from Dynatrace API (handles pagination automatically)
-    #[allow(unused_assignments)]
-    pub async fn fetch_problems(&self) -> Result<ProblemsResponse> {
-        debug!("Fetching problems from: {}", self.problems_url);
+    /// Build an authenticated GET request, tagged with a fresh X-Request-Id if enabled
+    fn get(&self, url: &str) -> RequestBuilder {
+        self.request(Method::GET, url)
+    }
 
-        let mut all_problems = Vec::new();
-        let mut next_page_key: Option<String> = None;
-        let mut page_num = 1;
-        // Initialize to 0, will be set from API response on first iteration
-        let mut total_count = 0;
+    /// Build an authenticated request, tagged with a fresh X-Request-Id if enabled
+    fn request(&self, method: Method, url: &str) -> RequestBuilder {
+        let mut request = self
+            .client
+            .request(method, url)
+            .header(header::AUTHORIZATION, format!("Api-Token {}", self.api_token))
+            .header(header::ACCEPT, "application/json");
 
+        if self.send_request_id {
+            let request_id = uuid::Uuid::new_v4().to_string();
+            debug!("Dynatrace request X-Request-Id: {}", request_id);
+            request = request.header("X-Request-Id", request_id);
+        }
+
+        request
+    }
+
+    /// Send a request, waiting out 429 responses for as long as Dynatrace asks
+    /// (`Retry-After` or `X-RateLimit-Reset`) up to `rate_limit_retries` times.
+    /// A wait longer than `rate_limit_max_wait_seconds` returns the 429 response.
+    async fn send(&self, request: RequestBuilder) -> Result<Response> {
+        let mut retries = 0;
         loop {
-            // Build URL with pagination key if available
-            let url = if let Some(ref page_key) = next_page_key {
-                format!("{}&nextPageKey={}", self.problems_url, page_key)
-            } else {
-                self.problems_url.clone()
+            let Some(attempt) = request.try_clone() else {
+                return Ok(request.send().await?);
             };
 
-            debug!("Fetching page {} from Dynatrace...", page_num);
+            let response = attempt.send().await?;
+            self.record_quota(response.headers());
+            if response.status() != StatusCode::TOO_MANY_REQUESTS {
+                return Ok(response);
+            }
 
-            let response = self
-                .client
-                .get(&url)
-                .header(header::AUTHORIZATION, format!("Api-Token {}", self.api_token))
-                .header(header::ACCEPT, "application/json")
-                .send()
-                .await?;
+            self.quota.lock().unwrap_or_else(|e| e.into_inner()).rate_limited += 1;
+            let wait = rate_limit_wait(response.headers(), chrono::Utc::now().timestamp_millis())
+                .unwrap_or(DEFAULT_RATE_LIMIT_WAIT);
+            if retries >= self.rate_limit_retries || wait > self.rate_limit_max_wait {
+                warn!(
+                    "Dynatrace API rate limit of '{}' reached, giving up after {} retries (reset in {}s)",
+                    self.environment,
+                    retries,
+                    wait.as_secs()
+                );
+                return Ok(response);
+            }
 
-            let status = response.status();
+            retries += 1;
+            warn!(
+                "Dynatrace API rate limit of '{}' reached, retrying in {}s ({}/{})",
+                self.environment,
+                wait.as_secs_f64(),
+                retries,
+                self.rate_limit_retries
+            );
+            tokio::time::sleep(wait).await;
+        }
+    }
 
-            if !status.is_success() {
-                let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-                warn!("Dynatrace API returned error ({}): {}", status, error_text);
-                return Err(ForwarderError::Config(format!(
-                    "Dynatrace API error ({}): {}",
-                    status, error_text
-                )));
-            }
+    /// Remember the quota headers of a response
+    fn record_quota(&self, headers: &HeaderMap) {
+        let limit = header_number(headers, "x-ratelimit-limit");
+        let remaining = header_number(headers, "x-ratelimit-remaining");
+        if limit.is_none() && remaining.is_none() {
+            return;
+        }
 
-            let mut problems_response = response.json::<ProblemsResponse>().await?;
+        debug!(
+            "Dynatrace API quota of '{}': {} of {} requests remaining",
+            self.environment,
+            remaining.map_or("?".to_string(), |n| n.to_string()),
+            limit.map_or("?".to_string(), |n| n.to_string())
+        );
 
-            debug!(
-                "Fetched page {} with {} problems (page size: {})",
-                page_num,
-                problems_response.problems.len(),
-                problems_response.page_size
-            );
+        let mut quota = self.quota.lock().unwrap_or_else(|e| e.into_inner());
+        quota.limit = limit.or(quota.limit);
+        quota.remaining = remaining.or(quota.remaining);
+    }
+
+    /// The latest quota, resetting the count of rate-limited responses
+    pub fn take_quota(&self) -> ApiQuota {
+        let mut quota = self.quota.lock().unwrap_or_else(|e| e.into_inner());
+        let taken = quota.clone();
+        quota.rate_limited = 0;
+        taken
+    }
 
-            total_count = problems_response.total_count;
-            all_problems.append(&mut problems_response.problems);
+    /// Fetch problems from Dynatrace API (handles pagination automatically)
+    pub async fn fetch_problems(&self) -> Result<ProblemsResponse> {
+        self.fetch_problems_between(None, None).await
+    }
 
-            // Check if there are more pages
-            if problems_response.next_page_key.is_some() {
-                next_page_key = problems_response.next_page_key;
-                page_num += 1;
-            } else {
-                break;
+    /// Fetch the problems active in the `from`/`to` timeframe (epoch milliseconds
+    /// or relative times such as `now-2h`); the API default applies to an unset bound
+    pub async fn fetch_problems_between(&self, from: Option<&str>, to: Option<&str>) -> Result<ProblemsResponse> {
+        if self.parallel_pages > 1 {
+            let now = chrono::Utc::now().timestamp_millis();
+            match (resolve_time(from.unwrap_or(DEFAULT_FROM), now), resolve_time(to.unwrap_or("now"), now)) {
+                (Some(start), Some(end)) if start < end => return self.fetch_problems_parallel(start, end).await,
+                _ => debug!("Timeframe {:?} to {:?} cannot be split, fetching pages one by one", from, to),
             }
         }
 
+        let mut pages = self.problem_pages(from, to);
+        let mut all_problems = Vec::new();
+        while let Some(mut page) = pages.next_page().await? {
+            all_problems.append(&mut page);
+        }
+
         info!(
             "Fetched {} problems from Dynatrace across {} page(s) (total count: {})",
             all_problems.len(),
-            page_num,
+            pages.pages(),
+            pages.total_count()
+        );
+
+        Ok(ProblemsResponse {
+            total_count: pages.total_count(),
+            page_size: all_problems.len() as i32,
+            problems: all_problems,
+            next_page_key: None,
+        })
+    }
+
+    /// Fetch one page of a problem list. Transient failures (5xx responses,
+    /// timeouts, connection errors) are retried with backoff, so a hiccup of the
+    /// API doesn't fail the whole poll.
+    async fn fetch_problem_page(&self, url: &str) -> Result<ProblemsResponse> {
+        let (result, _) = retry_with_backoff(
+            &format!("fetch Dynatrace problems of {}", self.environment),
+            &self.retry_policy,
+            || {
+                Box::pin(async move {
+                    let response = self.send(self.get(url)).await?;
+
+                    let status = response.status();
+                    let dt_request_id = response_request_id(&response);
+
+                    if let Some(request_id) = &dt_request_id {
+                        debug!("Dynatrace response request id: {}", request_id);
+                    }
+
+                    if !status.is_success() {
+                        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+                        let request_id = dt_request_id
+                            .map(|id| format!(" [request id: {}]", id))
+                            .unwrap_or_default();
+                        warn!("Dynatrace API returned error ({}){}: {}", status, request_id, error_text);
+                        return Err(ForwarderError::from_status(
+                            "Dynatrace API",
+                            status,
+                            &format!("{}{}", error_text, request_id),
+                        ));
+                    }
+
+                    Ok(response.json::<ProblemsResponse>().await?)
+                })
+            },
+        )
+        .await;
+
+        result
+    }
+
+    /// Fetch a problem list of several pages as `parallel_pages` timeframe slices
+    /// at once. `nextPageKey` chains the pages of one query, so they cannot be
+    /// requested in parallel; the slices can. A problem active in several slices
+    /// is returned by each and kept once.
+    async fn fetch_problems_parallel(&self, start: i64, end: i64) -> Result<ProblemsResponse> {
+        let (start_param, end_param) = (start.to_string(), end.to_string());
+        let mut pages = self.problem_pages(Some(&start_param), Some(&end_param));
+        let first = pages.next_page().await?.unwrap_or_default();
+        let total_count = pages.total_count();
+
+        if !pages.has_next() {
+            return Ok(ProblemsResponse {
+                total_count,
+                page_size: first.len() as i32,
+                problems: first,
+                next_page_key: None,
+            });
+        }
+
+        let slices = timeframe_slices(start, end, self.parallel_pages);
+        debug!(
+            "Fetching {} problems of '{}' in {} timeframe slices",
+            total_count,
+            self.environment,
+            slices.len()
+        );
+
+        let responses = futures::future::join_all(slices.iter().map(|(slice_start, slice_end)| {
+            let (from, to) = (slice_start.to_string(), slice_end.to_string());
+            async move {
+                let mut pages = self.problem_pages(Some(&from), Some(&to));
+                let mut problems = Vec::new();
+                while let Some(mut page) = pages.next_page().await? {
+                    problems.append(&mut page);
+                }
+                Ok::<_, ForwarderError>((problems, pages.pages()))
+            }
+        }))
+        .await;
+
+        let mut seen = std::collections::HashSet::new();
+        let mut all_problems = Vec::new();
+        let mut page_count = 1;
+        for response in responses {
+            let (problems, pages) = response?;
+            page_count += pages;
+            all_problems.extend(problems.into_iter().filter(|problem| seen.insert(problem.problem_id.clone())));
+        }
+
+        info!(
+            "Fetched {} problems from Dynatrace across {} page(s) in {} slices (total count: {})",
+            all_problems.len(),
+            page_count,
+            slices.len(),
             total_count
         );
 
@@ -108,9 +354,291 @@ impl DynatraceClient {
         })
     }
 
+    /// Page through the problems active in the `from`/`to` timeframe, one
+    /// request per page, so each page can be processed before the next is fetched
+    pub fn problem_pages(&self, from: Option<&str>, to: Option<&str>) -> ProblemPages<'_> {
+        let timeframe: Vec<String> = [("from", from), ("to", to)]
+            .into_iter()
+            .filter_map(|(param, value)| value.map(|value| format!("{}={}", param, urlencoding::encode(value))))
+            .collect();
+        let problems_url = if timeframe.is_empty() {
+            self.problems_url.clone()
+        } else {
+            let separator = if self.problems_url.contains('?') { '&' } else { '?' };
+            format!("{}{}{}", self.problems_url, separator, timeframe.join("&"))
+        };
+
+        debug!("Fetching problems from: {}", problems_url);
+
+        ProblemPages {
+            client: self,
+            next_url: Some(problems_url),
+            pages: 0,
+            total_count: 0,
+        }
+    }
+
+    /// Fetch one problem with its evidence, recent comments and impact analysis
+    pub async fn fetch_problem_details(&self, problem_id: &str) -> Result<Problem> {
+        let base = self.problems_url.split('?').next().unwrap_or(&self.problems_url);
+        let url = format!("{}/{}?fields={}", base, urlencoding::encode(problem_id), PROBLEM_DETAIL_FIELDS);
+
+        let response = checked(self.send(self.get(&url)).await?, "Dynatrace API").await?;
+
+        Ok(response.json::<Problem>().await?)
+    }
+
+    /// Close a problem, adding `message` as its closing comment (needs the problems.write scope)
+    pub async fn close_problem(&self, problem_id: &str, message: &str) -> Result<()> {
+        let base = self.problems_url.split('?').next().unwrap_or(&self.problems_url);
+        let url = format!("{}/{}/close", base, urlencoding::encode(problem_id));
+
+        let request = self
+            .request(Method::POST, &url)
+            .json(&serde_json::json!({ "message": message }));
+        checked(self.send(request).await?, "Dynatrace API").await?;
+
+        info!("Closed problem {} in '{}'", problem_id, self.environment);
+        Ok(())
+    }
+
+    /// Add a comment to a problem (needs the problems.write scope)
+    pub async fn add_comment(&self, problem_id: &str, message: &str, context: &str) -> Result<()> {
+        let base = self.problems_url.split('?').next().unwrap_or(&self.problems_url);
+        let url = format!("{}/{}/comments", base, urlencoding::encode(problem_id));
+
+        let request = self
+            .request(Method::POST, &url)
+            .json(&serde_json::json!({ "message": message, "context": context }));
+        checked(self.send(request).await?, "Dynatrace API").await?;
+
+        debug!("Commented on problem {}: {}", problem_id, message);
+        Ok(())
+    }
+
+    /// Fetch monitored entities with their properties and tags (handles pagination).
+    /// Entities Dynatrace no longer knows are left out.
+    pub async fn fetch_entities(&self, entity_ids: &[String]) -> Result<Vec<EntityDetails>> {
+        let mut entities = Vec::new();
+
+        for ids in entity_ids.chunks(ENTITIES_PER_REQUEST) {
+            let selector = format!(
+                "entityId({})",
+                ids.iter().map(|id| format!("\"{}\"", id)).collect::<Vec<_>>().join(",")
+            );
+            let mut url = format!(
+                "{}?entitySelector={}&fields=%2Bproperties,%2Btags&pageSize=500",
+                self.entities_url,
+                urlencoding::encode(&selector)
+            );
+
+            loop {
+                let response = checked(self.send(self.get(&url)).await?, "Dynatrace entities API").await?;
+                let page = response.json::<EntitiesResponse>().await?;
+                entities.extend(page.entities);
+
+                match page.next_page_key {
+                    Some(page_key) => {
+                        url = format!("{}?nextPageKey={}", self.entities_url, urlencoding::encode(&page_key))
+                    }
+                    None => break,
+                }
+            }
+        }
+
+        debug!("Fetched {} of {} entities from Dynatrace", entities.len(), entity_ids.len());
+        Ok(entities)
+    }
+
+    /// Fetch the events matching `event_selector` since `from` (handles pagination;
+    /// needs the events.read scope)
+    pub async fn fetch_events(&self, event_selector: Option<&str>, from: Option<&str>) -> Result<Vec<Event>> {
+        let params: Vec<String> = [("eventSelector", event_selector), ("from", from)]
+            .into_iter()
+            .filter_map(|(param, value)| value.map(|value| format!("{}={}", param, urlencoding::encode(value))))
+            .chain(std::iter::once("pageSize=1000".to_string()))
+            .collect();
+        let mut url = format!("{}?{}", self.events_url, params.join("&"));
+        let mut events = Vec::new();
+
+        loop {
+            let response = checked(self.send(self.get(&url)).await?, "Dynatrace events API").await?;
+            let page = response.json::<EventsResponse>().await?;
+            events.extend(page.events);
+
+            match page.next_page_key {
+                Some(page_key) => url = format!("{}?nextPageKey={}", self.events_url, urlencoding::encode(&page_key)),
+                None => break,
+            }
+        }
+
+        debug!("Fetched {} events from Dynatrace environment '{}'", events.len(), self.environment);
+        Ok(events)
+    }
+
+    /// Fetch the security problems matching `selector` with their risk assessment
+    /// and management zones (handles pagination; needs the securityProblems.read scope)
+    pub async fn fetch_security_problems(&self, selector: Option<&str>) -> Result<Vec<SecurityProblem>> {
+        let mut url = format!(
+            "{}?fields={}&pageSize=500",
+            self.security_problems_url,
+            urlencoding::encode("+riskAssessment,+managementZones")
+        );
+        if let Some(selector) = selector {
+            url.push_str(&format!("&securityProblemSelector={}", urlencoding::encode(selector)));
+        }
+        let mut security_problems = Vec::new();
+
+        loop {
+            let response = checked(self.send(self.get(&url)).await?, "Dynatrace security problems API").await?;
+            let page = response.json::<SecurityProblemsResponse>().await?;
+            security_problems.extend(page.security_problems);
+
+            match page.next_page_key {
+                Some(page_key) => {
+                    url = format!("{}?nextPageKey={}", self.security_problems_url, urlencoding::encode(&page_key))
+                }
+                None => break,
+            }
+        }
+
+        debug!(
+            "Fetched {} security problems from Dynatrace environment '{}'",
+            security_problems.len(),
+            self.environment
+        );
+        Ok(security_problems)
+    }
+
+    /// Fetch the audit log entries matching `filter` since `from` (default: `now-2h`),
+    /// oldest first (handles pagination; needs the auditLogs.read scope)
+    pub async fn fetch_audit_logs(&self, filter: Option<&str>, from: Option<&str>) -> Result<Vec<AuditLogEntry>> {
+        let mut url = format!(
+            "{}?from={}&sort=timestamp&pageSize=1000",
+            self.audit_logs_url,
+            urlencoding::encode(from.unwrap_or(DEFAULT_FROM))
+        );
+        if let Some(filter) = filter {
+            url.push_str(&format!("&filter={}", urlencoding::encode(filter)));
+        }
+        let mut logs = Vec::new();
+
+        loop {
+            let response = checked(self.send(self.get(&url)).await?, "Dynatrace audit logs API").await?;
+            let page = response.json::<AuditLogResponse>().await?;
+            logs.extend(page.logs);
+
+            match page.next_page_key {
+                Some(page_key) => url = format!("{}?nextPageKey={}", self.audit_logs_url, urlencoding::encode(&page_key)),
+                None => break,
+            }
+        }
+
+        debug!("Fetched {} audit log entries from Dynatrace environment '{}'", logs.len(), self.environment);
+        Ok(logs)
+    }
+
+    /// Fetch all maintenance windows configured in the environment (handles pagination)
+    pub async fn fetch_maintenance_windows(&self) -> Result<Vec<MaintenanceWindow>> {
+        let mut windows = Vec::new();
+        let mut next_page_key: Option<String> = None;
+
+        loop {
+            let url = match &next_page_key {
+                Some(page_key) => {
+                    let base = self
+                        .maintenance_windows_url
+                        .split('?')
+                        .next()
+                        .unwrap_or(&self.maintenance_windows_url);
+                    format!("{}?nextPageKey={}", base, urlencoding::encode(page_key))
+                }
+                None => self.maintenance_windows_url.clone(),
+            };
+
+            let response = self.send(self.get(&url)).await?;
+            let status = response.status();
+
+            if !status.is_success() {
+                let request_id = response_request_id(&response)
+                    .map(|id| format!(" [request id: {}]", id))
+                    .unwrap_or_default();
+                let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+                warn!("Dynatrace settings API returned error ({}){}: {}", status, request_id, error_text);
+                return Err(ForwarderError::from_status(
+                    "Dynatrace API",
+                    status,
+                    &format!("{}{}", error_text, request_id),
+                ));
+            }
+
+            let page = response.json::<MaintenanceWindowPage>().await?;
+            windows.extend(page.items.into_iter().map(|item| item.value));
+
+            match page.next_page_key {
+                Some(page_key) => next_page_key = Some(page_key),
+                None => break,
+            }
+        }
+
+        debug!("Fetched {} maintenance window(s) from Dynatrace", windows.len());
+        Ok(windows)
+    }
+
+    /// Look up the API token and check that it is enabled and has every `required`
+    /// scope. Fails with an authentication error otherwise; a lookup that fails for
+    /// other reasons (e.g. an older Managed cluster without the endpoint) is only logged.
+    pub async fn verify_token_scopes(&self, required: &[&str]) -> Result<()> {
+        let request = self
+            .request(Method::POST, &self.token_lookup_url)
+            .json(&serde_json::json!({ "token": self.api_token }));
+        let token = match self.send(request).await {
+            Ok(response) => match checked(response, "Dynatrace token lookup API").await {
+                Ok(response) => response.json::<ApiTokenMetadata>().await?,
+                Err(e @ ForwarderError::Auth { .. }) => return Err(e),
+                Err(e) => {
+                    warn!("Could not verify the API token scopes of '{}': {}", self.environment, e);
+                    return Ok(());
+                }
+            },
+            Err(e) => {
+                warn!("Could not verify the API token scopes of '{}': {}", self.environment, e);
+                return Ok(());
+            }
+        };
+
+        let name = token.name.as_deref().unwrap_or("unnamed");
+        if !token.enabled {
+            return Err(ForwarderError::Auth {
+                target: "Dynatrace API".to_string(),
+                message: format!("API token '{}' of environment '{}' is disabled", name, self.environment),
+            });
+        }
+
+        let missing: Vec<&str> = required
+            .iter()
+            .copied()
+            .filter(|scope| !token.scopes.iter().any(|granted| granted == scope))
+            .collect();
+        if !missing.is_empty() {
+            return Err(ForwarderError::Auth {
+                target: "Dynatrace API".to_string(),
+                message: format!(
+                    "API token '{}' of environment '{}' lacks the scope(s) {} needed by the configuration",
+                    name,
+                    self.environment,
+                    missing.join(", ")
+                ),
+            });
+        }
+
+        debug!("API token '{}' of '{}' has the required scopes: {}", name, self.environment, required.join(", "));
+        Ok(())
+    }
+
     /// Test connectivity to Dynatrace API
     pub async fn test_connection(&self) -> Result<()> {
-        info!("Testing Dynatrace API connectivity...");
+        info!("Testing Dynatrace API connectivity of '{}'...", self.environment);
         
         let response = self.fetch_problems().await?;
         
@@ -122,8 +650,335 @@ impl DynatraceClient {
         Ok(())
     }
 
+    /// Name of the environment this client polls
+    pub fn environment(&self) -> &str {
+        &self.environment
+    }
+
     /// Get the base URL for display/logging
     pub fn base_url(&self) -> &str {
         &self.problems_url
     }
 }
+
+/// Cursor over the pages of a problem list, see [`DynatraceClient::problem_pages`]
+pub struct ProblemPages<'a> {
+    client: &'a DynatraceClient,
+    /// URL of the next page; `None` once the last page was fetched
+    next_url: Option<String>,
+    pages: usize,
+    total_count: i32,
+}
+
+impl ProblemPages<'_> {
+    /// Fetch the next page; `None` after the last one
+    pub async fn next_page(&mut self) -> Result<Option<Vec<Problem>>> {
+        let Some(url) = self.next_url.take() else {
+            return Ok(None);
+        };
+
+        self.pages += 1;
+        debug!("Fetching page {} from Dynatrace...", self.pages);
+
+        let client = self.client;
+        let problems_response = client.fetch_problem_page(&url).await?;
+
+        debug!(
+            "Fetched page {} with {} problems (page size: {})",
+            self.pages,
+            problems_response.problems.len(),
+            problems_response.page_size
+        );
+
+        self.total_count = problems_response.total_count;
+
+        // The API rejects other query parameters alongside nextPageKey
+        self.next_url = problems_response.next_page_key.map(|page_key| {
+            let base = client.problems_url.split('?').next().unwrap_or(&client.problems_url);
+            format!("{}?nextPageKey={}", base, urlencoding::encode(&page_key))
+        });
+
+        Ok(Some(problems_response.problems))
+    }
+
+    /// Whether another page follows
+    pub fn has_next(&self) -> bool {
+        self.next_url.is_some()
+    }
+
+    /// Pages fetched so far
+    pub fn pages(&self) -> usize {
+        self.pages
+    }
+
+    /// Total problem count reported by the latest page
+    pub fn total_count(&self) -> i32 {
+        self.total_count
+    }
+}
+
+/// Default User-Agent: crate name and version plus the host this instance runs on
+fn default_user_agent() -> String {
+    format!(
+        "{}/{} ({})",
+        env!("CARGO_PKG_NAME"),
+        env!("CARGO_PKG_VERSION"),
+        gethostname::gethostname().to_string_lossy()
+    )
+}
+
+/// Pass a successful response through; turn any other into an error carrying
+/// the response text and Dynatrace's request id
+async fn checked(response: Response, api: &str) -> Result<Response> {
+    let status = response.status();
+    if status.is_success() {
+        return Ok(response);
+    }
+
+    let request_id = response_request_id(&response)
+        .map(|id| format!(" [request id: {}]", id))
+        .unwrap_or_default();
+    let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+    warn!("{} returned error ({}){}: {}", api, status, request_id, error_text);
+    Err(ForwarderError::from_status(
+        "Dynatrace API",
+        status,
+        &format!("{}{}", error_text, request_id),
+    ))
+}
+
+/// How long a 429 response asks to wait: `Retry-After` in seconds or as an HTTP
+/// date, else until `X-RateLimit-Reset` (epoch seconds, milliseconds or, as
+/// Dynatrace sends it, microseconds)
+fn rate_limit_wait(headers: &HeaderMap, now_millis: i64) -> Option<Duration> {
+    let header = |name: &str| headers.get(name).and_then(|value| value.to_str().ok()).map(str::trim);
+
+    if let Some(retry_after) = header("retry-after") {
+        if let Ok(seconds) = retry_after.parse::<u64>() {
+            return Some(Duration::from_secs(seconds));
+        }
+        if let Ok(at) = chrono::DateTime::parse_from_rfc2822(retry_after) {
+            let millis = (at.timestamp_millis() - now_millis).max(0);
+            return Some(Duration::from_millis(millis as u64));
+        }
+    }
+
+    let reset = header("x-ratelimit-reset")?.parse::<i64>().ok()?;
+    let reset_millis = match reset {
+        r if r > 100_000_000_000_000 => r / 1000,
+        r if r > 100_000_000_000 => r,
+        r => r * 1000,
+    };
+    Some(Duration::from_millis((reset_millis - now_millis).max(0) as u64))
+}
+
+/// Epoch milliseconds of a timeframe bound: a timestamp or `now` minus an
+/// optional amount of s, m, h, d or w. Other forms (e.g. with rounding) give `None`.
+fn resolve_time(value: &str, now_millis: i64) -> Option<i64> {
+    if let Ok(timestamp) = value.parse::<i64>() {
+        return Some(timestamp);
+    }
+
+    let offset = value.strip_prefix("now")?;
+    if offset.is_empty() {
+        return Some(now_millis);
+    }
+
+    let amount = offset.strip_prefix('-')?;
+    let unit_millis = match amount.chars().last()? {
+        's' => 1_000,
+        'm' => 60_000,
+        'h' => 3_600_000,
+        'd' => 86_400_000,
+        'w' => 604_800_000,
+        _ => return None,
+    };
+    let count: i64 = amount[..amount.len() - 1].parse().ok()?;
+    Some(now_millis - count * unit_millis)
+}
+
+/// Split `start..end` into `slices` adjoining timeframes of equal length
+fn timeframe_slices(start: i64, end: i64, slices: usize) -> Vec<(i64, i64)> {
+    let slices = (slices as i64).clamp(1, (end - start).max(1));
+    let length = (end - start) / slices;
+    (0..slices)
+        .map(|slice| {
+            let slice_start = start + slice * length;
+            let slice_end = if slice == slices - 1 { end } else { slice_start + length };
+            (slice_start, slice_end)
+        })
+        .collect()
+}
+
+/// A numeric response header
+fn header_number(headers: &HeaderMap, name: &str) -> Option<u64> {
+    headers.get(name)?.to_str().ok()?.trim().parse().ok()
+}
+
+/// Request id Dynatrace assigned to a response, if any
+fn response_request_id(response: &Response) -> Option<String> {
+    RESPONSE_REQUEST_ID_HEADERS
+        .iter()
+        .find_map(|name| response.headers().get(*name))
+        .and_then(|value| value.to_str().ok())
+        .map(String::from)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{http::StatusCode, routing::get, Json, Router};
+    use reqwest::header::HeaderValue;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+
+    fn headers(pairs: &[(&'static str, &str)]) -> HeaderMap {
+        let mut headers = HeaderMap::new();
+        for (name, value) in pairs {
+            headers.insert(*name, HeaderValue::from_str(value).unwrap());
+        }
+        headers
+    }
+
+    #[test]
+    fn test_resolve_time_and_slices() {
+        let now = 1_770_700_000_000;
+
+        assert_eq!(resolve_time("now", now), Some(now));
+        assert_eq!(resolve_time("now-2h", now), Some(now - 7_200_000));
+        assert_eq!(resolve_time("now-30m", now), Some(now - 1_800_000));
+        assert_eq!(resolve_time("1770600000000", now), Some(1_770_600_000_000));
+        assert_eq!(resolve_time("now-1d/d", now), None);
+        assert_eq!(resolve_time("yesterday", now), None);
+
+        assert_eq!(timeframe_slices(0, 10, 3), vec![(0, 3), (3, 6), (6, 10)]);
+        assert_eq!(timeframe_slices(0, 2, 4), vec![(0, 1), (1, 2)]);
+    }
+
+    #[test]
+    fn test_rate_limit_wait() {
+        let now = 1_770_700_000_000;
+
+        assert_eq!(rate_limit_wait(&headers(&[("retry-after", "12")]), now), Some(Duration::from_secs(12)));
+        assert_eq!(
+            rate_limit_wait(&headers(&[("retry-after", "Tue, 10 Feb 2026 05:06:50 GMT")]), now),
+            Some(Duration::from_secs(10))
+        );
+        assert_eq!(
+            rate_limit_wait(&headers(&[("x-ratelimit-reset", "1770700003500000")]), now),
+            Some(Duration::from_millis(3500))
+        );
+        assert_eq!(
+            rate_limit_wait(&headers(&[("x-ratelimit-reset", "1770700002")]), now),
+            Some(Duration::from_secs(2))
+        );
+        assert_eq!(
+            rate_limit_wait(&headers(&[("x-ratelimit-reset", "1770699990000")]), now),
+            Some(Duration::ZERO)
+        );
+        assert_eq!(rate_limit_wait(&headers(&[("retry-after", "soon")]), now), None);
+    }
+
+    /// Serve `api` on a free local port and return its base URL
+    fn serve(api: Router) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        tokio::spawn(axum::Server::from_tcp(listener).unwrap().serve(api.into_make_service()));
+        url
+    }
+
+    /// Client of tenant `abc` at `base_url`, making up to `retry_attempts` attempts per page
+    fn client(base_url: &str, retry_attempts: u32) -> DynatraceClient {
+        let yaml = format!(
+            r#"
+dynatrace:
+  base_url: "{}"
+  tenant: "abc"
+  retry_attempts: {}
+  retry_max_delay_seconds: 1
+polling:
+  interval_seconds: 60
+database:
+  driver: memory
+connectors: []
+"#,
+            base_url, retry_attempts
+        );
+        let mut settings: Settings = serde_yaml::from_str(&yaml).unwrap();
+        settings.dynatrace.api_token = Some("test-token".to_string());
+        DynatraceClient::new(&settings).unwrap()
+    }
+
+    /// Problem list answering 503 to the first `failures` requests; returns its URL and request count
+    fn flaky_problem_list(failures: usize) -> (String, Arc<AtomicUsize>) {
+        let requests = Arc::new(AtomicUsize::new(0));
+        let counter = requests.clone();
+        let api = Router::new().route(
+            "/e/abc/api/v2/problems",
+            get(move || {
+                let counter = counter.clone();
+                async move {
+                    if counter.fetch_add(1, Ordering::SeqCst) < failures {
+                        Err(StatusCode::SERVICE_UNAVAILABLE)
+                    } else {
+                        Ok(Json(serde_json::json!({"totalCount": 0, "pageSize": 0, "problems": []})))
+                    }
+                }
+            }),
+        );
+        (serve(api), requests)
+    }
+
+    #[tokio::test]
+    async fn test_poll_retries_then_succeeds() {
+        let (url, requests) = flaky_problem_list(1);
+
+        let response = client(&url, 2).fetch_problems().await.unwrap();
+
+        assert_eq!(response.total_count, 0);
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    #[tokio::test]
+    async fn test_poll_retry_exhaustion() {
+        let (url, requests) = flaky_problem_list(usize::MAX);
+
+        let error = client(&url, 2).fetch_problems().await.unwrap_err();
+
+        assert!(error.is_retryable(), "{}", error);
+        assert_eq!(requests.load(Ordering::SeqCst), 2);
+    }
+
+    /// Token lookup API reporting a token granted `scopes`
+    fn token_lookup(scopes: &'static [&'static str]) -> String {
+        serve(Router::new().route(
+            "/e/abc/api/v2/apiTokens/lookup",
+            axum::routing::post(move || async move {
+                Json(serde_json::json!({"name": "dtpf", "enabled": true, "scopes": scopes}))
+            }),
+        ))
+    }
+
+    #[tokio::test]
+    async fn test_token_missing_scope() {
+        let url = token_lookup(&["problems.read"]);
+
+        let error = client(&url, 1)
+            .verify_token_scopes(&["problems.read", "problems.write"])
+            .await
+            .unwrap_err();
+
+        assert!(matches!(error, ForwarderError::Auth { .. }), "{}", error);
+        assert!(error.to_string().contains("problems.write"), "{}", error);
+    }
+
+    #[tokio::test]
+    async fn test_token_with_all_scopes() {
+        let url = token_lookup(&["problems.read", "problems.write", "entities.read"]);
+
+        client(&url, 1)
+            .verify_token_scopes(&["problems.read", "problems.write"])
+            .await
+            .unwrap();
+    }
+}
@@ -0,0 +1,141 @@
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use crate::dynatrace::models::{AffectedEntity, Entity, EntityTag, ManagementZone, Problem, ProblemStatus};
+
+/// One page of `GET /api/v2/events`
+#[derive(Debug, Deserialize)]
+pub struct EventsResponse {
+    #[serde(rename = "totalCount", default)]
+    pub total_count: i64,
+    #[serde(default)]
+    pub events: Vec<Event>,
+    #[serde(rename = "nextPageKey")]
+    pub next_page_key: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Event {
+    pub event_id: String,
+    /// e.g. `CUSTOM_DEPLOYMENT` or `CUSTOM_INFO`
+    pub event_type: String,
+    pub title: String,
+    pub start_time: i64,
+    pub end_time: Option<i64>,
+    /// `OPEN` or `CLOSED`
+    pub status: ProblemStatus,
+    pub entity_id: Option<Entity>,
+    #[serde(default)]
+    pub properties: Vec<EventProperty>,
+    pub correlation_id: Option<String>,
+    #[serde(default)]
+    pub management_zones: Vec<ManagementZone>,
+    #[serde(default)]
+    pub entity_tags: Vec<EntityTag>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct EventProperty {
+    pub key: String,
+    pub value: String,
+}
+
+/// What a forwarded event adds to its problem-shaped payload
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EventInfo {
+    pub event_type: String,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    pub properties: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub correlation_id: Option<String>,
+}
+
+impl Event {
+    /// The event in the shape connectors forward: the event ID as problem ID,
+    /// the event type as display ID and severity `INFO`, the event's entity as
+    /// the affected entity, and its type and properties under `event`
+    pub fn into_problem(self) -> Problem {
+        let affected_entities: Vec<AffectedEntity> = self
+            .entity_id
+            .into_iter()
+            .map(|entity| AffectedEntity {
+                entity_id: entity.entity_id,
+                name: entity.name,
+                details: None,
+            })
+            .collect();
+
+        Problem {
+            problem_id: self.event_id,
+            display_id: self.event_type.clone(),
+            title: self.title,
+            impact_level: "INFRASTRUCTURE".to_string(),
+            severity_level: "INFO".to_string(),
+            status: self.status,
+            impacted_entities: affected_entities.clone(),
+            affected_entities,
+            root_cause_entity: None,
+            management_zones: self.management_zones,
+            entity_tags: self.entity_tags,
+            problem_filters: vec![],
+            start_time: self.start_time,
+            end_time: self.end_time.unwrap_or(-1),
+            evidence_details: None,
+            recent_comments: None,
+            impact_analysis: None,
+            event: Some(EventInfo {
+                event_type: self.event_type,
+                properties: self.properties.into_iter().map(|p| (p.key, p.value)).collect(),
+                correlation_id: self.correlation_id,
+            }),
+            security: None,
+            audit: None,
+            runbooks: vec![],
+            correlated_problems: vec![],
+            recurrence: None,
+            tenant: None,
+            tenant_url_base: None,
+            change: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_event_into_problem() {
+        let page: EventsResponse = serde_json::from_str(
+            r#"{
+                "totalCount": 1,
+                "pageSize": 100,
+                "events": [{
+                    "eventId": "-5823417792061040040_1770700000000",
+                    "startTime": 1770700000000,
+                    "eventType": "CUSTOM_DEPLOYMENT",
+                    "title": "Deployed checkout 1.4.2",
+                    "entityId": {"entityId": {"id": "SERVICE-FEDCBA9876543210", "type": "SERVICE"}, "name": "checkout-service"},
+                    "properties": [{"key": "dt.event.deployment.version", "value": "1.4.2"}],
+                    "status": "OPEN",
+                    "managementZones": [{"id": "1", "name": "Production"}],
+                    "underMaintenance": false
+                }]
+            }"#,
+        )
+        .unwrap();
+
+        let problem = page.events.into_iter().next().unwrap().into_problem();
+        assert_eq!(problem.problem_id, "-5823417792061040040_1770700000000");
+        assert_eq!(problem.display_id, "CUSTOM_DEPLOYMENT");
+        assert_eq!(problem.status, ProblemStatus::Open);
+        assert_eq!(problem.end_time, -1);
+        assert_eq!(problem.affected_entities[0].entity_id.id, "SERVICE-FEDCBA9876543210");
+        assert_eq!(problem.management_zones[0].name, "Production");
+
+        let event = serde_json::to_value(&problem).unwrap()["event"].clone();
+        assert_eq!(event["eventType"], "CUSTOM_DEPLOYMENT");
+        assert_eq!(event["properties"]["dt.event.deployment.version"], "1.4.2");
+    }
+}
@@ -0,0 +1,263 @@
+use chrono::{DateTime, Datelike, NaiveDate, NaiveDateTime, NaiveTime, Utc, Weekday};
+use chrono_tz::Tz;
+use serde::Deserialize;
+use crate::dynatrace::models::Problem;
+use crate::forwarder::tag_filter::tag_matches;
+
+/// Settings schema of maintenance windows
+pub const MAINTENANCE_WINDOW_SCHEMA: &str = "builtin:alerting.maintenance-window";
+
+/// One page of `GET /api/v2/settings/objects` for the maintenance window schema
+#[derive(Debug, Deserialize)]
+pub struct MaintenanceWindowPage {
+    #[serde(default)]
+    pub items: Vec<MaintenanceWindowObject>,
+    #[serde(rename = "nextPageKey")]
+    pub next_page_key: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MaintenanceWindowObject {
+    #[serde(rename = "objectId")]
+    pub object_id: String,
+    pub value: MaintenanceWindow,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct MaintenanceWindow {
+    #[serde(default = "default_enabled")]
+    pub enabled: bool,
+    #[serde(rename = "generalProperties")]
+    pub general_properties: GeneralProperties,
+    pub schedule: Schedule,
+    /// Entities the window applies to; the whole environment when empty
+    #[serde(default)]
+    pub filters: Vec<MaintenanceFilter>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct GeneralProperties {
+    pub name: String,
+    /// `DETECT_PROBLEMS_AND_ALERT`, `DETECT_PROBLEMS_DONT_ALERT` or `DONT_DETECT_PROBLEMS`
+    #[serde(default)]
+    pub suppression: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Schedule {
+    /// `ONCE`, `DAILY`, `WEEKLY` or `MONTHLY`
+    pub schedule_type: String,
+    pub once_recurrence: Option<OnceRecurrence>,
+    pub daily_recurrence: Option<Recurrence>,
+    pub weekly_recurrence: Option<Recurrence>,
+    pub monthly_recurrence: Option<Recurrence>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct OnceRecurrence {
+    /// Local date and time, e.g. `2026-02-10T22:00:00`
+    pub start_time: String,
+    pub end_time: String,
+    pub time_zone: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Recurrence {
+    /// `MONDAY` ... `SUNDAY` for weekly windows
+    pub day_of_week: Option<String>,
+    /// 1-31 for monthly windows
+    pub day_of_month: Option<u32>,
+    pub time_window: TimeWindow,
+    pub recurrence_range: RecurrenceRange,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TimeWindow {
+    /// Local time of day, e.g. `22:00:00`
+    pub start_time: String,
+    pub end_time: String,
+    pub time_zone: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RecurrenceRange {
+    pub scope_start_date: String,
+    pub scope_end_date: String,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct MaintenanceFilter {
+    pub entity_type: Option<String>,
+    pub entity_id: Option<String>,
+    #[serde(default)]
+    pub entity_tags: Vec<String>,
+    /// Management zone ids
+    #[serde(default)]
+    pub management_zones: Vec<String>,
+}
+
+fn default_enabled() -> bool {
+    true
+}
+
+impl MaintenanceWindow {
+    pub fn name(&self) -> &str {
+        &self.general_properties.name
+    }
+
+    /// Whether the window is enabled, withholds alerts and is in effect at `now`
+    pub fn suppresses_alerts_at(&self, now: DateTime<Utc>) -> bool {
+        self.enabled && self.general_properties.suppression != "DETECT_PROBLEMS_AND_ALERT" && self.is_active(now)
+    }
+
+    fn is_active(&self, now: DateTime<Utc>) -> bool {
+        let schedule = &self.schedule;
+        if schedule.schedule_type == "ONCE" {
+            return schedule.once_recurrence.as_ref().is_some_and(|once| {
+                let local = now.with_timezone(&timezone(&once.time_zone)).naive_local();
+                let parse = |time: &str| NaiveDateTime::parse_from_str(time, "%Y-%m-%dT%H:%M:%S").ok();
+                matches!((parse(&once.start_time), parse(&once.end_time)), (Some(start), Some(end)) if local >= start && local < end)
+            });
+        }
+
+        let recurrence = match schedule.schedule_type.as_str() {
+            "DAILY" => schedule.daily_recurrence.as_ref(),
+            "WEEKLY" => schedule.weekly_recurrence.as_ref(),
+            "MONTHLY" => schedule.monthly_recurrence.as_ref(),
+            _ => None,
+        };
+        recurrence.is_some_and(|recurrence| recurrence.is_active(now))
+    }
+
+    /// Whether the problem's affected entities fall under the window's filters
+    pub fn covers(&self, problem: &Problem) -> bool {
+        self.filters.is_empty() || self.filters.iter().any(|filter| filter.matches(problem))
+    }
+}
+
+impl Recurrence {
+    fn is_active(&self, now: DateTime<Utc>) -> bool {
+        let window = &self.time_window;
+        let local = now.with_timezone(&timezone(&window.time_zone)).naive_local();
+        let (Some(start), Some(end)) = (parse_time(&window.start_time), parse_time(&window.end_time)) else {
+            return false;
+        };
+        let today = local.date();
+        let time = local.time();
+
+        if start <= end {
+            self.occurs_on(today) && time >= start && time < end
+        } else {
+            // Past midnight: the occurrence belongs to the day it starts on
+            (self.occurs_on(today) && time >= start) || (today.pred_opt().is_some_and(|day| self.occurs_on(day)) && time < end)
+        }
+    }
+
+    fn occurs_on(&self, day: NaiveDate) -> bool {
+        let range = &self.recurrence_range;
+        let in_range = |date: &str, after: bool| {
+            NaiveDate::parse_from_str(date, "%Y-%m-%d").map_or(true, |bound| if after { day >= bound } else { day <= bound })
+        };
+        if !in_range(&range.scope_start_date, true) || !in_range(&range.scope_end_date, false) {
+            return false;
+        }
+
+        match (&self.day_of_week, self.day_of_month) {
+            (Some(weekday), _) => weekday.parse::<Weekday>().is_ok_and(|weekday| weekday == day.weekday()),
+            (None, Some(day_of_month)) => day.day() == day_of_month,
+            (None, None) => true,
+        }
+    }
+}
+
+impl MaintenanceFilter {
+    /// All criteria that are set must match
+    fn matches(&self, problem: &Problem) -> bool {
+        let entities = &problem.affected_entities;
+        self.entity_id.as_ref().is_none_or(|id| entities.iter().any(|e| &e.entity_id.id == id))
+            && self
+                .entity_type
+                .as_ref()
+                .is_none_or(|entity_type| entities.iter().any(|e| &e.entity_id.entity_type == entity_type))
+            && self.entity_tags.iter().all(|wanted| {
+                problem
+                    .entity_tags
+                    .iter()
+                    .any(|tag| tag.string_representation == *wanted || tag_matches(tag, wanted))
+            })
+            && (self.management_zones.is_empty()
+                || problem.management_zones.iter().any(|zone| self.management_zones.contains(&zone.id)))
+    }
+}
+
+fn timezone(name: &str) -> Tz {
+    name.parse().unwrap_or(Tz::UTC)
+}
+
+fn parse_time(time: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(time, "%H:%M:%S")
+        .or_else(|_| NaiveTime::parse_from_str(time, "%H:%M"))
+        .ok()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dynatrace::ProblemFixture;
+    use chrono::TimeZone;
+    use serde_json::json;
+    use std::path::Path;
+
+    fn window(schedule: serde_json::Value, filters: serde_json::Value) -> MaintenanceWindow {
+        serde_json::from_value(json!({
+            "enabled": true,
+            "generalProperties": { "name": "Patch night", "suppression": "DETECT_PROBLEMS_DONT_ALERT" },
+            "schedule": schedule,
+            "filters": filters
+        }))
+        .unwrap()
+    }
+
+    #[test]
+    fn test_schedules_and_filters() {
+        let weekly = window(
+            json!({
+                "scheduleType": "WEEKLY",
+                "weeklyRecurrence": {
+                    "dayOfWeek": "FRIDAY",
+                    "timeWindow": { "startTime": "22:00:00", "endTime": "02:00:00", "timeZone": "Europe/Berlin" },
+                    "recurrenceRange": { "scopeStartDate": "2026-01-01", "scopeEndDate": "2026-12-31" }
+                }
+            }),
+            json!([]),
+        );
+        // 2026-02-06 is a Friday; Berlin is UTC+1 in February
+        assert!(weekly.suppresses_alerts_at(Utc.with_ymd_and_hms(2026, 2, 6, 21, 30, 0).unwrap()));
+        assert!(weekly.suppresses_alerts_at(Utc.with_ymd_and_hms(2026, 2, 7, 0, 30, 0).unwrap()));
+        assert!(!weekly.suppresses_alerts_at(Utc.with_ymd_and_hms(2026, 2, 7, 21, 30, 0).unwrap()));
+
+        let problem = ProblemFixture::load(Path::new("tests/fixtures/problems/open_problem.json"))
+            .unwrap()
+            .remove(0);
+        let entity = &problem.affected_entities[0].entity_id;
+        let once = |filters| {
+            window(
+                json!({
+                    "scheduleType": "ONCE",
+                    "onceRecurrence": { "startTime": "2026-02-10T00:00:00", "endTime": "2026-02-10T06:00:00", "timeZone": "UTC" }
+                }),
+                filters,
+            )
+        };
+        assert!(once(json!([{ "entityId": entity.id }])).covers(&problem));
+        assert!(!once(json!([{ "entityType": entity.entity_type, "entityId": "HOST-OTHER" }])).covers(&problem));
+        assert!(once(json!([])).suppresses_alerts_at(Utc.with_ymd_and_hms(2026, 2, 10, 5, 0, 0).unwrap()));
+        assert!(!once(json!([])).suppresses_alerts_at(Utc.with_ymd_and_hms(2026, 2, 10, 6, 0, 0).unwrap()));
+    }
+}
@@ -1,5 +1,13 @@
+pub mod audit;
 pub mod client;
+pub mod events;
+pub mod maintenance;
 pub mod models;
+pub mod security;
 
-pub use client::DynatraceClient;
-pub use models::{Problem, ProblemStatus, ProblemsResponse};
+pub use audit::{AuditInfo, AuditLogEntry};
+pub use client::{ApiQuota, DynatraceClient, ProblemPages};
+pub use events::{Event, EventInfo};
+pub use maintenance::MaintenanceWindow;
+pub use models::{EntityDetails, Problem, ProblemChange, ProblemFixture, ProblemStatus, ProblemsResponse, Recurrence};
+pub use security::{SecurityInfo, SecurityProblem};
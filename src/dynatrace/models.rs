@@ -1,4 +1,22 @@
 use serde::{Deserialize, Serialize};
+use crate::dynatrace::audit::AuditInfo;
+use crate::dynatrace::events::EventInfo;
+use crate::dynatrace::security::SecurityInfo;
+
+/// Metadata of an API token, from `POST /api/v2/apiTokens/lookup`
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ApiTokenMetadata {
+    pub name: Option<String>,
+    #[serde(default = "default_token_enabled")]
+    pub enabled: bool,
+    #[serde(default)]
+    pub scopes: Vec<String>,
+}
+
+fn default_token_enabled() -> bool {
+    true
+}
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ProblemsResponse {
@@ -39,6 +57,42 @@ pub struct Problem {
     pub start_time: i64,
     #[serde(rename = "endTime")]
     pub end_time: i64,
+    /// Only present when requested via `fields=+evidenceDetails`
+    #[serde(rename = "evidenceDetails", default, skip_serializing_if = "Option::is_none")]
+    pub evidence_details: Option<EvidenceDetails>,
+    /// Only present when fetched from the problem details endpoint
+    #[serde(rename = "recentComments", default, skip_serializing_if = "Option::is_none")]
+    pub recent_comments: Option<CommentsPage>,
+    /// Only present when fetched from the problem details endpoint
+    #[serde(rename = "impactAnalysis", default, skip_serializing_if = "Option::is_none")]
+    pub impact_analysis: Option<ImpactAnalysis>,
+    /// Type and properties of a forwarded Dynatrace event (see `events`)
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub event: Option<EventInfo>,
+    /// Risk assessment and vulnerability details of a forwarded security problem
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub security: Option<SecurityInfo>,
+    /// User, event type and patch of a forwarded audit log entry
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub audit: Option<AuditInfo>,
+    /// Remediation links added by the forwarder's runbook mapping
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub runbooks: Vec<Runbook>,
+    /// Problems folded into this one by the forwarder's correlation stage
+    #[serde(rename = "correlatedProblems", default, skip_serializing_if = "Vec::is_empty")]
+    pub correlated_problems: Vec<ProblemRef>,
+    /// Occurrence counts added by a connector's recurrence sampling
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub recurrence: Option<Recurrence>,
+    /// Name of the Dynatrace environment the problem comes from, when several are polled
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tenant: Option<String>,
+    /// Link prefix of that environment's problem pages; not part of the payload
+    #[serde(skip)]
+    pub tenant_url_base: Option<String>,
+    /// Why the forwarder sends the problem this cycle; not part of the payload
+    #[serde(skip)]
+    pub change: Option<ProblemChange>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
@@ -49,12 +103,31 @@ pub enum ProblemStatus {
     Resolved,
 }
 
-impl ToString for ProblemStatus {
-    fn to_string(&self) -> String {
+impl std::fmt::Display for ProblemStatus {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
-            ProblemStatus::Open => "OPEN".to_string(),
-            ProblemStatus::Closed => "CLOSED".to_string(),
-            ProblemStatus::Resolved => "RESOLVED".to_string(),
+            ProblemStatus::Open => write!(f, "OPEN"),
+            ProblemStatus::Closed => write!(f, "CLOSED"),
+            ProblemStatus::Resolved => write!(f, "RESOLVED"),
+        }
+    }
+}
+
+/// Reason a problem is forwarded, as detected against the problem cache
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ProblemChange {
+    New,
+    StatusChange,
+    /// Still open after `renotify.after_minutes`; sent again as a reminder
+    Renotification,
+}
+
+impl ProblemChange {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            ProblemChange::New => "new",
+            ProblemChange::StatusChange => "status_change",
+            ProblemChange::Renotification => "renotification",
         }
     }
 }
@@ -64,6 +137,9 @@ pub struct AffectedEntity {
     #[serde(rename = "entityId")]
     pub entity_id: EntityId,
     pub name: String,
+    /// Properties from the Monitored Entities API, added by entity enrichment
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub details: Option<EntityDetails>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -95,23 +171,201 @@ pub struct EntityTag {
     pub string_representation: String,
 }
 
+/// A monitored entity as returned by `/api/v2/entities` with `fields=+properties,+tags`
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EntityDetails {
+    #[serde(rename = "entityId", default, skip_serializing)]
+    pub entity_id: String,
+    #[serde(rename = "displayName", default, skip_serializing_if = "Option::is_none")]
+    pub display_name: Option<String>,
+    /// Host IPs, cloud metadata, ... (depends on the entity type)
+    #[serde(default)]
+    pub properties: serde_json::Map<String, serde_json::Value>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<EntityTag>,
+}
+
+/// Page of the Monitored Entities API
+#[derive(Debug, Deserialize)]
+pub struct EntitiesResponse {
+    #[serde(default)]
+    pub entities: Vec<EntityDetails>,
+    #[serde(rename = "nextPageKey")]
+    pub next_page_key: Option<String>,
+}
+
+/// Runbook/playbook link matched from the `runbooks` configuration (not part of the Dynatrace API)
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Runbook {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    pub url: String,
+}
+
+/// How often a recurring problem occurred, for connectors sampling recurrences
+/// (not part of the Dynatrace API)
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct Recurrence {
+    /// Occurrences with the same title and/or entity, including this one
+    pub occurrences: i64,
+    /// Occurrences not forwarded since the previous forwarded one
+    pub skipped_since_last_forward: i64,
+}
+
+/// Reference to a problem that was correlated into another one (not part of the Dynatrace API)
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct ProblemRef {
+    pub problem_id: String,
+    pub display_id: String,
+    pub title: String,
+    pub status: ProblemStatus,
+    pub severity_level: String,
+}
+
+impl From<&Problem> for ProblemRef {
+    fn from(problem: &Problem) -> Self {
+        Self {
+            problem_id: problem.problem_id.clone(),
+            display_id: problem.display_id.clone(),
+            title: problem.title.clone(),
+            status: problem.status.clone(),
+            severity_level: problem.severity_level.clone(),
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct EvidenceDetails {
+    #[serde(rename = "totalCount")]
+    pub total_count: i32,
+    pub details: Vec<Evidence>,
+}
+
+/// One piece of evidence Davis used to detect the problem (event, metric, transaction, ...)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Evidence {
+    #[serde(rename = "evidenceType")]
+    pub evidence_type: String,
+    #[serde(rename = "displayName")]
+    pub display_name: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub entity: Option<Entity>,
+    #[serde(rename = "rootCauseRelevant", default)]
+    pub root_cause_relevant: bool,
+    #[serde(rename = "startTime", default)]
+    pub start_time: i64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+    #[serde(rename = "valueBeforeChangePoint", default, skip_serializing_if = "Option::is_none")]
+    pub value_before_change_point: Option<f64>,
+    #[serde(rename = "valueAfterChangePoint", default, skip_serializing_if = "Option::is_none")]
+    pub value_after_change_point: Option<f64>,
+}
+
+/// The latest comments on a problem
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CommentsPage {
+    #[serde(rename = "totalCount", default)]
+    pub total_count: i32,
+    #[serde(default)]
+    pub comments: Vec<Comment>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Comment {
+    pub id: String,
+    #[serde(rename = "createdAtTimestamp")]
+    pub created_at_timestamp: i64,
+    pub content: String,
+    #[serde(rename = "authorName", default, skip_serializing_if = "Option::is_none")]
+    pub author_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub context: Option<String>,
+}
+
+/// Who and what a problem affects (services, applications, synthetic monitors, ...)
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ImpactAnalysis {
+    #[serde(default)]
+    pub impacts: Vec<Impact>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct Impact {
+    #[serde(rename = "impactType")]
+    pub impact_type: String,
+    #[serde(rename = "impactedEntity")]
+    pub impacted_entity: Entity,
+    #[serde(rename = "estimatedAffectedUsers", default)]
+    pub estimated_affected_users: i64,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct ProblemFilter {
     pub id: String,
     pub name: String,
 }
 
+/// Problem fixture file contents: a single problem, an array, or a full API response
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub enum ProblemFixture {
+    Response(ProblemsResponse),
+    Many(Vec<Problem>),
+    One(Box<Problem>),
+}
+
+impl ProblemFixture {
+    /// Load problems from a JSON fixture file
+    pub fn load(path: &std::path::Path) -> crate::error::Result<Vec<Problem>> {
+        let content = std::fs::read_to_string(path)?;
+        let fixture: ProblemFixture = serde_json::from_str(&content)?;
+
+        Ok(match fixture {
+            ProblemFixture::Response(response) => response.problems,
+            ProblemFixture::Many(problems) => problems,
+            ProblemFixture::One(problem) => vec![*problem],
+        })
+    }
+}
+
 impl Problem {
     /// Check if the problem is currently open
     pub fn is_open(&self) -> bool {
         self.status == ProblemStatus::Open
     }
 
+    /// Link to the problem in Dynatrace: in its own environment, or under
+    /// `problem_url_base` when it carries none
+    pub fn url(&self, problem_url_base: &str) -> String {
+        format!("{}{}", self.url_base(problem_url_base), self.problem_id)
+    }
+
+    /// Link prefix of the problem's environment, `problem_url_base` unless it carries its own
+    pub fn url_base<'a>(&'a self, problem_url_base: &'a str) -> &'a str {
+        self.tenant_url_base.as_deref().unwrap_or(problem_url_base)
+    }
+
+    /// Take evidence, comments and impact analysis from the problem's details,
+    /// keeping what the list endpoint and the forwarder already set
+    pub fn merge_details(&mut self, details: Problem) {
+        self.evidence_details = details.evidence_details.or(self.evidence_details.take());
+        self.recent_comments = details.recent_comments;
+        self.impact_analysis = details.impact_analysis;
+    }
+
+    /// Names of the management zones the problem belongs to
+    pub fn management_zone_names(&self) -> Vec<String> {
+        self.management_zones.iter().map(|zone| zone.name.clone()).collect()
+    }
+
     /// Get a summary string for logging
     pub fn summary(&self) -> String {
         format!(
             "[{}] {} - {} ({})",
-            self.display_id, self.title, self.status.to_string(), self.severity_level
+            self.display_id, self.title, self.status, self.severity_level
         )
     }
 }
@@ -0,0 +1,200 @@
+use serde::{Deserialize, Serialize};
+use crate::dynatrace::models::{ManagementZone, Problem, ProblemStatus};
+
+/// One page of `GET /api/v2/securityProblems`
+#[derive(Debug, Deserialize)]
+pub struct SecurityProblemsResponse {
+    #[serde(rename = "totalCount", default)]
+    pub total_count: i64,
+    #[serde(rename = "securityProblems", default)]
+    pub security_problems: Vec<SecurityProblem>,
+    #[serde(rename = "nextPageKey")]
+    pub next_page_key: Option<String>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SecurityProblem {
+    pub security_problem_id: String,
+    /// e.g. `S-1234`
+    pub display_id: String,
+    pub title: String,
+    /// `OPEN` or `RESOLVED`
+    pub status: String,
+    #[serde(default)]
+    pub muted: bool,
+    /// `THIRD_PARTY`, `CODE_LEVEL` or `RUNTIME`
+    pub vulnerability_type: Option<String>,
+    pub external_vulnerability_id: Option<String>,
+    pub package_name: Option<String>,
+    pub technology: Option<String>,
+    /// Link to the vulnerability in Dynatrace
+    pub url: Option<String>,
+    #[serde(default)]
+    pub cve_ids: Vec<String>,
+    pub first_seen_timestamp: Option<i64>,
+    pub last_resolved_timestamp: Option<i64>,
+    pub risk_assessment: Option<RiskAssessment>,
+    #[serde(default)]
+    pub management_zones: Vec<ManagementZone>,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct RiskAssessment {
+    /// `CRITICAL`, `HIGH`, `MEDIUM`, `LOW` or `NONE`
+    pub risk_level: Option<String>,
+    pub risk_score: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub exposure: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub public_exploit: Option<String>,
+}
+
+/// What a forwarded security problem adds to its problem-shaped payload
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SecurityInfo {
+    /// `OPEN`, `RESOLVED` or `MUTED`
+    pub status: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub risk_assessment: Option<RiskAssessment>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub cve_ids: Vec<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub vulnerability_type: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub external_vulnerability_id: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub package_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub technology: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub url: Option<String>,
+}
+
+impl SecurityProblem {
+    /// Status the forwarder tracks: `MUTED` for muted problems, else `OPEN` or `RESOLVED`
+    pub fn tracked_status(&self) -> &str {
+        if self.muted {
+            "MUTED"
+        } else {
+            &self.status
+        }
+    }
+
+    pub fn risk_level(&self) -> Option<&str> {
+        self.risk_assessment.as_ref()?.risk_level.as_deref()
+    }
+
+    /// The security problem in the shape connectors forward: `OPEN` stays open,
+    /// `RESOLVED` is resolved and `MUTED` is closed; the risk level becomes the
+    /// severity and the vulnerability details go under `security`
+    pub fn into_problem(self) -> Problem {
+        let status = match self.tracked_status() {
+            "OPEN" => ProblemStatus::Open,
+            "RESOLVED" => ProblemStatus::Resolved,
+            _ => ProblemStatus::Closed,
+        };
+        let tracked_status = self.tracked_status().to_string();
+        let severity_level = format!("SECURITY_{}", self.risk_level().unwrap_or("NONE"));
+
+        Problem {
+            problem_id: self.security_problem_id,
+            display_id: self.display_id,
+            title: self.title,
+            impact_level: "APPLICATION".to_string(),
+            severity_level,
+            status,
+            affected_entities: vec![],
+            impacted_entities: vec![],
+            root_cause_entity: None,
+            management_zones: self.management_zones,
+            entity_tags: vec![],
+            problem_filters: vec![],
+            start_time: self.first_seen_timestamp.unwrap_or_default(),
+            end_time: self.last_resolved_timestamp.filter(|_| tracked_status == "RESOLVED").unwrap_or(-1),
+            evidence_details: None,
+            recent_comments: None,
+            impact_analysis: None,
+            event: None,
+            security: Some(SecurityInfo {
+                status: tracked_status,
+                risk_assessment: self.risk_assessment,
+                cve_ids: self.cve_ids,
+                vulnerability_type: self.vulnerability_type,
+                external_vulnerability_id: self.external_vulnerability_id,
+                package_name: self.package_name,
+                technology: self.technology,
+                url: self.url,
+            }),
+            audit: None,
+            runbooks: vec![],
+            correlated_problems: vec![],
+            recurrence: None,
+            tenant: None,
+            tenant_url_base: None,
+            change: None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_security_problem_into_problem() {
+        let page: SecurityProblemsResponse = serde_json::from_str(
+            r#"{
+                "totalCount": 2,
+                "pageSize": 500,
+                "securityProblems": [
+                    {
+                        "securityProblemId": "2919200225913269102",
+                        "displayId": "S-1234",
+                        "status": "OPEN",
+                        "muted": false,
+                        "externalVulnerabilityId": "SNYK-JAVA-ORGAPACHELOGGINGLOG4J-2314720",
+                        "vulnerabilityType": "THIRD_PARTY",
+                        "title": "Remote Code Execution (RCE)",
+                        "packageName": "org.apache.logging.log4j:log4j-core",
+                        "technology": "JAVA",
+                        "firstSeenTimestamp": 1770700000000,
+                        "riskAssessment": {"riskLevel": "CRITICAL", "riskScore": 10.0, "exposure": "PUBLIC_NETWORK"},
+                        "managementZones": [{"id": "1", "name": "Production"}],
+                        "cveIds": ["CVE-2021-44228"]
+                    },
+                    {
+                        "securityProblemId": "4512990031876544012",
+                        "displayId": "S-1235",
+                        "status": "OPEN",
+                        "muted": true,
+                        "title": "Denial of Service (DoS)"
+                    }
+                ]
+            }"#,
+        )
+        .unwrap();
+
+        let mut security_problems = page.security_problems.into_iter();
+
+        let critical = security_problems.next().unwrap();
+        assert_eq!(critical.tracked_status(), "OPEN");
+        assert_eq!(critical.risk_level(), Some("CRITICAL"));
+        let problem = critical.into_problem();
+        assert_eq!(problem.display_id, "S-1234");
+        assert_eq!(problem.severity_level, "SECURITY_CRITICAL");
+        assert_eq!(problem.status, ProblemStatus::Open);
+        let security = serde_json::to_value(&problem).unwrap()["security"].clone();
+        assert_eq!(security["cveIds"][0], "CVE-2021-44228");
+        assert_eq!(security["riskAssessment"]["riskScore"], 10.0);
+
+        let muted = security_problems.next().unwrap();
+        assert_eq!(muted.tracked_status(), "MUTED");
+        assert_eq!(muted.risk_level(), None);
+        let problem = muted.into_problem();
+        assert_eq!(problem.status, ProblemStatus::Closed);
+        assert_eq!(problem.severity_level, "SECURITY_NONE");
+    }
+}
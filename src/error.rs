@@ -1,3 +1,4 @@
+use reqwest::StatusCode;
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -5,29 +6,186 @@ pub enum ForwarderError {
     #[error("Configuration error: {0}")]
     Config(String),
 
-    #[error("Dynatrace API error: {0}")]
-    DynatraceApi(#[from] reqwest::Error),
+    #[error("Validation error: {0}")]
+    Validation(String),
 
-    #[error("Database error: {0}")]
-    Database(#[from] sqlx::Error),
+    #[error("YAML error: {0}")]
+    Yaml(#[from] serde_yaml::Error),
+
+    #[error("Authentication error: {target}: {message}")]
+    Auth { target: String, message: String },
+
+    #[error("Transient network error: {target}: {message}")]
+    TransientNetwork { target: String, message: String },
+
+    #[error("Downstream error: {target}: {message}")]
+    PermanentDownstream { target: String, message: String },
+
+    #[error("HTTP error: {0}")]
+    Http(#[from] reqwest::Error),
 
     #[error("Serialization error: {0}")]
     Serialization(#[from] serde_json::Error),
 
-    #[error("YAML error: {0}")]
-    Yaml(#[from] serde_yaml::Error),
+    #[error("Storage error: {target}: {message}")]
+    Storage { target: String, message: String },
+
+    #[error("Database error: {0}")]
+    Database(#[from] sqlx::Error),
 
-    #[error("Connector error: {connector}: {message}")]
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+
+    #[error("Connector error: {connector}: {source}")]
     Connector {
         connector: String,
-        message: String,
+        source: Box<ForwarderError>,
     },
+}
 
-    #[error("Validation error: {0}")]
-    Validation(String),
+/// Broad class of a failure, deciding the CLI exit code and whether a
+/// delivery is worth retrying
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ErrorCategory {
+    /// Invalid or incomplete configuration; fixing it requires operator action
+    Config,
+    /// Credentials were rejected by Dynatrace or a connector
+    Auth,
+    /// Timeouts, connection failures, throttling and 5xx responses
+    TransientNetwork,
+    /// The receiver rejected the request; resending it will not help
+    PermanentDownstream,
+    /// Local database or filesystem failures
+    Storage,
+}
 
-    #[error("IO error: {0}")]
-    Io(#[from] std::io::Error),
+impl ErrorCategory {
+    /// Process exit code reported by the CLI for this category
+    pub fn exit_code(self) -> i32 {
+        match self {
+            ErrorCategory::Config => 2,
+            ErrorCategory::Auth => 3,
+            ErrorCategory::TransientNetwork => 4,
+            ErrorCategory::PermanentDownstream => 5,
+            ErrorCategory::Storage => 6,
+        }
+    }
+
+    /// Short machine-readable name, used by the CLI's quiet mode
+    pub fn as_str(self) -> &'static str {
+        match self {
+            ErrorCategory::Config => "config",
+            ErrorCategory::Auth => "auth",
+            ErrorCategory::TransientNetwork => "transient_network",
+            ErrorCategory::PermanentDownstream => "permanent_downstream",
+            ErrorCategory::Storage => "storage",
+        }
+    }
+
+    /// Whether a failed operation may succeed when attempted again
+    pub fn is_retryable(self) -> bool {
+        matches!(self, ErrorCategory::TransientNetwork)
+    }
+}
+
+impl ForwarderError {
+    /// Classify a non-success HTTP response from `target`
+    pub fn from_status(target: impl Into<String>, status: StatusCode, body: &str) -> Self {
+        let target = target.into();
+        let message = format!("HTTP {}: {}", status, body);
+
+        match status_category(status) {
+            ErrorCategory::Auth => ForwarderError::Auth { target, message },
+            ErrorCategory::TransientNetwork => ForwarderError::TransientNetwork { target, message },
+            _ => ForwarderError::PermanentDownstream { target, message },
+        }
+    }
+
+    /// Attribute an error to the connector it occurred in, keeping its category
+    pub fn in_connector(self, connector: impl Into<String>) -> Self {
+        ForwarderError::Connector {
+            connector: connector.into(),
+            source: Box::new(self),
+        }
+    }
+
+    pub fn category(&self) -> ErrorCategory {
+        match self {
+            ForwarderError::Config(_) | ForwarderError::Validation(_) | ForwarderError::Yaml(_) => {
+                ErrorCategory::Config
+            }
+            ForwarderError::Auth { .. } => ErrorCategory::Auth,
+            ForwarderError::TransientNetwork { .. } => ErrorCategory::TransientNetwork,
+            ForwarderError::PermanentDownstream { .. } | ForwarderError::Serialization(_) => {
+                ErrorCategory::PermanentDownstream
+            }
+            ForwarderError::Http(e) => http_category(e),
+            ForwarderError::Storage { .. } | ForwarderError::Database(_) | ForwarderError::Io(_) => {
+                ErrorCategory::Storage
+            }
+            ForwarderError::Connector { source, .. } => source.category(),
+        }
+    }
+
+    pub fn exit_code(&self) -> i32 {
+        self.category().exit_code()
+    }
+
+    pub fn is_retryable(&self) -> bool {
+        self.category().is_retryable()
+    }
+}
+
+fn status_category(status: StatusCode) -> ErrorCategory {
+    match status {
+        StatusCode::UNAUTHORIZED | StatusCode::FORBIDDEN | StatusCode::PROXY_AUTHENTICATION_REQUIRED => {
+            ErrorCategory::Auth
+        }
+        StatusCode::REQUEST_TIMEOUT | StatusCode::TOO_MANY_REQUESTS => ErrorCategory::TransientNetwork,
+        status if status.is_server_error() => ErrorCategory::TransientNetwork,
+        _ => ErrorCategory::PermanentDownstream,
+    }
+}
+
+fn http_category(error: &reqwest::Error) -> ErrorCategory {
+    if let Some(status) = error.status() {
+        status_category(status)
+    } else if error.is_builder() {
+        ErrorCategory::Config
+    } else if error.is_decode() {
+        ErrorCategory::PermanentDownstream
+    } else {
+        ErrorCategory::TransientNetwork
+    }
 }
 
 pub type Result<T> = std::result::Result<T, ForwarderError>;
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_status_classification() {
+        let category = |status| ForwarderError::from_status("hook", status, "").category();
+
+        assert_eq!(category(StatusCode::UNAUTHORIZED), ErrorCategory::Auth);
+        assert_eq!(category(StatusCode::TOO_MANY_REQUESTS), ErrorCategory::TransientNetwork);
+        assert_eq!(category(StatusCode::BAD_GATEWAY), ErrorCategory::TransientNetwork);
+        assert_eq!(category(StatusCode::BAD_REQUEST), ErrorCategory::PermanentDownstream);
+    }
+
+    #[test]
+    fn test_connector_context_keeps_category() {
+        let error = ForwarderError::from_status("https://hook", StatusCode::FORBIDDEN, "denied")
+            .in_connector("primary");
+
+        assert_eq!(error.category(), ErrorCategory::Auth);
+        assert_eq!(error.exit_code(), 3);
+        assert!(!error.is_retryable());
+        assert_eq!(
+            error.to_string(),
+            "Connector error: primary: Authentication error: https://hook: HTTP 403 Forbidden: denied"
+        );
+    }
+}
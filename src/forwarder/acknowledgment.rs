@@ -0,0 +1,156 @@
+use reqwest::{header, Client, Response, StatusCode};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::time::Duration;
+use tokio::time::{sleep, Instant};
+use crate::config::AcknowledgmentConfig;
+use crate::error::{ForwarderError, Result};
+use tracing::{debug, warn};
+
+/// State of an asynchronously processed request, as reported by its status URL
+#[derive(Debug, PartialEq)]
+pub enum AckState {
+    Pending,
+    Accepted,
+    Rejected(String),
+}
+
+/// Follow a 202 Accepted response: poll its status URL until the receiver
+/// accepts or rejects the request, or the acknowledgment timeout passes
+pub async fn await_acknowledgment(
+    client: &Client,
+    headers: Option<&HashMap<String, String>>,
+    config: &AcknowledgmentConfig,
+    response: Response,
+    request_timeout: Duration,
+) -> Result<StatusCode> {
+    let Some(status_url) = status_url(config, response).await? else {
+        warn!("Receiver returned 202 without a status URL; treating the request as accepted");
+        return Ok(StatusCode::ACCEPTED);
+    };
+
+    let interval = Duration::from_secs(config.interval_seconds);
+    let deadline = Instant::now() + Duration::from_secs(config.timeout_seconds);
+
+    loop {
+        sleep(interval).await;
+
+        debug!("Checking acknowledgment status at {}", status_url);
+
+        let mut request = client.get(&status_url).timeout(request_timeout);
+        if let Some(headers_map) = headers {
+            for (key, value) in headers_map {
+                request = request.header(key, value);
+            }
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+        let body: Value = response.json().await.unwrap_or(Value::Null);
+
+        match evaluate(config, status, &body) {
+            AckState::Accepted => return Ok(status),
+            AckState::Rejected(reason) => {
+                return Err(ForwarderError::PermanentDownstream {
+                    target: status_url,
+                    message: format!("Request rejected: {}", reason),
+                });
+            }
+            AckState::Pending if Instant::now() >= deadline => {
+                return Err(ForwarderError::TransientNetwork {
+                    target: status_url,
+                    message: format!(
+                        "No acknowledgment within {}s",
+                        config.timeout_seconds
+                    ),
+                });
+            }
+            AckState::Pending => {}
+        }
+    }
+}
+
+/// Extract the status URL from the body field or Location header, resolved
+/// against the request URL
+async fn status_url(config: &AcknowledgmentConfig, response: Response) -> Result<Option<String>> {
+    let request_url = response.url().clone();
+
+    let location = match &config.status_url_field {
+        Some(pointer) => {
+            let body: Value = response.json().await?;
+            body.pointer(pointer).and_then(Value::as_str).map(String::from)
+        }
+        None => response
+            .headers()
+            .get(header::LOCATION)
+            .and_then(|value| value.to_str().ok())
+            .map(String::from),
+    };
+
+    Ok(location.map(|location| {
+        request_url
+            .join(&location)
+            .map(|url| url.to_string())
+            .unwrap_or(location)
+    }))
+}
+
+/// Interpret one status response
+pub fn evaluate(config: &AcknowledgmentConfig, status: StatusCode, body: &Value) -> AckState {
+    if status == StatusCode::ACCEPTED {
+        return AckState::Pending;
+    }
+
+    if !status.is_success() {
+        return AckState::Rejected(format!("status endpoint returned HTTP {}", status));
+    }
+
+    let Some(pointer) = &config.status_field else {
+        return AckState::Accepted;
+    };
+
+    let state = match body.pointer(pointer) {
+        Some(Value::String(state)) => state.clone(),
+        Some(other) => other.to_string(),
+        None => return AckState::Pending,
+    };
+
+    let matches = |values: &[String]| values.iter().any(|v| v.eq_ignore_ascii_case(&state));
+
+    if matches(&config.success_values) {
+        AckState::Accepted
+    } else if matches(&config.failure_values) {
+        AckState::Rejected(format!("status '{}'", state))
+    } else {
+        AckState::Pending
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_evaluate_status_field() {
+        let config: AcknowledgmentConfig = serde_yaml::from_str("status_field: /state").unwrap();
+
+        assert_eq!(evaluate(&config, StatusCode::ACCEPTED, &Value::Null), AckState::Pending);
+        assert_eq!(
+            evaluate(&config, StatusCode::OK, &json!({"state": "IN_PROGRESS"})),
+            AckState::Pending
+        );
+        assert_eq!(
+            evaluate(&config, StatusCode::OK, &json!({"state": "Completed"})),
+            AckState::Accepted
+        );
+        assert!(matches!(
+            evaluate(&config, StatusCode::OK, &json!({"state": "rejected"})),
+            AckState::Rejected(_)
+        ));
+        assert!(matches!(
+            evaluate(&config, StatusCode::NOT_FOUND, &Value::Null),
+            AckState::Rejected(_)
+        ));
+    }
+}
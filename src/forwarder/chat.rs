@@ -0,0 +1,268 @@
+use chrono_tz::Tz;
+use serde_json::{json, Value};
+use crate::dynatrace::models::Runbook;
+use crate::dynatrace::Problem;
+use crate::forwarder::digest;
+use crate::forwarder::evidence::{self, DEFAULT_EVIDENCE_LINES};
+use crate::utils::time::format_local;
+
+/// Build a Webex incoming webhook message (markdown), with times shown in `tz`
+pub fn webex_message(problems: &[Problem], problem_url_base: &str, tz: Tz) -> Value {
+    let mut lines = Vec::new();
+
+    if problems.len() > 1 {
+        lines.push(format!("**{} Dynatrace problems**", problems.len()));
+    }
+
+    for problem in problems {
+        lines.push(format!(
+            "{} **[{}] {}** — {}",
+            status_icon(problem),
+            problem.display_id,
+            problem.title,
+            problem.status
+        ));
+        lines.push(format!(
+            "Severity: {} · Impact: {} · Started: {}",
+            problem.severity_level,
+            problem.impact_level,
+            format_local(problem.start_time, tz)
+        ));
+
+        if !problem.management_zones.is_empty() {
+            lines.push(format!("Management zones: {}", problem.management_zone_names().join(", ")));
+        }
+
+        if let Some(root_cause) = &problem.root_cause_entity {
+            lines.push(format!("Root cause: {}", root_cause.name));
+        }
+
+        for evidence_line in evidence_summary(problem) {
+            lines.push(format!("- {}", evidence_line));
+        }
+
+        if !problem.correlated_problems.is_empty() {
+            lines.push(format!("Related problems: {}", related_summary(problem)));
+        }
+
+        for runbook in &problem.runbooks {
+            lines.push(format!("[{}]({})", runbook_label(runbook), runbook.url));
+        }
+
+        lines.push(format!("[Open in Dynatrace]({}{})", problem_url_base, problem.problem_id));
+    }
+
+    json!({ "markdown": lines.join("\n") })
+}
+
+/// Build a Google Chat incoming webhook message with one card per problem,
+/// with times shown in `tz`
+pub fn google_chat_message(problems: &[Problem], problem_url_base: &str, tz: Tz) -> Value {
+    let cards: Vec<Value> = problems
+        .iter()
+        .map(|problem| {
+            let mut widgets = vec![
+                json!({
+                    "decoratedText": { "topLabel": "Impact", "text": problem.impact_level }
+                }),
+                json!({
+                    "decoratedText": { "topLabel": "Started", "text": format_local(problem.start_time, tz) }
+                }),
+            ];
+
+            if !problem.management_zones.is_empty() {
+                widgets.push(json!({
+                    "decoratedText": {
+                        "topLabel": "Management zones",
+                        "text": problem.management_zone_names().join(", ")
+                    }
+                }));
+            }
+
+            if let Some(root_cause) = &problem.root_cause_entity {
+                widgets.push(json!({
+                    "decoratedText": { "topLabel": "Root cause", "text": root_cause.name }
+                }));
+            }
+
+            let evidence_lines = evidence_summary(problem);
+            if !evidence_lines.is_empty() {
+                widgets.push(json!({
+                    "decoratedText": {
+                        "topLabel": "Evidence",
+                        "text": evidence_lines.join("\n"),
+                        "wrapText": true
+                    }
+                }));
+            }
+
+            if !problem.correlated_problems.is_empty() {
+                widgets.push(json!({
+                    "decoratedText": {
+                        "topLabel": "Related problems",
+                        "text": related_summary(problem),
+                        "wrapText": true
+                    }
+                }));
+            }
+
+            let mut buttons = vec![json!({
+                "text": "Open in Dynatrace",
+                "onClick": {
+                    "openLink": { "url": problem.url(problem_url_base) }
+                }
+            })];
+            buttons.extend(problem.runbooks.iter().map(|runbook| {
+                json!({
+                    "text": runbook_label(runbook),
+                    "onClick": { "openLink": { "url": runbook.url } }
+                })
+            }));
+
+            widgets.push(json!({ "buttonList": { "buttons": buttons } }));
+
+            json!({
+                "cardId": problem.problem_id,
+                "card": {
+                    "header": {
+                        "title": format!("{} [{}] {}", status_icon(problem), problem.display_id, problem.title),
+                        "subtitle": format!("{} · {}", problem.status, problem.severity_level),
+                    },
+                    "sections": [{ "widgets": widgets }]
+                }
+            })
+        })
+        .collect();
+
+    let text = match problems {
+        [problem] => problem.summary(),
+        _ => format!("{} Dynatrace problems", problems.len()),
+    };
+
+    json!({ "text": text, "cardsV2": cards })
+}
+
+/// Build a Webex digest message: counts per management zone and severity, then
+/// the `top_n` most severe problems
+pub fn webex_digest(problems: &[Problem], problem_url_base: &str, top_n: usize, tz: Tz) -> Value {
+    let mut lines = vec![format!("**{}**", digest::summary(problems))];
+
+    for (zone, zone_problems) in digest::group_by_zone(problems) {
+        lines.push(format!(
+            "- {}: {}",
+            zone,
+            digest::severity_counts(zone_problems.into_iter())
+        ));
+    }
+
+    let top = digest::top_problems(problems, top_n);
+    lines.push("**Most severe**".to_string());
+    for problem in &top {
+        lines.push(format!(
+            "{} [{}]({}{}) {} — {} · {} · {}",
+            status_icon(problem),
+            problem.display_id,
+            problem_url_base,
+            problem.problem_id,
+            problem.title,
+            problem.status,
+            problem.severity_level,
+            format_local(problem.start_time, tz)
+        ));
+    }
+
+    if problems.len() > top.len() {
+        lines.push(format!("…and {} more", problems.len() - top.len()));
+    }
+
+    json!({ "markdown": lines.join("\n") })
+}
+
+/// Build a Google Chat digest card: counts per management zone and severity,
+/// then the `top_n` most severe problems
+pub fn google_chat_digest(problems: &[Problem], problem_url_base: &str, top_n: usize, tz: Tz) -> Value {
+    let zone_widgets: Vec<Value> = digest::group_by_zone(problems)
+        .into_iter()
+        .map(|(zone, zone_problems)| {
+            json!({
+                "decoratedText": {
+                    "topLabel": zone,
+                    "text": digest::severity_counts(zone_problems.into_iter()),
+                    "wrapText": true
+                }
+            })
+        })
+        .collect();
+
+    let top = digest::top_problems(problems, top_n);
+    let mut problem_widgets: Vec<Value> = top
+        .iter()
+        .map(|problem| {
+            json!({
+                "decoratedText": {
+                    "topLabel": format!("{} · {} · {}", problem.status, problem.severity_level, format_local(problem.start_time, tz)),
+                    "text": format!("{} [{}] {}", status_icon(problem), problem.display_id, problem.title),
+                    "wrapText": true,
+                    "button": {
+                        "text": "Open",
+                        "onClick": {
+                            "openLink": { "url": problem.url(problem_url_base) }
+                        }
+                    }
+                }
+            })
+        })
+        .collect();
+
+    if problems.len() > top.len() {
+        problem_widgets.push(json!({
+            "textParagraph": { "text": format!("…and {} more", problems.len() - top.len()) }
+        }));
+    }
+
+    let summary = digest::summary(problems);
+    json!({
+        "text": summary,
+        "cardsV2": [{
+            "cardId": "digest",
+            "card": {
+                "header": { "title": summary },
+                "sections": [
+                    { "header": "By management zone", "widgets": zone_widgets },
+                    { "header": "Most severe", "widgets": problem_widgets }
+                ]
+            }
+        }]
+    })
+}
+
+/// Evidence lines for a problem, empty when evidence details were not fetched
+fn evidence_summary(problem: &Problem) -> Vec<String> {
+    problem
+        .evidence_details
+        .as_ref()
+        .map(|details| evidence::summarize(details, DEFAULT_EVIDENCE_LINES))
+        .unwrap_or_default()
+}
+
+/// Correlated child problems as "[P-2] title, [P-3] title"
+fn related_summary(problem: &Problem) -> String {
+    problem
+        .correlated_problems
+        .iter()
+        .map(|child| format!("[{}] {}", child.display_id, child.title))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn runbook_label(runbook: &Runbook) -> &str {
+    runbook.name.as_deref().unwrap_or("Runbook")
+}
+
+fn status_icon(problem: &Problem) -> &'static str {
+    if problem.is_open() {
+        "🔴"
+    } else {
+        "✅"
+    }
+}
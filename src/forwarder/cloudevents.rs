@@ -0,0 +1,131 @@
+use chrono::{DateTime, SecondsFormat};
+use serde_json::{json, Value};
+use crate::config::{CloudEventsConfig, CloudEventsMode, ConnectorConfig};
+use crate::dynatrace::{Problem, ProblemStatus};
+use crate::error::Result;
+use crate::forwarder::payload;
+
+pub const SPEC_VERSION: &str = "1.0";
+
+const STRUCTURED_CONTENT_TYPE: &str = "application/cloudevents+json";
+const BATCH_CONTENT_TYPE: &str = "application/cloudevents-batch+json";
+
+/// Context attributes of the event describing a problem's current state
+struct Attributes {
+    id: String,
+    source: String,
+    event_type: String,
+    subject: String,
+    time: Option<String>,
+}
+
+fn attributes(problem: &Problem, config: &CloudEventsConfig, problem_url_base: &str) -> Attributes {
+    let status = match problem.status {
+        ProblemStatus::Open => "open",
+        ProblemStatus::Closed => "closed",
+        ProblemStatus::Resolved => "resolved",
+    };
+
+    // An open problem happened at its start, a closed one at its end
+    let millis = if problem.status != ProblemStatus::Open && problem.end_time > 0 {
+        problem.end_time
+    } else {
+        problem.start_time
+    };
+
+    Attributes {
+        // Stable per state change, so receivers can deduplicate redeliveries
+        id: format!("{}-{}", problem.problem_id, status),
+        source: config.source.clone().unwrap_or_else(|| environment_url(problem.url_base(problem_url_base))),
+        event_type: format!("{}.{}", config.type_prefix, status),
+        subject: problem.display_id.clone(),
+        time: DateTime::from_timestamp_millis(millis)
+            .map(|time| time.to_rfc3339_opts(SecondsFormat::Millis, true)),
+    }
+}
+
+/// Dynatrace environment URL, derived from the problem link prefix
+fn environment_url(problem_url_base: &str) -> String {
+    problem_url_base
+        .split("/#")
+        .next()
+        .unwrap_or(problem_url_base)
+        .to_string()
+}
+
+/// Structured-mode envelope carrying one problem as `data`
+fn envelope(
+    problem: &Problem,
+    connector: &ConnectorConfig,
+    config: &CloudEventsConfig,
+    problem_url_base: &str,
+) -> Result<Value> {
+    let attributes = attributes(problem, config, problem_url_base);
+
+    let mut event = json!({
+        "specversion": SPEC_VERSION,
+        "id": attributes.id,
+        "source": attributes.source,
+        "type": attributes.event_type,
+        "subject": attributes.subject,
+        "datacontenttype": "application/json",
+        "data": payload::problem_payload(problem, connector, problem_url_base)?,
+    });
+    if let Some(time) = attributes.time {
+        event["time"] = json!(time);
+    }
+
+    Ok(event)
+}
+
+/// Request body for a webhook connector in CloudEvents format. In binary mode
+/// the body is the plain problem JSON; the attributes go in headers.
+pub fn wrap(
+    problems: &[Problem],
+    batch: bool,
+    connector: &ConnectorConfig,
+    config: &CloudEventsConfig,
+    problem_url_base: &str,
+) -> Result<Value> {
+    match config.mode {
+        CloudEventsMode::Structured if batch => problems
+            .iter()
+            .map(|problem| envelope(problem, connector, config, problem_url_base))
+            .collect::<Result<Vec<_>>>()
+            .map(Value::Array),
+        CloudEventsMode::Structured => envelope(&problems[0], connector, config, problem_url_base),
+        CloudEventsMode::Binary => payload::problem_payload(&problems[0], connector, problem_url_base),
+    }
+}
+
+/// Content type and, in binary mode, `ce-*` attribute headers for a request
+pub fn http_headers(
+    problems: &[Problem],
+    batch: bool,
+    config: &CloudEventsConfig,
+    problem_url_base: &str,
+) -> Vec<(String, String)> {
+    match config.mode {
+        CloudEventsMode::Structured if batch => {
+            vec![("content-type".to_string(), BATCH_CONTENT_TYPE.to_string())]
+        }
+        CloudEventsMode::Structured => {
+            vec![("content-type".to_string(), STRUCTURED_CONTENT_TYPE.to_string())]
+        }
+        CloudEventsMode::Binary => {
+            let attributes = attributes(&problems[0], config, problem_url_base);
+            let mut headers = vec![
+                ("content-type".to_string(), "application/json".to_string()),
+                ("ce-specversion".to_string(), SPEC_VERSION.to_string()),
+                ("ce-id".to_string(), attributes.id),
+                ("ce-source".to_string(), attributes.source),
+                ("ce-type".to_string(), attributes.event_type),
+                ("ce-subject".to_string(), attributes.subject),
+            ];
+            if let Some(time) = attributes.time {
+                headers.push(("ce-time".to_string(), time));
+            }
+            headers
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::Write;
+use crate::config::GzipConfig;
+use crate::error::Result;
+
+/// Gzip a request body, or `None` when it is below `min_bytes` and should be
+/// sent as is
+pub fn gzip(body: &[u8], config: &GzipConfig) -> Result<Option<Vec<u8>>> {
+    if body.is_empty() || body.len() < config.min_bytes {
+        return Ok(None);
+    }
+
+    let mut encoder = GzEncoder::new(Vec::with_capacity(body.len() / 4), Compression::new(config.level));
+    encoder.write_all(body)?;
+    Ok(Some(encoder.finish()?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use flate2::read::GzDecoder;
+    use std::io::Read;
+
+    #[test]
+    fn test_gzip_round_trip_above_threshold() {
+        let config = GzipConfig { min_bytes: 64, level: 6 };
+        let body = br#"{"problemId":"P-1","title":"High CPU"}"#.repeat(20);
+
+        let compressed = gzip(&body, &config).unwrap().unwrap();
+        assert!(compressed.len() < body.len());
+
+        let mut decompressed = Vec::new();
+        GzDecoder::new(compressed.as_slice()).read_to_end(&mut decompressed).unwrap();
+        assert_eq!(decompressed, body);
+
+        assert!(gzip(b"{}", &config).unwrap().is_none());
+    }
+}
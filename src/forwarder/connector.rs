@@ -1,20 +1,110 @@
-use reqwest::{Client, Response};
-use serde_json::json;
-use std::time::Duration;
-use crate::config::{ConnectorConfig, HttpMethod};
-use crate::dynatrace::Problem;
+use chrono_tz::Tz;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue, CONTENT_ENCODING, CONTENT_TYPE};
+use reqwest::{Client, StatusCode};
+use serde_json::Value;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use crate::config::{ActiveHoursConfig, ConnectorConfig, ConnectorType, DigestConfig, HttpMethod, PayloadFormat, RetryConfig};
+use crate::dynatrace::{Problem, ProblemStatus};
 use crate::error::{ForwarderError, Result};
-use crate::forwarder::retry::retry_with_backoff;
+use crate::forwarder::acknowledgment;
+use crate::forwarder::chat;
+use crate::forwarder::cloudevents;
+use crate::forwarder::compression;
+use crate::forwarder::digest::build_digest;
+use crate::forwarder::elasticsearch::ElasticsearchIndexer;
+use crate::forwarder::eventhubs::EventHubsPublisher;
+use crate::forwarder::exec::ExecRunner;
+use crate::forwarder::file_sink::FileSink;
+use crate::forwarder::form;
+use crate::forwarder::grpc::GrpcForwarder;
+use crate::forwarder::health::{ConnectorHealth, HealthSnapshot};
+use crate::forwarder::payload;
+use crate::forwarder::payload_limit;
+use crate::forwarder::pubsub::PubSubPublisher;
+use crate::forwarder::recurrence::RecurrenceSampler;
+use crate::forwarder::retry::{retry_with_backoff, RetryMetrics, RetryPolicy};
+use crate::forwarder::signing;
+use crate::forwarder::tag_filter::TagFilter;
+use crate::forwarder::throttle::Throttle;
+use crate::forwarder::xmatters::XMattersNotifier;
+use crate::forwarder::xml;
 use tracing::{debug, info, error, warn};
 
 pub struct Connector {
     client: Client,
     config: ConnectorConfig,
+    backend: Option<ServiceBackend>,
+    health: Arc<Mutex<ConnectorHealth>>,
+    retry: RetryConfig,
+    problem_url_base: String,
+    tag_filter: Option<TagFilter>,
+    recurrence: Option<RecurrenceSampler>,
+    throttle: Arc<Throttle>,
+    /// Receiver references captured by `dynatrace_comment.reference_field`, by problem ID
+    references: Arc<Mutex<HashMap<String, String>>>,
+}
+
+/// One request of a delivery that `batch_max_items` or `max_payload_bytes` split
+/// into several, with the problems it carried. A failed result also carries the
+/// problems of the requests that were not sent after it.
+pub struct ChunkResult {
+    pub problems: Vec<Problem>,
+    /// When the request was first attempted
+    pub started: Instant,
+    pub result: Result<StatusCode>,
+}
+
+impl ChunkResult {
+    pub fn new(problems: Vec<Problem>, started: Instant, result: Result<StatusCode>) -> Self {
+        Self { problems, started, result }
+    }
+
+    /// The first failure of a delivery, or the status of its last request
+    pub fn combined(results: Vec<ChunkResult>) -> Result<StatusCode> {
+        let mut status = StatusCode::OK;
+        for chunk in results {
+            status = chunk.result?;
+        }
+        Ok(status)
+    }
+}
+
+/// Connectors that talk to a service API instead of a plain webhook
+#[derive(Clone)]
+enum ServiceBackend {
+    PubSub(Arc<PubSubPublisher>),
+    Elasticsearch(Arc<ElasticsearchIndexer>),
+    EventHubs(Arc<EventHubsPublisher>),
+    XMatters(Arc<XMattersNotifier>),
+    File(Arc<FileSink>),
+    Exec(Arc<ExecRunner>),
+    Grpc(Arc<GrpcForwarder>),
+}
+
+impl ServiceBackend {
+    async fn deliver(&self, problems: &[Problem], timeout: Duration) -> Result<StatusCode> {
+        match self {
+            ServiceBackend::PubSub(publisher) => publisher.publish(problems, timeout).await,
+            ServiceBackend::Elasticsearch(indexer) => indexer.index(problems, timeout).await,
+            ServiceBackend::EventHubs(publisher) => publisher.publish(problems, timeout).await,
+            ServiceBackend::XMatters(notifier) => notifier.notify(problems, timeout).await,
+            ServiceBackend::File(sink) => sink.append(problems),
+            ServiceBackend::Exec(runner) => runner.run(problems, timeout).await,
+            ServiceBackend::Grpc(forwarder) => forwarder.forward(problems, timeout).await,
+        }
+    }
 }
 
 impl Connector {
-    /// Create a new connector
-    pub fn new(config: ConnectorConfig) -> Result<Self> {
+    /// Create a new connector. `problem_url_base` is used for links in chat messages.
+    pub fn new(
+        config: ConnectorConfig,
+        health_window: usize,
+        retry: RetryConfig,
+        problem_url_base: String,
+    ) -> Result<Self> {
         let timeout = Duration::from_secs(config.timeout_seconds.unwrap_or(30));
 
         // Warn if SSL verification is disabled
@@ -25,189 +115,512 @@ impl Connector {
             );
         }
 
-        let client = Client::builder()
+        let mut builder = Client::builder()
             .timeout(timeout)
-            .danger_accept_invalid_certs(!config.verify_ssl)
-            .build()?;
+            .danger_accept_invalid_certs(!config.verify_ssl);
+
+        if let Some(proxy) = &config.proxy {
+            builder = builder.proxy(proxy.to_proxy()?);
+        }
+
+        if let Some(tls) = &config.tls {
+            builder = tls.apply(builder)?;
+        }
+
+        let client = builder.build()?;
+
+        let backend = match config.connector_type {
+            ConnectorType::Webhook | ConnectorType::Webex | ConnectorType::GoogleChat => None,
+            ConnectorType::Pubsub => config.pubsub.clone().map(|pubsub_config| {
+                ServiceBackend::PubSub(Arc::new(PubSubPublisher::new(
+                    client.clone(),
+                    pubsub_config,
+                    config.clone(),
+                    problem_url_base.clone(),
+                )))
+            }),
+            ConnectorType::Elasticsearch => Some(ServiceBackend::Elasticsearch(Arc::new(
+                ElasticsearchIndexer::new(
+                    client.clone(),
+                    config.elasticsearch.clone().unwrap_or_default(),
+                    config.clone(),
+                    problem_url_base.clone(),
+                ),
+            ))),
+            ConnectorType::Eventhubs => config.eventhubs.clone().map(|eh_config| {
+                ServiceBackend::EventHubs(Arc::new(EventHubsPublisher::new(
+                    client.clone(),
+                    eh_config,
+                    config.clone(),
+                    problem_url_base.clone(),
+                )))
+            }),
+            ConnectorType::File => config
+                .file
+                .clone()
+                .map(|file_config| {
+                    FileSink::new(file_config, config.clone(), problem_url_base.clone())
+                        .map(|sink| ServiceBackend::File(Arc::new(sink)))
+                })
+                .transpose()?,
+            ConnectorType::Exec => config.exec.clone().map(|exec_config| {
+                ServiceBackend::Exec(Arc::new(ExecRunner::new(
+                    exec_config,
+                    config.clone(),
+                    problem_url_base.clone(),
+                )))
+            }),
+            ConnectorType::Grpc => Some(ServiceBackend::Grpc(Arc::new(GrpcForwarder::new(
+                config.clone(),
+                problem_url_base.clone(),
+            )?))),
+            ConnectorType::Xmatters => Some(ServiceBackend::XMatters(Arc::new(XMattersNotifier::new(
+                client.clone(),
+                config.xmatters.clone(),
+                config.clone(),
+                problem_url_base.clone(),
+            )))),
+        };
+
+        let tag_filter = config
+            .tag_filter
+            .as_deref()
+            .map(TagFilter::parse)
+            .transpose()
+            .map_err(|e| ForwarderError::Config(format!("Connector '{}' tag_filter is invalid: {}", config.name, e)))?;
+        let recurrence = config
+            .recurrence_sampling
+            .as_ref()
+            .map(|sampling| RecurrenceSampler::new(&config.name, sampling))
+            .transpose()?;
+
+        Ok(Self {
+            client,
+            config,
+            backend,
+            health: Arc::new(Mutex::new(ConnectorHealth::new(health_window))),
+            retry,
+            problem_url_base,
+            tag_filter,
+            recurrence,
+            throttle: Arc::new(Throttle::unlimited()),
+            references: Arc::new(Mutex::new(HashMap::new())),
+        })
+    }
 
-        Ok(Self { client, config })
+    /// Share an outbound throttle with other connectors; every request attempt,
+    /// including retries, waits for it
+    pub fn with_throttle(mut self, throttle: Arc<Throttle>) -> Self {
+        self.throttle = throttle;
+        self
     }
 
     /// Forward a problem to the connector
-    pub async fn forward_problem(&self, problem: &Problem) -> Result<Response> {
-        let max_attempts = self.config.retry_attempts.unwrap_or(3);
+    pub async fn forward_problem(&self, problem: &Problem) -> Result<StatusCode> {
+        if let Some(backend) = &self.backend {
+            return self.deliver_with_retry(backend, vec![problem.clone()]).await;
+        }
 
-        let connector_name = self.config.name.clone();
-        let url = self.config.url.clone();
-        let method = self.config.method.clone();
-        let headers = self.config.headers.clone();
-        let client = self.client.clone();
+        ChunkResult::combined(self.send_fitted(std::slice::from_ref(problem), false).await)
+    }
+
+    /// Deliver problems the way the connector is configured: as batches, or
+    /// one request per problem in individual mode, with one result each
+    pub async fn deliver(&self, problems: &[Problem]) -> Vec<ChunkResult> {
+        if self.is_batch_mode() {
+            return self.forward_problems_batch(problems).await;
+        }
+
+        let mut results = Vec::with_capacity(problems.len());
+        for problem in problems {
+            let started = Instant::now();
+            let result = self.forward_problem(problem).await;
+            results.push(ChunkResult::new(vec![problem.clone()], started, result));
+        }
+        results
+    }
+
+    /// Forward multiple problems to the connector in batch requests of up to
+    /// `batch_max_items`. Returns one result per delivered request and, after
+    /// the first failure, one failed result for the rest, which is not sent.
+    pub async fn forward_problems_batch(&self, problems: &[Problem]) -> Vec<ChunkResult> {
+        // xMatters takes one problem per request, so each is retried on its own
+        // and a retry never triggers the problems before it again
+        let chunks: Vec<&[Problem]> = match &self.backend {
+            Some(ServiceBackend::XMatters(_)) => problems.chunks(1).collect(),
+            _ => self.config.batch_chunks(problems).collect(),
+        };
+        if chunks.len() > 1 {
+            info!(
+                "Sending {} problems to connector '{}' in {} batches of up to {}",
+                problems.len(),
+                self.config.name,
+                chunks.len(),
+                chunks[0].len()
+            );
+        }
 
-        let result = retry_with_backoff(
-            &format!("forward to {}", connector_name),
-            max_attempts,
+        let mut results = Vec::new();
+        let mut sent = 0;
+        for chunk in chunks {
+            sent += chunk.len();
+            match &self.backend {
+                Some(backend) => {
+                    let started = Instant::now();
+                    let result = self.deliver_with_retry(backend, chunk.to_vec()).await;
+                    results.push(ChunkResult::new(chunk.to_vec(), started, result));
+                }
+                None => results.extend(self.send_fitted(chunk, true).await),
+            }
+
+            if let Some(failed) = results.last_mut().filter(|result| result.result.is_err()) {
+                failed.problems.extend_from_slice(&problems[sent..]);
+                break;
+            }
+        }
+        results
+    }
+
+    /// Send a pre-built JSON payload (e.g. a digest) on behalf of these problems.
+    /// It cannot be reshaped, so a body over `max_payload_bytes` fails.
+    pub async fn forward_payload(&self, problems: &[Problem], payload: Value) -> Result<StatusCode> {
+        if let Some(max_bytes) = self.config.max_payload_bytes {
+            let size = payload::webhook_body(&payload, &self.config)?.len();
+            if size > max_bytes {
+                return Err(payload_limit::oversize_error(&self.config, size, max_bytes));
+            }
+        }
+        self.send_with_retry(problems, "digest".to_string(), payload, Vec::new()).await
+    }
+
+    /// Build the webhook requests for these problems within `max_payload_bytes`
+    /// and send them one after another. Stops at the first failure, whose result
+    /// also carries the problems of the requests not sent.
+    async fn send_fitted(&self, problems: &[Problem], batch: bool) -> Vec<ChunkResult> {
+        let requests = match payload_limit::fit(problems, batch, &self.config, &self.problem_url_base) {
+            Ok(requests) => requests,
+            Err(e) => return vec![ChunkResult::new(problems.to_vec(), Instant::now(), Err(e))],
+        };
+        if requests.len() > 1 {
+            info!(
+                "Splitting {} problems into {} requests to connector '{}'",
+                problems.len(),
+                requests.len(),
+                self.config.name
+            );
+        }
+
+        let mut results: Vec<ChunkResult> = Vec::new();
+        for request in requests {
+            if let Some(failed) = results.last_mut().filter(|result| result.result.is_err()) {
+                failed.problems.extend(request.problems);
+                continue;
+            }
+
+            let description = match (batch, request.problems.as_slice()) {
+                (false, [problem]) => format!("problem {}", problem.problem_id),
+                (_, problems) => format!("batch of {} problems", problems.len()),
+            };
+            let headers = self.payload_headers(&request.problems, batch);
+            let started = Instant::now();
+            let result = self
+                .send_with_retry(&request.problems, description, request.payload, headers)
+                .await;
+            results.push(ChunkResult::new(request.problems, started, result));
+        }
+        results
+    }
+
+    /// Headers describing a webhook payload (CEF/LEEF text, XML/SOAP, CloudEvents content type and attributes)
+    fn payload_headers(&self, problems: &[Problem], batch: bool) -> Vec<(String, String)> {
+        match self.config.format {
+            PayloadFormat::Json => {}
+            PayloadFormat::Xml => return xml::http_headers(self.config.xml.as_ref()),
+            PayloadFormat::Form => {
+                return vec![("content-type".to_string(), "application/x-www-form-urlencoded".to_string())];
+            }
+            PayloadFormat::Query => return Vec::new(),
+            PayloadFormat::Cef | PayloadFormat::Leef => {
+                return vec![("content-type".to_string(), "text/plain; charset=utf-8".to_string())];
+            }
+        }
+
+        match &self.config.cloudevents {
+            Some(config) => cloudevents::http_headers(problems, batch, config, &self.problem_url_base),
+            None => Vec::new(),
+        }
+    }
+
+    /// Send a JSON payload to the webhook with retries. `headers` are added to
+    /// the connector's own and may replace the JSON content type.
+    async fn send_with_retry(
+        &self,
+        problems: &[Problem],
+        description: String,
+        payload: Value,
+        headers: Vec<(String, String)>,
+    ) -> Result<StatusCode> {
+        let (policy, timeout) = self.delivery_policy(problems);
+
+        let config = Arc::new(self.request_config(problems));
+        let client = self.client.clone();
+        let health = Arc::clone(&self.health);
+        let throttle = Arc::clone(&self.throttle);
+        let references = Arc::clone(&self.references);
+        let referenced: Vec<String> = problems.iter().map(|p| p.problem_id.clone()).collect();
+
+        let (result, metrics) = retry_with_backoff(
+            &format!("forward {} to {}", description, config.name),
+            &policy,
             move || {
-                let connector_name = connector_name.clone();
-                let url = url.clone();
-                let method = method.clone();
-                let headers = headers.clone();
+                let config = Arc::clone(&config);
                 let client = client.clone();
-                let problem = problem.clone();
+                let description = description.clone();
+                let payload = payload.clone();
+                let headers = headers.clone();
+                let health = Arc::clone(&health);
+                let throttle = Arc::clone(&throttle);
+                let references = Arc::clone(&references);
+                let referenced = referenced.clone();
 
                 Box::pin(async move {
-                    Self::send_request(&client, &url, &method, headers.as_ref(), &problem).await
-                        .map_err(|e| {
-                            ForwarderError::Connector {
-                                connector: connector_name.clone(),
-                                message: e.to_string(),
-                            }
-                        })
+                    let _permit = throttle.acquire().await;
+                    let started = Instant::now();
+                    let result =
+                        Self::send_request(&client, &config, timeout, &description, &payload, &headers).await;
+                    record_health(&health, started, result.is_ok());
+
+                    let (status, reference) = result.map_err(|e| e.in_connector(config.name.as_str()))?;
+                    if let Some(reference) = reference {
+                        let mut references = references.lock().unwrap_or_else(|e| e.into_inner());
+                        for problem_id in referenced {
+                            references.insert(problem_id, reference.clone());
+                        }
+                    }
+                    Ok(status)
                 })
             },
         )
-        .await?;
+        .await;
 
-        Ok(result)
+        self.record_retries(&metrics);
+        result
     }
 
-    /// Forward multiple problems to the connector in a single batch request
-    pub async fn forward_problems_batch(&self, problems: &[Problem]) -> Result<Response> {
-        let max_attempts = self.config.retry_attempts.unwrap_or(3);
+    /// Connector config for a request about these problems, with the URL and
+    /// method replaced by `close_endpoint` for a problem that is no longer open
+    fn request_config(&self, problems: &[Problem]) -> ConnectorConfig {
+        let mut config = self.config.clone();
+
+        if let (Some(close), [problem]) = (&self.config.close_endpoint, problems) {
+            if problem.status != ProblemStatus::Open {
+                if let Some(url) = &close.url {
+                    config.url = url
+                        .replace("{problemId}", &problem.problem_id)
+                        .replace("{displayId}", &problem.display_id);
+                }
+                if let Some(method) = &close.method {
+                    config.method = method.clone();
+                }
+            }
+        }
+
+        config
+    }
+
+    /// Retry policy and request timeout for these problems, honoring severity overrides
+    fn delivery_policy(&self, problems: &[Problem]) -> (RetryPolicy, Duration) {
+        let (attempts, timeout_secs) = self
+            .config
+            .delivery_policy(problems.iter().map(|p| p.severity_level.as_str()));
+
+        let policy = RetryPolicy::new(
+            attempts,
+            Duration::from_secs(self.retry.max_delay_seconds),
+            Duration::from_secs(self.retry.max_elapsed_seconds),
+        );
+
+        (policy, Duration::from_secs(timeout_secs))
+    }
 
+    /// Add a delivery's retry cost to the connector's health totals
+    fn record_retries(&self, metrics: &RetryMetrics) {
+        let mut health = self.health.lock().unwrap_or_else(|e| e.into_inner());
+        health.record_retries(metrics);
+    }
+
+    /// Deliver problems through a service backend (Pub/Sub, Elasticsearch, ...)
+    async fn deliver_with_retry(
+        &self,
+        backend: &ServiceBackend,
+        problems: Vec<Problem>,
+    ) -> Result<StatusCode> {
+        let (policy, timeout) = self.delivery_policy(&problems);
         let connector_name = self.config.name.clone();
-        let url = self.config.url.clone();
-        let method = self.config.method.clone();
-        let headers = self.config.headers.clone();
-        let client = self.client.clone();
-        let problems = problems.to_vec();
+        let backend = backend.clone();
+        let health = Arc::clone(&self.health);
+        let throttle = Arc::clone(&self.throttle);
 
-        let result = retry_with_backoff(
-            &format!("forward batch to {}", connector_name),
-            max_attempts,
+        let (result, metrics) = retry_with_backoff(
+            &format!("deliver to {}", connector_name),
+            &policy,
             move || {
                 let connector_name = connector_name.clone();
-                let url = url.clone();
-                let method = method.clone();
-                let headers = headers.clone();
-                let client = client.clone();
+                let backend = backend.clone();
                 let problems = problems.clone();
+                let health = Arc::clone(&health);
+                let throttle = Arc::clone(&throttle);
 
                 Box::pin(async move {
-                    Self::send_batch_request(&client, &url, &method, headers.as_ref(), &problems).await
-                        .map_err(|e| {
-                            ForwarderError::Connector {
-                                connector: connector_name.clone(),
-                                message: e.to_string(),
-                            }
-                        })
+                    let _permit = throttle.acquire().await;
+                    let started = Instant::now();
+                    let result = backend.deliver(&problems, timeout).await;
+                    record_health(&health, started, result.is_ok());
+
+                    result.map_err(|e| e.in_connector(connector_name.as_str()))
                 })
             },
         )
-        .await?;
+        .await;
 
-        Ok(result)
+        self.record_retries(&metrics);
+        result
     }
 
-    /// Send HTTP request with problem payload
+    /// Send HTTP request with a JSON payload, following up on 202 Accepted
+    /// responses when acknowledgment polling is configured. Also returns the
+    /// reference at `dynatrace_comment.reference_field` of the response, if any.
     async fn send_request(
         client: &Client,
-        url: &str,
-        method: &HttpMethod,
-        headers: Option<&std::collections::HashMap<String, String>>,
-        problem: &Problem,
-    ) -> Result<Response> {
-        debug!("Sending problem {} to {}", problem.problem_id, url);
+        config: &ConnectorConfig,
+        timeout: Duration,
+        description: &str,
+        payload: &Value,
+        payload_headers: &[(String, String)],
+    ) -> Result<(StatusCode, Option<String>)> {
+        // Query fields go on the URL instead of the body
+        let url = match payload {
+            Value::String(query) if config.format == PayloadFormat::Query => form::with_query(&config.url, query),
+            _ => config.url.clone(),
+        };
+        let url = url.as_str();
+        debug!("Sending {} to {}", description, url);
 
         // Build the request
-        let mut request = match method {
+        let mut request = match config.method {
             HttpMethod::Post => client.post(url),
             HttpMethod::Put => client.put(url),
             HttpMethod::Patch => client.patch(url),
             HttpMethod::Get => client.get(url),
-        };
+        }
+        .timeout(timeout);
 
         // Add custom headers
-        if let Some(headers_map) = headers {
+        if let Some(headers_map) = &config.headers {
             for (key, value) in headers_map {
                 request = request.header(key, value);
             }
         }
 
-        // Add JSON body (serialize the problem)
-        let payload = json!(problem);
-        request = request.json(&payload);
+        // Serialize the body ourselves so the signature covers the exact bytes sent
+        let mut body = payload::webhook_body(payload, config)?;
+        // Compress before signing, so the signature is over the gzipped bytes
+        if let Some(gzip) = &config.gzip {
+            if let Some(compressed) = compression::gzip(&body, gzip)? {
+                debug!("Compressed {} from {} to {} bytes", description, body.len(), compressed.len());
+                body = compressed;
+                request = request.header(CONTENT_ENCODING, "gzip");
+            }
+        }
+        if let Some(signing) = &config.signing {
+            request = request.header(signing.header.as_str(), signing::signature(&signing.secret, &body)?);
+        }
+
+        let mut headers = HeaderMap::new();
+        if config.format != PayloadFormat::Query {
+            headers.insert(CONTENT_TYPE, HeaderValue::from_static("application/json"));
+        }
+        for (key, value) in payload_headers {
+            let name = HeaderName::from_bytes(key.as_bytes())
+                .map_err(|e| ForwarderError::Config(format!("Invalid header name '{}': {}", key, e)))?;
+            let value = HeaderValue::from_str(value)
+                .map_err(|e| ForwarderError::Config(format!("Invalid value for header '{}': {}", key, e)))?;
+            headers.insert(name, value);
+        }
+        request = request.headers(headers).body(body);
 
         // Send request
         let response = request.send().await?;
-        
+
         let status = response.status();
-        
+
         if !status.is_success() {
             let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
             error!(
                 "Connector returned error ({}): {}",
                 status, error_text
             );
-            return Err(ForwarderError::Connector {
-                connector: url.to_string(),
-                message: format!("HTTP {}: {}", status, error_text),
-            });
+            return Err(ForwarderError::from_status(url, status, &error_text));
         }
 
-        debug!("Successfully forwarded problem {} (status: {})", problem.problem_id, status);
-
-        Ok(response)
-    }
-
-    /// Send HTTP request with multiple problems as array payload
-    async fn send_batch_request(
-        client: &Client,
-        url: &str,
-        method: &HttpMethod,
-        headers: Option<&std::collections::HashMap<String, String>>,
-        problems: &[Problem],
-    ) -> Result<Response> {
-        debug!("Sending batch of {} problems to {}", problems.len(), url);
-
-        // Build the request
-        let mut request = match method {
-            HttpMethod::Post => client.post(url),
-            HttpMethod::Put => client.put(url),
-            HttpMethod::Patch => client.patch(url),
-            HttpMethod::Get => client.get(url),
-        };
-
-        // Add custom headers
-        if let Some(headers_map) = headers {
-            for (key, value) in headers_map {
-                request = request.header(key, value);
+        if status == StatusCode::ACCEPTED {
+            if let Some(ack) = &config.acknowledgment {
+                let status = acknowledgment::await_acknowledgment(
+                    client,
+                    config.headers.as_ref(),
+                    ack,
+                    response,
+                    timeout,
+                )
+                .await?;
+
+                debug!("Forward of {} acknowledged (status: {})", description, status);
+                return Ok((status, None));
             }
         }
 
-        // Add JSON body (array of problems)
-        let payload = json!(problems);
-        request = request.json(&payload);
+        debug!("Successfully forwarded {} (status: {})", description, status);
+
+        let reference_field = config.dynatrace_comment.as_ref().and_then(|c| c.reference_field.as_deref());
+        let reference = match reference_field {
+            Some(pointer) => {
+                let body: Value = response.json().await.unwrap_or(Value::Null);
+                let reference = body.pointer(pointer).and_then(|value| match value {
+                    Value::String(reference) => Some(reference.clone()),
+                    Value::Number(reference) => Some(reference.to_string()),
+                    _ => None,
+                });
+                if reference.is_none() {
+                    debug!("No reference at {} in the response to {}", pointer, description);
+                }
+                reference
+            }
+            None => None,
+        };
 
-        // Send request
-        let response = request.send().await?;
+        Ok((status, reference))
+    }
 
-        let status = response.status();
+    /// Take the receiver reference captured for a problem's last delivery, if any
+    pub fn take_reference(&self, problem_id: &str) -> Option<String> {
+        self.references.lock().unwrap_or_else(|e| e.into_inner()).remove(problem_id)
+    }
 
-        if !status.is_success() {
-            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
-            error!(
-                "Connector returned error ({}): {}",
-                status, error_text
-            );
-            return Err(ForwarderError::Connector {
-                connector: url.to_string(),
-                message: format!("HTTP {}: {}", status, error_text),
-            });
-        }
+    /// Comment for the Dynatrace problem after a successful delivery, when configured
+    pub fn dynatrace_comment(&self, display_id: &str, reference: Option<&str>) -> Option<(String, String)> {
+        let comment = self.config.dynatrace_comment.as_ref()?;
+        let template = match (&comment.message, reference) {
+            (Some(message), _) => message.as_str(),
+            (None, Some(_)) => "Forwarded to {connector} as {reference}",
+            (None, None) => "Forwarded to {connector}",
+        };
 
-        debug!("Successfully forwarded batch of {} problems (status: {})", problems.len(), status);
+        let message = template
+            .replace("{connector}", &self.config.name)
+            .replace("{reference}", reference.unwrap_or("(no reference)"))
+            .replace("{displayId}", display_id);
 
-        Ok(response)
+        Some((message, comment.context.clone()))
     }
 
     /// Test the connector with a dummy payload
@@ -229,14 +642,26 @@ impl Connector {
             problem_filters: vec![],
             start_time: chrono::Utc::now().timestamp_millis(),
             end_time: -1,
+            evidence_details: None,
+            recent_comments: None,
+            impact_analysis: None,
+            event: None,
+            security: None,
+            audit: None,
+            runbooks: vec![],
+            correlated_problems: vec![],
+            recurrence: None,
+            tenant: None,
+            tenant_url_base: None,
+            change: None,
         };
 
-        let response = self.forward_problem(&test_problem).await?;
-        
+        let status = self.forward_problem(&test_problem).await?;
+
         info!(
             "✓ Connector '{}' test successful (status: {})",
             self.config.name,
-            response.status()
+            status
         );
 
         Ok(())
@@ -251,4 +676,168 @@ impl Connector {
     pub fn is_batch_mode(&self) -> bool {
         self.config.batch_mode
     }
+
+    /// Get the failover group this connector belongs to, if any
+    pub fn failover_group(&self) -> Option<&str> {
+        self.config.failover_group.as_deref()
+    }
+
+    /// Check if this problem is within the connector's sample and is an update
+    /// listed in its `forward_on`
+    pub fn samples(&self, problem: &Problem) -> bool {
+        self.config.samples(&problem.problem_id) && self.config.forwards(problem)
+    }
+
+    /// Check if the problem is in the connector's management zones and matches its
+    /// tag filter, impact levels and entity types
+    pub fn routes(&self, problem: &Problem) -> bool {
+        if !self.config.impact_levels.is_empty() && !self.config.impact_levels.contains(&problem.impact_level) {
+            return false;
+        }
+
+        if !self.config.entity_types.is_empty()
+            && !problem
+                .affected_entities
+                .iter()
+                .any(|entity| self.config.entity_types.contains(&entity.entity_id.entity_type))
+        {
+            return false;
+        }
+
+        if let Some(filter) = &self.tag_filter {
+            if !filter.matches(&problem.entity_tags) {
+                return false;
+            }
+        }
+
+        let Some(filter) = &self.config.management_zones else {
+            return true;
+        };
+        let zones: Vec<&str> = problem
+            .management_zones
+            .iter()
+            .flat_map(|zone| [zone.name.as_str(), zone.id.as_str()])
+            .collect();
+        filter.matches(&zones)
+    }
+
+    /// Check if failures of this connector are ignored
+    pub fn is_shadow(&self) -> bool {
+        self.config.shadow
+    }
+
+    /// Current rolling health of this connector
+    pub fn health(&self) -> HealthSnapshot {
+        self.health.lock().map(|h| h.snapshot()).unwrap_or_else(|e| e.into_inner().snapshot())
+    }
+
+    /// Recurrence sampling, when configured
+    pub fn recurrence_sampler(&self) -> Option<&RecurrenceSampler> {
+        self.recurrence.as_ref()
+    }
+
+    /// Get the digest settings if digest mode is enabled
+    pub fn digest_config(&self) -> Option<&DigestConfig> {
+        self.config.digest.as_ref().filter(|digest| digest.enabled)
+    }
+
+    /// Get the active hours, if notifications are restricted to them
+    pub fn active_hours(&self) -> Option<&ActiveHoursConfig> {
+        self.config.active_hours.as_ref()
+    }
+
+    /// Check if the connector is outside its active hours right now
+    pub fn is_quiet(&self) -> bool {
+        self.active_hours()
+            .is_some_and(|hours| !hours.is_active(&chrono::Utc::now().with_timezone(&self.timezone())))
+    }
+
+    /// Timezone for schedules and rendered timestamps
+    pub fn timezone(&self) -> Tz {
+        self.config.timezone()
+    }
+
+    /// Digest of these problems in the connector's message format: a chat message
+    /// for Webex and Google Chat, the JSON digest otherwise
+    pub fn digest_payload(&self, problems: &[Problem], top_n: usize) -> Value {
+        let tz = self.timezone();
+        match self.config.connector_type {
+            ConnectorType::Webex => chat::webex_digest(problems, &self.problem_url_base, top_n, tz),
+            ConnectorType::GoogleChat => chat::google_chat_digest(problems, &self.problem_url_base, top_n, tz),
+            _ => build_digest(problems, &self.problem_url_base, top_n, tz),
+        }
+    }
+
+    /// Check if the connector can take a JSON digest instead of its usual payload:
+    /// plain JSON webhooks, not CloudEvents or CEF/LEEF consumers
+    pub fn accepts_digest_payload(&self) -> bool {
+        self.is_webhook() && self.config.cloudevents.is_none() && self.config.format == PayloadFormat::Json
+    }
+
+    /// Check if connector posts to a plain webhook (as opposed to a service API)
+    pub fn is_webhook(&self) -> bool {
+        self.backend.is_none()
+    }
+}
+
+/// Record one request attempt in the connector's rolling health window
+fn record_health(health: &Mutex<ConnectorHealth>, started: Instant, success: bool) {
+    let mut health = health.lock().unwrap_or_else(|e| e.into_inner());
+    health.record(started.elapsed(), success);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+
+    /// Individual mode connector posting to a local receiver that answers
+    /// 503 for the second request; returns the count of requests received
+    fn individual_connector() -> (Connector, Arc<AtomicUsize>) {
+        let received = Arc::new(AtomicUsize::new(0));
+        let counter = received.clone();
+        let app = axum::Router::new().route(
+            "/hook",
+            axum::routing::post(move || {
+                let counter = counter.clone();
+                async move {
+                    match counter.fetch_add(1, Ordering::SeqCst) {
+                        1 => axum::http::StatusCode::SERVICE_UNAVAILABLE,
+                        _ => axum::http::StatusCode::OK,
+                    }
+                }
+            }),
+        );
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}/hook", listener.local_addr().unwrap());
+        tokio::spawn(axum::Server::from_tcp(listener).unwrap().serve(app.into_make_service()));
+
+        let config: ConnectorConfig = serde_yaml::from_str(&format!(
+            "name: hook\nurl: \"{}\"\nbatch_mode: false\nretry_attempts: 1",
+            url
+        ))
+        .unwrap();
+        let connector = Connector::new(config, 10, RetryConfig::default(), String::new()).unwrap();
+        (connector, received)
+    }
+
+    #[tokio::test]
+    async fn test_individual_mode_delivers_every_problem() {
+        let (connector, received) = individual_connector();
+        let problems: Vec<Problem> = (1..=3).map(crate::forwarder::loadtest::synthetic_problem).collect();
+
+        let results = connector.deliver(&problems).await;
+
+        assert_eq!(received.load(Ordering::SeqCst), 3);
+        assert_eq!(results.len(), 3);
+        for (result, problem) in results.iter().zip(&problems) {
+            assert_eq!(result.problems.len(), 1);
+            assert_eq!(result.problems[0].problem_id, problem.problem_id);
+        }
+        assert!(results[0].result.is_ok());
+        assert!(results[1].result.is_err());
+        assert!(results[2].result.is_ok());
+
+        assert!(connector.deliver(&[]).await.is_empty());
+    }
 }
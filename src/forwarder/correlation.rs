@@ -0,0 +1,117 @@
+use crate::config::CorrelationConfig;
+use crate::dynatrace::models::ProblemRef;
+use crate::dynatrace::Problem;
+
+/// Fold problems of one poll cycle that belong together into composite
+/// problems. The earliest problem of a group is kept and carries references
+/// to the others in `correlated_problems`; ungrouped problems pass through.
+/// Only problems with the same status are grouped, so an open cascade is not
+/// mixed with problems that already closed.
+pub fn correlate(problems: Vec<Problem>, config: &CorrelationConfig) -> Vec<Problem> {
+    if !config.enabled || problems.len() < 2 {
+        return problems;
+    }
+
+    let window_millis = config.window_seconds as i64 * 1000;
+
+    // Visit problems in start order so each group's first member is its earliest problem
+    let mut order: Vec<usize> = (0..problems.len()).collect();
+    order.sort_by_key(|&index| problems[index].start_time);
+
+    let mut groups: Vec<Vec<usize>> = Vec::new();
+    for index in order {
+        let problem = &problems[index];
+        let group = groups.iter_mut().find(|group| {
+            let first = &problems[group[0]];
+            first.status == problem.status
+                && ((config.by_root_cause && same_root_cause(first, problem))
+                    || (config.by_management_zone
+                        && shares_management_zone(first, problem)
+                        && problem.start_time - first.start_time <= window_millis))
+        });
+
+        match group {
+            Some(group) => group.push(index),
+            None => groups.push(vec![index]),
+        }
+    }
+
+    // Emit composites in the order their first member was received
+    groups.sort_by_key(|group| group[0]);
+
+    let mut problems: Vec<Option<Problem>> = problems.into_iter().map(Some).collect();
+    groups
+        .into_iter()
+        .filter_map(|group| {
+            let mut parent = problems[group[0]].take()?;
+            parent.correlated_problems = group[1..]
+                .iter()
+                .filter_map(|&index| problems[index].take())
+                .map(|child| ProblemRef::from(&child))
+                .collect();
+            Some(parent)
+        })
+        .collect()
+}
+
+fn same_root_cause(a: &Problem, b: &Problem) -> bool {
+    match (&a.root_cause_entity, &b.root_cause_entity) {
+        (Some(a), Some(b)) => a.entity_id.id == b.entity_id.id,
+        _ => false,
+    }
+}
+
+fn shares_management_zone(a: &Problem, b: &Problem) -> bool {
+    a.management_zones
+        .iter()
+        .any(|zone| b.management_zones.iter().any(|other| other.id == zone.id))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixture_problem(id: &str, start_time: i64, root_cause: Option<&str>) -> Problem {
+        let mut problem: Problem =
+            serde_json::from_str(include_str!("../../tests/fixtures/problems/open_problem.json")).unwrap();
+        problem.problem_id = id.to_string();
+        problem.display_id = format!("P-{}", id);
+        problem.start_time = start_time;
+        match (root_cause, problem.root_cause_entity.as_mut()) {
+            (Some(entity), Some(root)) => root.entity_id.id = entity.to_string(),
+            (None, _) => problem.root_cause_entity = None,
+            _ => panic!("fixture has no root cause entity"),
+        }
+        problem
+    }
+
+    #[test]
+    fn test_groups_by_root_cause_and_zone_window() {
+        let problems = vec![
+            fixture_problem("2", 2_000, Some("HOST-A")),
+            fixture_problem("1", 1_000, Some("HOST-A")),
+            fixture_problem("3", 1_500, Some("HOST-B")),
+            fixture_problem("4", 1_000_000, None),
+        ];
+
+        let by_root_cause = CorrelationConfig {
+            enabled: true,
+            ..CorrelationConfig::default()
+        };
+        let correlated = correlate(problems.clone(), &by_root_cause);
+        let ids: Vec<&str> = correlated.iter().map(|p| p.problem_id.as_str()).collect();
+        assert_eq!(ids, ["1", "3", "4"]);
+        assert_eq!(correlated[0].correlated_problems[0].problem_id, "2");
+
+        let by_zone = CorrelationConfig {
+            enabled: true,
+            by_root_cause: false,
+            by_management_zone: true,
+            window_seconds: 60,
+        };
+        let correlated = correlate(problems, &by_zone);
+        let ids: Vec<&str> = correlated.iter().map(|p| p.problem_id.as_str()).collect();
+        assert_eq!(ids, ["1", "4"]);
+        assert_eq!(correlated[0].correlated_problems.len(), 2);
+    }
+}
@@ -0,0 +1,235 @@
+use chrono::{NaiveTime, TimeZone};
+use chrono_tz::Tz;
+use serde_json::{json, Value};
+use std::collections::BTreeMap;
+use crate::dynatrace::Problem;
+use crate::utils::time::format_local;
+
+/// Default number of problems listed individually in a digest
+pub const DEFAULT_TOP_N: usize = 10;
+
+/// Group name for problems outside every management zone
+pub const NO_MANAGEMENT_ZONE: &str = "(no management zone)";
+
+/// Summarize problems into a single digest payload: counts by severity, status
+/// and management zone plus the most severe problems with links back to
+/// Dynatrace. Start times are rendered in `tz`.
+pub fn build_digest(problems: &[Problem], problem_url_base: &str, top_n: usize, tz: Tz) -> Value {
+    let by_severity = count_by_severity(problems.iter());
+    let mut by_status: BTreeMap<String, usize> = BTreeMap::new();
+
+    for problem in problems {
+        *by_status.entry(problem.status.to_string()).or_default() += 1;
+    }
+
+    let by_zone: BTreeMap<String, Value> = group_by_zone(problems)
+        .into_iter()
+        .map(|(zone, zone_problems)| {
+            let summary = json!({
+                "problemCount": zone_problems.len(),
+                "bySeverity": count_by_severity(zone_problems.into_iter()),
+            });
+            (zone, summary)
+        })
+        .collect();
+
+    let top_problems: Vec<Value> = top_problems(problems, top_n)
+        .into_iter()
+        .map(|p| {
+            json!({
+                "problemId": p.problem_id,
+                "displayId": p.display_id,
+                "title": p.title,
+                "severityLevel": p.severity_level,
+                "status": p.status.to_string(),
+                "startTime": format_local(p.start_time, tz),
+                "url": p.url(problem_url_base),
+            })
+        })
+        .collect();
+
+    json!({
+        "type": "digest",
+        "summary": summary(problems),
+        "problemCount": problems.len(),
+        "bySeverity": by_severity,
+        "byStatus": by_status,
+        "byManagementZone": by_zone,
+        "topProblems": top_problems,
+        "omittedCount": problems.len().saturating_sub(top_n),
+    })
+}
+
+/// One line such as "42 Dynatrace problems (3 AVAILABILITY, 39 PERFORMANCE)"
+pub fn summary(problems: &[Problem]) -> String {
+    format!("{} Dynatrace problems ({})", problems.len(), severity_counts(problems.iter()))
+}
+
+/// Counts such as "3 AVAILABILITY, 39 PERFORMANCE"
+pub fn severity_counts<'a>(problems: impl Iterator<Item = &'a Problem>) -> String {
+    count_by_severity(problems)
+        .iter()
+        .map(|(severity, count)| format!("{} {}", count, severity))
+        .collect::<Vec<_>>()
+        .join(", ")
+}
+
+fn count_by_severity<'a>(problems: impl Iterator<Item = &'a Problem>) -> BTreeMap<&'a str, usize> {
+    let mut by_severity = BTreeMap::new();
+    for problem in problems {
+        *by_severity.entry(problem.severity_level.as_str()).or_default() += 1;
+    }
+    by_severity
+}
+
+/// Problems per management zone name; a problem in several zones is listed under each
+pub fn group_by_zone(problems: &[Problem]) -> BTreeMap<String, Vec<&Problem>> {
+    let mut groups: BTreeMap<String, Vec<&Problem>> = BTreeMap::new();
+    for problem in problems {
+        if problem.management_zones.is_empty() {
+            groups.entry(NO_MANAGEMENT_ZONE.to_string()).or_default().push(problem);
+        }
+        for zone in &problem.management_zones {
+            groups.entry(zone.name.clone()).or_default().push(problem);
+        }
+    }
+    groups
+}
+
+/// The `top_n` most severe problems, most recent first within a severity
+pub fn top_problems(problems: &[Problem], top_n: usize) -> Vec<&Problem> {
+    let mut ranked: Vec<&Problem> = problems.iter().collect();
+    ranked.sort_by_key(|p| (severity_rank(&p.severity_level), std::cmp::Reverse(p.start_time)));
+    ranked.truncate(top_n);
+    ranked
+}
+
+/// Whether one of the local `send_times` (in `tz`) has passed since the oldest
+/// queued problem was added. Timestamps are epoch seconds.
+pub fn schedule_due(send_times: &[NaiveTime], tz: Tz, oldest: i64, now: i64) -> bool {
+    if send_times.is_empty() || now <= oldest {
+        return false;
+    }
+
+    // Every time of day occurs within any 24 hour span
+    if now - oldest >= 86_400 {
+        return true;
+    }
+
+    let (Some(start), Some(end)) = (
+        tz.timestamp_opt(oldest, 0).single(),
+        tz.timestamp_opt(now, 0).single(),
+    ) else {
+        return false;
+    };
+
+    start
+        .date_naive()
+        .iter_days()
+        .take_while(|date| *date <= end.date_naive())
+        .flat_map(|date| send_times.iter().map(move |time| date.and_time(*time)))
+        .filter_map(|local| tz.from_local_datetime(&local).earliest())
+        .any(|scheduled| scheduled.timestamp() > oldest && scheduled.timestamp() <= now)
+}
+
+/// Ordering used to pick the most important problems (lower = more severe)
+pub fn severity_rank(severity: &str) -> u8 {
+    match severity {
+        "AVAILABILITY" => 0,
+        "ERROR" => 1,
+        "PERFORMANCE" => 2,
+        "RESOURCE_CONTENTION" => 3,
+        "CUSTOM_ALERT" => 4,
+        "MONITORING_UNAVAILABLE" => 5,
+        _ => 6,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dynatrace::models::ManagementZone;
+    use crate::dynatrace::ProblemStatus;
+
+    fn problem(id: &str, severity: &str, status: ProblemStatus) -> Problem {
+        Problem {
+            problem_id: id.to_string(),
+            display_id: format!("P-{}", id),
+            title: format!("Problem {}", id),
+            impact_level: "SERVICES".to_string(),
+            severity_level: severity.to_string(),
+            status,
+            affected_entities: vec![],
+            impacted_entities: vec![],
+            root_cause_entity: None,
+            management_zones: vec![],
+            entity_tags: vec![],
+            problem_filters: vec![],
+            start_time: 0,
+            end_time: -1,
+            evidence_details: None,
+            recent_comments: None,
+            impact_analysis: None,
+            event: None,
+            security: None,
+            audit: None,
+            runbooks: vec![],
+            correlated_problems: vec![],
+            recurrence: None,
+            tenant: None,
+            tenant_url_base: None,
+            change: None,
+        }
+    }
+
+    #[test]
+    fn test_digest_counts_and_top_problems() {
+        let problems = vec![
+            problem("1", "PERFORMANCE", ProblemStatus::Open),
+            problem("2", "AVAILABILITY", ProblemStatus::Open),
+            problem("3", "PERFORMANCE", ProblemStatus::Closed),
+        ];
+
+        let digest = build_digest(&problems, "https://dt/#pid=", 2, Tz::UTC);
+
+        assert_eq!(digest["problemCount"], 3);
+        assert_eq!(digest["bySeverity"]["PERFORMANCE"], 2);
+        assert_eq!(digest["byStatus"]["CLOSED"], 1);
+        assert_eq!(digest["topProblems"][0]["problemId"], "2");
+        assert_eq!(digest["topProblems"][0]["url"], "https://dt/#pid=2");
+        assert_eq!(digest["omittedCount"], 1);
+        assert_eq!(digest["byManagementZone"][NO_MANAGEMENT_ZONE]["problemCount"], 3);
+    }
+
+    #[test]
+    fn test_digest_groups_by_zone_and_severity() {
+        let zone = |name: &str| ManagementZone { id: name.to_lowercase(), name: name.to_string() };
+        let mut problems = vec![
+            problem("1", "PERFORMANCE", ProblemStatus::Open),
+            problem("2", "AVAILABILITY", ProblemStatus::Open),
+            problem("3", "AVAILABILITY", ProblemStatus::Open),
+        ];
+        problems[0].management_zones = vec![zone("Payments")];
+        problems[1].management_zones = vec![zone("Payments"), zone("Shop")];
+
+        let digest = build_digest(&problems, "https://dt/#pid=", 10, Tz::UTC);
+        let by_zone = &digest["byManagementZone"];
+
+        assert_eq!(by_zone["Payments"]["problemCount"], 2);
+        assert_eq!(by_zone["Payments"]["bySeverity"]["AVAILABILITY"], 1);
+        assert_eq!(by_zone["Payments"]["bySeverity"]["PERFORMANCE"], 1);
+        assert_eq!(by_zone["Shop"]["problemCount"], 1);
+        assert_eq!(by_zone[NO_MANAGEMENT_ZONE]["bySeverity"]["AVAILABILITY"], 1);
+    }
+
+    #[test]
+    fn test_schedule_due_in_connector_timezone() {
+        let nine = [NaiveTime::from_hms_opt(9, 0, 0).unwrap()];
+        // 2026-02-10 07:30Z and 08:30Z are 08:30 and 09:30 in Berlin (CET)
+        let (before, after) = (1770708600, 1770712200);
+
+        assert!(schedule_due(&nine, Tz::Europe__Berlin, before, after));
+        assert!(!schedule_due(&nine, Tz::UTC, before, after));
+        assert!(!schedule_due(&nine, Tz::Europe__Berlin, after, after + 3600));
+    }
+}
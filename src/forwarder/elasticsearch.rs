@@ -0,0 +1,139 @@
+use chrono::{TimeZone, Utc};
+use chrono_tz::Tz;
+use reqwest::{header, Client, StatusCode};
+use serde_json::{json, Value};
+use std::fmt::Write;
+use std::time::Duration;
+use crate::config::{ConnectorConfig, ElasticsearchConfig};
+use crate::dynatrace::Problem;
+use crate::error::{ForwarderError, Result};
+use crate::forwarder::payload;
+use tracing::debug;
+
+/// Indexes problems into Elasticsearch/OpenSearch via the bulk API
+pub struct ElasticsearchIndexer {
+    client: Client,
+    config: ElasticsearchConfig,
+    connector: ConnectorConfig,
+    problem_url_base: String,
+}
+
+impl ElasticsearchIndexer {
+    /// Create a new indexer sharing the connector's HTTP client
+    pub fn new(client: Client, config: ElasticsearchConfig, connector: ConnectorConfig, problem_url_base: String) -> Self {
+        Self {
+            client,
+            config,
+            connector,
+            problem_url_base,
+        }
+    }
+
+    /// Get the bulk endpoint URL
+    pub fn bulk_url(&self) -> String {
+        format!("{}/_bulk", self.connector.url.trim_end_matches('/'))
+    }
+
+    /// Index problems in a single bulk request
+    pub async fn index(&self, problems: &[Problem], timeout: Duration) -> Result<StatusCode> {
+        let url = self.bulk_url();
+        let body = build_bulk_lines(problems, &self.connector, &self.problem_url_base)?
+            .iter()
+            .map(|line| serde_json::to_string(line).map(|line| line + "\n"))
+            .collect::<std::result::Result<String, _>>()?;
+
+        debug!("Indexing {} problem(s) via {}", problems.len(), url);
+
+        let mut request = self
+            .client
+            .post(&url)
+            .timeout(timeout)
+            .header(header::CONTENT_TYPE, "application/x-ndjson")
+            .body(body);
+
+        if let Some(api_key) = &self.config.api_key {
+            request = request.header(header::AUTHORIZATION, format!("ApiKey {}", api_key));
+        } else if let Some(username) = &self.config.username {
+            request = request.basic_auth(username, self.config.password.as_ref());
+        }
+
+        let response = request.send().await?;
+        let status = response.status();
+
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(ForwarderError::from_status(url, status, &error_text));
+        }
+
+        // The bulk API returns 200 even when individual items fail
+        let result: Value = response.json().await?;
+        if result["errors"].as_bool().unwrap_or(false) {
+            let first_error = result["items"]
+                .as_array()
+                .and_then(|items| items.iter().find_map(|item| item["index"].get("error")))
+                .map(|error| error.to_string())
+                .unwrap_or_else(|| "unknown item error".to_string());
+
+            return Err(ForwarderError::PermanentDownstream {
+                target: url,
+                message: format!("Bulk request had item errors: {}", first_error),
+            });
+        }
+
+        Ok(status)
+    }
+}
+
+/// Build the bulk API lines (action, document, action, document, ...)
+pub fn build_bulk_lines(problems: &[Problem], connector: &ConnectorConfig, problem_url_base: &str) -> Result<Vec<Value>> {
+    let index_pattern = connector
+        .elasticsearch
+        .as_ref()
+        .map(|es| es.index.as_str())
+        .unwrap_or("dt-problems");
+    let mut lines = Vec::with_capacity(problems.len() * 2);
+
+    for problem in problems {
+        lines.push(json!({
+            "index": {
+                "_index": index_name(index_pattern, problem.start_time, connector.timezone())?,
+                "_id": problem.problem_id,
+            }
+        }));
+        lines.push(payload::problem_payload(problem, connector, problem_url_base)?);
+    }
+
+    Ok(lines)
+}
+
+/// Expand strftime placeholders in the index pattern using the problem start
+/// time in the connector's timezone
+pub fn index_name(pattern: &str, start_time_millis: i64, tz: Tz) -> Result<String> {
+    let start_time = tz
+        .timestamp_millis_opt(start_time_millis)
+        .single()
+        .unwrap_or_else(|| Utc::now().with_timezone(&tz));
+
+    let mut name = String::new();
+    write!(name, "{}", start_time.format(pattern)).map_err(|_| {
+        ForwarderError::Config(format!("Invalid elasticsearch index pattern '{}'", pattern))
+    })?;
+
+    Ok(name)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_index_name_uses_problem_start_time() {
+        // 2026-02-10T04:30:00Z
+        assert_eq!(index_name("dt-problems-%Y.%m", 1770697800000, Tz::UTC).unwrap(), "dt-problems-2026.02");
+        assert_eq!(index_name("dt-problems", 1770697800000, Tz::UTC).unwrap(), "dt-problems");
+        assert_eq!(
+            index_name("dt-problems-%Y.%m.%d", 1770697800000, Tz::America__Los_Angeles).unwrap(),
+            "dt-problems-2026.02.09"
+        );
+    }
+}
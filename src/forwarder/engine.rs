@@ -1,40 +1,148 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
 use std::sync::Arc;
-use tokio::time::{sleep, Duration};
-use crate::config::Settings;
-use crate::dynatrace::{DynatraceClient, Problem};
+use tokio::sync::{broadcast, Mutex, Notify};
+use tokio::time::{sleep, sleep_until, Duration, Instant};
+use crate::config::{DatabaseDriver, HeldProblems, Settings};
+use crate::dynatrace::{ApiQuota, DynatraceClient, MaintenanceWindow, Problem, ProblemChange, ProblemStatus, Recurrence};
+use crate::forwarder::correlation;
+use crate::forwarder::digest::{build_digest, schedule_due, DEFAULT_TOP_N};
+use crate::forwarder::events::{EngineEvent, EVENT_CHANNEL_CAPACITY};
+use crate::forwarder::health::HealthSnapshot;
+use crate::forwarder::priority::prioritize;
+use crate::forwarder::receiver::{PushQueue, PushedProblem};
+use crate::forwarder::retry::RetryPolicy;
+use crate::forwarder::report::{CycleReport, DeliveryOutcome};
+use crate::forwarder::routing::Router;
+use crate::forwarder::runbook::RunbookMatcher;
+use crate::forwarder::throttle::Throttle;
+use crate::forwarder::connector::ChunkResult;
 use crate::forwarder::Connector;
-use crate::storage::{Database, ForwardedProblem, ForwardHistory};
-use crate::error::Result;
-use tracing::{info, error, debug};
+use crate::storage::{Database, FlapState, ForwardedProblem, ForwardHistory, PollCycle, SKIPPED, SUPPRESSED, UNROUTED};
+use crate::error::{ForwarderError, Result};
+use tracing::{info, error, debug, warn};
 
 pub struct ForwardingEngine {
     settings: Arc<Settings>,
-    dynatrace_client: Arc<DynatraceClient>,
+    /// One client per environment, the main one first
+    dynatrace_clients: Vec<Arc<DynatraceClient>>,
+    /// Problem link prefixes of the environments, when several are polled
+    tenant_url_bases: HashMap<String, String>,
     connectors: Vec<Arc<Connector>>,
+    runbooks: RunbookMatcher,
+    router: Option<Router>,
     database: Arc<Database>,
+    events: broadcast::Sender<EngineEvent>,
+    poll_trigger: Arc<Notify>,
+    /// Problems announced by the push receiver
+    push_queue: PushQueue,
+    /// Fetch problems every cycle; without, cycles only handle pushed problems and housekeeping
+    polling: bool,
+    /// Maintenance windows per environment from the last successful fetch and when it happened
+    maintenance_windows: Mutex<HashMap<String, (Instant, Vec<MaintenanceWindow>)>>,
+    /// Epoch milliseconds the engine was created
+    started_at: i64,
 }
 
 impl ForwardingEngine {
     /// Create a new forwarding engine
     pub async fn new(settings: Settings) -> Result<Self> {
-        let dynatrace_client = Arc::new(DynatraceClient::new(&settings)?);
-        
-        let database = Arc::new(Database::new(&settings.database.path).await?);
+        let environments = settings.dynatrace_environments();
+        let dynatrace_clients = environments
+            .iter()
+            .map(|environment| DynatraceClient::for_environment(&settings, environment).map(Arc::new))
+            .collect::<Result<Vec<_>>>()?;
+        let tenant_url_bases = if settings.is_multi_tenant() {
+            environments
+                .iter()
+                .map(|environment| (environment.name.clone(), environment.problem_url_base()))
+                .collect()
+        } else {
+            HashMap::new()
+        };
+
+        let database = Arc::new(
+            Database::new(&settings.database)
+                .await?
+                .with_instance_id(settings.instance_id()),
+        );
+
+        // One throttle for all connectors: the limits are process-wide
+        let throttle = Arc::new(Throttle::new(&settings.outbound));
 
         let mut connectors = Vec::new();
         for connector_config in &settings.connectors {
-            let connector = Connector::new(connector_config.clone())?;
+            let connector = Connector::new(
+                connector_config.clone(),
+                settings.health.window,
+                settings.retry.clone(),
+                settings.get_problem_url_base(),
+            )?
+            .with_throttle(Arc::clone(&throttle));
             connectors.push(Arc::new(connector));
         }
 
+        let runbooks = RunbookMatcher::new(&settings.runbooks)?;
+        let router = settings.routing.as_ref().map(Router::new).transpose()?;
+
+        let (events, _) = broadcast::channel(EVENT_CHANNEL_CAPACITY);
+
         Ok(Self {
             settings: Arc::new(settings),
-            dynatrace_client,
+            dynatrace_clients,
+            tenant_url_bases,
             connectors,
+            runbooks,
+            router,
             database,
+            events,
+            poll_trigger: Arc::new(Notify::new()),
+            push_queue: PushQueue::default(),
+            polling: true,
+            maintenance_windows: Mutex::new(HashMap::new()),
+            started_at: chrono::Utc::now().timestamp_millis(),
         })
     }
 
+    /// Subscribe to engine events. Subscribe before calling `run` to see every cycle;
+    /// a subscriber that falls more than EVENT_CHANNEL_CAPACITY events behind gets
+    /// `RecvError::Lagged` and skips ahead.
+    pub fn subscribe(&self) -> broadcast::Receiver<EngineEvent> {
+        self.events.subscribe()
+    }
+
+    /// Handle that makes a running engine start its next poll cycle immediately.
+    /// A trigger received during a cycle starts another one right after it.
+    pub fn poll_trigger(&self) -> Arc<Notify> {
+        self.poll_trigger.clone()
+    }
+
+    /// Queue the push receiver adds notified problems to; each addition starts a push cycle
+    pub fn push_queue(&self) -> PushQueue {
+        self.push_queue.clone()
+    }
+
+    /// Stop polling Dynatrace: problems only arrive through the push receiver, and
+    /// interval cycles just send due digests and held problems (`dtpf serve`)
+    pub fn without_polling(mut self) -> Self {
+        self.polling = false;
+        self
+    }
+
+    /// Check that the API token of every environment has the scopes the enabled
+    /// features need, so a missing scope fails at startup instead of as a 403 later
+    pub async fn verify_token_scopes(&self) -> Result<()> {
+        let required = self.settings.required_token_scopes();
+        for client in &self.dynatrace_clients {
+            client.verify_token_scopes(&required).await?;
+        }
+        Ok(())
+    }
+
+    /// Publish an event to subscribers (no-op when nobody is listening)
+    fn emit(&self, event: EngineEvent) {
+        let _ = self.events.send(event);
+    }
+
     /// Start the polling loop
     pub async fn run(&self) -> Result<()> {
         info!("Starting Dynatrace Problem Forwarder...");
@@ -43,43 +151,565 @@ impl ForwardingEngine {
 
         let interval = Duration::from_secs(self.settings.polling.interval_seconds);
 
+        self.verify_token_scopes().await?;
+
+        let startup_delay = self.settings.polling.startup_delay();
+        if !startup_delay.is_zero() {
+            info!("Delaying first poll by {:.1}s", startup_delay.as_secs_f64());
+            tokio::select! {
+                _ = sleep(startup_delay) => {}
+                _ = self.poll_trigger.notified() => {
+                    info!("Poll cycle triggered on demand");
+                }
+            }
+        }
+
+        let poll_events = self.polling && self.settings.events.enabled;
+        let events_interval = Duration::from_secs(
+            self.settings
+                .events
+                .interval_seconds
+                .unwrap_or(self.settings.polling.interval_seconds),
+        );
+
+        let poll_security = self.polling && self.settings.security_problems.enabled;
+        let security_interval = Duration::from_secs(
+            self.settings
+                .security_problems
+                .interval_seconds
+                .unwrap_or(self.settings.polling.interval_seconds),
+        );
+
+        let poll_audit_logs = self.polling && self.settings.audit_logs.enabled;
+        let audit_logs_interval = Duration::from_secs(
+            self.settings
+                .audit_logs
+                .interval_seconds
+                .unwrap_or(self.settings.polling.interval_seconds),
+        );
+
+        let mut source = CycleSource::Poll;
+        let mut next_poll = Instant::now();
+        let mut next_events_poll = Instant::now();
+        let mut next_security_poll = Instant::now();
+        let mut next_audit_logs_poll = Instant::now();
+
         loop {
-            if let Err(e) = self.poll_and_forward().await {
-                error!("Error in polling cycle: {}", e);
+            let mut report = CycleReport::start(self.connectors.iter().map(|c| c.name()));
+            let result = match source {
+                CycleSource::Poll if self.polling => self.poll_and_forward(&mut report).await,
+                CycleSource::Poll => self.process_problems(Vec::new(), source, &mut report).await,
+                CycleSource::Push => self.forward_pushed(&mut report).await,
+                CycleSource::Events => self.forward_events(&mut report).await,
+                CycleSource::Security => self.forward_security_problems(&mut report).await,
+                CycleSource::AuditLogs => self.forward_audit_logs(&mut report).await,
+            };
+            if let Err(e) = result {
+                error!("Error in {} cycle: {}", source.as_str(), e);
+                if source == CycleSource::Poll {
+                    self.emit(EngineEvent::PollFailed { error: e.to_string() });
+                }
+                report.error = Some(e.to_string());
             }
+            self.write_cycle_report(report).await;
 
-            debug!("Sleeping for {}s until next poll...", self.settings.polling.interval_seconds);
-            sleep(interval).await;
+            // Push cycles run in between polls without delaying the next one
+            match source {
+                CycleSource::Poll => {
+                    next_poll = Instant::now() + interval;
+                    debug!("Sleeping for {}s until next poll...", self.settings.polling.interval_seconds);
+                }
+                CycleSource::Events => next_events_poll = Instant::now() + events_interval,
+                CycleSource::Security => next_security_poll = Instant::now() + security_interval,
+                CycleSource::AuditLogs => next_audit_logs_poll = Instant::now() + audit_logs_interval,
+                CycleSource::Push => {}
+            }
+            source = tokio::select! {
+                _ = sleep_until(next_poll) => CycleSource::Poll,
+                _ = sleep_until(next_events_poll), if poll_events => CycleSource::Events,
+                _ = sleep_until(next_security_poll), if poll_security => CycleSource::Security,
+                _ = sleep_until(next_audit_logs_poll), if poll_audit_logs => CycleSource::AuditLogs,
+                _ = self.poll_trigger.notified() => {
+                    info!("Poll cycle triggered on demand");
+                    CycleSource::Poll
+                }
+                _ = self.push_queue.notified() => CycleSource::Push,
+            };
         }
     }
 
-    /// Poll Dynatrace and forward problems
-    async fn poll_and_forward(&self) -> Result<()> {
+    /// Poll Dynatrace and forward problems, filling in the cycle report
+    async fn poll_and_forward(&self, report: &mut CycleReport) -> Result<()> {
         info!("Polling Dynatrace for problems...");
+        self.emit(EngineEvent::PollStarted);
+
+        // Earlier failures go first, so newer states of the same problems follow them
+        if self.settings.redelivery.enabled {
+            self.redeliver_failed(report).await;
+        }
+
+        let polled_at = chrono::Utc::now().timestamp_millis();
+        let polled = if self.settings.polling.stream_pages {
+            self.stream_and_forward(polled_at, report).await?
+        } else {
+            let (fetched, polled) = self.fetch_problems(polled_at).await?;
+
+            info!("Found {} problems to process", fetched.len());
+
+            self.process_problems(fetched, CycleSource::Poll, report).await?;
+            polled
+        };
+
+        if self.settings.dynatrace.since_last_poll {
+            for environment in polled {
+                self.database
+                    .set_app_state(&poll_window_key(&environment), &polled_at.to_string())
+                    .await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Fetch the configured events of every environment and forward them like
+    /// problems. An environment whose events cannot be fetched is skipped.
+    async fn forward_events(&self, report: &mut CycleReport) -> Result<()> {
+        info!("Polling Dynatrace for events...");
+        let config = &self.settings.events;
+
+        let responses = futures::future::join_all(self.dynatrace_clients.iter().map(|client| {
+            client.fetch_events(config.event_selector.as_deref(), config.from.as_deref())
+        }))
+        .await;
+
+        let mut problems = Vec::new();
+        let mut failures = 0;
+        let mut last_error = None;
+        for (client, response) in self.dynatrace_clients.iter().zip(responses) {
+            match response {
+                Ok(events) => problems.extend(events.into_iter().map(|event| {
+                    let mut problem = event.into_problem();
+                    if self.settings.is_multi_tenant() {
+                        self.tag_tenant(client, &mut problem);
+                    }
+                    problem
+                })),
+                Err(e) => {
+                    error!("Failed to fetch events from environment '{}': {}", client.environment(), e);
+                    failures += 1;
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        if let Some(e) = last_error.filter(|_| failures == self.dynatrace_clients.len()) {
+            return Err(e);
+        }
+
+        info!("Found {} events to process", problems.len());
+
+        self.process_problems(problems, CycleSource::Events, report).await
+    }
+
+    /// Fetch the security problems of every environment and forward those that are
+    /// new or whose status or risk level changed since they were last forwarded.
+    /// Security problems first seen after they were resolved or muted are only
+    /// recorded. An environment whose security problems cannot be fetched is skipped.
+    async fn forward_security_problems(&self, report: &mut CycleReport) -> Result<()> {
+        info!("Polling Dynatrace for security problems...");
+        let selector = self.settings.security_problems.selector.as_deref();
+
+        let responses = futures::future::join_all(
+            self.dynatrace_clients
+                .iter()
+                .map(|client| client.fetch_security_problems(selector)),
+        )
+        .await;
+
+        let mut cycle = self.start_cycle().await;
+        let mut to_forward = Vec::new();
+        let mut failures = 0;
+        let mut last_error = None;
+        for (client, response) in self.dynatrace_clients.iter().zip(responses) {
+            let security_problems = match response {
+                Ok(security_problems) => security_problems,
+                Err(e) => {
+                    error!("Failed to fetch security problems from environment '{}': {}", client.environment(), e);
+                    failures += 1;
+                    last_error = Some(e);
+                    continue;
+                }
+            };
+
+            for security_problem in security_problems {
+                cycle.fetched += 1;
+                let status = security_problem.tracked_status().to_string();
+                let risk_level = security_problem.risk_level().map(str::to_string);
+
+                let change = match self
+                    .database
+                    .get_forwarded_security_problem(&security_problem.security_problem_id)
+                    .await?
+                {
+                    Some(previous) if previous.status == status && previous.risk_level == risk_level => {
+                        continue;
+                    }
+                    Some(_) => ProblemChange::StatusChange,
+                    None => ProblemChange::New,
+                };
+
+                self.database
+                    .upsert_forwarded_security_problem(
+                        &security_problem.security_problem_id,
+                        &security_problem.display_id,
+                        &status,
+                        risk_level.as_deref(),
+                    )
+                    .await?;
+
+                if change == ProblemChange::New && status != "OPEN" {
+                    debug!("Recording {} security problem {} without forwarding", status, security_problem.display_id);
+                    continue;
+                }
+                if cycle.muted.contains(&security_problem.security_problem_id) {
+                    info!("Security problem {} is muted; not forwarding", security_problem.display_id);
+                    cycle.skipped += 1;
+                    continue;
+                }
+
+                match change {
+                    ProblemChange::New => cycle.new_problems += 1,
+                    _ => cycle.status_changes += 1,
+                }
+                let mut problem = security_problem.into_problem();
+                problem.change = Some(change);
+                if self.settings.is_multi_tenant() {
+                    self.tag_tenant(client, &mut problem);
+                }
+                to_forward.push(problem);
+            }
+        }
+
+        if let Some(e) = last_error.filter(|_| failures == self.dynatrace_clients.len()) {
+            return Err(e);
+        }
+
+        info!("Found {} security problems to forward", to_forward.len());
+
+        self.forward_checked(to_forward, Vec::new(), false, CycleSource::Security, &cycle, report)
+            .await;
+        self.finish_cycle(CycleSource::Security, cycle, false, report).await;
+        Ok(())
+    }
+
+    /// Fetch each environment's audit log entries written after its checkpoint and
+    /// forward them, then move the checkpoint to the newest entry. An environment
+    /// whose audit log cannot be fetched keeps its checkpoint and is skipped.
+    async fn forward_audit_logs(&self, report: &mut CycleReport) -> Result<()> {
+        info!("Polling Dynatrace for audit log entries...");
+        let config = &self.settings.audit_logs;
+
+        let mut checkpoints = Vec::new();
+        for client in &self.dynatrace_clients {
+            let checkpoint = self
+                .database
+                .get_app_state(&audit_log_checkpoint_key(client.environment()))
+                .await?
+                .and_then(|(value, _)| value.parse::<i64>().ok());
+            checkpoints.push(checkpoint);
+        }
+
+        let responses = futures::future::join_all(self.dynatrace_clients.iter().zip(&checkpoints).map(
+            |(client, checkpoint)| {
+                let from = checkpoint.map(|timestamp| (timestamp + 1).to_string());
+                async move {
+                    client
+                        .fetch_audit_logs(config.filter.as_deref(), from.as_deref().or(config.initial_from.as_deref()))
+                        .await
+                }
+            },
+        ))
+        .await;
+
+        let mut cycle = self.start_cycle().await;
+        let mut to_forward = Vec::new();
+        let mut new_checkpoints = Vec::new();
+        let mut failures = 0;
+        let mut last_error = None;
+        for ((client, checkpoint), response) in self.dynatrace_clients.iter().zip(checkpoints).zip(responses) {
+            let logs = match response {
+                Ok(logs) => logs,
+                Err(e) => {
+                    error!("Failed to fetch audit logs from environment '{}': {}", client.environment(), e);
+                    failures += 1;
+                    last_error = Some(e);
+                    continue;
+                }
+            };
+
+            let mut newest = checkpoint;
+            for entry in logs {
+                cycle.fetched += 1;
+                if checkpoint.is_some_and(|checkpoint| entry.timestamp <= checkpoint) {
+                    cycle.skipped += 1;
+                    continue;
+                }
+                newest = newest.max(Some(entry.timestamp));
+                cycle.new_problems += 1;
+
+                let mut problem = entry.into_problem();
+                problem.change = Some(ProblemChange::New);
+                if self.settings.is_multi_tenant() {
+                    self.tag_tenant(client, &mut problem);
+                }
+                to_forward.push(problem);
+            }
+            if newest != checkpoint {
+                new_checkpoints.extend(newest.map(|newest| (client.environment().to_string(), newest)));
+            }
+        }
+
+        if let Some(e) = last_error.filter(|_| failures == self.dynatrace_clients.len()) {
+            return Err(e);
+        }
+
+        info!("Found {} audit log entries to forward", to_forward.len());
+
+        self.forward_checked(to_forward, Vec::new(), false, CycleSource::AuditLogs, &cycle, report)
+            .await;
+        for (environment, newest) in new_checkpoints {
+            self.database
+                .set_app_state(&audit_log_checkpoint_key(&environment), &newest.to_string())
+                .await?;
+        }
+        self.finish_cycle(CycleSource::AuditLogs, cycle, false, report).await;
+        Ok(())
+    }
+
+    /// Check and forward every page of each environment's problem list as soon
+    /// as it arrives, so only one page is held in memory. Correlation works
+    /// within a page; the cycle's volume is judged after the last page, when a
+    /// storm only adds the meta-alert. Returns the environments fully polled.
+    async fn stream_and_forward(&self, polled_at: i64, report: &mut CycleReport) -> Result<Vec<String>> {
+        let mut cycle = self.start_cycle().await;
+        let mut polled = Vec::new();
+        let mut last_error = None;
+
+        for client in &self.dynatrace_clients {
+            let (from, to) = self.poll_window(client.environment(), polled_at).await?;
+            let mut pages = client.problem_pages(from.as_deref(), to.as_deref());
+
+            loop {
+                match pages.next_page().await {
+                    Ok(Some(mut page)) => {
+                        if self.settings.is_multi_tenant() {
+                            for problem in page.iter_mut() {
+                                self.tag_tenant(client, problem);
+                            }
+                        }
+
+                        debug!(
+                            "Processing page {} of '{}' with {} problems",
+                            pages.pages(),
+                            client.environment(),
+                            page.len()
+                        );
+                        let (problems_to_forward, renotifications) =
+                            self.check_problems(page, &mut cycle, report).await;
+                        self.forward_checked(
+                            problems_to_forward,
+                            renotifications,
+                            false,
+                            CycleSource::Poll,
+                            &cycle,
+                            report,
+                        )
+                        .await;
+                    }
+                    Ok(None) => {
+                        polled.push(client.environment().to_string());
+                        break;
+                    }
+                    Err(e) => {
+                        error!(
+                            "Failed to fetch problems from environment '{}' after {} page(s): {}",
+                            client.environment(),
+                            pages.pages() - 1,
+                            e
+                        );
+                        last_error = Some(e);
+                        break;
+                    }
+                }
+            }
+        }
+
+        if let (true, Some(e)) = (polled.is_empty(), last_error) {
+            return Err(e);
+        }
+
+        info!("Processed {} problems", cycle.fetched);
+
+        let storm = self.cycle_volume(CycleSource::Poll, &cycle).await;
+        if storm {
+            self.forward_checked(Vec::new(), Vec::new(), true, CycleSource::Poll, &cycle, report)
+                .await;
+        }
+        self.finish_cycle(CycleSource::Poll, cycle, storm, report).await;
+
+        Ok(polled)
+    }
+
+    /// Fetch the problems announced by push notifications and forward them like
+    /// polled ones. A problem that cannot be fetched is queued again with backoff,
+    /// up to `PUSH_FETCH_ATTEMPTS` fetches.
+    async fn forward_pushed(&self, report: &mut CycleReport) -> Result<()> {
+        let pushed = self.push_queue.take().await;
+        info!("Processing {} pushed problem notification(s)", pushed.len());
+
+        let mut problems = Vec::with_capacity(pushed.len());
+        for chunk in pushed.chunks(DETAIL_FETCH_CONCURRENCY) {
+            let fetched = futures::future::join_all(chunk.iter().map(|notified| {
+                self.client_named(notified.environment.as_deref())
+                    .fetch_problem_details(&notified.problem_id)
+            }))
+            .await;
+
+            for (notified, result) in chunk.iter().zip(fetched) {
+                match result {
+                    Ok(mut problem) => {
+                        if self.settings.is_multi_tenant() {
+                            let client = self.client_named(notified.environment.as_deref());
+                            problem.tenant = Some(client.environment().to_string());
+                            self.link_to_tenant(&mut problem);
+                        }
+                        problems.push(problem);
+                    }
+                    Err(e) => self.retry_pushed(notified, e),
+                }
+            }
+        }
+
+        self.process_problems(problems, CycleSource::Push, report).await
+    }
+
+    /// Queue a pushed problem again after a failed details fetch, giving up after
+    /// `PUSH_FETCH_ATTEMPTS`; a later poll still picks up the problem
+    fn retry_pushed(&self, notified: &PushedProblem, error: ForwarderError) {
+        let mut retry = notified.clone();
+        retry.fetch_failures += 1;
+        if retry.fetch_failures >= PUSH_FETCH_ATTEMPTS {
+            error!(
+                "Giving up on pushed problem {} after {} failed fetches: {}",
+                notified.problem_id, retry.fetch_failures, error
+            );
+            return;
+        }
+
+        let max_delay = Duration::from_secs(self.settings.dynatrace.retry_max_delay_seconds);
+        let policy = RetryPolicy::new(PUSH_FETCH_ATTEMPTS, max_delay, Duration::MAX);
+        let delay = policy.delay(retry.fetch_failures);
+        warn!(
+            "Failed to fetch pushed problem {}, retrying in {:?}: {}",
+            notified.problem_id, delay, error
+        );
+        self.push_queue.push_after(retry, delay);
+    }
+
+    /// Forward new and changed problems of a cycle, then send due digests and held
+    /// problems. Poll cycles also record their volume for anomaly detection.
+    async fn process_problems(&self, fetched: Vec<Problem>, source: CycleSource, report: &mut CycleReport) -> Result<()> {
+        let mut cycle = self.start_cycle().await;
+        let (problems_to_forward, renotifications) = self.check_problems(fetched, &mut cycle, report).await;
+        let storm = self.cycle_volume(source, &cycle).await;
+        self.forward_checked(problems_to_forward, renotifications, storm, source, &cycle, report)
+            .await;
+        self.finish_cycle(source, cycle, storm, report).await;
+        Ok(())
+    }
+
+    /// Load the state every problem of a cycle is checked against
+    async fn start_cycle(&self) -> CycleState {
+        let pending_flaps: HashMap<String, FlapState> = if self.settings.flap_suppression.enabled {
+            match self.database.get_pending_flaps().await {
+                Ok(flaps) => flaps.into_iter().map(|flap| (flap.problem_id.clone(), flap)).collect(),
+                Err(e) => {
+                    error!("Error loading flap suppression state: {}", e);
+                    HashMap::new()
+                }
+            }
+        } else {
+            HashMap::new()
+        };
+
+        let muted: HashSet<String> = match self.database.get_active_mutes().await {
+            Ok(mutes) => mutes.into_iter().map(|mute| mute.problem_id).collect(),
+            Err(e) => {
+                error!("Error loading problem mutes: {}", e);
+                HashSet::new()
+            }
+        };
 
-        let response = self.dynatrace_client.fetch_problems().await?;
+        let disabled: HashSet<String> = match self.database.get_disabled_connectors().await {
+            Ok(connectors) => connectors.into_iter().map(|c| c.connector_name).collect(),
+            Err(e) => {
+                error!("Error loading disabled connectors: {}", e);
+                HashSet::new()
+            }
+        };
 
-        info!("Found {} problems to process", response.problems.len());
+        CycleState {
+            pending_flaps,
+            muted,
+            disabled,
+            ..CycleState::default()
+        }
+    }
 
-        let mut new_problems = 0;
-        let mut status_changes = 0;
-        let mut skipped = 0;
+    /// Check problems against the cache, returning those to forward and the
+    /// reminders for long-open ones
+    async fn check_problems(
+        &self,
+        fetched: Vec<Problem>,
+        cycle: &mut CycleState,
+        report: &mut CycleReport,
+    ) -> (Vec<Problem>, Vec<Problem>) {
         let mut problems_to_forward = Vec::new();
+        let mut renotifications = Vec::new();
+        cycle.fetched += fetched.len();
 
         // Collect problems that need forwarding
-        for problem in response.problems {
-            match self.check_problem(&problem).await {
+        for mut problem in fetched {
+            // Left out of the cache comparison so changes made while muted are
+            // forwarded once the mute ends
+            if cycle.muted.contains(&problem.problem_id) {
+                debug!("Problem {} is muted, skipping", problem.problem_id);
+                report.muted += 1;
+                continue;
+            }
+
+            self.runbooks.attach(&mut problem);
+
+            match self.check_problem(&problem, cycle.pending_flaps.get(&problem.problem_id)).await {
                 Ok(action) => {
                     match action {
                         ProcessAction::NewProblem => {
-                            new_problems += 1;
+                            cycle.new_problems += 1;
+                            problem.change = Some(ProblemChange::New);
                             problems_to_forward.push(problem);
                         }
                         ProcessAction::StatusChange => {
-                            status_changes += 1;
+                            cycle.status_changes += 1;
+                            problem.change = Some(ProblemChange::StatusChange);
                             problems_to_forward.push(problem);
                         }
-                        ProcessAction::Skipped => skipped += 1,
+                        ProcessAction::Skipped => cycle.skipped += 1,
+                        ProcessAction::FlapSuppressed => report.flaps_suppressed += 1,
+                        ProcessAction::TooOld => report.too_old += 1,
+                        ProcessAction::Renotify => {
+                            problem.change = Some(ProblemChange::Renotification);
+                            renotifications.push(problem);
+                        }
                     }
                 }
                 Err(e) => {
@@ -88,45 +718,319 @@ impl ForwardingEngine {
             }
         }
 
+        (problems_to_forward, renotifications)
+    }
+
+    /// Record the cycle's volume; true when it is a problem storm. Pushed
+    /// problems arrive one by one, so only full polls say anything about volume.
+    async fn cycle_volume(&self, source: CycleSource, cycle: &CycleState) -> bool {
+        if source != CycleSource::Poll || !self.polling {
+            return false;
+        }
+
+        match self
+            .detect_volume_anomaly(cycle.fetched, cycle.new_problems, cycle.status_changes)
+            .await
+        {
+            Ok(true) => true,
+            Ok(false) => {
+                self.close_volume_anomaly().await;
+                false
+            }
+            Err(e) => {
+                error!("Error recording poll cycle volume: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Meta-alert for a storm, or None while the alert for the ongoing storm is
+    /// still open. The alert is tracked like a problem so its deliveries show
+    /// up in `dtpf problem show` and it fires once per storm.
+    async fn open_volume_anomaly(&self, volume: usize) -> Option<Problem> {
+        match self.database.get_app_state(VOLUME_ANOMALY_KEY).await {
+            Ok(Some((open, _))) if !open.is_empty() => {
+                debug!("Volume anomaly {} is still open; not alerting again", open);
+                return None;
+            }
+            Ok(_) => {}
+            Err(e) => {
+                error!("Error reading the open volume anomaly: {}", e);
+                return None;
+            }
+        }
+
+        let problem = volume_anomaly_problem(volume);
+        let tracked = ForwardedProblem::new(
+            problem.problem_id.clone(),
+            problem.status.to_string(),
+            Some(problem.severity_level.clone()),
+            problem.title.clone(),
+            Vec::new(),
+        );
+        if let Err(e) = self.database.insert_problem(&tracked).await {
+            error!("Error tracking volume anomaly {}: {}", problem.problem_id, e);
+            return None;
+        }
+        if let Err(e) = self.database.set_app_state(VOLUME_ANOMALY_KEY, &problem.problem_id).await {
+            error!("Error recording the open volume anomaly: {}", e);
+        }
+        Some(problem)
+    }
+
+    /// Close the open volume anomaly once a poll cycle is back to normal
+    async fn close_volume_anomaly(&self) {
+        let open = match self.database.get_app_state(VOLUME_ANOMALY_KEY).await {
+            Ok(Some((open, _))) if !open.is_empty() => open,
+            Ok(_) => return,
+            Err(e) => {
+                error!("Error reading the open volume anomaly: {}", e);
+                return;
+            }
+        };
+
+        info!("Problem volume is back to normal; closing volume anomaly {}", open);
+        if let Err(e) = self.database.set_problem_status(&open, &ProblemStatus::Closed.to_string(), &[]).await {
+            error!("Error closing volume anomaly {}: {}", open, e);
+        }
+        if let Err(e) = self.database.set_app_state(VOLUME_ANOMALY_KEY, "").await {
+            error!("Error clearing the open volume anomaly: {}", e);
+        }
+    }
+
+    /// Prepare checked problems (maintenance windows, details, correlation,
+    /// enrichment) and hand them to the connectors
+    async fn forward_checked(
+        &self,
+        mut problems_to_forward: Vec<Problem>,
+        mut renotifications: Vec<Problem>,
+        storm: bool,
+        source: CycleSource,
+        cycle: &CycleState,
+        report: &mut CycleReport,
+    ) {
+        if self.settings.maintenance_windows.enabled && !problems_to_forward.is_empty() {
+            problems_to_forward = self.skip_in_maintenance(problems_to_forward, report).await;
+        }
+
+        // Pushed problems already come from the details endpoint
+        if self.settings.dynatrace.fetch_details && source == CycleSource::Poll && !problems_to_forward.is_empty() {
+            self.fetch_details(&mut problems_to_forward).await;
+        }
+
+        // Security problems and audit log entries share no entities or root causes with problems
+        let correlated_source = !matches!(source, CycleSource::Security | CycleSource::AuditLogs);
+        if self.settings.correlation.enabled && correlated_source {
+            let collected = problems_to_forward.len();
+            problems_to_forward = correlation::correlate(problems_to_forward, &self.settings.correlation);
+            if problems_to_forward.len() < collected {
+                info!(
+                    "Correlated {} problems into {} notifications",
+                    collected,
+                    problems_to_forward.len()
+                );
+            }
+        }
+
+        if storm && self.settings.anomaly_detection.meta_alert {
+            problems_to_forward.extend(self.open_volume_anomaly(cycle.new_problems + cycle.status_changes).await);
+        }
+
+        // Reminders are never correlated: each one refers to a single long-open problem
+        if self.settings.maintenance_windows.enabled && !renotifications.is_empty() {
+            renotifications = self.skip_in_maintenance(renotifications, report).await;
+        }
+        report.renotified += renotifications.len();
+        problems_to_forward.extend(renotifications);
+
+        if self.settings.entity_enrichment.enabled && !problems_to_forward.is_empty() {
+            self.enrich_entities(&mut problems_to_forward).await;
+        }
+
+        // Snapshots of the complete problems, updating the rows of tracked ones
+        for problem in &problems_to_forward {
+            if let Err(e) = self.database.save_problem_snapshot(problem).await {
+                warn!("Failed to store snapshot of {}: {}", problem.problem_id, e);
+            }
+        }
+
+        report.forwarded += problems_to_forward.len();
+
         // Forward collected problems (batch or individual depending on connector config)
         if !problems_to_forward.is_empty() {
-            if let Err(e) = self.forward_collected_problems(&problems_to_forward).await {
+            if let Err(e) = self
+                .forward_collected_problems(&problems_to_forward, storm, &cycle.disabled, report)
+                .await
+            {
                 error!("Error forwarding problems: {}", e);
             }
         }
+    }
+
+    /// Send due digests and held problems, and record the cycle's totals
+    async fn finish_cycle(&self, source: CycleSource, cycle: CycleState, storm: bool, report: &mut CycleReport) {
+        report.fetched = cycle.fetched;
+        report.new_problems = cycle.new_problems;
+        report.status_changes = cycle.status_changes;
+        report.skipped = cycle.skipped;
+        report.anomaly = storm;
+
+        // Send digests whose collection window has elapsed
+        self.flush_due_digests(&cycle.disabled, report).await;
+
+        // Send problems held while connectors were outside their active hours
+        self.release_held_problems(&cycle.disabled, report).await;
+
+        if let Err(e) = self.save_connector_health().await {
+            error!("Error saving connector health: {}", e);
+        }
 
         info!(
-            "Poll complete: {} new, {} status changes, {} skipped",
-            new_problems, status_changes, skipped
+            "{} complete: {} new, {} status changes, {} skipped",
+            source.label(),
+            cycle.new_problems,
+            cycle.status_changes,
+            cycle.skipped
         );
 
-        Ok(())
+        if source == CycleSource::Poll {
+            self.emit(EngineEvent::PollCompleted {
+                new_problems: cycle.new_problems,
+                status_changes: cycle.status_changes,
+                skipped: cycle.skipped,
+            });
+        }
+    }
+
+    /// Record this cycle's volume and compare it against the recent baseline.
+    /// Returns true when the cycle is a problem storm.
+    async fn detect_volume_anomaly(
+        &self,
+        fetched_count: usize,
+        new_problems: usize,
+        status_changes: usize,
+    ) -> Result<bool> {
+        let config = &self.settings.anomaly_detection;
+        let volume = (new_problems + status_changes) as f64;
+
+        let (baseline, samples) = self.database.poll_volume_baseline(config.baseline_cycles).await?;
+
+        // Require some history before judging, and treat a near-zero baseline as 1
+        let anomaly = config.enabled
+            && samples >= 3
+            && volume >= config.min_problems as f64
+            && volume > baseline.max(1.0) * config.threshold_multiplier;
+
+        if anomaly {
+            warn!(
+                "⚠️  Problem volume anomaly: {} new/changed problems this cycle (baseline {:.1} over {} cycles)",
+                volume, baseline, samples
+            );
+            self.emit(EngineEvent::VolumeAnomaly {
+                volume: new_problems + status_changes,
+                baseline,
+            });
+        }
+
+        self.database
+            .insert_poll_cycle(&PollCycle {
+                polled_at: chrono::Utc::now().timestamp(),
+                fetched_count: fetched_count as i64,
+                new_count: new_problems as i64,
+                status_change_count: status_changes as i64,
+                baseline: (samples > 0).then_some(baseline),
+                anomaly,
+            })
+            .await?;
+
+        Ok(anomaly)
     }
 
     /// Check if a problem needs forwarding and update database
-    async fn check_problem(&self, problem: &Problem) -> Result<ProcessAction> {
+    async fn check_problem(&self, problem: &Problem, pending_flap: Option<&FlapState>) -> Result<ProcessAction> {
         debug!("Processing problem: {}", problem.summary());
 
         // Check if problem exists in database
         let db_problem = self.database.get_problem(&problem.problem_id).await?;
 
         match db_problem {
+            None if self.too_old(problem) => {
+                // Cached without a forward so later updates are skipped too
+                info!("Skipping {}: started more than max_problem_age_hours ago", problem.summary());
+
+                let mut skipped_problem = ForwardedProblem::new(
+                    problem.problem_id.clone(),
+                    problem.status.to_string(),
+                    Some(problem.severity_level.clone()),
+                    problem.title.clone(),
+                    problem.management_zone_names(),
+                );
+                skipped_problem.forward_count = 0;
+                skipped_problem.tenant = problem.tenant.clone();
+                self.database.insert_problem(&skipped_problem).await?;
+                self.record_too_old(problem).await;
+
+                Ok(ProcessAction::TooOld)
+            }
+            None if self.is_stateless() && problem.start_time < self.started_at => {
+                // Forwarded by an earlier instance whose state is gone; later changes are forwarded
+                info!("Not forwarding {}: it started before this stateless instance", problem.summary());
+
+                let mut adopted = ForwardedProblem::new(
+                    problem.problem_id.clone(),
+                    problem.status.to_string(),
+                    Some(problem.severity_level.clone()),
+                    problem.title.clone(),
+                    problem.management_zone_names(),
+                );
+                adopted.tenant = problem.tenant.clone();
+                self.database.insert_problem(&adopted).await?;
+
+                Ok(ProcessAction::Skipped)
+            }
             None => {
                 // New problem - will forward it
                 info!("New problem detected: {}", problem.summary());
 
                 // Insert into database
-                let forwarded_problem = ForwardedProblem::new(
+                let mut forwarded_problem = ForwardedProblem::new(
                     problem.problem_id.clone(),
                     problem.status.to_string(),
                     Some(problem.severity_level.clone()),
                     problem.title.clone(),
+                    problem.management_zone_names(),
                 );
+                forwarded_problem.tenant = problem.tenant.clone();
                 self.database.insert_problem(&forwarded_problem).await?;
+                if pending_flap.is_some() {
+                    self.database.clear_pending_flap(&problem.problem_id).await?;
+                }
+
+                self.emit(EngineEvent::NewProblem {
+                    problem_id: problem.problem_id.clone(),
+                    title: problem.title.clone(),
+                });
 
                 Ok(ProcessAction::NewProblem)
             }
+            Some(db_record) if db_record.forward_count == 0 && db_record.status != problem.status.to_string() => {
+                // Never forwarded because it was too old; receivers have nothing to update
+                self.database
+                    .set_problem_status(
+                        &problem.problem_id,
+                        &problem.status.to_string(),
+                        &problem.management_zone_names(),
+                    )
+                    .await?;
+                self.record_too_old(problem).await;
+
+                Ok(ProcessAction::TooOld)
+            }
             Some(db_record) if db_record.status != problem.status.to_string() => {
+                if self.suppress_flap(problem, &db_record.status, pending_flap).await? {
+                    return Ok(ProcessAction::FlapSuppressed);
+                }
+
                 // Status changed - will forward update
                 info!(
                     "Status change detected for {}: {} -> {}",
@@ -137,160 +1041,2200 @@ impl ForwardingEngine {
 
                 // Update database
                 self.database
-                    .update_problem_status(&problem.problem_id, &problem.status.to_string())
+                    .update_problem_status(
+                        &problem.problem_id,
+                        &problem.status.to_string(),
+                        &problem.management_zone_names(),
+                    )
                     .await?;
 
+                self.emit(EngineEvent::StatusChanged {
+                    problem_id: problem.problem_id.clone(),
+                    from: db_record.status,
+                    to: problem.status.to_string(),
+                });
+
                 Ok(ProcessAction::StatusChange)
             }
-            Some(_) => {
-                // No change - skip
-                debug!("Problem {} unchanged, skipping", problem.problem_id);
-                Ok(ProcessAction::Skipped)
-            }
+            Some(_) if pending_flap.is_some_and(|flap| self.flap_settled(flap)) => {
+                // A withheld status held for the whole window - forward it now
+                let flap = pending_flap.expect("checked by the match guard");
+                info!(
+                    "Problem {} stopped flapping, forwarding {} (receivers last got {})",
+                    problem.problem_id, problem.status, flap.forwarded_status
+                );
+
+                self.database
+                    .update_problem_status(
+                        &problem.problem_id,
+                        &problem.status.to_string(),
+                        &problem.management_zone_names(),
+                    )
+                    .await?;
+                self.database.clear_pending_flap(&problem.problem_id).await?;
+
+                self.emit(EngineEvent::StatusChanged {
+                    problem_id: problem.problem_id.clone(),
+                    from: flap.forwarded_status.clone(),
+                    to: problem.status.to_string(),
+                });
+
+                Ok(ProcessAction::StatusChange)
+            }
+            Some(db_record) if pending_flap.is_none() && self.renotify_due(problem, &db_record) => {
+                info!(
+                    "Problem {} is still open, re-notifying (last forwarded at {})",
+                    problem.problem_id, db_record.last_forwarded_at
+                );
+                // Marked before delivery so a failing receiver is not retried every cycle
+                self.database.update_last_forwarded(&problem.problem_id).await?;
+
+                Ok(ProcessAction::Renotify)
+            }
+            Some(_) => {
+                // No change - skip
+                debug!("Problem {} unchanged, skipping", problem.problem_id);
+                Ok(ProcessAction::Skipped)
+            }
+        }
+    }
+
+    /// Withhold a status change of a flapping problem: one whose new status was
+    /// forwarded less than `flap_suppression.window_minutes` ago, or that already has a
+    /// withheld change. The cached status follows the problem either way.
+    async fn suppress_flap(&self, problem: &Problem, cached_status: &str, pending_flap: Option<&FlapState>) -> Result<bool> {
+        if !self.settings.flap_suppression.enabled {
+            return Ok(false);
+        }
+
+        let status = problem.status.to_string();
+        let forwarded_status = match pending_flap {
+            Some(flap) => flap.forwarded_status.as_str(),
+            None => {
+                let window = self.flap_window_seconds();
+                let recent = self
+                    .database
+                    .last_delivered_at(&problem.problem_id, &status)
+                    .await?
+                    .is_some_and(|delivered_at| chrono::Utc::now().timestamp() - delivered_at < window);
+                if !recent {
+                    return Ok(false);
+                }
+                cached_status
+            }
+        };
+
+        // Back at the status receivers already have: nothing is left to forward
+        let pending = status != forwarded_status;
+        info!(
+            "Problem {} is flapping ({} -> {}), not forwarding",
+            problem.problem_id, cached_status, status
+        );
+        self.database
+            .record_flap(
+                &problem.problem_id,
+                &status,
+                &problem.management_zone_names(),
+                forwarded_status,
+                pending,
+            )
+            .await?;
+
+        Ok(true)
+    }
+
+    /// Whether a problem seen for the first time started longer than
+    /// `polling.max_problem_age_hours` ago
+    fn too_old(&self, problem: &Problem) -> bool {
+        self.settings.polling.max_problem_age_hours.is_some_and(|hours| {
+            chrono::Utc::now().timestamp_millis() - problem.start_time > (hours * 3_600_000) as i64
+        })
+    }
+
+    async fn record_too_old(&self, problem: &Problem) {
+        let history = ForwardHistory::new(
+            problem.problem_id.clone(),
+            problem.status.to_string(),
+            String::new(),
+            SKIPPED.to_string(),
+            None,
+            Some("older than max_problem_age_hours".to_string()),
+        );
+        if let Err(e) = self.database.insert_forward_history(&history).await {
+            error!("Failed to record skipped problem {}: {}", problem.problem_id, e);
+        }
+    }
+
+    /// Whether an unchanged open problem is due for a reminder: `after_minutes` after
+    /// it was forwarded as open, then every `repeat_minutes` if set
+    fn renotify_due(&self, problem: &Problem, db_record: &ForwardedProblem) -> bool {
+        let renotify = &self.settings.renotify;
+        if !renotify.enabled || problem.status != ProblemStatus::Open || db_record.forward_count == 0 {
+            return false;
+        }
+
+        let now = chrono::Utc::now().timestamp();
+        if db_record.last_forwarded_at <= db_record.last_status_change_at {
+            now - db_record.last_status_change_at >= (renotify.after_minutes * 60) as i64
+        } else {
+            renotify
+                .repeat_minutes
+                .is_some_and(|repeat| now - db_record.last_forwarded_at >= (repeat * 60) as i64)
+        }
+    }
+
+    /// Whether a withheld status has held for the whole flap window
+    fn flap_settled(&self, flap: &FlapState) -> bool {
+        chrono::Utc::now().timestamp() - flap.last_suppressed_at >= self.flap_window_seconds()
+    }
+
+    fn flap_window_seconds(&self) -> i64 {
+        (self.settings.flap_suppression.window_minutes * 60) as i64
+    }
+
+    /// Fetch problems from every environment at once. With several environments,
+    /// problems are tagged with theirs and an environment that fails is left out of
+    /// this cycle; the cycle only fails when all of them fail.
+    async fn fetch_problems(&self, polled_at: i64) -> Result<(Vec<Problem>, Vec<String>)> {
+        let mut windows = Vec::with_capacity(self.dynatrace_clients.len());
+        for client in &self.dynatrace_clients {
+            windows.push(self.poll_window(client.environment(), polled_at).await?);
+        }
+
+        if !self.settings.is_multi_tenant() {
+            let (from, to) = &windows[0];
+            let client = &self.dynatrace_clients[0];
+            let response = client.fetch_problems_between(from.as_deref(), to.as_deref()).await?;
+            return Ok((response.problems, vec![client.environment().to_string()]));
+        }
+
+        let responses = futures::future::join_all(
+            self.dynatrace_clients
+                .iter()
+                .zip(&windows)
+                .map(|(client, (from, to))| client.fetch_problems_between(from.as_deref(), to.as_deref())),
+        )
+        .await;
+
+        let mut problems = Vec::new();
+        let mut polled = Vec::new();
+        let mut failures = 0;
+        let mut last_error = None;
+        for (client, response) in self.dynatrace_clients.iter().zip(responses) {
+            match response {
+                Ok(response) => {
+                    debug!("Fetched {} problems from environment '{}'", response.problems.len(), client.environment());
+                    polled.push(client.environment().to_string());
+                    problems.extend(response.problems.into_iter().map(|mut problem| {
+                        self.tag_tenant(client, &mut problem);
+                        problem
+                    }));
+                }
+                Err(e) => {
+                    error!("Failed to fetch problems from environment '{}': {}", client.environment(), e);
+                    failures += 1;
+                    last_error = Some(e);
+                }
+            }
+        }
+
+        match last_error {
+            Some(e) if failures == self.dynatrace_clients.len() => Err(e),
+            _ => Ok((problems, polled)),
+        }
+    }
+
+    /// Name the environment a polled problem comes from and link to it
+    fn tag_tenant(&self, client: &DynatraceClient, problem: &mut Problem) {
+        problem.tenant = Some(client.environment().to_string());
+        self.link_to_tenant(problem);
+    }
+
+    /// Whether state lives only in memory (`database.driver: memory`)
+    fn is_stateless(&self) -> bool {
+        self.settings.database.driver == DatabaseDriver::Memory
+    }
+
+    /// `from`/`to` of an environment's poll: with `since_last_poll`, from shortly
+    /// before the last successful poll (or `initial_from`) up to this one
+    async fn poll_window(&self, environment: &str, polled_at: i64) -> Result<(Option<String>, Option<String>)> {
+        let dynatrace = &self.settings.dynatrace;
+        if !dynatrace.since_last_poll {
+            return Ok((None, None));
+        }
+
+        let last_poll = self
+            .database
+            .get_app_state(&poll_window_key(environment))
+            .await?
+            .and_then(|(value, _)| value.parse::<i64>().ok());
+
+        let from = match last_poll {
+            Some(last_poll) => Some((last_poll - POLL_WINDOW_OVERLAP_MS).to_string()),
+            // A stateless instance starts where its predecessor stopped, at the latest
+            None if self.is_stateless() && dynatrace.initial_from.is_none() => {
+                Some((self.started_at - POLL_WINDOW_OVERLAP_MS).to_string())
+            }
+            None => dynatrace.initial_from.clone(),
+        };
+
+        Ok((from, Some(polled_at.to_string())))
+    }
+
+    /// Add details to the new problems, a few requests at a time. A problem whose
+    /// details cannot be fetched is forwarded as listed.
+    async fn fetch_details(&self, problems: &mut [Problem]) {
+        let new: Vec<usize> = (0..problems.len())
+            .filter(|&position| problems[position].change == Some(ProblemChange::New))
+            .collect();
+
+        for positions in new.chunks(DETAIL_FETCH_CONCURRENCY) {
+            let details = futures::future::join_all(positions.iter().map(|&position| {
+                let problem = &problems[position];
+                self.client_for(problem).fetch_problem_details(&problem.problem_id)
+            }))
+            .await;
+
+            for (&position, result) in positions.iter().zip(details) {
+                let problem = &mut problems[position];
+                match result {
+                    Ok(details) => problem.merge_details(details),
+                    Err(e) => warn!(
+                        "Failed to fetch details of problem {}, forwarding it without: {}",
+                        problem.problem_id, e
+                    ),
+                }
+            }
+        }
+    }
+
+    /// Attach monitored entity properties to the affected and impacted entities,
+    /// from the entity cache or fetched per environment. Entities that cannot be
+    /// fetched are forwarded as listed.
+    async fn enrich_entities(&self, problems: &mut [Problem]) {
+        let config = &self.settings.entity_enrichment;
+        let fetched_after = chrono::Utc::now().timestamp() - (config.cache_ttl_minutes * 60) as i64;
+
+        for client in &self.dynatrace_clients {
+            let environment = client.environment();
+            let in_environment = |problem: &Problem| self.client_for(problem).environment() == environment;
+
+            let entity_ids: Vec<String> = problems
+                .iter()
+                .filter(|problem| in_environment(problem))
+                .flat_map(|problem| problem.affected_entities.iter().chain(&problem.impacted_entities))
+                .map(|entity| entity.entity_id.id.clone())
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect();
+            if entity_ids.is_empty() {
+                continue;
+            }
+
+            let mut entities = match self.database.get_cached_entities(environment, &entity_ids, fetched_after).await {
+                Ok(entities) => entities,
+                Err(e) => {
+                    error!("Error reading the entity cache: {}", e);
+                    HashMap::new()
+                }
+            };
+
+            let missing: Vec<String> = entity_ids.into_iter().filter(|id| !entities.contains_key(id)).collect();
+            if !missing.is_empty() {
+                match client.fetch_entities(&missing).await {
+                    Ok(fetched) => {
+                        if let Err(e) = self.database.cache_entities(environment, &fetched).await {
+                            error!("Error caching entities: {}", e);
+                        }
+                        entities.extend(fetched.into_iter().map(|entity| (entity.entity_id.clone(), entity)));
+                    }
+                    Err(e) => warn!("Failed to fetch {} entities from '{}': {}", missing.len(), environment, e),
+                }
+            }
+
+            for problem in problems.iter_mut().filter(|problem| in_environment(problem)) {
+                for entity in problem.affected_entities.iter_mut().chain(problem.impacted_entities.iter_mut()) {
+                    entity.details = entities.get(&entity.entity_id.id).map(|details| {
+                        let mut details = details.clone();
+                        if !config.properties.is_empty() {
+                            details.properties.retain(|key, _| config.properties.contains(key));
+                        }
+                        details
+                    });
+                }
+            }
+        }
+    }
+
+    /// Comment on the Dynatrace problems of successful deliveries whose connector
+    /// has `dynatrace_comment`. A failed comment is only logged.
+    async fn comment_on_deliveries(&self, outcomes: &[DeliveryOutcome], problems: &[Problem]) {
+        let mut comments = Vec::new();
+        for outcome in outcomes.iter().filter(|outcome| outcome.status == "success") {
+            let Some(connector) = self.connectors.iter().find(|c| c.name() == outcome.connector) else {
+                continue;
+            };
+
+            for problem_id in &outcome.problem_ids {
+                // Correlated problems live in their composite's environment
+                let Some(problem) = problems.iter().find(|p| {
+                    p.problem_id == *problem_id || p.correlated_problems.iter().any(|c| c.problem_id == *problem_id)
+                }) else {
+                    continue;
+                };
+                if problem.display_id == VOLUME_ANOMALY_DISPLAY_ID {
+                    continue;
+                }
+                let display_id = if problem.problem_id == *problem_id {
+                    problem.display_id.as_str()
+                } else {
+                    problem
+                        .correlated_problems
+                        .iter()
+                        .find(|c| c.problem_id == *problem_id)
+                        .map_or(problem.display_id.as_str(), |c| c.display_id.as_str())
+                };
+
+                let reference = connector.take_reference(problem_id);
+                if let Some((message, context)) = connector.dynatrace_comment(display_id, reference.as_deref()) {
+                    comments.push((self.client_for(problem), problem_id.clone(), message, context));
+                }
+            }
+        }
+
+        for chunk in comments.chunks(DETAIL_FETCH_CONCURRENCY) {
+            let results = futures::future::join_all(
+                chunk
+                    .iter()
+                    .map(|(client, problem_id, message, context)| client.add_comment(problem_id, message, context)),
+            )
+            .await;
+
+            for ((_, problem_id, _, _), result) in chunk.iter().zip(results) {
+                if let Err(e) = result {
+                    warn!("Failed to comment on problem {} in Dynatrace: {}", problem_id, e);
+                }
+            }
+        }
+    }
+
+    /// Client of the environment a problem comes from
+    fn client_for(&self, problem: &Problem) -> &DynatraceClient {
+        self.client_named(problem.tenant.as_deref())
+    }
+
+    /// Client of the named environment, the main one when not named or unknown
+    fn client_named(&self, environment: Option<&str>) -> &DynatraceClient {
+        environment
+            .and_then(|name| self.dynatrace_clients.iter().find(|client| client.environment() == name))
+            .unwrap_or(&self.dynatrace_clients[0])
+    }
+
+    /// Point the links of a problem from a further environment at that environment
+    fn link_to_tenant(&self, problem: &mut Problem) {
+        problem.tenant_url_base = problem
+            .tenant
+            .as_ref()
+            .and_then(|tenant| self.tenant_url_bases.get(tenant))
+            .cloned();
+    }
+
+    /// Drop problems covered by an active maintenance window of their environment,
+    /// recording each one as suppressed in forward history
+    async fn skip_in_maintenance(&self, problems: Vec<Problem>, report: &mut CycleReport) -> Vec<Problem> {
+        let active = self.active_maintenance_windows().await;
+        if active.values().all(Vec::is_empty) {
+            return problems;
+        }
+
+        let mut remaining = Vec::with_capacity(problems.len());
+        for problem in problems {
+            let environment = problem.tenant.as_deref().unwrap_or(self.dynatrace_clients[0].environment());
+            let window = active
+                .get(environment)
+                .and_then(|windows| windows.iter().find(|window| window.covers(&problem)));
+            let Some(window) = window else {
+                remaining.push(problem);
+                continue;
+            };
+
+            info!(
+                "Problem {} is inside maintenance window '{}', not forwarding",
+                problem.problem_id,
+                window.name()
+            );
+            let history = ForwardHistory::new(
+                problem.problem_id.clone(),
+                problem.status.to_string(),
+                String::new(),
+                SUPPRESSED.to_string(),
+                None,
+                Some(format!("maintenance window '{}'", window.name())),
+            );
+            if let Err(e) = self.database.insert_forward_history(&history).await {
+                error!("Failed to record suppressed problem {}: {}", problem.problem_id, e);
+            }
+            report.in_maintenance += 1;
+        }
+        remaining
+    }
+
+    /// Maintenance windows currently withholding alerts per environment, refreshing
+    /// an environment's cached list when it is older than
+    /// `maintenance_windows.refresh_seconds`. A failed refresh keeps the previous list.
+    async fn active_maintenance_windows(&self) -> HashMap<String, Vec<MaintenanceWindow>> {
+        let mut cache = self.maintenance_windows.lock().await;
+        let refresh = Duration::from_secs(self.settings.maintenance_windows.refresh_seconds);
+
+        for client in &self.dynatrace_clients {
+            if cache
+                .get(client.environment())
+                .is_some_and(|(fetched, _)| fetched.elapsed() < refresh)
+            {
+                continue;
+            }
+
+            match client.fetch_maintenance_windows().await {
+                Ok(windows) => {
+                    debug!("Refreshed {} maintenance window(s) of '{}'", windows.len(), client.environment());
+                    cache.insert(client.environment().to_string(), (Instant::now(), windows));
+                }
+                Err(e) => warn!(
+                    "Failed to refresh maintenance windows of '{}', keeping the last known list: {}",
+                    client.environment(),
+                    e
+                ),
+            }
         }
+
+        let now = chrono::Utc::now();
+        cache
+            .iter()
+            .map(|(environment, (_, windows))| {
+                let active = windows.iter().filter(|window| window.suppresses_alerts_at(now)).cloned().collect();
+                (environment.clone(), active)
+            })
+            .collect()
     }
 
     /// Forward collected problems to all connectors (batch or individual based on connector config)
-    async fn forward_collected_problems(&self, problems: &[Problem]) -> Result<()> {
+    async fn forward_collected_problems(
+        &self,
+        problems: &[Problem],
+        storm: bool,
+        disabled: &HashSet<String>,
+        report: &mut CycleReport,
+    ) -> Result<()> {
         info!("Forwarding {} problems to connectors", problems.len());
 
+        // Most important problems first, so they lead batches and get throttle slots first
+        let prioritized;
+        let problems = if self.settings.priority.enabled {
+            prioritized = prioritize(problems, &self.settings.priority.order_by);
+            &prioritized[..]
+        } else {
+            problems
+        };
+
+        // Routing rules are evaluated once per problem; connector filters apply on top
+        let rule_targets: HashMap<&str, BTreeSet<String>> = match &self.router {
+            Some(router) => problems.iter().map(|p| (p.problem_id.as_str(), router.connectors(p))).collect(),
+            None => HashMap::new(),
+        };
+        let renotify_connectors = &self.settings.renotify.connectors;
+        let routes = |connector: &Connector, problem: &Problem| {
+            if problem.change == Some(ProblemChange::Renotification) && !renotify_connectors.is_empty() {
+                return renotify_connectors.iter().any(|name| name == connector.name()) && connector.routes(problem);
+            }
+            connector.routes(problem)
+                && (self.router.is_none()
+                    || rule_targets
+                        .get(problem.problem_id.as_str())
+                        .is_some_and(|targets| targets.contains(connector.name())))
+        };
+
+        // Problems no routing rule or connector filter sends anywhere are only recorded
+        // (reminders were recorded when the problem was first routed)
+        for problem in problems.iter().filter(|p| {
+            p.change != Some(ProblemChange::Renotification) && !self.connectors.iter().any(|c| routes(c, p))
+        }) {
+            info!(
+                "No connector routes {} (zones: {:?}), recording as unrouted",
+                problem.problem_id,
+                problem.management_zone_names()
+            );
+            let history = ForwardHistory::new(
+                problem.problem_id.clone(),
+                problem.status.to_string(),
+                String::new(),
+                UNROUTED.to_string(),
+                None,
+                None,
+            );
+            if let Err(e) = self.database.insert_forward_history(&history).await {
+                error!("Failed to record unrouted problem {}: {}", problem.problem_id, e);
+            }
+            report.unrouted += 1;
+        }
+
+        // Disabled connectors receive nothing; each skipped problem is recorded
+        let (disabled_connectors, enabled_connectors): (Vec<_>, Vec<_>) =
+            self.connectors.iter().partition(|c| disabled.contains(c.name()));
+
+        for connector in disabled_connectors {
+            let mut suppressed = 0;
+            for problem in problems.iter().filter(|p| routes(connector, p) && connector.samples(p)) {
+                let history = ForwardHistory::new(
+                    problem.problem_id.clone(),
+                    problem.status.to_string(),
+                    connector.name().to_string(),
+                    SUPPRESSED.to_string(),
+                    None,
+                    Some("connector disabled".to_string()),
+                );
+                if let Err(e) = self.database.insert_forward_history(&history).await {
+                    error!("Failed to record suppressed problem {}: {}", problem.problem_id, e);
+                }
+                suppressed += 1;
+            }
+            if suppressed > 0 {
+                info!("'{}' is disabled: suppressed {} problems", connector.name(), suppressed);
+            }
+            report.record_suppressed(connector.name(), suppressed);
+        }
+
+        // Digest connectors only queue problems; they are sent when the window elapses
+        let (digest_connectors, immediate_connectors): (Vec<_>, Vec<_>) = enabled_connectors
+            .into_iter()
+            .partition(|c| c.digest_config().is_some());
+
+        for connector in digest_connectors {
+            let sampled = problems.iter().filter(|p| routes(connector, p) && connector.samples(p)).cloned().collect();
+            let pending = self.skip_delivered(&[connector.name()], sampled).await;
+            let mut queued = 0;
+            for problem in &self.sample_recurrences(connector, pending, report).await {
+                match self.database.enqueue_digest(connector.name(), problem).await {
+                    Ok(_) => queued += 1,
+                    Err(e) => error!(
+                        "Failed to queue {} for digest on '{}': {}",
+                        problem.problem_id, connector.name(), e
+                    ),
+                }
+            }
+            debug!("Queued {} problems for digest on '{}'", queued, connector.name());
+            report.record_queued(connector.name(), queued);
+        }
+
+        // Connectors outside their active hours hold problems until the window opens
+        let (quiet_connectors, immediate_connectors): (Vec<_>, Vec<_>) =
+            immediate_connectors.into_iter().partition(|c| c.is_quiet());
+
+        for connector in quiet_connectors {
+            let held = connector.active_hours().map(|hours| hours.held).unwrap_or_default();
+            let sampled = problems.iter().filter(|p| routes(connector, p) && connector.samples(p)).cloned().collect();
+            let pending = self.skip_delivered(&[connector.name()], sampled).await;
+            let (mut queued, mut suppressed) = (0, 0);
+            for problem in &self.sample_recurrences(connector, pending, report).await {
+                if held == HeldProblems::Drop {
+                    let history = ForwardHistory::new(
+                        problem.problem_id.clone(),
+                        problem.status.to_string(),
+                        connector.name().to_string(),
+                        SUPPRESSED.to_string(),
+                        None,
+                        Some("outside active hours".to_string()),
+                    );
+                    if let Err(e) = self.database.insert_forward_history(&history).await {
+                        error!("Failed to record suppressed problem {}: {}", problem.problem_id, e);
+                    }
+                    suppressed += 1;
+                    continue;
+                }
+
+                match self.database.enqueue_digest(connector.name(), problem).await {
+                    Ok(_) => queued += 1,
+                    Err(e) => error!(
+                        "Failed to hold {} for '{}' until active hours: {}",
+                        problem.problem_id, connector.name(), e
+                    ),
+                }
+            }
+            info!(
+                "'{}' is outside its active hours: held {}, suppressed {} problems",
+                connector.name(), queued, suppressed
+            );
+            report.record_queued(connector.name(), queued);
+            report.record_suppressed(connector.name(), suppressed);
+        }
+
+        // Connectors in a failover group share delivery: one member per problem
+        let mut failover_groups: BTreeMap<String, Vec<Arc<Connector>>> = BTreeMap::new();
+        let mut standalone_connectors = Vec::new();
+        for connector in immediate_connectors {
+            match connector.failover_group() {
+                Some(group) => failover_groups
+                    .entry(group.to_string())
+                    .or_default()
+                    .push(Arc::clone(connector)),
+                None => standalone_connectors.push(connector),
+            }
+        }
+
         // Group connectors by batch mode
-        let (batch_connectors, individual_connectors): (Vec<_>, Vec<_>) = self
-            .connectors
-            .iter()
+        let (batch_connectors, individual_connectors): (Vec<_>, Vec<_>) = standalone_connectors
+            .into_iter()
             .partition(|c| c.is_batch_mode());
 
         let mut forward_tasks = Vec::new();
 
+        for (group, mut members) in failover_groups {
+            if self.settings.health.prefer_healthiest {
+                members.sort_by(|a, b| b.health().score.total_cmp(&a.health().score));
+            }
+
+            // Routing rules may name any member; members share their own filters
+            let routed = problems
+                .iter()
+                .filter(|p| members.iter().any(|m| routes(m, p)) && members[0].samples(p))
+                .cloned()
+                .collect();
+            let member_names: Vec<&str> = members.iter().map(|m| m.name()).collect();
+            let problems = self.skip_delivered(&member_names, routed).await;
+            if problems.is_empty() {
+                continue;
+            }
+
+            // Members share batch_mode (enforced by config validation)
+            let units: Vec<Vec<Problem>> = if members[0].is_batch_mode() {
+                vec![problems]
+            } else {
+                problems.into_iter().map(|p| vec![p]).collect()
+            };
+
+            for unit in units {
+                let members = members.clone();
+                let group = group.clone();
+                let database = Arc::clone(&self.database);
+                let events = self.events.clone();
+
+                forward_tasks.push(tokio::spawn(async move {
+                    forward_with_failover(&group, &members, &unit, &database, &events, true).await
+                }));
+            }
+        }
+
+        // During a storm, plain JSON webhook batch connectors can get one digest instead of the full batch
+        let storm_digest = storm && self.settings.anomaly_detection.digest_during_storm;
+        let problem_url_base = self.settings.get_problem_url_base();
+
         // Batch mode connectors - send all problems in one request
         for connector in batch_connectors {
             let connector = Arc::clone(connector);
-            let problems = problems.to_vec();
+            let sampled = problems.iter().filter(|p| routes(&connector, p) && connector.samples(p)).cloned().collect();
+            let pending = self.skip_delivered(&[connector.name()], sampled).await;
+            let problems = self.sample_recurrences(&connector, pending, report).await;
+            if problems.is_empty() {
+                continue;
+            }
             let database = Arc::clone(&self.database);
+            let events = self.events.clone();
+            let digest = (storm_digest && connector.accepts_digest_payload())
+                .then(|| build_digest(&problems, &problem_url_base, DEFAULT_TOP_N, connector.timezone()));
 
             let task = tokio::spawn(async move {
-                let connector_name = connector.name().to_string();
-                match connector.forward_problems_batch(&problems).await {
-                    Ok(response) => {
-                        info!(
-                            "✓ Forwarded batch of {} problems to '{}' (status: {})",
-                            problems.len(),
-                            connector_name,
-                            response.status()
-                        );
-
-                        // Record success in history for each problem
-                        for problem in &problems {
-                            let history = ForwardHistory::new(
-                                problem.problem_id.clone(),
-                                connector_name.clone(),
-                                "success".to_string(),
-                                Some(response.status().as_u16() as i32),
-                                None,
-                            );
-                            let _ = database.insert_forward_history(&history).await;
-                        }
-                    }
-                    Err(e) => {
-                        error!(
-                            "✗ Failed to forward batch to '{}': {}",
-                            connector_name, e
-                        );
-
-                        // Record failure in history for each problem
-                        for problem in &problems {
-                            let history = ForwardHistory::new(
-                                problem.problem_id.clone(),
-                                connector_name.clone(),
-                                "failed".to_string(),
-                                None,
-                                Some(e.to_string()),
-                            );
-                            let _ = database.insert_forward_history(&history).await;
-                        }
+                let chunks = match digest {
+                    Some(digest) => {
+                        let started = std::time::Instant::now();
+                        let result = connector.forward_payload(&problems, digest).await;
+                        vec![ChunkResult::new(problems, started, result)]
                     }
-                }
+                    None => connector.forward_problems_batch(&problems).await,
+                };
+                record_chunk_results(&connector, chunks, &database, &events, true).await
             });
             forward_tasks.push(task);
         }
 
-        // Individual mode connectors - send each problem separately
+        // Individual mode connectors - send each problem separately, starting every
+        // connector's delivery of a problem before any of the next problem
+        let position: HashMap<&str, usize> =
+            problems.iter().enumerate().map(|(i, p)| (p.problem_id.as_str(), i)).collect();
+        let mut deliveries = Vec::new();
         for connector in individual_connectors {
-            for problem in problems {
-                let connector = Arc::clone(connector);
-                let problem = problem.clone();
-                let database = Arc::clone(&self.database);
-
-                let task = tokio::spawn(async move {
-                    let connector_name = connector.name().to_string();
-                    match connector.forward_problem(&problem).await {
-                        Ok(response) => {
-                            info!(
-                                "✓ Forwarded {} to '{}' (status: {})",
-                                problem.problem_id,
-                                connector_name,
-                                response.status()
-                            );
-
-                            // Record success in history
-                            let history = ForwardHistory::new(
-                                problem.problem_id.clone(),
-                                connector_name,
-                                "success".to_string(),
-                                Some(response.status().as_u16() as i32),
-                                None,
-                            );
-                            let _ = database.insert_forward_history(&history).await;
-                        }
-                        Err(e) => {
-                            error!(
-                                "✗ Failed to forward {} to '{}': {}",
-                                problem.problem_id, connector_name, e
-                            );
-
-                            // Record failure in history
-                            let history = ForwardHistory::new(
-                                problem.problem_id.clone(),
-                                connector_name,
-                                "failed".to_string(),
-                                None,
-                                Some(e.to_string()),
-                            );
-                            let _ = database.insert_forward_history(&history).await;
-                        }
-                    }
-                });
-                forward_tasks.push(task);
+            let sampled = problems.iter().filter(|p| routes(connector, p) && connector.samples(p)).cloned().collect();
+            let pending = self.skip_delivered(&[connector.name()], sampled).await;
+            for problem in self.sample_recurrences(connector, pending, report).await {
+                deliveries.push((position[problem.problem_id.as_str()], Arc::clone(connector), problem));
             }
         }
+        deliveries.sort_by_key(|(position, _, _)| *position);
+
+        for (_, connector, problem) in deliveries {
+            let database = Arc::clone(&self.database);
+            let events = self.events.clone();
+
+            let task = tokio::spawn(async move {
+                let chunks = connector.deliver(&[problem]).await;
+                record_chunk_results(&connector, chunks, &database, &events, true).await
+            });
+            forward_tasks.push(task);
+        }
 
         // Wait for all tasks to complete
         for task in forward_tasks {
-            let _ = task.await;
+            if let Ok(outcomes) = task.await {
+                self.comment_on_deliveries(&outcomes, problems).await;
+                outcomes.into_iter().for_each(|outcome| report.record(outcome));
+            }
         }
 
         Ok(())
     }
 
-    /// Get reference to database (for CLI commands)
-    pub fn database(&self) -> &Database {
-        &self.database
+    /// Apply the connector's recurrence sampling: a new problem recurring with the
+    /// same title and/or entity is only forwarded every Nth time, carrying the
+    /// occurrence counts. Updates of skipped problems are skipped as well.
+    async fn sample_recurrences(
+        &self,
+        connector: &Connector,
+        problems: Vec<Problem>,
+        report: &mut CycleReport,
+    ) -> Vec<Problem> {
+        let Some(sampler) = connector.recurrence_sampler() else {
+            return problems;
+        };
+
+        let skipped_problems = match self.database.get_recurrence_skipped(connector.name()).await {
+            Ok(ids) => ids,
+            Err(e) => {
+                error!("Failed to read recurrence sampling state for '{}': {}", connector.name(), e);
+                HashSet::new()
+            }
+        };
+
+        let mut forwarded = Vec::with_capacity(problems.len());
+        let mut suppressed = 0;
+        for mut problem in problems {
+            let message = if skipped_problems.contains(&problem.problem_id) {
+                if problem.status != ProblemStatus::Open {
+                    if let Err(e) = self.database.clear_recurrence_skipped(connector.name(), &problem.problem_id).await {
+                        error!("Failed to clear recurrence sampling state of {}: {}", problem.problem_id, e);
+                    }
+                }
+                "recurrence sampling: occurrence was skipped".to_string()
+            } else {
+                let key = match problem.change {
+                    Some(ProblemChange::New) => sampler.key(&problem),
+                    _ => None,
+                };
+                let Some(key) = key else {
+                    forwarded.push(problem);
+                    continue;
+                };
+
+                match self
+                    .database
+                    .record_recurrence(connector.name(), &key, &problem.problem_id, sampler.every, sampler.reset_after_seconds)
+                    .await
+                {
+                    Ok(decision) if decision.forward => {
+                        if decision.occurrences > 1 || decision.skipped > 0 {
+                            problem.recurrence = Some(Recurrence {
+                                occurrences: decision.occurrences,
+                                skipped_since_last_forward: decision.skipped,
+                            });
+                        }
+                        forwarded.push(problem);
+                        continue;
+                    }
+                    Ok(decision) => format!("recurrence sampling: occurrence {}", decision.occurrences),
+                    Err(e) => {
+                        // Forward rather than lose a problem when the counter is unavailable
+                        error!("Failed to count recurrence of {}: {}", problem.problem_id, e);
+                        forwarded.push(problem);
+                        continue;
+                    }
+                }
+            };
+
+            debug!("Skipping {} for '{}': {}", problem.problem_id, connector.name(), message);
+            let history = ForwardHistory::new(
+                problem.problem_id.clone(),
+                problem.status.to_string(),
+                connector.name().to_string(),
+                SUPPRESSED.to_string(),
+                None,
+                Some(message),
+            );
+            if let Err(e) = self.database.insert_forward_history(&history).await {
+                error!("Failed to record suppressed problem {}: {}", problem.problem_id, e);
+            }
+            suppressed += 1;
+        }
+
+        if suppressed > 0 {
+            info!("'{}' skipped {} problems by recurrence sampling", connector.name(), suppressed);
+        }
+        report.record_suppressed(connector.name(), suppressed);
+        forwarded
     }
 
-    /// Get reference to Dynatrace client (for CLI commands)
-    pub fn dynatrace_client(&self) -> &DynatraceClient {
-        &self.dynatrace_client
+    /// With the duplicate guard enabled, drop problems whose current status forward
+    /// history records as delivered to one of these connectors, e.g. when problems
+    /// are reprocessed after a crash or `clear-cache`
+    async fn skip_delivered(&self, connectors: &[&str], problems: Vec<Problem>) -> Vec<Problem> {
+        if !self.settings.duplicate_guard.enabled {
+            return problems;
+        }
+
+        let mut pending = Vec::with_capacity(problems.len());
+        for problem in problems {
+            // Reminders repeat an earlier delivery on purpose
+            if problem.change == Some(ProblemChange::Renotification) {
+                pending.push(problem);
+                continue;
+            }
+
+            let status = problem.status.to_string();
+            match self.database.has_delivered(&problem.problem_id, &status, connectors).await {
+                Ok(true) => info!(
+                    "Skipping {} ({}) for '{}': already delivered according to forward history",
+                    problem.problem_id,
+                    status,
+                    connectors.join("', '")
+                ),
+                Ok(false) => pending.push(problem),
+                Err(e) => {
+                    // Prefer a possible duplicate over a lost notification
+                    warn!("Duplicate guard lookup failed for {}: {}", problem.problem_id, e);
+                    pending.push(problem);
+                }
+            }
+        }
+        pending
     }
 
-    /// Get reference to connectors (for CLI commands)
-    pub fn connectors(&self) -> &[Arc<Connector>] {
-        &self.connectors
+    /// Send every connector the problems it failed to receive on earlier cycles,
+    /// giving up on those first failed more than `redelivery.max_age_minutes` ago
+    async fn redeliver_failed(&self, report: &mut CycleReport) {
+        let disabled: HashSet<String> = match self.database.get_disabled_connectors().await {
+            Ok(connectors) => connectors.into_iter().map(|c| c.connector_name).collect(),
+            Err(e) => {
+                error!("Error loading disabled connectors: {}", e);
+                return;
+            }
+        };
+        let cutoff = chrono::Utc::now().timestamp() - self.settings.redelivery.max_age_minutes as i64 * 60;
+
+        for connector in self.connectors.iter().filter(|c| !disabled.contains(c.name())) {
+            let failover = self.failover_members(connector, &disabled);
+            let undelivered = match self.database.get_undelivered(connector.name()).await {
+                Ok(undelivered) => undelivered,
+                Err(e) => {
+                    error!("Failed to read undelivered problems of '{}': {}", connector.name(), e);
+                    continue;
+                }
+            };
+
+            let mut problems = Vec::new();
+            for entry in undelivered {
+                if entry.first_failed_at >= cutoff {
+                    problems.push(entry.problem);
+                    continue;
+                }
+                warn!(
+                    "Giving up re-delivering {} to '{}' after {} failed attempt(s); `dtpf replay` can still send it",
+                    entry.problem.problem_id,
+                    connector.name(),
+                    entry.attempts
+                );
+                let _ = self
+                    .database
+                    .clear_undelivered(connector.name(), &[entry.problem.problem_id.as_str()])
+                    .await;
+            }
+            if problems.is_empty() {
+                continue;
+            }
+
+            problems.iter_mut().for_each(|problem| self.link_to_tenant(problem));
+            info!("Re-delivering {} problem(s) to '{}'", problems.len(), connector.name());
+            report.redelivered += problems.len();
+
+            let units: Vec<Vec<Problem>> = if connector.is_batch_mode() {
+                vec![problems]
+            } else {
+                problems.into_iter().map(|p| vec![p]).collect()
+            };
+            for unit in units {
+                // Already dead-lettered when the delivery first failed
+                let outcomes = match &failover {
+                    Some((group, members)) => {
+                        forward_with_failover(group, members, &unit, &self.database, &self.events, false).await
+                    }
+                    None => {
+                        let mut outcomes = Vec::new();
+                        for chunk in connector.deliver(&unit).await {
+                            let problems = chunk.problems.clone();
+                            let outcome =
+                                record_forward_result(connector, chunk, &self.database, &self.events, false).await;
+                            if let Some(error) = &outcome.error {
+                                if let Err(e) = self.database.mark_undelivered(connector.name(), &problems, error).await {
+                                    error!("Failed to record undelivered problems of '{}': {}", connector.name(), e);
+                                }
+                            }
+                            outcomes.push(outcome);
+                        }
+                        outcomes
+                    }
+                };
+
+                // Their dead letters need no replay any more
+                let delivered: Vec<&str> = outcomes
+                    .iter()
+                    .filter(|outcome| outcome.status == "success")
+                    .flat_map(|outcome| outcome.problem_ids.iter().map(String::as_str))
+                    .collect();
+                if let Err(e) = self.database.remove_from_dead_letters(connector.name(), &delivered).await {
+                    error!("Failed to clear re-delivered dead letters of '{}': {}", connector.name(), e);
+                }
+                outcomes.into_iter().for_each(|outcome| report.record(outcome));
+            }
+        }
     }
-}
 
-#[derive(Debug, PartialEq)]
-enum ProcessAction {
-    NewProblem,
-    StatusChange,
+    /// The failover group of a connector whose undelivered problems are kept
+    /// under it, with the connector first and its enabled fellow members after
+    fn failover_members(
+        &self,
+        connector: &Arc<Connector>,
+        disabled: &HashSet<String>,
+    ) -> Option<(String, Vec<Arc<Connector>>)> {
+        let group = connector.failover_group()?;
+        let mut others: Vec<Arc<Connector>> = self
+            .connectors
+            .iter()
+            .filter(|c| c.failover_group() == Some(group) && c.name() != connector.name())
+            .filter(|c| !disabled.contains(c.name()))
+            .cloned()
+            .collect();
+        if self.settings.health.prefer_healthiest {
+            others.sort_by(|a, b| b.health().score.total_cmp(&a.health().score));
+        }
+
+        let members = std::iter::once(Arc::clone(connector)).chain(others).collect();
+        Some((group.to_string(), members))
+    }
+
+    /// Send a digest for every digest connector whose oldest queued problem is
+    /// older than its window, or whose next scheduled send time has passed
+    async fn flush_due_digests(&self, disabled: &HashSet<String>, report: &mut CycleReport) {
+        let now = chrono::Utc::now().timestamp();
+
+        // Queues of disabled connectors are kept until they are enabled again
+        for connector in self.connectors.iter().filter(|c| !disabled.contains(c.name())) {
+            let Some(digest_config) = connector.digest_config() else {
+                continue;
+            };
+
+            let oldest = match self.database.oldest_digest_entry(connector.name()).await {
+                Ok(Some(oldest)) => oldest,
+                Ok(None) => continue,
+                Err(e) => {
+                    error!("Failed to read digest queue for '{}': {}", connector.name(), e);
+                    continue;
+                }
+            };
+
+            let due = if digest_config.every_poll {
+                true
+            } else if digest_config.send_at.is_empty() {
+                now - oldest >= digest_config.window_seconds as i64
+            } else {
+                schedule_due(&digest_config.send_times(), connector.timezone(), oldest, now)
+            };
+
+            if !due {
+                continue;
+            }
+
+            let result = self.send_digest(connector, digest_config.top_n).await;
+            record_digest_outcome(report, connector, result);
+        }
+    }
+
+    /// Once a connector with active hours is inside its window again, send the
+    /// problems held meanwhile: as one digest, or as ordinary deliveries
+    async fn release_held_problems(&self, disabled: &HashSet<String>, report: &mut CycleReport) {
+        for connector in self.connectors.iter().filter(|c| !disabled.contains(c.name())) {
+            let Some(held) = connector.active_hours().map(|hours| hours.held) else {
+                continue;
+            };
+            if held == HeldProblems::Drop || connector.is_quiet() {
+                continue;
+            }
+
+            match self.database.oldest_digest_entry(connector.name()).await {
+                Ok(Some(_)) => {}
+                Ok(None) => continue,
+                Err(e) => {
+                    error!("Failed to read held problems for '{}': {}", connector.name(), e);
+                    continue;
+                }
+            }
+
+            if held == HeldProblems::Digest {
+                let result = self.send_digest(connector, DEFAULT_TOP_N).await;
+                record_digest_outcome(report, connector, result);
+                continue;
+            }
+
+            let (mut problems, max_id) = match self.database.get_digest_queue(connector.name()).await {
+                Ok(queue) => queue,
+                Err(e) => {
+                    error!("Failed to read held problems for '{}': {}", connector.name(), e);
+                    continue;
+                }
+            };
+            problems.iter_mut().for_each(|problem| self.link_to_tenant(problem));
+            info!("Releasing {} held problems to '{}'", problems.len(), connector.name());
+
+            let units: Vec<Vec<Problem>> = if connector.is_batch_mode() {
+                vec![problems]
+            } else {
+                problems.into_iter().map(|p| vec![p]).collect()
+            };
+            for unit in units {
+                let chunks = connector.deliver(&unit).await;
+                let outcomes = record_chunk_results(connector, chunks, &self.database, &self.events, true).await;
+                self.comment_on_deliveries(&outcomes, &unit).await;
+                outcomes.into_iter().for_each(|outcome| report.record(outcome));
+            }
+
+            // Failed deliveries are recorded in forward history like any other
+            if let Err(e) = self.database.delete_digest_entries(connector.name(), max_id).await {
+                error!("Failed to clear held problems for '{}': {}", connector.name(), e);
+            }
+        }
+    }
+
+    /// Build and send one digest from a connector's queue, clearing it on success
+    /// or on a non-retryable failure. Returns the number of problems sent.
+    async fn send_digest(&self, connector: &Connector, top_n: usize) -> Result<usize> {
+        let connector_name = connector.name().to_string();
+        let (mut problems, max_id) = self.database.get_digest_queue(&connector_name).await?;
+        problems.iter_mut().for_each(|problem| self.link_to_tenant(problem));
+
+        if problems.is_empty() {
+            return Ok(0);
+        }
+
+        let digest = connector.digest_payload(&problems, top_n);
+
+        match connector.forward_payload(&problems, digest).await {
+            Ok(status) => {
+                info!(
+                    "✓ Sent digest of {} problems to '{}' (status: {})",
+                    problems.len(),
+                    connector_name,
+                    status
+                );
+
+                for problem in &problems {
+                    let history = ForwardHistory::new(
+                        problem.problem_id.clone(),
+                        problem.status.to_string(),
+                        connector_name.clone(),
+                        "success".to_string(),
+                        Some(status.as_u16() as i32),
+                        None,
+                    );
+                    if let Err(e) = self.database.insert_forward_history(&history).await {
+                        error!("Failed to record digest delivery of {}: {}", problem.problem_id, e);
+                    }
+                }
+
+                self.database.delete_digest_entries(&connector_name, max_id).await?;
+                self.emit(EngineEvent::DigestSent {
+                    connector: connector_name,
+                    problem_count: problems.len(),
+                });
+                Ok(problems.len())
+            }
+            Err(e) => {
+                // Keep the queue so the digest is retried on the next cycle,
+                // unless the receiver rejected it in a way a retry cannot fix
+                if !e.is_retryable() {
+                    warn!(
+                        "Dropping digest of {} problems for '{}' after a non-retryable error",
+                        problems.len(),
+                        connector_name
+                    );
+                    self.database.delete_digest_entries(&connector_name, max_id).await?;
+                }
+
+                for problem in &problems {
+                    let history = ForwardHistory::new(
+                        problem.problem_id.clone(),
+                        problem.status.to_string(),
+                        connector_name.clone(),
+                        "failed".to_string(),
+                        None,
+                        Some(e.to_string()),
+                    );
+                    if let Err(e) = self.database.insert_forward_history(&history).await {
+                        error!("Failed to record digest delivery of {}: {}", problem.problem_id, e);
+                    }
+                }
+                self.emit(EngineEvent::ForwardFailed {
+                    connector: connector_name,
+                    problem_ids: problems.iter().map(|p| p.problem_id.clone()).collect(),
+                    error: e.to_string(),
+                });
+                Err(e)
+            }
+        }
+    }
+
+    /// Write the cycle report when `cycle_reports` is enabled. Failures are
+    /// logged and do not affect forwarding.
+    async fn write_cycle_report(&self, mut report: CycleReport) {
+        for client in &self.dynatrace_clients {
+            let quota = client.take_quota();
+            if quota != ApiQuota::default() {
+                report.dynatrace_api.insert(client.environment().to_string(), quota);
+            }
+        }
+
+        if !self.settings.cycle_reports.enabled {
+            return;
+        }
+
+        report.completed_at = Some(chrono::Utc::now());
+        match report.write(&self.settings.cycle_reports.directory).await {
+            Ok(path) => debug!("Wrote cycle report {}", path.display()),
+            Err(e) => error!("Failed to write cycle report {}: {}", report.cycle_id, e),
+        }
+    }
+
+    /// Persist every connector's health snapshot so `dtpf status` can show it
+    async fn save_connector_health(&self) -> Result<()> {
+        let snapshots: BTreeMap<&str, HealthSnapshot> = self
+            .connectors
+            .iter()
+            .map(|c| (c.name(), c.health()))
+            .collect();
+
+        self.database
+            .set_app_state(CONNECTOR_HEALTH_KEY, &serde_json::to_string(&snapshots)?)
+            .await
+    }
+
+    /// Get reference to database (for CLI commands)
+    pub fn database(&self) -> &Database {
+        &self.database
+    }
+
+    /// Get references to the Dynatrace clients, the main environment first (for CLI commands)
+    pub fn dynatrace_clients(&self) -> &[Arc<DynatraceClient>] {
+        &self.dynatrace_clients
+    }
+
+    /// Get reference to connectors (for CLI commands)
+    pub fn connectors(&self) -> &[Arc<Connector>] {
+        &self.connectors
+    }
+
+    /// Re-send dead letters of a connector, oldest first (only `id` when given).
+    /// Delivered ones are removed; failed ones stay with their replay count
+    /// raised. Returns how many were delivered and how many failed again.
+    pub async fn replay_dead_letters(&self, connector_name: &str, id: Option<i64>) -> Result<(usize, usize)> {
+        let connector = self
+            .connectors
+            .iter()
+            .find(|c| c.name() == connector_name)
+            .ok_or_else(|| ForwarderError::Config(format!("Connector '{}' not found in configuration", connector_name)))?;
+
+        let dead_letters = self.database.get_dead_letters(connector_name, id).await?;
+        if let Some(id) = id.filter(|_| dead_letters.is_empty()) {
+            return Err(ForwarderError::Config(format!(
+                "No dead letter {} for connector '{}'",
+                id, connector_name
+            )));
+        }
+
+        let (mut delivered, mut failed) = (0, 0);
+        for mut dead_letter in dead_letters {
+            dead_letter.problems.iter_mut().for_each(|problem| self.link_to_tenant(problem));
+            let (mut undelivered, mut last_error) = (Vec::new(), None);
+            for chunk in connector.deliver(&dead_letter.problems).await {
+                let problems = chunk.problems.clone();
+                let outcome = record_forward_result(connector, chunk, &self.database, &self.events, false).await;
+                if let Some(error) = outcome.error {
+                    undelivered.extend(problems);
+                    last_error = Some(error);
+                }
+            }
+
+            match last_error {
+                None => {
+                    info!("Replayed dead letter {} to '{}'", dead_letter.id, connector_name);
+                    self.database.delete_dead_letter(dead_letter.id).await?;
+                    delivered += 1;
+                }
+                // Problems of requests that went through are not replayed again
+                Some(error) => {
+                    self.database.record_dead_letter_replay(dead_letter.id, &undelivered, &error).await?;
+                    failed += 1;
+                }
+            }
+        }
+
+        Ok((delivered, failed))
+    }
+}
+
+/// What started an engine cycle
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum CycleSource {
+    /// The polling interval or `dtpf trigger-poll`
+    Poll,
+    /// Problems queued by the push receiver
+    Push,
+    /// The events poll interval (`events.enabled`)
+    Events,
+    /// The security problems poll interval (`security_problems.enabled`)
+    Security,
+    /// The audit log poll interval (`audit_logs.enabled`)
+    AuditLogs,
+}
+
+impl CycleSource {
+    fn as_str(&self) -> &'static str {
+        match self {
+            CycleSource::Poll => "polling",
+            CycleSource::Push => "push",
+            CycleSource::Events => "events",
+            CycleSource::Security => "security_problems",
+            CycleSource::AuditLogs => "audit_logs",
+        }
+    }
+
+    fn label(&self) -> &'static str {
+        match self {
+            CycleSource::Poll => "Poll",
+            CycleSource::Push => "Push",
+            CycleSource::Events => "Events poll",
+            CycleSource::Security => "Security problems poll",
+            CycleSource::AuditLogs => "Audit log poll",
+        }
+    }
+}
+
+/// What a cycle checks problems against, and its running totals
+#[derive(Default)]
+struct CycleState {
+    pending_flaps: HashMap<String, FlapState>,
+    muted: HashSet<String>,
+    disabled: HashSet<String>,
+    fetched: usize,
+    new_problems: usize,
+    status_changes: usize,
+    skipped: usize,
+}
+
+/// Fetches of a pushed problem's details before it is left to the next poll
+const PUSH_FETCH_ATTEMPTS: u32 = 5;
+
+/// Problem detail requests in flight at once when `dynatrace.fetch_details` is on
+const DETAIL_FETCH_CONCURRENCY: usize = 8;
+
+/// How far each time-range constrained poll reaches back before the previous
+/// one, covering problems Dynatrace records with a slight delay
+const POLL_WINDOW_OVERLAP_MS: i64 = 5 * 60 * 1000;
+
+/// app_state key holding the end of an environment's last successful poll
+fn poll_window_key(environment: &str) -> String {
+    format!("poll_window:{}", environment)
+}
+
+/// app_state key holding the timestamp of an environment's newest forwarded audit log entry
+fn audit_log_checkpoint_key(environment: &str) -> String {
+    format!("audit_log_checkpoint:{}", environment)
+}
+
+/// app_state key holding the latest connector health snapshots
+pub const CONNECTOR_HEALTH_KEY: &str = "connector_health";
+
+/// Try each failover group member in order until one accepts the problems.
+/// Problems no member accepted are kept for re-delivery under the first member,
+/// and dead-lettered for it when `dead_letter` is set.
+async fn forward_with_failover(
+    group: &str,
+    members: &[Arc<Connector>],
+    problems: &[Problem],
+    database: &Database,
+    events: &broadcast::Sender<EngineEvent>,
+    dead_letter: bool,
+) -> Vec<DeliveryOutcome> {
+    let mut outcomes = Vec::new();
+    // Problems a member delivered are not sent to the next one
+    let mut remaining = problems.to_vec();
+    for connector in members {
+        let mut failed = Vec::new();
+        for chunk in connector.deliver(&remaining).await {
+            let problems = chunk.problems.clone();
+            let outcome = record_forward_result(connector, chunk, database, events, false).await;
+            if outcome.error.is_some() {
+                failed.extend(problems);
+            } else {
+                // Undelivered problems of the group are kept under its primary
+                let problem_ids: Vec<&str> = problems.iter().map(|p| p.problem_id.as_str()).collect();
+                let _ = database.clear_undelivered(members[0].name(), &problem_ids).await;
+            }
+            outcomes.push(outcome);
+        }
+
+        remaining = failed;
+        if remaining.is_empty() {
+            return outcomes;
+        }
+
+        warn!("Failover group '{}': '{}' failed, trying next member", group, connector.name());
+    }
+
+    error!(
+        "✗ All connectors in failover group '{}' failed for {} problem(s)",
+        group,
+        remaining.len()
+    );
+
+    // Dead-lettered for the primary, which replays them once it recovers
+    let error = outcomes.iter().rev().find_map(|o| o.error.clone()).unwrap_or_default();
+    if dead_letter {
+        if let Err(e) = database.insert_dead_letter(members[0].name(), &remaining, &error).await {
+            error!("Failed to dead-letter problems of failover group '{}': {}", group, e);
+        }
+    }
+    if let Err(e) = database.mark_undelivered(members[0].name(), &remaining, &error).await {
+        error!("Failed to record undelivered problems of failover group '{}': {}", group, e);
+    }
+    outcomes
+}
+
+/// Add the result of sending a digest to the cycle report
+fn record_digest_outcome(report: &mut CycleReport, connector: &Connector, result: Result<usize>) {
+    match result {
+        Ok(0) => {}
+        Ok(sent) => report.record(DeliveryOutcome {
+            connector: connector.name().to_string(),
+            status: "success",
+            problems: sent,
+            error: None,
+            problem_ids: Vec::new(),
+        }),
+        Err(e) => {
+            error!("✗ Failed to send digest to '{}': {}", connector.name(), e);
+            report.record(DeliveryOutcome {
+                connector: connector.name().to_string(),
+                status: "failed",
+                problems: 0,
+                error: Some(e.to_string()),
+                problem_ids: Vec::new(),
+            });
+        }
+    }
+}
+
+/// Record each request of a delivery on its own, so problems of requests that
+/// went through are not dead-lettered with a later one that failed
+async fn record_chunk_results(
+    connector: &Connector,
+    chunks: Vec<ChunkResult>,
+    database: &Database,
+    events: &broadcast::Sender<EngineEvent>,
+    dead_letter: bool,
+) -> Vec<DeliveryOutcome> {
+    let mut outcomes = Vec::new();
+    for chunk in chunks {
+        outcomes.push(record_forward_result(connector, chunk, database, events, dead_letter).await);
+    }
+    outcomes
+}
+
+/// Log, record history and emit an event for one delivery attempt. Failures
+/// of shadow connectors are recorded as `shadow_failed` and emit no event;
+/// other failures keep their problems in the dead-letter queue and for
+/// re-delivery when `dead_letter` is set. A success clears pending re-deliveries.
+async fn record_forward_result(
+    connector: &Connector,
+    chunk: ChunkResult,
+    database: &Database,
+    events: &broadcast::Sender<EngineEvent>,
+    dead_letter: bool,
+) -> DeliveryOutcome {
+    let ChunkResult { problems, started, result } = chunk;
+    let problems = problems.as_slice();
+    let duration_ms = started.elapsed().as_millis() as i64;
+    let connector_name = connector.name();
+    // Correlated problems were delivered as part of their composite
+    let delivered: Vec<(String, String)> = problems
+        .iter()
+        .flat_map(|p| {
+            std::iter::once((p.problem_id.clone(), p.status.to_string())).chain(
+                p.correlated_problems
+                    .iter()
+                    .map(|child| (child.problem_id.clone(), child.status.to_string())),
+            )
+        })
+        .collect();
+    let problem_ids: Vec<String> = delivered.iter().map(|(problem_id, _)| problem_id.clone()).collect();
+
+    let (status, response_code, error_message) = match result {
+        Ok(status) => {
+            info!(
+                "✓ Forwarded {} problem(s) to '{}' (status: {})",
+                problems.len(),
+                connector_name,
+                status
+            );
+            let _ = events.send(EngineEvent::ForwardSucceeded {
+                connector: connector_name.to_string(),
+                problem_ids: problem_ids.clone(),
+                status_code: status.as_u16(),
+            });
+            let delivered_ids: Vec<&str> = problems.iter().map(|p| p.problem_id.as_str()).collect();
+            let _ = database.clear_undelivered(connector_name, &delivered_ids).await;
+            ("success", Some(status.as_u16() as i32), None)
+        }
+        Err(e) if connector.is_shadow() => {
+            warn!("Shadow connector '{}' failed (ignored): {}", connector_name, e);
+            ("shadow_failed", None, Some(e.to_string()))
+        }
+        Err(e) => {
+            error!("✗ Failed to forward to '{}': {}", connector_name, e);
+            let _ = events.send(EngineEvent::ForwardFailed {
+                connector: connector_name.to_string(),
+                problem_ids: problem_ids.clone(),
+                error: e.to_string(),
+            });
+            if dead_letter {
+                if let Err(e) = database.insert_dead_letter(connector_name, problems, &e.to_string()).await {
+                    error!("Failed to dead-letter problems for '{}': {}", connector_name, e);
+                }
+                if let Err(e) = database.mark_undelivered(connector_name, problems, &e.to_string()).await {
+                    error!("Failed to record undelivered problems for '{}': {}", connector_name, e);
+                }
+            }
+            ("failed", None, Some(e.to_string()))
+        }
+    };
+
+    let outcome = DeliveryOutcome {
+        connector: connector_name.to_string(),
+        status,
+        problems: problem_ids.len(),
+        error: error_message.clone(),
+        problem_ids,
+    };
+
+    for (problem_id, problem_status) in delivered {
+        let mut history = ForwardHistory::new(
+            problem_id,
+            problem_status,
+            connector_name.to_string(),
+            status.to_string(),
+            response_code,
+            error_message.clone(),
+        );
+        history.duration_ms = Some(duration_ms);
+        if let Err(e) = database.insert_forward_history(&history).await {
+            error!("Failed to record delivery of {} to '{}': {}", history.problem_id, connector_name, e);
+        }
+    }
+
+    outcome
+}
+
+/// Display ID of the volume anomaly meta-alert, which exists only in the forwarder
+const VOLUME_ANOMALY_DISPLAY_ID: &str = "DTPF-VOLUME-ANOMALY";
+
+/// app_state key holding the problem ID of the open volume anomaly meta-alert, empty when there is none
+const VOLUME_ANOMALY_KEY: &str = "volume_anomaly";
+
+/// Synthetic problem used as a meta-alert when a volume anomaly is detected
+fn volume_anomaly_problem(volume: usize) -> Problem {
+    let now = chrono::Utc::now().timestamp_millis();
+
+    Problem {
+        problem_id: format!("{}-{}", VOLUME_ANOMALY_DISPLAY_ID, now),
+        display_id: VOLUME_ANOMALY_DISPLAY_ID.to_string(),
+        title: format!("Problem volume anomaly: {} new/changed problems in one poll cycle", volume),
+        impact_level: "INFRASTRUCTURE".to_string(),
+        severity_level: "CUSTOM_ALERT".to_string(),
+        status: ProblemStatus::Open,
+        affected_entities: vec![],
+        impacted_entities: vec![],
+        root_cause_entity: None,
+        management_zones: vec![],
+        entity_tags: vec![],
+        problem_filters: vec![],
+        start_time: now,
+        end_time: -1,
+        evidence_details: None,
+        recent_comments: None,
+        impact_analysis: None,
+        event: None,
+        security: None,
+        audit: None,
+        runbooks: vec![],
+        correlated_problems: vec![],
+        recurrence: None,
+        tenant: None,
+        tenant_url_base: None,
+        change: Some(ProblemChange::New),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum ProcessAction {
+    NewProblem,
+    StatusChange,
     Skipped,
+    FlapSuppressed,
+    Renotify,
+    TooOld,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::HistoryFilter;
+    use axum::http::StatusCode;
+    use axum::{routing::get, Json};
+    use serde_json::{json, Value};
+    use std::path::PathBuf;
+
+    /// Serve `api` on a local port as a stand-in for the Dynatrace API
+    fn serve(api: axum::Router) -> String {
+        let listener = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let server = axum::Server::from_tcp(listener).unwrap().serve(api.into_make_service());
+        tokio::spawn(server);
+        url
+    }
+
+    /// Engine on an in-memory database; `tail` holds the connectors and any other settings
+    async fn engine(base_url: &str, tail: &str) -> ForwardingEngine {
+        let yaml = format!(
+            r#"
+dynatrace:
+  base_url: "{}"
+  tenant: "abc"
+polling:
+  interval_seconds: 60
+database:
+  driver: memory
+{}
+"#,
+            base_url, tail
+        );
+        let mut settings: Settings = serde_yaml::from_str(&yaml).unwrap();
+        settings.dynatrace.api_token = Some("test-token".to_string());
+        ForwardingEngine::new(settings).await.unwrap()
+    }
+
+    /// Engine with one NDJSON file connector, plus any `extra` top-level settings
+    async fn file_engine(base_url: &str, name: &str, extra: &str) -> (ForwardingEngine, PathBuf) {
+        let out = std::env::temp_dir().join(format!("dtpf-{}-{}.ndjson", name, std::process::id()));
+        let _ = std::fs::remove_file(&out);
+        let tail = format!(
+            r#"
+connectors:
+  - name: archive
+    type: file
+    batch_mode: false
+    file:
+      path: "{}"
+{}
+"#,
+            out.display(),
+            extra
+        );
+        (engine(base_url, &tail).await, out)
+    }
+
+    async fn history_of(engine: &ForwardingEngine, problem_id: &str) -> Vec<ForwardHistory> {
+        let filter = HistoryFilter { problem_id: Some(problem_id.to_string()), ..Default::default() };
+        engine.database().get_forward_history(&filter).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_security_problem_delivery_is_recorded() {
+        let api = axum::Router::new().route(
+            "/e/abc/api/v2/securityProblems",
+            get(|| async {
+                Json(json!({
+                    "totalCount": 1,
+                    "securityProblems": [{
+                        "securityProblemId": "2919200225913269102",
+                        "displayId": "S-1234",
+                        "status": "OPEN",
+                        "title": "Remote Code Execution (RCE)",
+                        "riskAssessment": {"riskLevel": "HIGH", "riskScore": 9.0}
+                    }]
+                }))
+            }),
+        );
+        let (engine, out) = file_engine(&serve(api), "security", "").await;
+
+        let mut report = CycleReport::start(["archive"]);
+        engine.forward_security_problems(&mut report).await.unwrap();
+
+        let history = history_of(&engine, "2919200225913269102").await;
+        assert_eq!(history.len(), 1);
+        assert_eq!((history[0].connector_name.as_str(), history[0].status.as_str()), ("archive", "success"));
+        let deliveries = engine.database().get_problem_deliveries("2919200225913269102").await.unwrap();
+        assert_eq!(deliveries[0].state, "delivered");
+        let written: Value = serde_json::from_str(std::fs::read_to_string(&out).unwrap().trim()).unwrap();
+        assert_eq!(written["displayId"], "S-1234");
+        let _ = std::fs::remove_file(out);
+    }
+
+    #[tokio::test]
+    async fn test_audit_log_delivery_is_recorded() {
+        let api = axum::Router::new().route(
+            "/e/abc/api/v2/auditlogs",
+            get(|| async {
+                Json(json!({
+                    "totalCount": 1,
+                    "logs": [{
+                        "logId": "166712345678900000",
+                        "eventType": "UPDATE",
+                        "category": "CONFIG",
+                        "user": "admin@example.com",
+                        "timestamp": 1667123456789i64,
+                        "success": true
+                    }]
+                }))
+            }),
+        );
+        let (engine, out) = file_engine(&serve(api), "audit", "").await;
+
+        let mut report = CycleReport::start(["archive"]);
+        engine.forward_audit_logs(&mut report).await.unwrap();
+
+        let history = history_of(&engine, "166712345678900000").await;
+        assert_eq!(history.len(), 1);
+        assert_eq!((history[0].connector_name.as_str(), history[0].status.as_str()), ("archive", "success"));
+        assert!(std::fs::read_to_string(&out).unwrap().contains("166712345678900000"));
+        let _ = std::fs::remove_file(out);
+    }
+
+    #[tokio::test]
+    async fn test_volume_anomaly_alerts_once_per_storm() {
+        let extra = "anomaly_detection:\n  enabled: true\n  meta_alert: true";
+        let (engine, out) = file_engine("http://127.0.0.1:9", "anomaly", extra).await;
+
+        let mut report = CycleReport::start(["archive"]);
+        let cycle = engine.start_cycle().await;
+        engine.forward_checked(Vec::new(), Vec::new(), true, CycleSource::Poll, &cycle, &mut report).await;
+        engine.forward_checked(Vec::new(), Vec::new(), true, CycleSource::Poll, &cycle, &mut report).await;
+
+        let (open, _) = engine.database().get_app_state(VOLUME_ANOMALY_KEY).await.unwrap().unwrap();
+        assert!(open.starts_with(VOLUME_ANOMALY_DISPLAY_ID));
+        let history = history_of(&engine, &open).await;
+        assert_eq!(history.len(), 1, "the second storm cycle must not alert again");
+        assert_eq!(history[0].status, "success");
+        assert_eq!(std::fs::read_to_string(&out).unwrap().lines().count(), 1);
+
+        engine.close_volume_anomaly().await;
+        let tracked = engine.database().get_problem(&open).await.unwrap().unwrap();
+        assert_eq!(tracked.status, "CLOSED");
+        assert!(engine.open_volume_anomaly(42).await.is_some(), "a new storm alerts again");
+        let _ = std::fs::remove_file(out);
+    }
+
+    /// Send three problems to a batch webhook that `limit` splits into one
+    /// request per problem, failing the second request
+    async fn deliver_with_second_request_failing(limit: &str) {
+        let hook = axum::Router::new().route(
+            "/hook",
+            axum::routing::post(|body: String| async move {
+                if body.contains("LOADTEST-00000002") {
+                    StatusCode::SERVICE_UNAVAILABLE
+                } else {
+                    StatusCode::OK
+                }
+            }),
+        );
+        let tail = format!(
+            r#"
+connectors:
+  - name: hook
+    type: webhook
+    url: "{}/hook"
+    batch_mode: true
+    retry_attempts: 1
+    {}
+"#,
+            serve(hook),
+            limit
+        );
+        let engine = engine("http://127.0.0.1:9", &tail).await;
+
+        let problems: Vec<Problem> = (1..=3).map(crate::forwarder::loadtest::synthetic_problem).collect();
+        let mut report = CycleReport::start(["hook"]);
+        engine
+            .forward_collected_problems(&problems, false, &HashSet::new(), &mut report)
+            .await
+            .unwrap();
+
+        let status = |history: Vec<ForwardHistory>| history.into_iter().map(|h| h.status).collect::<Vec<_>>();
+        assert_eq!(status(history_of(&engine, "LOADTEST-00000001").await), ["success"]);
+        assert_eq!(status(history_of(&engine, "LOADTEST-00000002").await), ["failed"]);
+        assert_eq!(status(history_of(&engine, "LOADTEST-00000003").await), ["failed"]);
+
+        let dead_letters = engine.database().get_dead_letters("hook", None).await.unwrap();
+        assert_eq!(dead_letters.len(), 1);
+        assert_eq!(dead_letters[0].problem_ids, ["LOADTEST-00000002", "LOADTEST-00000003"]);
+        let undelivered: Vec<String> = engine
+            .database()
+            .get_undelivered("hook")
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|entry| entry.problem.problem_id)
+            .collect();
+        assert_eq!(undelivered, ["LOADTEST-00000002", "LOADTEST-00000003"]);
+    }
+
+    #[tokio::test]
+    async fn test_batch_chunks_are_recorded_separately() {
+        deliver_with_second_request_failing("batch_max_items: 1").await;
+    }
+
+    #[tokio::test]
+    async fn test_oversized_batch_parts_are_recorded_separately() {
+        deliver_with_second_request_failing("batch_max_bytes: 1").await;
+    }
+
+    /// Webhook that accepts requests on `/ok` and fails them on `/down`
+    fn webhook_receiver() -> String {
+        serve(
+            axum::Router::new()
+                .route("/ok", axum::routing::post(|| async { StatusCode::OK }))
+                .route("/down", axum::routing::post(|| async { StatusCode::SERVICE_UNAVAILABLE })),
+        )
+    }
+
+    /// Engine whose connector `hook` failed to deliver a problem earlier
+    async fn redelivery_engine(tail: &str) -> (ForwardingEngine, Problem) {
+        let engine = engine("http://127.0.0.1:9", tail).await;
+        let problem = crate::forwarder::loadtest::synthetic_problem(7);
+        let database = engine.database();
+        database.insert_dead_letter("hook", std::slice::from_ref(&problem), "HTTP 503").await.unwrap();
+        database.mark_undelivered("hook", std::slice::from_ref(&problem), "HTTP 503").await.unwrap();
+        (engine, problem)
+    }
+
+    #[tokio::test]
+    async fn test_redelivery_clears_dead_letter() {
+        let tail = format!(
+            "connectors:\n  - name: hook\n    url: \"{}/ok\"\n    retry_attempts: 1",
+            webhook_receiver()
+        );
+        let (engine, problem) = redelivery_engine(&tail).await;
+
+        engine.redeliver_failed(&mut CycleReport::start(["hook"])).await;
+
+        let database = engine.database();
+        assert!(database.get_dead_letters("hook", None).await.unwrap().is_empty());
+        assert!(database.get_undelivered("hook").await.unwrap().is_empty());
+        let history = history_of(&engine, &problem.problem_id).await;
+        assert_eq!(history[0].status, "success");
+    }
+
+    #[tokio::test]
+    async fn test_failed_redelivery_keeps_dead_letter() {
+        let tail = format!(
+            "connectors:\n  - name: hook\n    url: \"{}/down\"\n    retry_attempts: 1",
+            webhook_receiver()
+        );
+        let (engine, problem) = redelivery_engine(&tail).await;
+
+        engine.redeliver_failed(&mut CycleReport::start(["hook"])).await;
+
+        let database = engine.database();
+        let dead_letters = database.get_dead_letters("hook", None).await.unwrap();
+        assert_eq!(dead_letters.len(), 1, "the first failure's dead letter is not duplicated");
+        assert_eq!(dead_letters[0].problem_ids, std::slice::from_ref(&problem.problem_id));
+        let undelivered = database.get_undelivered("hook").await.unwrap();
+        assert_eq!(undelivered[0].attempts, 2);
+        let history = history_of(&engine, &problem.problem_id).await;
+        assert_eq!(history[0].status, "failed");
+    }
+
+    #[tokio::test]
+    async fn test_redelivery_fails_over() {
+        let receiver = webhook_receiver();
+        let tail = format!(
+            r#"
+connectors:
+  - name: hook
+    url: "{0}/down"
+    retry_attempts: 1
+    failover_group: paging
+  - name: backup
+    url: "{0}/ok"
+    retry_attempts: 1
+    failover_group: paging
+"#,
+            receiver
+        );
+        let (engine, problem) = redelivery_engine(&tail).await;
+
+        engine.redeliver_failed(&mut CycleReport::start(["hook", "backup"])).await;
+
+        let delivered: Vec<(String, String)> = history_of(&engine, &problem.problem_id)
+            .await
+            .into_iter()
+            .map(|h| (h.connector_name, h.status))
+            .collect();
+        assert!(delivered.contains(&("backup".to_string(), "success".to_string())));
+        let database = engine.database();
+        assert!(database.get_dead_letters("hook", None).await.unwrap().is_empty());
+        assert!(database.get_undelivered("hook").await.unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_xmatters_retries_only_the_failed_problem() {
+        // The trigger fails the first request for the second problem
+        let requests: Arc<std::sync::Mutex<Vec<String>>> = Arc::default();
+        let received = Arc::clone(&requests);
+        let trigger = axum::Router::new().route(
+            "/trigger",
+            axum::routing::post(move |Json(body): Json<Value>| async move {
+                let problem_id = body["properties"]["problemId"].as_str().unwrap_or_default().to_string();
+                let mut received = received.lock().unwrap();
+                let first_try = !received.contains(&problem_id);
+                received.push(problem_id.clone());
+                if problem_id == "LOADTEST-00000002" && first_try {
+                    StatusCode::BAD_GATEWAY
+                } else {
+                    StatusCode::ACCEPTED
+                }
+            }),
+        );
+        let tail = format!(
+            r#"
+connectors:
+  - name: pager
+    type: xmatters
+    url: "{}/trigger"
+    batch_mode: true
+    retry_attempts: 2
+"#,
+            serve(trigger)
+        );
+        let engine = engine("http://127.0.0.1:9", &tail).await;
+
+        let problems: Vec<Problem> = (1..=3).map(crate::forwarder::loadtest::synthetic_problem).collect();
+        let mut report = CycleReport::start(["pager"]);
+        engine
+            .forward_collected_problems(&problems, false, &HashSet::new(), &mut report)
+            .await
+            .unwrap();
+
+        assert_eq!(
+            *requests.lock().unwrap(),
+            ["LOADTEST-00000001", "LOADTEST-00000002", "LOADTEST-00000002", "LOADTEST-00000003"]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_pushed_problem_is_refetched_after_a_failure() {
+        // Dynatrace fails the first details request
+        let requests = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counted = Arc::clone(&requests);
+        let api = axum::Router::new().route(
+            "/e/abc/api/v2/problems/:problem_id",
+            get(move || async move {
+                if counted.fetch_add(1, std::sync::atomic::Ordering::SeqCst) == 0 {
+                    return Err(StatusCode::SERVICE_UNAVAILABLE);
+                }
+                Ok(Json(serde_json::to_value(crate::forwarder::loadtest::synthetic_problem(1)).unwrap()))
+            }),
+        );
+        let (engine, out) = file_engine(&serve(api), "pushed", "").await;
+        let queue = engine.push_queue();
+        queue
+            .push(PushedProblem { environment: None, problem_id: "LOADTEST-00000001".to_string(), fetch_failures: 0 })
+            .await;
+
+        engine.forward_pushed(&mut CycleReport::start(["archive"])).await.unwrap();
+        assert!(history_of(&engine, "LOADTEST-00000001").await.is_empty());
+
+        // The push woke the engine once already; wait for the requeued problem
+        let history = tokio::time::timeout(Duration::from_secs(5), async {
+            loop {
+                queue.notified().await;
+                engine.forward_pushed(&mut CycleReport::start(["archive"])).await.unwrap();
+                let history = history_of(&engine, "LOADTEST-00000001").await;
+                if !history.is_empty() {
+                    return history;
+                }
+            }
+        })
+        .await
+        .unwrap();
+        assert_eq!(history.len(), 1);
+        assert_eq!(history[0].status, "success");
+        let _ = std::fs::remove_file(out);
+    }
+
+    /// A synthetic problem with `status`
+    fn problem_with_status(sequence: usize, status: ProblemStatus) -> Problem {
+        let mut problem = crate::forwarder::loadtest::synthetic_problem(sequence);
+        problem.status = status;
+        problem
+    }
+
+    /// Record a successful delivery of the problem's current status, `ago` seconds back
+    async fn delivered(engine: &ForwardingEngine, problem: &Problem, ago: i64) {
+        let mut history = ForwardHistory::new(
+            problem.problem_id.clone(),
+            problem.status.to_string(),
+            "hook".to_string(),
+            "success".to_string(),
+            Some(200),
+            None,
+        );
+        history.forwarded_at -= ago;
+        engine.database().insert_forward_history(&history).await.unwrap();
+    }
+
+    async fn pending_flap(engine: &ForwardingEngine, problem_id: &str) -> Option<FlapState> {
+        let flaps = engine.database().get_pending_flaps().await.unwrap();
+        flaps.into_iter().find(|flap| flap.problem_id == problem_id)
+    }
+
+    const FLAP_ENGINE: &str = "flap_suppression:\n  enabled: true\n  window_minutes: 10\nconnectors:\n  - name: hook\n    url: \"http://127.0.0.1:9/hook\"";
+
+    #[tokio::test]
+    async fn test_flap_suppressed_within_window_and_released_after() {
+        let engine = engine("http://127.0.0.1:9", FLAP_ENGINE).await;
+        let open = problem_with_status(1, ProblemStatus::Open);
+        let closed = problem_with_status(1, ProblemStatus::Closed);
+
+        assert_eq!(engine.check_problem(&open, None).await.unwrap(), ProcessAction::NewProblem);
+        delivered(&engine, &open, 0).await;
+        assert_eq!(engine.check_problem(&closed, None).await.unwrap(), ProcessAction::StatusChange);
+        delivered(&engine, &closed, 0).await;
+
+        // Re-opened while OPEN was forwarded less than 10 minutes ago
+        assert_eq!(engine.check_problem(&open, None).await.unwrap(), ProcessAction::FlapSuppressed);
+        let flap = pending_flap(&engine, &open.problem_id).await.unwrap();
+        assert_eq!((flap.forwarded_status.as_str(), flap.pending_status.as_deref()), ("CLOSED", Some("OPEN")));
+
+        // Still withheld until the status has held for the whole window
+        assert_eq!(engine.check_problem(&open, Some(&flap)).await.unwrap(), ProcessAction::Skipped);
+
+        sqlx::query("UPDATE flap_suppressions SET last_suppressed_at = last_suppressed_at - 600")
+            .execute(engine.database().pool())
+            .await
+            .unwrap();
+        let flap = pending_flap(&engine, &open.problem_id).await.unwrap();
+        assert_eq!(engine.check_problem(&open, Some(&flap)).await.unwrap(), ProcessAction::StatusChange);
+        assert!(pending_flap(&engine, &open.problem_id).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_flap_back_to_forwarded_status_is_not_pending() {
+        let engine = engine("http://127.0.0.1:9", FLAP_ENGINE).await;
+        let open = problem_with_status(1, ProblemStatus::Open);
+        let closed = problem_with_status(1, ProblemStatus::Closed);
+
+        engine.check_problem(&open, None).await.unwrap();
+        delivered(&engine, &open, 0).await;
+        engine.check_problem(&closed, None).await.unwrap();
+        delivered(&engine, &closed, 0).await;
+        engine.check_problem(&open, None).await.unwrap();
+
+        // Closed again: receivers already have CLOSED, so nothing is left to forward
+        let flap = pending_flap(&engine, &open.problem_id).await;
+        assert_eq!(engine.check_problem(&closed, flap.as_ref()).await.unwrap(), ProcessAction::FlapSuppressed);
+        assert!(pending_flap(&engine, &open.problem_id).await.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_status_change_outside_flap_window_is_forwarded() {
+        let engine = engine("http://127.0.0.1:9", FLAP_ENGINE).await;
+        let open = problem_with_status(1, ProblemStatus::Open);
+        let closed = problem_with_status(1, ProblemStatus::Closed);
+
+        engine.check_problem(&open, None).await.unwrap();
+        delivered(&engine, &open, 601).await;
+        engine.check_problem(&closed, None).await.unwrap();
+        delivered(&engine, &closed, 0).await;
+
+        assert_eq!(engine.check_problem(&open, None).await.unwrap(), ProcessAction::StatusChange);
+    }
+
+    /// Date a tracked problem's last status change and last forward `ago` seconds back
+    async fn forwarded_ago(engine: &ForwardingEngine, problem_id: &str, status_change_ago: i64, forward_ago: i64) {
+        let now = chrono::Utc::now().timestamp();
+        sqlx::query("UPDATE forwarded_problems SET last_status_change_at = ?, last_forwarded_at = ? WHERE problem_id = ?")
+            .bind(now - status_change_ago)
+            .bind(now - forward_ago)
+            .bind(problem_id)
+            .execute(engine.database().pool())
+            .await
+            .unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_renotify_after_interval_not_before() {
+        let tail = "renotify:\n  enabled: true\n  after_minutes: 30\n  repeat_minutes: 60\nconnectors:\n  - name: hook\n    url: \"http://127.0.0.1:9/hook\"";
+        let engine = engine("http://127.0.0.1:9", tail).await;
+        let open = problem_with_status(1, ProblemStatus::Open);
+        assert_eq!(engine.check_problem(&open, None).await.unwrap(), ProcessAction::NewProblem);
+
+        // First reminder `after_minutes` after it was forwarded as open
+        forwarded_ago(&engine, &open.problem_id, 1_740, 1_740).await;
+        assert_eq!(engine.check_problem(&open, None).await.unwrap(), ProcessAction::Skipped);
+        forwarded_ago(&engine, &open.problem_id, 1_800, 1_800).await;
+        assert_eq!(engine.check_problem(&open, None).await.unwrap(), ProcessAction::Renotify);
+        assert_eq!(engine.check_problem(&open, None).await.unwrap(), ProcessAction::Skipped);
+
+        // Then every `repeat_minutes` after the last reminder
+        forwarded_ago(&engine, &open.problem_id, 5_400, 3_540).await;
+        assert_eq!(engine.check_problem(&open, None).await.unwrap(), ProcessAction::Skipped);
+        forwarded_ago(&engine, &open.problem_id, 5_400, 3_600).await;
+        assert_eq!(engine.check_problem(&open, None).await.unwrap(), ProcessAction::Renotify);
+    }
+
+    #[tokio::test]
+    async fn test_renotify_skips_closed_problems_and_repeats_only_when_set() {
+        let tail = "renotify:\n  enabled: true\n  after_minutes: 30\nconnectors:\n  - name: hook\n    url: \"http://127.0.0.1:9/hook\"";
+        let engine = engine("http://127.0.0.1:9", tail).await;
+        let open = problem_with_status(1, ProblemStatus::Open);
+        engine.check_problem(&open, None).await.unwrap();
+
+        forwarded_ago(&engine, &open.problem_id, 1_800, 1_800).await;
+        assert_eq!(engine.check_problem(&open, None).await.unwrap(), ProcessAction::Renotify);
+        // Without repeat_minutes there is only the one reminder
+        forwarded_ago(&engine, &open.problem_id, 90_000, 86_400).await;
+        assert_eq!(engine.check_problem(&open, None).await.unwrap(), ProcessAction::Skipped);
+
+        let closed = problem_with_status(2, ProblemStatus::Closed);
+        engine.check_problem(&closed, None).await.unwrap();
+        forwarded_ago(&engine, &closed.problem_id, 86_400, 86_400).await;
+        assert_eq!(engine.check_problem(&closed, None).await.unwrap(), ProcessAction::Skipped);
+    }
+
+    #[tokio::test]
+    async fn test_max_problem_age_cutoff() {
+        let mut engine = engine("http://127.0.0.1:9", "connectors:\n  - name: hook\n    url: \"http://127.0.0.1:9/hook\"").await;
+        Arc::get_mut(&mut engine.settings).unwrap().polling.max_problem_age_hours = Some(2);
+        // As a stateful instance, which forwards problems that started before it
+        engine.started_at = 0;
+        let started_ago = |seconds: i64| {
+            let mut problem = crate::forwarder::loadtest::synthetic_problem(seconds as usize);
+            problem.start_time = chrono::Utc::now().timestamp_millis() - seconds * 1000;
+            problem
+        };
+
+        let inside = started_ago(7_200 - 60);
+        assert_eq!(engine.check_problem(&inside, None).await.unwrap(), ProcessAction::NewProblem);
+
+        let outside = started_ago(7_200 + 1);
+        assert_eq!(engine.check_problem(&outside, None).await.unwrap(), ProcessAction::TooOld);
+        let history = history_of(&engine, &outside.problem_id).await;
+        assert_eq!(history[0].status, SKIPPED);
+
+        // Later updates of a skipped problem are not forwarded either
+        let mut closed = outside.clone();
+        closed.status = ProblemStatus::Closed;
+        assert_eq!(engine.check_problem(&closed, None).await.unwrap(), ProcessAction::TooOld);
+    }
+
+    /// Dynatrace stand-in listing one synthetic problem for `tenant`
+    fn problem_list(tenant: &str, sequence: usize) -> String {
+        let problem = serde_json::to_value(crate::forwarder::loadtest::synthetic_problem(sequence)).unwrap();
+        serve(axum::Router::new().route(
+            &format!("/e/{}/api/v2/problems", tenant),
+            get(move || {
+                let problem = problem.clone();
+                async move { Json(json!({"totalCount": 1, "pageSize": 1, "problems": [problem]})) }
+            }),
+        ))
+    }
+
+    /// Engine polling a main environment and `eu`
+    async fn multi_tenant_engine(main_url: &str, eu_url: &str) -> ForwardingEngine {
+        let yaml = format!(
+            r#"
+dynatrace:
+  base_url: "{}"
+  tenant: "abc"
+  retry_attempts: 1
+  environments:
+    - name: eu
+      base_url: "{}"
+      tenant: "def"
+      token_env: DTPF_TEST_EU_TOKEN
+polling:
+  interval_seconds: 60
+database:
+  driver: memory
+connectors:
+  - name: hook
+    url: "http://127.0.0.1:9/hook"
+"#,
+            main_url, eu_url
+        );
+        let mut settings: Settings = serde_yaml::from_str(&yaml).unwrap();
+        settings.dynatrace.api_token = Some("test-token".to_string());
+        settings.dynatrace.environments[0].api_token = Some("eu-token".to_string());
+        ForwardingEngine::new(settings).await.unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_multi_tenant_problems_link_to_their_environment() {
+        let (main_url, eu_url) = (problem_list("abc", 1), problem_list("def", 2));
+        let engine = multi_tenant_engine(&main_url, &eu_url).await;
+
+        let (mut problems, polled) = engine.fetch_problems(chrono::Utc::now().timestamp()).await.unwrap();
+        problems.sort_by(|a, b| a.problem_id.cmp(&b.problem_id));
+        assert_eq!(polled, vec!["default", "eu"]);
+        assert_eq!(problems.len(), 2);
+        assert_eq!(problems[0].tenant.as_deref(), Some("default"));
+        assert_eq!(
+            problems[0].tenant_url_base.as_deref(),
+            Some(format!("{}/e/abc/#problems/problemdetails;pid=", main_url).as_str())
+        );
+        assert_eq!(problems[1].tenant.as_deref(), Some("eu"));
+        assert_eq!(
+            problems[1].tenant_url_base.as_deref(),
+            Some(format!("{}/e/def/#problems/problemdetails;pid=", eu_url).as_str())
+        );
+        assert_eq!(engine.client_for(&problems[1]).environment(), "eu");
+    }
+
+    #[tokio::test]
+    async fn test_multi_tenant_unmatched_problems() {
+        // The eu environment is down: its problems are left out of the cycle
+        let engine = multi_tenant_engine(&problem_list("abc", 1), "http://127.0.0.1:9").await;
+        let (problems, polled) = engine.fetch_problems(chrono::Utc::now().timestamp()).await.unwrap();
+        assert_eq!(polled, vec!["default"]);
+        assert_eq!(problems.len(), 1);
+
+        // A problem of an environment that is not configured keeps the main links and client
+        let mut problem = crate::forwarder::loadtest::synthetic_problem(3);
+        problem.tenant = Some("apac".to_string());
+        engine.link_to_tenant(&mut problem);
+        assert_eq!(problem.tenant_url_base, None);
+        assert_eq!(engine.client_for(&problem).environment(), "default");
+    }
 }
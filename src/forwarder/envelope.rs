@@ -0,0 +1,119 @@
+use chrono::{DateTime, SecondsFormat, Utc};
+use serde_json::{json, Value};
+use crate::config::{ConnectorConfig, EnvelopeConfig};
+use crate::dynatrace::{Problem, ProblemChange, ProblemStatus};
+use crate::error::Result;
+use crate::forwarder::payload;
+
+/// Wrap each problem's JSON in an envelope (an array of envelopes in batch mode)
+pub fn wrap(
+    problems: &[Problem],
+    batch: bool,
+    connector: &ConnectorConfig,
+    config: &EnvelopeConfig,
+    problem_url_base: &str,
+) -> Result<Value> {
+    let metadata = Metadata {
+        hostname: gethostname::gethostname().to_string_lossy().into_owned(),
+        tenant: tenant(problem_url_base),
+        forwarded_at: Utc::now(),
+    };
+
+    let mut envelopes = problems
+        .iter()
+        .map(|problem| {
+            let data = payload::problem_payload(problem, connector, problem_url_base)?;
+            Ok(envelope(data, problem, config, &metadata))
+        })
+        .collect::<Result<Vec<_>>>()?;
+
+    if batch {
+        Ok(Value::Array(envelopes))
+    } else {
+        Ok(envelopes.remove(0))
+    }
+}
+
+/// Values shared by all envelopes of one request
+struct Metadata {
+    hostname: String,
+    tenant: String,
+    forwarded_at: DateTime<Utc>,
+}
+
+fn envelope(data: Value, problem: &Problem, config: &EnvelopeConfig, metadata: &Metadata) -> Value {
+    let mut envelope = json!({
+        "eventType": event_type(problem).as_str(),
+        "forwardedAt": metadata.forwarded_at.to_rfc3339_opts(SecondsFormat::Millis, true),
+        "forwarder": {
+            "hostname": metadata.hostname,
+            "version": env!("CARGO_PKG_VERSION"),
+        },
+        // Problems from further environments carry their own link prefix
+        "tenant": problem.tenant_url_base.as_deref().map(tenant).unwrap_or_else(|| metadata.tenant.clone()),
+    });
+    if let Some(environment) = &config.environment {
+        envelope["environment"] = json!(environment);
+    }
+    envelope["problem"] = data;
+
+    envelope
+}
+
+/// The change detected by the engine. Problems rendered outside a poll cycle
+/// (`dtpf render`, connector tests) count as new while open.
+fn event_type(problem: &Problem) -> ProblemChange {
+    problem.change.unwrap_or(if problem.status == ProblemStatus::Open {
+        ProblemChange::New
+    } else {
+        ProblemChange::StatusChange
+    })
+}
+
+/// Tenant id from the problem link prefix `<base>/e/<tenant>/#problems/...`
+fn tenant(problem_url_base: &str) -> String {
+    problem_url_base
+        .split("/#")
+        .next()
+        .and_then(|environment| environment.rsplit('/').next())
+        .unwrap_or_default()
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_envelope_metadata() {
+        let problem: Problem = serde_json::from_value(json!({
+            "problemId": "P-1", "displayId": "P-1", "title": "High CPU", "impactLevel": "INFRASTRUCTURE",
+            "severityLevel": "RESOURCE_CONTENTION", "status": "CLOSED", "affectedEntities": [],
+            "impactedEntities": [], "rootCauseEntity": null, "managementZones": [], "entityTags": [],
+            "problemFilters": [], "startTime": 0, "endTime": 1
+        }))
+        .unwrap();
+        let metadata = Metadata {
+            hostname: "fwd-01".to_string(),
+            tenant: tenant("https://dt.example.com/e/abc12345/#problems/problemdetails;pid="),
+            forwarded_at: DateTime::from_timestamp(1_770_700_000, 0).unwrap(),
+        };
+        let config = EnvelopeConfig {
+            environment: Some("production".to_string()),
+        };
+
+        let wrapped = envelope(json!({ "problemId": "P-1" }), &problem, &config, &metadata);
+
+        assert_eq!(
+            wrapped,
+            json!({
+                "eventType": "status_change",
+                "forwardedAt": "2026-02-10T05:06:40.000Z",
+                "forwarder": { "hostname": "fwd-01", "version": env!("CARGO_PKG_VERSION") },
+                "tenant": "abc12345",
+                "environment": "production",
+                "problem": { "problemId": "P-1" }
+            })
+        );
+    }
+}
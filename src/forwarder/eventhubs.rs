@@ -0,0 +1,202 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use hmac::{Hmac, Mac};
+use reqwest::{header, Client, StatusCode};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use sha2::Sha256;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use crate::config::{ConnectorConfig, EventHubsAuth, EventHubsConfig};
+use crate::dynatrace::Problem;
+use crate::error::{ForwarderError, Result};
+use crate::forwarder::payload;
+use tracing::debug;
+
+const EVENTHUBS_SCOPE: &str = "https://eventhubs.azure.net/.default";
+
+/// Lifetime of generated SAS tokens
+const SAS_TOKEN_TTL: Duration = Duration::from_secs(3600);
+
+/// Refresh AAD tokens this long before they expire
+const TOKEN_EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+/// Publishes problems to an Azure Event Hub via the REST batch API
+pub struct EventHubsPublisher {
+    client: Client,
+    config: EventHubsConfig,
+    connector: ConnectorConfig,
+    problem_url_base: String,
+    cached_token: Mutex<Option<(String, Instant)>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AadTokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+impl EventHubsPublisher {
+    /// Create a new publisher sharing the connector's HTTP client
+    pub fn new(client: Client, config: EventHubsConfig, connector: ConnectorConfig, problem_url_base: String) -> Self {
+        Self {
+            client,
+            config,
+            connector,
+            problem_url_base,
+            cached_token: Mutex::new(None),
+        }
+    }
+
+    /// Event Hub resource URI (also the SAS token audience)
+    pub fn resource_uri(&self) -> String {
+        format!(
+            "https://{}.servicebus.windows.net/{}",
+            self.config.namespace, self.config.event_hub
+        )
+    }
+
+    /// Send each problem as one event in a single batch request
+    pub async fn publish(&self, problems: &[Problem], timeout: Duration) -> Result<StatusCode> {
+        let url = format!("{}/messages", self.resource_uri());
+        let authorization = self.authorization().await?;
+        let body = serde_json::to_string(&build_event_batch(problems, &self.connector, &self.problem_url_base)?)?;
+
+        debug!("Sending {} event(s) to {}", problems.len(), url);
+
+        let response = self
+            .client
+            .post(&url)
+            .timeout(timeout)
+            .header(header::AUTHORIZATION, authorization)
+            .header(header::CONTENT_TYPE, "application/vnd.microsoft.servicebus.json")
+            .body(body)
+            .send()
+            .await?;
+
+        let status = response.status();
+
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(ForwarderError::from_status(url, status, &error_text));
+        }
+
+        Ok(status)
+    }
+
+    /// Build the Authorization header value for the configured auth method
+    async fn authorization(&self) -> Result<String> {
+        match self.config.auth {
+            EventHubsAuth::Sas => {
+                let key_name = self.config.sas_key_name.as_deref().unwrap_or_default();
+                let key = self.config.sas_key.as_deref().unwrap_or_default();
+                let expiry = chrono::Utc::now().timestamp() + SAS_TOKEN_TTL.as_secs() as i64;
+
+                sas_token(&self.resource_uri(), key_name, key, expiry)
+            }
+            EventHubsAuth::Aad => Ok(format!("Bearer {}", self.aad_token().await?)),
+        }
+    }
+
+    /// Get a cached or fresh Azure AD token using the client credentials flow
+    async fn aad_token(&self) -> Result<String> {
+        let mut cached = self.cached_token.lock().await;
+
+        if let Some((token, expires_at)) = cached.as_ref() {
+            if *expires_at > Instant::now() + TOKEN_EXPIRY_MARGIN {
+                return Ok(token.clone());
+            }
+        }
+
+        let token_url = format!(
+            "https://login.microsoftonline.com/{}/oauth2/v2.0/token",
+            self.config.tenant_id.as_deref().unwrap_or_default()
+        );
+
+        let response = self
+            .client
+            .post(&token_url)
+            .form(&[
+                ("grant_type", "client_credentials"),
+                ("client_id", self.config.client_id.as_deref().unwrap_or_default()),
+                ("client_secret", self.config.client_secret.as_deref().unwrap_or_default()),
+                ("scope", EVENTHUBS_SCOPE),
+            ])
+            .send()
+            .await?
+            .error_for_status()?;
+
+        let token: AadTokenResponse = response.json().await?;
+        let access_token = token.access_token.clone();
+        *cached = Some((token.access_token, Instant::now() + Duration::from_secs(token.expires_in)));
+
+        Ok(access_token)
+    }
+}
+
+/// Generate a Service Bus SAS token for a resource URI
+pub fn sas_token(resource_uri: &str, key_name: &str, key: &str, expiry: i64) -> Result<String> {
+    let encoded_uri = urlencoding::encode(resource_uri);
+    let string_to_sign = format!("{}\n{}", encoded_uri, expiry);
+
+    let mut mac = Hmac::<Sha256>::new_from_slice(key.as_bytes())
+        .map_err(|e| ForwarderError::Config(format!("Invalid Event Hubs SAS key: {}", e)))?;
+    mac.update(string_to_sign.as_bytes());
+    let signature = BASE64.encode(mac.finalize().into_bytes());
+
+    Ok(format!(
+        "SharedAccessSignature sr={}&sig={}&se={}&skn={}",
+        encoded_uri,
+        urlencoding::encode(&signature),
+        expiry,
+        key_name
+    ))
+}
+
+/// Build the Event Hubs batch body: one event per problem with routing properties
+pub fn build_event_batch(problems: &[Problem], connector: &ConnectorConfig, problem_url_base: &str) -> Result<Value> {
+    let mut events = Vec::with_capacity(problems.len());
+
+    for problem in problems {
+        let body = serde_json::to_string(&payload::problem_payload(problem, connector, problem_url_base)?)?;
+        let management_zones = problem
+            .management_zones
+            .iter()
+            .map(|zone| zone.name.as_str())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        events.push(json!({
+            "Body": body,
+            "BrokerProperties": { "PartitionKey": problem.problem_id },
+            "UserProperties": {
+                "problemId": problem.problem_id,
+                "severity": problem.severity_level,
+                "status": problem.status.to_string(),
+                "managementZone": management_zones,
+            }
+        }));
+    }
+
+    Ok(Value::Array(events))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_sas_token_format() {
+        let token = sas_token(
+            "https://ns.servicebus.windows.net/hub",
+            "send",
+            "c2VjcmV0",
+            1770000000,
+        )
+        .unwrap();
+
+        assert!(token.starts_with(
+            "SharedAccessSignature sr=https%3A%2F%2Fns.servicebus.windows.net%2Fhub&sig="
+        ));
+        assert!(token.ends_with("&se=1770000000&skn=send"));
+    }
+}
@@ -0,0 +1,44 @@
+use serde::Serialize;
+
+/// Capacity of the event channel; slow subscribers skip the oldest events
+pub const EVENT_CHANNEL_CAPACITY: usize = 1024;
+
+/// Events emitted by the forwarding engine, for embedding applications
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum EngineEvent {
+    /// A poll cycle started
+    PollStarted,
+    /// A poll cycle finished
+    PollCompleted {
+        new_problems: usize,
+        status_changes: usize,
+        skipped: usize,
+    },
+    /// A poll cycle failed before problems could be processed
+    PollFailed { error: String },
+    /// A problem not seen before was detected
+    NewProblem { problem_id: String, title: String },
+    /// A known problem changed status
+    StatusChanged {
+        problem_id: String,
+        from: String,
+        to: String,
+    },
+    /// Problems were delivered to a connector
+    ForwardSucceeded {
+        connector: String,
+        problem_ids: Vec<String>,
+        status_code: u16,
+    },
+    /// Delivery to a connector failed after all retries
+    ForwardFailed {
+        connector: String,
+        problem_ids: Vec<String>,
+        error: String,
+    },
+    /// A digest was sent to a connector
+    DigestSent { connector: String, problem_count: usize },
+    /// The cycle's problem volume exceeded the anomaly threshold
+    VolumeAnomaly { volume: usize, baseline: f64 },
+}
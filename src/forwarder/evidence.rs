@@ -0,0 +1,102 @@
+use crate::dynatrace::models::{Evidence, EvidenceDetails};
+
+/// Default number of evidence lines shown in chat messages
+pub const DEFAULT_EVIDENCE_LINES: usize = 3;
+
+/// Summarize a problem's evidence as short human-readable lines: root-cause
+/// relevant evidence first, then the earliest, with metric changes spelled out
+/// ("Response time degradation on checkout: 120 ms → 950 ms")
+pub fn summarize(details: &EvidenceDetails, max_lines: usize) -> Vec<String> {
+    let mut ranked: Vec<&Evidence> = details.details.iter().collect();
+    ranked.sort_by_key(|evidence| (!evidence.root_cause_relevant, evidence.start_time));
+
+    let mut lines: Vec<String> = ranked.iter().take(max_lines).map(|e| describe(e)).collect();
+
+    let remaining = details.details.len().saturating_sub(max_lines);
+    if remaining > 0 {
+        lines.push(format!("(+{} more)", remaining));
+    }
+
+    lines
+}
+
+fn describe(evidence: &Evidence) -> String {
+    let mut line = evidence.display_name.clone();
+
+    if let Some(entity) = &evidence.entity {
+        line.push_str(&format!(" on {}", entity.name));
+    }
+
+    if let (Some(before), Some(after)) = (
+        evidence.value_before_change_point,
+        evidence.value_after_change_point,
+    ) {
+        let unit = evidence.unit.as_deref().map(short_unit).unwrap_or_default();
+        line.push_str(&format!(
+            ": {}{} → {}{}",
+            format_value(before),
+            unit,
+            format_value(after),
+            unit
+        ));
+    }
+
+    line
+}
+
+fn format_value(value: f64) -> String {
+    if value.fract() == 0.0 || value.abs() >= 100.0 {
+        format!("{:.0}", value)
+    } else {
+        format!("{:.2}", value)
+    }
+}
+
+/// Shorten common Dynatrace unit names
+fn short_unit(unit: &str) -> String {
+    match unit {
+        "Percent" => "%".to_string(),
+        "MilliSecond" => " ms".to_string(),
+        "MicroSecond" => " µs".to_string(),
+        "Second" => " s".to_string(),
+        "Byte" => " B".to_string(),
+        "Count" | "Unspecified" => String::new(),
+        other => format!(" {}", other),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn evidence(name: &str, root_cause: bool, start: i64, values: Option<(f64, f64)>) -> Evidence {
+        Evidence {
+            evidence_type: "METRIC".to_string(),
+            display_name: name.to_string(),
+            entity: None,
+            root_cause_relevant: root_cause,
+            start_time: start,
+            unit: Some("MilliSecond".to_string()),
+            value_before_change_point: values.map(|v| v.0),
+            value_after_change_point: values.map(|v| v.1),
+        }
+    }
+
+    #[test]
+    fn test_summary_ranks_root_cause_first() {
+        let details = EvidenceDetails {
+            total_count: 3,
+            details: vec![
+                evidence("CPU saturation", false, 1, None),
+                evidence("Response time degradation", true, 2, Some((120.0, 950.0))),
+                evidence("GC pauses", false, 3, None),
+            ],
+        };
+
+        let lines = summarize(&details, 2);
+
+        assert_eq!(lines[0], "Response time degradation: 120 ms → 950 ms");
+        assert_eq!(lines[1], "CPU saturation");
+        assert_eq!(lines[2], "(+1 more)");
+    }
+}
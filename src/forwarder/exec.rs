@@ -0,0 +1,106 @@
+use reqwest::StatusCode;
+use std::io::ErrorKind;
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+use crate::config::{ConnectorConfig, ExecConfig};
+use crate::dynatrace::Problem;
+use crate::error::{ForwarderError, Result};
+use crate::forwarder::{payload, siem};
+use tracing::debug;
+
+/// Exit code (sysexits EX_TEMPFAIL) a command uses to ask for a retry
+pub const EXIT_TEMPFAIL: i32 = 75;
+
+/// Longest stderr excerpt included in error messages
+const MAX_STDERR_CHARS: usize = 500;
+
+/// Runs a local command per delivery, writing the problem JSON (or CEF/LEEF
+/// lines) to its stdin
+pub struct ExecRunner {
+    config: ExecConfig,
+    connector: ConnectorConfig,
+    problem_url_base: String,
+}
+
+impl ExecRunner {
+    pub fn new(config: ExecConfig, connector: ConnectorConfig, problem_url_base: String) -> Self {
+        Self {
+            config,
+            connector,
+            problem_url_base,
+        }
+    }
+
+    /// Run the command once for these problems. Exit code 0 counts as 200 OK;
+    /// EX_TEMPFAIL and timeouts are retried, any other exit code is not.
+    pub async fn run(&self, problems: &[Problem], timeout: Duration) -> Result<StatusCode> {
+        let stdin_bytes = if let Some(lines) = siem::lines(problems, &self.connector, &self.problem_url_base) {
+            lines.into_bytes()
+        } else if self.connector.batch_mode {
+            serde_json::to_vec(&payload::batch_payload(problems, &self.connector, &self.problem_url_base)?)?
+        } else {
+            serde_json::to_vec(&payload::problem_payload(&problems[0], &self.connector, &self.problem_url_base)?)?
+        };
+
+        let mut command = Command::new(&self.config.command);
+        command
+            .args(&self.config.args)
+            .envs(&self.config.env)
+            .env("DTPF_CONNECTOR", &self.connector.name)
+            .env("DTPF_PROBLEM_COUNT", problems.len().to_string())
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .kill_on_drop(true);
+
+        if let Some(dir) = &self.config.working_dir {
+            command.current_dir(dir);
+        }
+
+        debug!("Running '{}' for {} problem(s)", self.config.command, problems.len());
+
+        let mut child = command.spawn().map_err(|e| {
+            ForwarderError::Config(format!("Failed to start '{}': {}", self.config.command, e))
+        })?;
+
+        if let Some(mut stdin) = child.stdin.take() {
+            // A command that exits without reading its input is judged by its exit code
+            if let Err(e) = stdin.write_all(&stdin_bytes).await {
+                if e.kind() != ErrorKind::BrokenPipe {
+                    return Err(e.into());
+                }
+            }
+        }
+
+        let output = tokio::time::timeout(timeout, child.wait_with_output())
+            .await
+            .map_err(|_| ForwarderError::TransientNetwork {
+                target: self.config.command.clone(),
+                message: format!("Command did not finish within {}s", timeout.as_secs()),
+            })??;
+
+        let stdout = String::from_utf8_lossy(&output.stdout);
+        if !stdout.trim().is_empty() {
+            debug!("'{}' output: {}", self.config.command, stdout.trim());
+        }
+
+        if output.status.success() {
+            return Ok(StatusCode::OK);
+        }
+
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        let mut message = format!("Command failed ({})", output.status);
+        if !stderr.trim().is_empty() {
+            message.push_str(": ");
+            message.extend(stderr.trim().chars().take(MAX_STDERR_CHARS));
+        }
+        let target = self.config.command.clone();
+
+        Err(match output.status.code() {
+            Some(EXIT_TEMPFAIL) | None => ForwarderError::TransientNetwork { target, message },
+            Some(_) => ForwarderError::PermanentDownstream { target, message },
+        })
+    }
+}
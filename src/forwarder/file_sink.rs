@@ -0,0 +1,210 @@
+use reqwest::StatusCode;
+use std::fs::{self, OpenOptions};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use crate::config::{ConnectorConfig, FileCompression, FileSinkConfig};
+use crate::dynatrace::Problem;
+use crate::error::{ForwarderError, Result};
+use crate::forwarder::{payload, siem};
+use tracing::{debug, info};
+
+/// Appends problems as NDJSON (or CEF/LEEF lines) to a local file with size-based rotation
+pub struct FileSink {
+    config: FileSinkConfig,
+    connector: ConnectorConfig,
+    problem_url_base: String,
+    archive: Archive,
+    /// Serializes writes and rotation between concurrent forwards
+    lock: Mutex<()>,
+}
+
+impl FileSink {
+    pub fn new(config: FileSinkConfig, connector: ConnectorConfig, problem_url_base: String) -> Result<Self> {
+        let recipients = config
+            .age_recipients
+            .iter()
+            .map(|recipient| {
+                recipient.parse().map_err(|e| {
+                    ForwarderError::Config(format!("Connector '{}' age recipient '{}': {}", connector.name, recipient, e))
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            archive: Archive {
+                compression: config.compression,
+                recipients,
+            },
+            config,
+            connector,
+            problem_url_base,
+            lock: Mutex::new(()),
+        })
+    }
+
+    /// Append one line per problem (JSON, or CEF/LEEF per the connector's format).
+    /// Returns 200 OK so file writes are recorded like successful HTTP deliveries.
+    pub fn append(&self, problems: &[Problem]) -> Result<StatusCode> {
+        let mut lines = String::new();
+        if let Some(siem_lines) = siem::lines(problems, &self.connector, &self.problem_url_base) {
+            lines.push_str(&siem_lines);
+            lines.push('\n');
+        } else {
+            for problem in problems {
+                lines.push_str(&serde_json::to_string(&payload::problem_payload(problem, &self.connector, &self.problem_url_base)?)?);
+                lines.push('\n');
+            }
+        }
+
+        let _guard = self.lock.lock().unwrap_or_else(|e| e.into_inner());
+        let path = &self.config.path;
+
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            fs::create_dir_all(parent)?;
+        }
+
+        let current_size = fs::metadata(path).map(|m| m.len()).unwrap_or(0);
+        if current_size > 0 && current_size + lines.len() as u64 > self.config.max_size_bytes() {
+            rotate(path, self.config.max_files, &self.archive)?;
+        }
+
+        let mut file = OpenOptions::new().create(true).append(true).open(path).map_err(|e| {
+            ForwarderError::Storage {
+                target: path.display().to_string(),
+                message: format!("Failed to open file: {}", e),
+            }
+        })?;
+        file.write_all(lines.as_bytes())?;
+
+        debug!("Appended {} problem(s) to {}", problems.len(), path.display());
+
+        Ok(StatusCode::OK)
+    }
+}
+
+/// How rotated files are stored: as written, or compressed and/or encrypted
+#[derive(Default)]
+struct Archive {
+    compression: Option<FileCompression>,
+    /// age public keys the rotated files are encrypted to
+    recipients: Vec<age::x25519::Recipient>,
+}
+
+impl Archive {
+    /// Extension of the rotated files, e.g. `.zst.age`
+    fn suffix(&self) -> &'static str {
+        match (self.compression, self.recipients.is_empty()) {
+            (None, true) => "",
+            (None, false) => ".age",
+            (Some(FileCompression::Zstd), true) => ".zst",
+            (Some(FileCompression::Zstd), false) => ".zst.age",
+        }
+    }
+
+    /// Compress, then encrypt the contents of a rotated file
+    fn seal(&self, mut data: Vec<u8>) -> Result<Vec<u8>> {
+        if self.compression == Some(FileCompression::Zstd) {
+            data = zstd::encode_all(data.as_slice(), 0)?;
+        }
+
+        if !self.recipients.is_empty() {
+            let recipients = self.recipients.iter().map(|r| r as &dyn age::Recipient);
+            let encryptor = age::Encryptor::with_recipients(recipients).map_err(|e| ForwarderError::Storage {
+                target: "file".to_string(),
+                message: format!("Failed to encrypt rotated file: {}", e),
+            })?;
+            let mut encrypted = Vec::with_capacity(data.len() + 256);
+            let mut writer = encryptor.wrap_output(&mut encrypted)?;
+            writer.write_all(&data)?;
+            writer.finish()?;
+            data = encrypted;
+        }
+
+        Ok(data)
+    }
+}
+
+/// Shift `path` to `path.1`, `path.1` to `path.2`, ... keeping at most
+/// `max_files` rotated files, which `archive` compresses and encrypts
+fn rotate(path: &Path, max_files: usize, archive: &Archive) -> Result<()> {
+    if max_files == 0 {
+        fs::remove_file(path)?;
+        return Ok(());
+    }
+
+    let suffix = archive.suffix();
+    let _ = fs::remove_file(rotated_path(path, max_files, suffix));
+
+    for index in (1..max_files).rev() {
+        let from = rotated_path(path, index, suffix);
+        if from.exists() {
+            fs::rename(&from, rotated_path(path, index + 1, suffix))?;
+        }
+    }
+
+    let rotated = rotated_path(path, 1, suffix);
+    if suffix.is_empty() {
+        fs::rename(path, &rotated)?;
+    } else {
+        fs::write(&rotated, archive.seal(fs::read(path)?)?)?;
+        fs::remove_file(path)?;
+    }
+    info!("Rotated {} to {}", path.display(), rotated.display());
+
+    Ok(())
+}
+
+fn rotated_path(path: &Path, index: usize, suffix: &str) -> PathBuf {
+    let mut rotated = path.as_os_str().to_owned();
+    rotated.push(format!(".{}{}", index, suffix));
+    PathBuf::from(rotated)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotate_keeps_max_files() {
+        let dir = std::env::temp_dir().join(format!("dtpf-rotate-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("problems.ndjson");
+
+        for generation in 0..4 {
+            fs::write(&path, format!("{}\n", generation)).unwrap();
+            rotate(&path, 2, &Archive::default()).unwrap();
+        }
+
+        assert!(!path.exists());
+        assert_eq!(fs::read_to_string(rotated_path(&path, 1, "")).unwrap(), "3\n");
+        assert_eq!(fs::read_to_string(rotated_path(&path, 2, "")).unwrap(), "2\n");
+        assert!(!rotated_path(&path, 3, "").exists());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_rotate_compresses_and_encrypts() {
+        let dir = std::env::temp_dir().join(format!("dtpf-archive-{}", std::process::id()));
+        fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("problems.ndjson");
+        let identity = age::x25519::Identity::generate();
+        let archive = Archive {
+            compression: Some(FileCompression::Zstd),
+            recipients: vec![identity.to_public()],
+        };
+
+        let lines = "{\"problemId\":\"P-1\"}\n".repeat(50);
+        fs::write(&path, &lines).unwrap();
+        rotate(&path, 2, &archive).unwrap();
+
+        assert!(!path.exists());
+        let sealed = fs::read(dir.join("problems.ndjson.1.zst.age")).unwrap();
+        let compressed = age::decrypt(&identity, &sealed).unwrap();
+        assert!(compressed.len() < lines.len());
+        assert_eq!(zstd::decode_all(compressed.as_slice()).unwrap(), lines.as_bytes());
+
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
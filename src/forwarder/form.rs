@@ -0,0 +1,44 @@
+use crate::config::ConnectorConfig;
+use crate::dynatrace::Problem;
+use crate::error::Result;
+use crate::forwarder::template;
+
+/// Render the connector's `form.fields` for one problem as an
+/// `application/x-www-form-urlencoded` string (also used as the query string)
+pub fn encode(problem: &Problem, connector: &ConnectorConfig, problem_url_base: &str) -> Result<String> {
+    let mut encoded = form_urlencoded::Serializer::new(String::new());
+
+    for field in connector.form.iter().flat_map(|form| &form.fields) {
+        let value = template::render_text(&field.value, problem, connector, problem_url_base)?;
+        encoded.append_pair(&field.name, &value);
+    }
+
+    Ok(encoded.finish())
+}
+
+/// Append an encoded query string to a URL that may already have one
+pub fn with_query(url: &str, query: &str) -> String {
+    if query.is_empty() {
+        return url.to_string();
+    }
+
+    let separator = match url.contains('?') {
+        true if url.ends_with('?') || url.ends_with('&') => "",
+        true => "&",
+        false => "?",
+    };
+    format!("{}{}{}", url, separator, query)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_with_query() {
+        assert_eq!(with_query("https://sms.example.com/send", "to=1&msg=a+b"), "https://sms.example.com/send?to=1&msg=a+b");
+        assert_eq!(with_query("https://sms.example.com/send?key=x", "to=1"), "https://sms.example.com/send?key=x&to=1");
+        assert_eq!(with_query("https://sms.example.com/send?", "to=1"), "https://sms.example.com/send?to=1");
+        assert_eq!(with_query("https://sms.example.com/send", ""), "https://sms.example.com/send");
+    }
+}
@@ -0,0 +1,333 @@
+use reqwest::StatusCode;
+use std::str::FromStr;
+use std::time::Duration;
+use tonic::metadata::{MetadataKey, MetadataValue};
+use tonic::transport::{Certificate, ClientTlsConfig, Endpoint, Identity};
+use tonic::Code;
+use crate::config::ConnectorConfig;
+use crate::dynatrace::{Problem, ProblemStatus};
+use crate::error::{ForwarderError, Result};
+use crate::forwarder::payload;
+use tracing::debug;
+
+use proto::problem_forward_client::ProblemForwardClient;
+
+/// Sends problems to a `dtpf.v1.ProblemForward` gRPC service
+pub struct GrpcForwarder {
+    client: ProblemForwardClient,
+    connector: ConnectorConfig,
+    problem_url_base: String,
+}
+
+impl GrpcForwarder {
+    /// Create the forwarder. The channel connects lazily on the first call, so
+    /// this must run inside a Tokio runtime but does not contact the receiver.
+    pub fn new(connector: ConnectorConfig, problem_url_base: String) -> Result<Self> {
+        let mut endpoint = Endpoint::from_shared(connector.url.clone())
+            .map_err(|e| ForwarderError::Config(format!("Invalid gRPC endpoint '{}': {}", connector.url, e)))?
+            .connect_timeout(Duration::from_secs(connector.timeout_seconds.unwrap_or(30)));
+
+        if connector.url.starts_with("https://") {
+            let mut tls_config = ClientTlsConfig::new();
+            if let Some(tls) = &connector.tls {
+                if let Some(ca) = tls.ca_pem()? {
+                    tls_config = tls_config.ca_certificate(Certificate::from_pem(ca));
+                }
+                if let Some((cert, key)) = tls.identity_pem()? {
+                    let key = if key.is_empty() { cert.clone() } else { key };
+                    tls_config = tls_config.identity(Identity::from_pem(cert, key));
+                }
+            }
+
+            endpoint = endpoint
+                .tls_config(tls_config)
+                .map_err(|e| ForwarderError::Config(format!("Invalid gRPC TLS configuration: {}", e)))?;
+        }
+
+        Ok(Self {
+            client: ProblemForwardClient::new(endpoint.connect_lazy()),
+            connector,
+            problem_url_base,
+        })
+    }
+
+    /// Call `Forward` once for these problems. Returns 200 OK on an OK status
+    /// so gRPC calls are recorded like successful HTTP deliveries.
+    pub async fn forward(&self, problems: &[Problem], timeout: Duration) -> Result<StatusCode> {
+        let mut request = tonic::Request::new(build_forward_request(
+            problems,
+            &self.connector,
+            &self.problem_url_base,
+        ));
+        request.set_timeout(timeout);
+
+        // Connector headers are sent as gRPC metadata
+        if let Some(headers) = &self.connector.headers {
+            for (key, value) in headers {
+                let key = MetadataKey::from_str(&key.to_ascii_lowercase()).map_err(|_| {
+                    ForwarderError::Config(format!("Invalid gRPC metadata key '{}'", key))
+                })?;
+                let value = MetadataValue::try_from(value.as_str()).map_err(|_| {
+                    ForwarderError::Config(format!("Invalid gRPC metadata value for '{}'", key))
+                })?;
+                request.metadata_mut().insert(key, value);
+            }
+        }
+
+        let response = self
+            .client
+            .clone()
+            .forward(request)
+            .await
+            .map_err(|status| status_error(&self.connector.url, status))?;
+
+        debug!(
+            "gRPC receiver {} accepted {} problem(s)",
+            self.connector.url,
+            response.get_ref().accepted
+        );
+
+        Ok(StatusCode::OK)
+    }
+}
+
+/// Build the `Forward` request message for a batch of problems
+pub fn build_forward_request(
+    problems: &[Problem],
+    connector: &ConnectorConfig,
+    problem_url_base: &str,
+) -> proto::ForwardRequest {
+    proto::ForwardRequest {
+        connector: connector.name.clone(),
+        problems: problems
+            .iter()
+            .map(|problem| problem_event(problem, connector, problem_url_base))
+            .collect(),
+    }
+}
+
+fn problem_event(problem: &Problem, connector: &ConnectorConfig, problem_url_base: &str) -> proto::ProblemEvent {
+    proto::ProblemEvent {
+        problem_id: problem.problem_id.clone(),
+        display_id: problem.display_id.clone(),
+        title: problem.title.clone(),
+        status: proto_status(&problem.status) as i32,
+        severity_level: problem.severity_level.clone(),
+        impact_level: problem.impact_level.clone(),
+        start_time: problem.start_time,
+        end_time: problem.end_time,
+        management_zones: problem.management_zone_names(),
+        affected_entities: problem
+            .affected_entities
+            .iter()
+            .map(|entity| proto::EntityRef {
+                id: entity.entity_id.id.clone(),
+                r#type: entity.entity_id.entity_type.clone(),
+                name: entity.name.clone(),
+            })
+            .collect(),
+        root_cause_entity: problem.root_cause_entity.as_ref().map(|entity| proto::EntityRef {
+            id: entity.entity_id.id.clone(),
+            r#type: entity.entity_id.entity_type.clone(),
+            name: entity.name.clone(),
+        }),
+        labels: connector
+            .labels
+            .as_ref()
+            .map(|labels| payload::build_labels(problem, labels))
+            .unwrap_or_default(),
+        runbooks: problem
+            .runbooks
+            .iter()
+            .map(|runbook| proto::Runbook {
+                name: runbook.name.clone().unwrap_or_default(),
+                url: runbook.url.clone(),
+            })
+            .collect(),
+        problem_url: problem.url(problem_url_base),
+        correlated_problems: problem
+            .correlated_problems
+            .iter()
+            .map(|child| proto::CorrelatedProblem {
+                problem_id: child.problem_id.clone(),
+                display_id: child.display_id.clone(),
+                title: child.title.clone(),
+                status: proto_status(&child.status) as i32,
+            })
+            .collect(),
+    }
+}
+
+fn proto_status(status: &ProblemStatus) -> proto::ProblemStatus {
+    match status {
+        ProblemStatus::Open => proto::ProblemStatus::Open,
+        ProblemStatus::Closed => proto::ProblemStatus::Closed,
+        ProblemStatus::Resolved => proto::ProblemStatus::Resolved,
+    }
+}
+
+/// Map a gRPC status to the error taxonomy used for retries and exit codes
+fn status_error(target: &str, status: tonic::Status) -> ForwarderError {
+    let target = target.to_string();
+    let message = format!("gRPC {:?}: {}", status.code(), status.message());
+
+    match status.code() {
+        Code::Unavailable | Code::DeadlineExceeded | Code::ResourceExhausted | Code::Aborted => {
+            ForwarderError::TransientNetwork { target, message }
+        }
+        Code::Unauthenticated | Code::PermissionDenied => ForwarderError::Auth { target, message },
+        _ => ForwarderError::PermanentDownstream { target, message },
+    }
+}
+
+/// Message and client types for `proto/problem_forward.proto`, written in the
+/// shape prost/tonic code generation produces so no protoc is needed to build.
+/// Keep field tags in sync with the proto file.
+pub mod proto {
+    use serde::{Serialize, Serializer};
+    use std::collections::BTreeMap;
+
+    #[derive(Clone, PartialEq, prost::Message, Serialize)]
+    pub struct ForwardRequest {
+        #[prost(string, tag = "1")]
+        pub connector: String,
+        #[prost(message, repeated, tag = "2")]
+        pub problems: Vec<ProblemEvent>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message)]
+    pub struct ForwardResponse {
+        #[prost(uint32, tag = "1")]
+        pub accepted: u32,
+    }
+
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord, prost::Enumeration)]
+    #[repr(i32)]
+    pub enum ProblemStatus {
+        Unspecified = 0,
+        Open = 1,
+        Closed = 2,
+        Resolved = 3,
+    }
+
+    impl ProblemStatus {
+        /// Enum value name as written in the proto file
+        pub fn as_str_name(&self) -> &'static str {
+            match self {
+                ProblemStatus::Unspecified => "PROBLEM_STATUS_UNSPECIFIED",
+                ProblemStatus::Open => "PROBLEM_STATUS_OPEN",
+                ProblemStatus::Closed => "PROBLEM_STATUS_CLOSED",
+                ProblemStatus::Resolved => "PROBLEM_STATUS_RESOLVED",
+            }
+        }
+    }
+
+    #[derive(Clone, PartialEq, prost::Message, Serialize)]
+    #[serde(rename_all = "camelCase")]
+    pub struct ProblemEvent {
+        #[prost(string, tag = "1")]
+        pub problem_id: String,
+        #[prost(string, tag = "2")]
+        pub display_id: String,
+        #[prost(string, tag = "3")]
+        pub title: String,
+        #[prost(enumeration = "ProblemStatus", tag = "4")]
+        #[serde(serialize_with = "serialize_status")]
+        pub status: i32,
+        #[prost(string, tag = "5")]
+        pub severity_level: String,
+        #[prost(string, tag = "6")]
+        pub impact_level: String,
+        #[prost(int64, tag = "7")]
+        pub start_time: i64,
+        #[prost(int64, tag = "8")]
+        pub end_time: i64,
+        #[prost(string, repeated, tag = "9")]
+        pub management_zones: Vec<String>,
+        #[prost(message, repeated, tag = "10")]
+        pub affected_entities: Vec<EntityRef>,
+        #[prost(message, optional, tag = "11")]
+        pub root_cause_entity: Option<EntityRef>,
+        #[prost(btree_map = "string, string", tag = "12")]
+        pub labels: BTreeMap<String, String>,
+        #[prost(message, repeated, tag = "13")]
+        pub runbooks: Vec<Runbook>,
+        #[prost(string, tag = "14")]
+        pub problem_url: String,
+        #[prost(message, repeated, tag = "15")]
+        pub correlated_problems: Vec<CorrelatedProblem>,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message, Serialize)]
+    pub struct EntityRef {
+        #[prost(string, tag = "1")]
+        pub id: String,
+        #[prost(string, tag = "2")]
+        pub r#type: String,
+        #[prost(string, tag = "3")]
+        pub name: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message, Serialize)]
+    pub struct Runbook {
+        #[prost(string, tag = "1")]
+        pub name: String,
+        #[prost(string, tag = "2")]
+        pub url: String,
+    }
+
+    #[derive(Clone, PartialEq, prost::Message, Serialize)]
+    pub struct CorrelatedProblem {
+        #[prost(string, tag = "1")]
+        pub problem_id: String,
+        #[prost(string, tag = "2")]
+        pub display_id: String,
+        #[prost(string, tag = "3")]
+        pub title: String,
+        #[prost(enumeration = "ProblemStatus", tag = "4")]
+        #[serde(serialize_with = "serialize_status")]
+        pub status: i32,
+    }
+
+    /// Render the enum field by name when showing requests as JSON
+    fn serialize_status<S: Serializer>(status: &i32, serializer: S) -> Result<S::Ok, S::Error> {
+        let name = ProblemStatus::try_from(*status)
+            .unwrap_or(ProblemStatus::Unspecified)
+            .as_str_name();
+        serializer.serialize_str(name)
+    }
+
+    pub mod problem_forward_client {
+        use tonic::codegen::http::uri::PathAndQuery;
+        use tonic::transport::Channel;
+
+        /// Client for the `dtpf.v1.ProblemForward` service
+        #[derive(Debug, Clone)]
+        pub struct ProblemForwardClient {
+            inner: tonic::client::Grpc<Channel>,
+        }
+
+        impl ProblemForwardClient {
+            pub fn new(channel: Channel) -> Self {
+                Self {
+                    inner: tonic::client::Grpc::new(channel),
+                }
+            }
+
+            pub async fn forward(
+                &mut self,
+                request: tonic::Request<super::ForwardRequest>,
+            ) -> Result<tonic::Response<super::ForwardResponse>, tonic::Status> {
+                self.inner
+                    .ready()
+                    .await
+                    .map_err(|e| tonic::Status::unavailable(format!("Service was not ready: {}", e)))?;
+
+                let path = PathAndQuery::from_static("/dtpf.v1.ProblemForward/Forward");
+                self.inner
+                    .unary(request, path, tonic::codec::ProstCodec::default())
+                    .await
+            }
+        }
+    }
+}
@@ -0,0 +1,156 @@
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::time::Duration;
+use crate::forwarder::retry::RetryMetrics;
+
+/// Default number of recent requests kept per connector
+pub const DEFAULT_HEALTH_WINDOW: usize = 50;
+
+/// Rolling latency and error-rate tracker for one connector
+#[derive(Debug)]
+pub struct ConnectorHealth {
+    window: usize,
+    samples: VecDeque<Sample>,
+    retries: u64,
+    retry_delay: Duration,
+    budget_exhausted: u64,
+}
+
+#[derive(Debug, Clone, Copy)]
+struct Sample {
+    latency: Duration,
+    success: bool,
+}
+
+/// Point-in-time view of a connector's health
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct HealthSnapshot {
+    /// 0-100, higher is healthier
+    pub score: f64,
+    pub samples: usize,
+    pub success_rate: f64,
+    pub avg_latency_ms: f64,
+    pub p95_latency_ms: f64,
+    /// Retries since startup (attempts beyond the first)
+    #[serde(default)]
+    pub retries: u64,
+    /// Time spent waiting between retries since startup
+    #[serde(default)]
+    pub retry_delay_ms: u64,
+    /// Deliveries abandoned because the retry time budget ran out
+    #[serde(default)]
+    pub retry_budget_exhausted: u64,
+}
+
+impl ConnectorHealth {
+    pub fn new(window: usize) -> Self {
+        Self {
+            window: window.max(1),
+            samples: VecDeque::new(),
+            retries: 0,
+            retry_delay: Duration::ZERO,
+            budget_exhausted: 0,
+        }
+    }
+
+    /// Add the retry cost of one delivery to the running totals
+    pub fn record_retries(&mut self, metrics: &RetryMetrics) {
+        self.retries += u64::from(metrics.attempts.saturating_sub(1));
+        self.retry_delay += metrics.total_delay;
+        if metrics.budget_exhausted {
+            self.budget_exhausted += 1;
+        }
+    }
+
+    /// Record the outcome of one request attempt
+    pub fn record(&mut self, latency: Duration, success: bool) {
+        if self.samples.len() == self.window {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(Sample { latency, success });
+    }
+
+    /// Health score: success rate scaled down by average latency (1s halves the score).
+    /// A connector without samples is assumed healthy.
+    pub fn score(&self) -> f64 {
+        self.snapshot().score
+    }
+
+    pub fn snapshot(&self) -> HealthSnapshot {
+        if self.samples.is_empty() {
+            return HealthSnapshot {
+                score: 100.0,
+                samples: 0,
+                success_rate: 1.0,
+                avg_latency_ms: 0.0,
+                p95_latency_ms: 0.0,
+                retries: self.retries,
+                retry_delay_ms: self.retry_delay.as_millis() as u64,
+                retry_budget_exhausted: self.budget_exhausted,
+            };
+        }
+
+        let count = self.samples.len();
+        let successes = self.samples.iter().filter(|s| s.success).count();
+        let success_rate = successes as f64 / count as f64;
+
+        let mut latencies: Vec<f64> = self
+            .samples
+            .iter()
+            .map(|s| s.latency.as_secs_f64() * 1000.0)
+            .collect();
+        latencies.sort_by(|a, b| a.total_cmp(b));
+
+        let avg_latency_ms = latencies.iter().sum::<f64>() / count as f64;
+        let p95_index = ((count as f64 * 0.95).ceil() as usize).clamp(1, count) - 1;
+        let p95_latency_ms = latencies[p95_index];
+
+        let score = 100.0 * success_rate / (1.0 + avg_latency_ms / 1000.0);
+
+        HealthSnapshot {
+            score,
+            samples: count,
+            success_rate,
+            avg_latency_ms,
+            p95_latency_ms,
+            retries: self.retries,
+            retry_delay_ms: self.retry_delay.as_millis() as u64,
+            retry_budget_exhausted: self.budget_exhausted,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_score_penalizes_errors_and_latency() {
+        let mut fast = ConnectorHealth::new(10);
+        let mut slow = ConnectorHealth::new(10);
+        let mut failing = ConnectorHealth::new(10);
+
+        for _ in 0..10 {
+            fast.record(Duration::from_millis(50), true);
+            slow.record(Duration::from_millis(2000), true);
+            failing.record(Duration::from_millis(50), false);
+        }
+
+        assert!(fast.score() > slow.score());
+        assert!(slow.score() > failing.score());
+        assert_eq!(failing.score(), 0.0);
+        assert_eq!(ConnectorHealth::new(10).score(), 100.0);
+    }
+
+    #[test]
+    fn test_window_drops_old_samples() {
+        let mut health = ConnectorHealth::new(2);
+        health.record(Duration::from_millis(10), false);
+        health.record(Duration::from_millis(10), true);
+        health.record(Duration::from_millis(10), true);
+
+        let snapshot = health.snapshot();
+        assert_eq!(snapshot.samples, 2);
+        assert_eq!(snapshot.success_rate, 1.0);
+    }
+}
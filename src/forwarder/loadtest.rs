@@ -0,0 +1,242 @@
+use std::collections::BTreeMap;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use tokio::sync::{Mutex, Semaphore};
+use tokio::time::MissedTickBehavior;
+use tracing::{debug, info};
+use crate::dynatrace::models::{
+    AffectedEntity, Entity, EntityId, EntityTag, ManagementZone, Problem, ProblemStatus,
+};
+use crate::forwarder::connector::ChunkResult;
+use crate::forwarder::Connector;
+
+const SEVERITIES: [&str; 5] = ["AVAILABILITY", "ERROR", "PERFORMANCE", "RESOURCE_CONTENTION", "CUSTOM_ALERT"];
+
+/// How hard to drive a connector during `dtpf loadtest`
+#[derive(Debug, Clone)]
+pub struct LoadTestPlan {
+    /// Deliveries started per second
+    pub rate: f64,
+    pub duration: Duration,
+    /// Deliveries allowed in flight at once; further starts wait for a free slot
+    pub concurrency: usize,
+}
+
+/// Throughput, latency and error figures of a finished load test
+#[derive(Debug, Clone)]
+pub struct LoadTestReport {
+    pub elapsed: Duration,
+    pub succeeded: usize,
+    pub failed: usize,
+    /// Latency of every delivery, including the connector's retries, sorted ascending
+    pub latencies: Vec<Duration>,
+    /// Failure count per error message
+    pub errors: BTreeMap<String, usize>,
+}
+
+impl LoadTestReport {
+    pub fn sent(&self) -> usize {
+        self.succeeded + self.failed
+    }
+
+    /// Completed deliveries per second
+    pub fn throughput(&self) -> f64 {
+        self.sent() as f64 / self.elapsed.as_secs_f64().max(f64::EPSILON)
+    }
+
+    pub fn error_rate(&self) -> f64 {
+        if self.sent() == 0 {
+            return 0.0;
+        }
+        self.failed as f64 / self.sent() as f64
+    }
+
+    /// Nearest-rank percentile (`0.0..=100.0`) of the delivery latencies
+    pub fn percentile(&self, percentile: f64) -> Option<Duration> {
+        if self.latencies.is_empty() {
+            return None;
+        }
+        let rank = (percentile / 100.0 * self.latencies.len() as f64).ceil() as usize;
+        Some(self.latencies[rank.clamp(1, self.latencies.len()) - 1])
+    }
+}
+
+/// Deliver synthetic problems through the connector at the planned rate and
+/// measure the results. Each delivery goes through the connector's normal
+/// payload, signing and retry handling, in its configured batch mode.
+pub async fn run(connector: Arc<Connector>, plan: &LoadTestPlan) -> LoadTestReport {
+    let total = (plan.rate * plan.duration.as_secs_f64()).round() as usize;
+    let slots = Arc::new(Semaphore::new(plan.concurrency.max(1)));
+    let results = Arc::new(Mutex::new(Vec::with_capacity(total)));
+
+    let mut ticker = tokio::time::interval(Duration::from_secs_f64(1.0 / plan.rate));
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+
+    info!(
+        "Load testing connector '{}': {} deliveries at {}/s, up to {} in flight",
+        connector.name(),
+        total,
+        plan.rate,
+        plan.concurrency
+    );
+
+    let started = Instant::now();
+    let mut tasks = Vec::with_capacity(total);
+
+    for sequence in 0..total {
+        ticker.tick().await;
+        let Ok(permit) = slots.clone().acquire_owned().await else {
+            break;
+        };
+
+        let connector = connector.clone();
+        let results = results.clone();
+        tasks.push(tokio::spawn(async move {
+            let problem = synthetic_problem(sequence);
+            let sent_at = Instant::now();
+
+            let result = ChunkResult::combined(connector.deliver(std::slice::from_ref(&problem)).await);
+            let latency = sent_at.elapsed();
+            drop(permit);
+
+            debug!("Load test delivery {} finished in {:?}", sequence, latency);
+            results.lock().await.push((latency, result.err().map(|e| e.to_string())));
+        }));
+    }
+
+    for task in tasks {
+        let _ = task.await;
+    }
+
+    let elapsed = started.elapsed();
+    let mut report = LoadTestReport {
+        elapsed,
+        succeeded: 0,
+        failed: 0,
+        latencies: Vec::with_capacity(total),
+        errors: BTreeMap::new(),
+    };
+
+    for (latency, error) in results.lock().await.drain(..) {
+        report.latencies.push(latency);
+        match error {
+            None => report.succeeded += 1,
+            Some(message) => {
+                report.failed += 1;
+                *report.errors.entry(message).or_default() += 1;
+            }
+        }
+    }
+    report.latencies.sort();
+
+    report
+}
+
+/// Open problem with a rotating severity, a few entities and tags, so payloads
+/// are about the size of real ones. Ids start with `LOADTEST-` to be easy to
+/// filter out on the receiving side.
+pub fn synthetic_problem(sequence: usize) -> Problem {
+    let host = |index: usize| AffectedEntity {
+        entity_id: EntityId {
+            id: format!("HOST-LOADTEST{:08}", index),
+            entity_type: "HOST".to_string(),
+        },
+        name: format!("loadtest-host-{:02}", index),
+        details: None,
+    };
+    let tag = |key: &str, value: &str| EntityTag {
+        context: "CONTEXTLESS".to_string(),
+        key: key.to_string(),
+        value: Some(value.to_string()),
+        string_representation: format!("{}:{}", key, value),
+    };
+
+    Problem {
+        problem_id: format!("LOADTEST-{:08}", sequence),
+        display_id: format!("P-LT{:06}", sequence),
+        title: format!("Synthetic load test problem #{}", sequence),
+        impact_level: "SERVICES".to_string(),
+        severity_level: SEVERITIES[sequence % SEVERITIES.len()].to_string(),
+        status: ProblemStatus::Open,
+        affected_entities: (0..3).map(|i| host(sequence * 3 + i)).collect(),
+        impacted_entities: (0..3).map(|i| host(sequence * 3 + i)).collect(),
+        root_cause_entity: Some(Entity {
+            entity_id: EntityId {
+                id: format!("SERVICE-LOADTEST{:08}", sequence),
+                entity_type: "SERVICE".to_string(),
+            },
+            name: "loadtest-service".to_string(),
+        }),
+        management_zones: vec![ManagementZone {
+            id: "0".to_string(),
+            name: "Load Test".to_string(),
+        }],
+        entity_tags: vec![tag("env", "loadtest"), tag("team", "sre")],
+        problem_filters: vec![],
+        start_time: chrono::Utc::now().timestamp_millis(),
+        end_time: -1,
+        evidence_details: None,
+        recent_comments: None,
+        impact_analysis: None,
+        event: None,
+        security: None,
+        audit: None,
+        runbooks: vec![],
+        correlated_problems: vec![],
+        recurrence: None,
+        tenant: None,
+        tenant_url_base: None,
+        change: None,
+    }
+}
+
+/// Parse a rate such as `50/s`, `600/m` or `50` (per second)
+pub fn parse_rate(text: &str) -> Result<f64, String> {
+    let (count, unit) = text.split_once('/').unwrap_or((text, "s"));
+    let count: f64 = count
+        .trim()
+        .parse()
+        .map_err(|_| format!("invalid rate '{}', expected e.g. 50/s or 600/m", text))?;
+    let per_second = match unit.trim() {
+        "s" => count,
+        "m" => count / 60.0,
+        "h" => count / 3600.0,
+        _ => return Err(format!("invalid rate unit in '{}', expected /s, /m or /h", text)),
+    };
+
+    if !per_second.is_finite() || per_second <= 0.0 {
+        return Err(format!("rate '{}' must be greater than 0", text));
+    }
+    Ok(per_second)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_rate() {
+        assert_eq!(parse_rate("50/s").unwrap(), 50.0);
+        assert_eq!(parse_rate("600/m").unwrap(), 10.0);
+        assert_eq!(parse_rate("20").unwrap(), 20.0);
+        assert!(parse_rate("0/s").is_err());
+        assert!(parse_rate("5/d").is_err());
+    }
+
+    #[test]
+    fn test_percentiles() {
+        let report = LoadTestReport {
+            elapsed: Duration::from_secs(10),
+            succeeded: 90,
+            failed: 10,
+            latencies: (1..=100).map(Duration::from_millis).collect(),
+            errors: BTreeMap::new(),
+        };
+
+        assert_eq!(report.percentile(50.0), Some(Duration::from_millis(50)));
+        assert_eq!(report.percentile(99.0), Some(Duration::from_millis(99)));
+        assert_eq!(report.percentile(100.0), Some(Duration::from_millis(100)));
+        assert_eq!(report.throughput(), 10.0);
+        assert_eq!(report.error_rate(), 0.1);
+    }
+}
@@ -1,6 +1,41 @@
+pub mod acknowledgment;
+pub mod chat;
+pub mod cloudevents;
+pub mod compression;
 pub mod connector;
+pub mod correlation;
+pub mod digest;
+pub mod elasticsearch;
+pub mod eventhubs;
+pub mod evidence;
+pub mod exec;
+pub mod file_sink;
+pub mod form;
+pub mod grpc;
 pub mod engine;
+pub mod envelope;
+pub mod events;
+pub mod health;
+pub mod loadtest;
+pub mod payload;
+pub mod payload_limit;
+pub mod priority;
+pub mod pubsub;
+pub mod receiver;
+pub mod recurrence;
+pub mod report;
 pub mod retry;
+pub mod routing;
+pub mod runbook;
+pub mod siem;
+pub mod tag_filter;
+pub mod signing;
+pub mod template;
+pub mod throttle;
+pub mod transform;
+pub mod xmatters;
+pub mod xml;
 
 pub use connector::Connector;
 pub use engine::ForwardingEngine;
+pub use events::EngineEvent;
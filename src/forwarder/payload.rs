@@ -0,0 +1,338 @@
+use serde_json::{json, Map, Value};
+use std::collections::BTreeMap;
+use chrono::TimeZone;
+use chrono_tz::Tz;
+use crate::config::{ComputedFieldsConfig, ConnectorConfig, ConnectorType, FieldMapConfig, LabelsConfig, PayloadFormat};
+use crate::dynatrace::Problem;
+use crate::error::{ForwarderError, Result};
+use crate::forwarder::{chat, cloudevents, elasticsearch, envelope, eventhubs, form, grpc, payload_limit, pubsub, siem, template, transform, xmatters, xml};
+use crate::utils::time::humanize_duration;
+
+/// Render the request bodies a connector would send for these problems
+/// (one body in batch mode, one per problem otherwise)
+pub fn render_requests(
+    problems: &[Problem],
+    config: &ConnectorConfig,
+    problem_url_base: &str,
+) -> Result<Vec<Value>> {
+    let groups: Vec<&[Problem]> = if config.batch_mode {
+        config.batch_chunks(problems).collect()
+    } else {
+        problems.chunks(1).collect()
+    };
+
+    // Size limits may turn one group into several requests
+    if config.connector_type == ConnectorType::Webhook
+        && (config.max_payload_bytes.is_some() || config.batch_max_bytes.is_some())
+    {
+        let mut bodies = Vec::new();
+        for group in groups {
+            let requests = payload_limit::fit(group, config.batch_mode, config, problem_url_base)?;
+            bodies.extend(requests.into_iter().map(|request| request.payload));
+        }
+        return Ok(bodies);
+    }
+
+    groups
+        .into_iter()
+        .map(|group| match config.connector_type {
+            ConnectorType::Pubsub => pubsub::build_publish_request(group, config, problem_url_base),
+            ConnectorType::Elasticsearch => elasticsearch::build_bulk_lines(group, config, problem_url_base).map(Value::Array),
+            ConnectorType::Eventhubs => eventhubs::build_event_batch(group, config, problem_url_base),
+            ConnectorType::File => match siem::lines(group, config, problem_url_base) {
+                Some(lines) => Ok(Value::String(lines)),
+                None => batch_payload(group, config, problem_url_base),
+            },
+            ConnectorType::Grpc => Ok(serde_json::to_value(grpc::build_forward_request(
+                group,
+                config,
+                problem_url_base,
+            ))?),
+            ConnectorType::Xmatters => Ok(Value::Array(
+                group
+                    .iter()
+                    .map(|problem| xmatters::build_trigger_payload(problem, problem_url_base))
+                    .collect(),
+            )),
+            ConnectorType::Webhook
+            | ConnectorType::Webex
+            | ConnectorType::GoogleChat
+            | ConnectorType::Exec => {
+                webhook_payload(group, config.batch_mode, config, problem_url_base)
+            }
+        })
+        .collect()
+}
+
+/// Bytes sent for a webhook payload. CEF/LEEF lines, XML documents and form
+/// bodies are carried as a JSON string and sent as text; `format: query` has no body.
+pub fn webhook_body(payload: &Value, config: &ConnectorConfig) -> Result<Vec<u8>> {
+    Ok(match payload {
+        _ if config.format == PayloadFormat::Query => Vec::new(),
+        Value::String(text) if config.format != PayloadFormat::Json => text.clone().into_bytes(),
+        _ => serde_json::to_vec(payload)?,
+    })
+}
+
+/// Render the body of one webhook request: the connector's template, the problem
+/// JSON (an array when `batch` is set, optionally in envelopes), CEF/LEEF lines, an XML document or form/query
+/// fields as a JSON string, or, for chat connector types, the
+/// platform's message format
+pub fn webhook_payload(
+    problems: &[Problem],
+    batch: bool,
+    config: &ConnectorConfig,
+    problem_url_base: &str,
+) -> Result<Value> {
+    match config.connector_type {
+        ConnectorType::Webex => Ok(chat::webex_message(problems, problem_url_base, config.timezone())),
+        ConnectorType::GoogleChat => {
+            Ok(chat::google_chat_message(problems, problem_url_base, config.timezone()))
+        }
+        _ => {
+            if let Some(body) = template::render(problems, batch, config, problem_url_base)? {
+                return Ok(body);
+            }
+
+            match config.format {
+                PayloadFormat::Xml => return Ok(Value::String(xml::document(problems, config, problem_url_base)?)),
+                PayloadFormat::Form | PayloadFormat::Query => {
+                    return Ok(Value::String(form::encode(&problems[0], config, problem_url_base)?));
+                }
+                _ => {}
+            }
+
+            if let Some(lines) = siem::lines(problems, config, problem_url_base) {
+                return Ok(Value::String(lines));
+            }
+
+            if let Some(envelope) = &config.envelope {
+                return envelope::wrap(problems, batch, config, envelope, problem_url_base);
+            }
+
+            match &config.cloudevents {
+                Some(cloudevents) => cloudevents::wrap(problems, batch, config, cloudevents, problem_url_base),
+                None if batch => batch_payload(problems, config, problem_url_base),
+                None => problem_payload(&problems[0], config, problem_url_base),
+            }
+        }
+    }
+}
+
+/// Render the JSON payload for a single problem
+pub fn problem_payload(problem: &Problem, config: &ConnectorConfig, problem_url_base: &str) -> Result<Value> {
+    let mut payload = serde_json::to_value(problem)?;
+
+    if let (Some(labels_config), Value::Object(map)) = (&config.labels, &mut payload) {
+        map.insert("labels".to_string(), json!(build_labels(problem, labels_config)));
+    }
+
+    if let (Some(computed), Value::Object(map)) = (&config.computed_fields, &mut payload) {
+        add_computed_fields(map, problem, computed, config.timezone(), problem_url_base);
+    }
+
+    if let Value::Object(map) = &mut payload {
+        if !config.include_fields.is_empty() {
+            map.retain(|field, _| config.include_fields.contains(field));
+        }
+        for field in &config.exclude_fields {
+            map.remove(field);
+        }
+    }
+
+    if let (Some(field_map), Value::Object(map)) = (&config.field_map, &mut payload) {
+        apply_field_map(map, field_map);
+    }
+
+    match &config.transform {
+        Some(expression) => transform::apply(expression, payload)
+            .map_err(|e| ForwarderError::Config(format!("Connector '{}': {}", config.name, e))),
+        None => Ok(payload),
+    }
+}
+
+/// Local start/end times, duration and the Dynatrace link. End time and
+/// duration are null while the problem is open.
+fn add_computed_fields(
+    map: &mut Map<String, Value>,
+    problem: &Problem,
+    config: &ComputedFieldsConfig,
+    tz: Tz,
+    problem_url_base: &str,
+) {
+    let local = |millis: i64| {
+        tz.timestamp_millis_opt(millis)
+            .single()
+            .map(|time| time.format(&config.time_format).to_string())
+    };
+    let duration_seconds = (problem.end_time > 0).then(|| (problem.end_time - problem.start_time).max(0) / 1000);
+
+    map.insert("startTimeLocal".to_string(), json!(local(problem.start_time)));
+    map.insert(
+        "endTimeLocal".to_string(),
+        json!((problem.end_time > 0).then(|| local(problem.end_time)).flatten()),
+    );
+    map.insert("durationSeconds".to_string(), json!(duration_seconds));
+    map.insert("duration".to_string(), json!(duration_seconds.map(humanize_duration)));
+    map.insert(
+        "problemUrl".to_string(),
+        json!(problem.url(problem_url_base)),
+    );
+}
+
+/// Drop, then rename top-level fields. Fields missing from the problem are ignored.
+fn apply_field_map(map: &mut Map<String, Value>, field_map: &FieldMapConfig) {
+    for field in &field_map.drop {
+        map.remove(field);
+    }
+
+    let renamed: Vec<(String, Value)> = field_map
+        .rename
+        .iter()
+        .filter_map(|(from, to)| map.remove(from).map(|value| (to.clone(), value)))
+        .collect();
+    map.extend(renamed);
+}
+
+/// Render the JSON array payload for a batch of problems
+pub fn batch_payload(problems: &[Problem], config: &ConnectorConfig, problem_url_base: &str) -> Result<Value> {
+    let payloads = problems
+        .iter()
+        .map(|problem| problem_payload(problem, config, problem_url_base))
+        .collect::<Result<Vec<_>>>()?;
+
+    Ok(Value::Array(payloads))
+}
+
+/// Build a normalized key/value label map from entity tags and management zones
+pub fn build_labels(problem: &Problem, config: &LabelsConfig) -> BTreeMap<String, String> {
+    let mut labels: BTreeMap<String, String> = BTreeMap::new();
+
+    for tag in &problem.entity_tags {
+        if !config.tags.iter().any(|allowed| allowed == &tag.key) {
+            continue;
+        }
+
+        let value = tag.value.clone().unwrap_or_default();
+        labels
+            .entry(label_key(&tag.key, config))
+            .and_modify(|existing| {
+                if !existing.split(',').any(|v| v == value) {
+                    existing.push(',');
+                    existing.push_str(&value);
+                }
+            })
+            .or_insert(value);
+    }
+
+    if config.management_zones && !problem.management_zones.is_empty() {
+        let zones = problem
+            .management_zones
+            .iter()
+            .map(|zone| zone.name.as_str())
+            .collect::<Vec<_>>()
+            .join(",");
+        labels.insert(label_key("management_zone", config), zones);
+    }
+
+    labels
+}
+
+/// Apply renaming, or normalize the key to lowercase snake_case
+fn label_key(key: &str, config: &LabelsConfig) -> String {
+    if let Some(renamed) = config.rename.get(key) {
+        return renamed.clone();
+    }
+
+    key.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c.to_ascii_lowercase() } else { '_' })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dynatrace::models::{EntityTag, ManagementZone};
+    use crate::dynatrace::ProblemStatus;
+    use std::collections::HashMap;
+
+    fn tag(key: &str, value: Option<&str>) -> EntityTag {
+        EntityTag {
+            context: "CONTEXTLESS".to_string(),
+            key: key.to_string(),
+            value: value.map(String::from),
+            string_representation: match value {
+                Some(v) => format!("{}:{}", key, v),
+                None => key.to_string(),
+            },
+        }
+    }
+
+    fn sample_problem() -> Problem {
+        Problem {
+            problem_id: "P-1".to_string(),
+            display_id: "P-1".to_string(),
+            title: "High CPU".to_string(),
+            impact_level: "INFRASTRUCTURE".to_string(),
+            severity_level: "RESOURCE_CONTENTION".to_string(),
+            status: ProblemStatus::Open,
+            affected_entities: vec![],
+            impacted_entities: vec![],
+            root_cause_entity: None,
+            management_zones: vec![ManagementZone { id: "1".to_string(), name: "prod".to_string() }],
+            entity_tags: vec![
+                tag("Env", Some("prod")),
+                tag("team", Some("payments")),
+                tag("team", Some("checkout")),
+                tag("ignored", Some("x")),
+            ],
+            problem_filters: vec![],
+            start_time: 0,
+            end_time: -1,
+            evidence_details: None,
+            recent_comments: None,
+            impact_analysis: None,
+            event: None,
+            security: None,
+            audit: None,
+            runbooks: vec![],
+            correlated_problems: vec![],
+            recurrence: None,
+            tenant: None,
+            tenant_url_base: None,
+            change: None,
+        }
+    }
+
+    #[test]
+    fn test_build_labels_allowlist_and_rename() {
+        let config = LabelsConfig {
+            tags: vec!["Env".to_string(), "team".to_string()],
+            management_zones: true,
+            rename: HashMap::from([("management_zone".to_string(), "zone".to_string())]),
+        };
+
+        let labels = build_labels(&sample_problem(), &config);
+
+        assert_eq!(labels.get("env").map(String::as_str), Some("prod"));
+        assert_eq!(labels.get("team").map(String::as_str), Some("payments,checkout"));
+        assert_eq!(labels.get("zone").map(String::as_str), Some("prod"));
+        assert!(!labels.contains_key("ignored"));
+    }
+
+    #[test]
+    fn test_field_map_swaps_names_without_losing_values() {
+        let mut payload = json!({ "problemId": "P-1", "title": "High CPU", "entityTags": [] });
+        let field_map = FieldMapConfig {
+            rename: HashMap::from([
+                ("problemId".to_string(), "title".to_string()),
+                ("title".to_string(), "summary".to_string()),
+            ]),
+            drop: vec!["entityTags".to_string(), "missing".to_string()],
+        };
+
+        apply_field_map(payload.as_object_mut().unwrap(), &field_map);
+
+        assert_eq!(payload, json!({ "title": "P-1", "summary": "High CPU" }));
+    }
+}
@@ -0,0 +1,171 @@
+use serde_json::Value;
+use tracing::warn;
+use crate::config::{ConnectorConfig, OversizeStrategy};
+use crate::dynatrace::Problem;
+use crate::error::{ForwarderError, Result};
+use crate::forwarder::payload;
+
+/// One webhook request: the problems it covers and its body
+#[derive(Debug, Clone)]
+pub struct FittedRequest {
+    pub problems: Vec<Problem>,
+    pub payload: Value,
+    /// Body size in bytes, before compression
+    pub size: usize,
+}
+
+/// Build the webhook requests for these problems. A batch is first halved
+/// until each part is within `batch_max_bytes`, then every body over
+/// `max_payload_bytes` is handled according to `oversize_strategy`. Without
+/// limits this is a single request.
+pub fn fit(
+    problems: &[Problem],
+    batch: bool,
+    config: &ConnectorConfig,
+    problem_url_base: &str,
+) -> Result<Vec<FittedRequest>> {
+    let build = |problems: &[Problem]| -> Result<FittedRequest> {
+        let payload = payload::webhook_payload(problems, batch, config, problem_url_base)?;
+        let size = payload::webhook_body(&payload, config)?.len();
+        Ok(FittedRequest {
+            problems: problems.to_vec(),
+            payload,
+            size,
+        })
+    };
+
+    let mut parts = Vec::new();
+    match config.batch_max_bytes.filter(|_| batch) {
+        Some(max_bytes) => halve(problems, max_bytes, &build, &mut parts)?,
+        None => parts.push(build(problems)?),
+    }
+
+    let mut requests = Vec::with_capacity(parts.len());
+    for part in parts {
+        match config.max_payload_bytes.filter(|max_bytes| part.size > *max_bytes) {
+            Some(max_bytes) => requests.extend(limit(part, max_bytes, config, &build)?),
+            None => requests.push(part),
+        }
+    }
+    Ok(requests)
+}
+
+/// Apply the connector's `oversize_strategy` to a request over `max_bytes`
+fn limit(
+    request: FittedRequest,
+    max_bytes: usize,
+    config: &ConnectorConfig,
+    build: &impl Fn(&[Problem]) -> Result<FittedRequest>,
+) -> Result<Vec<FittedRequest>> {
+    match config.oversize_strategy {
+        OversizeStrategy::Fail => Err(oversize_error(config, request.size, max_bytes)),
+        OversizeStrategy::Split => {
+            let mut parts = Vec::new();
+            halve(&request.problems, max_bytes, build, &mut parts)?;
+            match parts.iter().find(|part| part.size > max_bytes) {
+                Some(part) => Err(oversize_error(config, part.size, max_bytes)),
+                None => Ok(parts),
+            }
+        }
+        OversizeStrategy::TruncateEntities => {
+            let mut keep = request.problems.iter().map(entity_count).max().unwrap_or(0);
+            while keep > 0 {
+                keep /= 2;
+                let truncated: Vec<Problem> = request.problems.iter().map(|p| truncate_entities(p, keep)).collect();
+                let fitted = build(&truncated)?;
+                if fitted.size <= max_bytes {
+                    warn!(
+                        "Connector '{}': payload exceeded {} bytes, entity lists truncated to {} entries",
+                        config.name, max_bytes, keep
+                    );
+                    return Ok(vec![fitted]);
+                }
+            }
+            Err(oversize_error(config, request.size, max_bytes))
+        }
+    }
+}
+
+/// Halve the batch until every part is within `max_bytes`. A single problem
+/// cannot be split further and is kept even when larger.
+fn halve(
+    problems: &[Problem],
+    max_bytes: usize,
+    build: &impl Fn(&[Problem]) -> Result<FittedRequest>,
+    parts: &mut Vec<FittedRequest>,
+) -> Result<()> {
+    let part = build(problems)?;
+    if part.size <= max_bytes || problems.len() == 1 {
+        parts.push(part);
+        return Ok(());
+    }
+
+    let (first, second) = problems.split_at(problems.len() / 2);
+    halve(first, max_bytes, build, parts)?;
+    halve(second, max_bytes, build, parts)
+}
+
+fn entity_count(problem: &Problem) -> usize {
+    problem.affected_entities.len().max(problem.impacted_entities.len())
+}
+
+/// Keep the first `keep` affected and impacted entities
+fn truncate_entities(problem: &Problem, keep: usize) -> Problem {
+    let mut problem = problem.clone();
+    problem.affected_entities.truncate(keep);
+    problem.impacted_entities.truncate(keep);
+    problem
+}
+
+/// A body that cannot be made to fit; sending it would only be rejected again
+pub fn oversize_error(config: &ConnectorConfig, size: usize, max_bytes: usize) -> ForwarderError {
+    ForwarderError::PermanentDownstream {
+        target: config.url.clone(),
+        message: format!("payload of {} bytes exceeds max_payload_bytes ({})", size, max_bytes),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::forwarder::loadtest::synthetic_problem;
+
+    fn connector(max_payload_bytes: usize, strategy: &str) -> ConnectorConfig {
+        serde_yaml::from_str(&format!(
+            "name: limited\nurl: http://localhost/\nmax_payload_bytes: {}\noversize_strategy: {}",
+            max_payload_bytes, strategy
+        ))
+        .unwrap()
+    }
+
+    fn size(problems: &[Problem]) -> usize {
+        let config = connector(1, "fail");
+        let payload = payload::webhook_payload(problems, true, &config, "").unwrap();
+        payload::webhook_body(&payload, &config).unwrap().len()
+    }
+
+    #[test]
+    fn test_split_and_truncate_fit_the_limit() {
+        let problems: Vec<Problem> = (0..4).map(synthetic_problem).collect();
+        let pair = size(&problems[..2]).max(size(&problems[2..]));
+
+        let split = fit(&problems, true, &connector(pair, "split"), "").unwrap();
+        assert_eq!(split.iter().map(|r| r.problems.len()).collect::<Vec<_>>(), vec![2, 2]);
+
+        let truncated = fit(&problems[..1], true, &connector(size(&problems[..1]) - 200, "truncate_entities"), "").unwrap();
+        assert_eq!(truncated[0].problems[0].affected_entities.len(), 1);
+
+        assert!(fit(&problems, true, &connector(pair, "fail"), "").is_err());
+    }
+
+    #[test]
+    fn test_batch_max_bytes_halves_without_failing() {
+        let problems: Vec<Problem> = (0..3).map(synthetic_problem).collect();
+        let mut config = connector(1, "fail");
+        config.max_payload_bytes = None;
+        config.batch_max_bytes = Some(size(&problems[..1]) - 1);
+
+        let requests = fit(&problems, true, &config, "").unwrap();
+        assert_eq!(requests.iter().map(|r| r.problems.len()).collect::<Vec<_>>(), vec![1, 1, 1]);
+    }
+}
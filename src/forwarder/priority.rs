@@ -0,0 +1,160 @@
+use std::cmp::{Ordering, Reverse};
+use std::collections::BinaryHeap;
+use crate::config::PriorityKey;
+use crate::dynatrace::{Problem, ProblemStatus};
+use crate::forwarder::digest::severity_rank;
+
+/// Problems of one poll, popped most important first by the configured keys.
+/// Problems with equal keys keep the order they were pushed in.
+pub struct PriorityQueue<'a> {
+    heap: BinaryHeap<Reverse<Entry>>,
+    order_by: &'a [PriorityKey],
+    pushed: usize,
+}
+
+struct Entry {
+    key: Vec<i64>,
+    seq: usize,
+    problem: Problem,
+}
+
+impl PartialEq for Entry {
+    fn eq(&self, other: &Self) -> bool {
+        self.cmp(other) == Ordering::Equal
+    }
+}
+
+impl Eq for Entry {}
+
+impl PartialOrd for Entry {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Entry {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.key.cmp(&other.key).then(self.seq.cmp(&other.seq))
+    }
+}
+
+impl<'a> PriorityQueue<'a> {
+    pub fn new(order_by: &'a [PriorityKey]) -> Self {
+        Self {
+            heap: BinaryHeap::new(),
+            order_by,
+            pushed: 0,
+        }
+    }
+
+    pub fn push(&mut self, problem: Problem) {
+        let key = self.order_by.iter().map(|key| rank(*key, &problem)).collect();
+        self.heap.push(Reverse(Entry { key, seq: self.pushed, problem }));
+        self.pushed += 1;
+    }
+
+    pub fn pop(&mut self) -> Option<Problem> {
+        self.heap.pop().map(|Reverse(entry)| entry.problem)
+    }
+
+    /// Drain the queue into a list, most important first
+    pub fn into_sorted_vec(mut self) -> Vec<Problem> {
+        let mut problems = Vec::with_capacity(self.heap.len());
+        while let Some(problem) = self.pop() {
+            problems.push(problem);
+        }
+        problems
+    }
+}
+
+/// Lower ranks are forwarded first
+fn rank(key: PriorityKey, problem: &Problem) -> i64 {
+    match key {
+        PriorityKey::Severity => severity_rank(&problem.severity_level) as i64,
+        PriorityKey::Impact => match problem.impact_level.as_str() {
+            "APPLICATION" => 0,
+            "SERVICES" => 1,
+            "INFRASTRUCTURE" => 2,
+            "ENVIRONMENT" => 3,
+            _ => 4,
+        },
+        PriorityKey::Status => (problem.status != ProblemStatus::Open) as i64,
+        PriorityKey::Oldest => problem.start_time,
+        PriorityKey::Newest => -problem.start_time,
+    }
+}
+
+/// Order problems by the configured keys
+pub fn prioritize(problems: &[Problem], order_by: &[PriorityKey]) -> Vec<Problem> {
+    let mut queue = PriorityQueue::new(order_by);
+    problems.iter().cloned().for_each(|problem| queue.push(problem));
+    queue.into_sorted_vec()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn problem(id: &str, severity: &str, impact: &str, start_time: i64) -> Problem {
+        Problem {
+            problem_id: id.to_string(),
+            display_id: format!("P-{}", id),
+            title: format!("Problem {}", id),
+            impact_level: impact.to_string(),
+            severity_level: severity.to_string(),
+            status: ProblemStatus::Open,
+            affected_entities: vec![],
+            impacted_entities: vec![],
+            root_cause_entity: None,
+            management_zones: vec![],
+            entity_tags: vec![],
+            problem_filters: vec![],
+            start_time,
+            end_time: -1,
+            evidence_details: None,
+            recent_comments: None,
+            impact_analysis: None,
+            event: None,
+            security: None,
+            audit: None,
+            runbooks: vec![],
+            correlated_problems: vec![],
+            recurrence: None,
+            tenant: None,
+            tenant_url_base: None,
+            change: None,
+        }
+    }
+
+    fn ids(problems: &[Problem]) -> Vec<&str> {
+        problems.iter().map(|p| p.problem_id.as_str()).collect()
+    }
+
+    #[test]
+    fn test_prioritize() {
+        let problems = vec![
+            problem("perf", "PERFORMANCE", "SERVICES", 10),
+            problem("avail-infra", "AVAILABILITY", "INFRASTRUCTURE", 20),
+            problem("avail-app-new", "AVAILABILITY", "APPLICATION", 40),
+            problem("avail-app-old", "AVAILABILITY", "APPLICATION", 30),
+            problem("custom", "CUSTOM_ALERT", "SERVICES", 5),
+        ];
+
+        let order = [PriorityKey::Severity, PriorityKey::Impact, PriorityKey::Oldest];
+        assert_eq!(
+            ids(&prioritize(&problems, &order)),
+            ["avail-app-old", "avail-app-new", "avail-infra", "perf", "custom"]
+        );
+
+        assert_eq!(
+            ids(&prioritize(&problems, &[PriorityKey::Newest])),
+            ["avail-app-new", "avail-app-old", "avail-infra", "perf", "custom"]
+        );
+
+        // Equal keys keep fetch order
+        assert_eq!(
+            ids(&prioritize(&problems, &[PriorityKey::Status])),
+            ["perf", "avail-infra", "avail-app-new", "avail-app-old", "custom"]
+        );
+    }
+}
@@ -0,0 +1,277 @@
+use base64::{engine::general_purpose::STANDARD as BASE64, Engine};
+use reqwest::{Client, StatusCode};
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+use tokio::sync::Mutex;
+use crate::config::{ConnectorConfig, PubSubAuth, PubSubConfig};
+use crate::dynatrace::Problem;
+use crate::error::{ForwarderError, Result};
+use crate::forwarder::payload;
+use tracing::debug;
+
+const PUBSUB_SCOPE: &str = "https://www.googleapis.com/auth/pubsub";
+const METADATA_TOKEN_URL: &str =
+    "http://metadata.google.internal/computeMetadata/v1/instance/service-accounts/default/token";
+const DEFAULT_TOKEN_URI: &str = "https://oauth2.googleapis.com/token";
+
+/// Refresh tokens this long before Google reports them as expired
+const TOKEN_EXPIRY_MARGIN: Duration = Duration::from_secs(60);
+
+/// Publishes problems to a Google Cloud Pub/Sub topic
+pub struct PubSubPublisher {
+    client: Client,
+    config: PubSubConfig,
+    connector: ConnectorConfig,
+    problem_url_base: String,
+    cached_token: Mutex<Option<CachedToken>>,
+}
+
+struct CachedToken {
+    access_token: String,
+    expires_at: Instant,
+}
+
+#[derive(Debug, Deserialize)]
+struct ServiceAccountKey {
+    client_email: String,
+    private_key: String,
+    token_uri: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct JwtClaims<'a> {
+    iss: &'a str,
+    scope: &'a str,
+    aud: &'a str,
+    iat: i64,
+    exp: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct TokenResponse {
+    access_token: String,
+    expires_in: u64,
+}
+
+impl PubSubPublisher {
+    /// Create a new publisher sharing the connector's HTTP client
+    pub fn new(client: Client, config: PubSubConfig, connector: ConnectorConfig, problem_url_base: String) -> Self {
+        Self {
+            client,
+            config,
+            connector,
+            problem_url_base,
+            cached_token: Mutex::new(None),
+        }
+    }
+
+    /// Get the topic publish URL
+    pub fn publish_url(&self) -> String {
+        format!(
+            "https://pubsub.googleapis.com/v1/projects/{}/topics/{}:publish",
+            self.config.project_id, self.config.topic
+        )
+    }
+
+    /// Publish each problem as a separate message in a single publish call
+    pub async fn publish(&self, problems: &[Problem], timeout: Duration) -> Result<StatusCode> {
+        let token = self.access_token().await?;
+        let url = self.publish_url();
+
+        debug!("Publishing {} message(s) to {}", problems.len(), url);
+
+        let response = self
+            .client
+            .post(&url)
+            .timeout(timeout)
+            .bearer_auth(token)
+            .json(&build_publish_request(problems, &self.connector, &self.problem_url_base)?)
+            .send()
+            .await?;
+
+        let status = response.status();
+
+        if !status.is_success() {
+            let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+            return Err(ForwarderError::from_status(url, status, &error_text));
+        }
+
+        Ok(status)
+    }
+
+    /// Get a valid access token, refreshing it if needed
+    async fn access_token(&self) -> Result<String> {
+        let mut cached = self.cached_token.lock().await;
+
+        if let Some(token) = cached.as_ref() {
+            if token.expires_at > Instant::now() + TOKEN_EXPIRY_MARGIN {
+                return Ok(token.access_token.clone());
+            }
+        }
+
+        let token = match self.config.auth {
+            PubSubAuth::ServiceAccount => self.fetch_service_account_token().await?,
+            PubSubAuth::WorkloadIdentity => self.fetch_metadata_token().await?,
+        };
+
+        let access_token = token.access_token.clone();
+        *cached = Some(CachedToken {
+            access_token: token.access_token,
+            expires_at: Instant::now() + Duration::from_secs(token.expires_in),
+        });
+
+        Ok(access_token)
+    }
+
+    /// Exchange a signed service account JWT for an access token
+    async fn fetch_service_account_token(&self) -> Result<TokenResponse> {
+        let key_path = self
+            .config
+            .credentials_file
+            .clone()
+            .or_else(|| std::env::var("GOOGLE_APPLICATION_CREDENTIALS").ok().map(PathBuf::from))
+            .ok_or_else(|| ForwarderError::Config("No service account credentials file configured".to_string()))?;
+
+        let key_content = std::fs::read_to_string(&key_path).map_err(|e| {
+            ForwarderError::Config(format!(
+                "Failed to read service account key '{}': {}",
+                key_path.display(),
+                e
+            ))
+        })?;
+        let key: ServiceAccountKey = serde_json::from_str(&key_content)?;
+        let token_uri = key.token_uri.as_deref().unwrap_or(DEFAULT_TOKEN_URI);
+
+        let now = chrono::Utc::now().timestamp();
+        let claims = JwtClaims {
+            iss: &key.client_email,
+            scope: PUBSUB_SCOPE,
+            aud: token_uri,
+            iat: now,
+            exp: now + 3600,
+        };
+
+        let encoding_key = jsonwebtoken::EncodingKey::from_rsa_pem(key.private_key.as_bytes())
+            .map_err(|e| ForwarderError::Config(format!("Invalid service account private key: {}", e)))?;
+        let assertion = jsonwebtoken::encode(
+            &jsonwebtoken::Header::new(jsonwebtoken::Algorithm::RS256),
+            &claims,
+            &encoding_key,
+        )
+        .map_err(|e| ForwarderError::Config(format!("Failed to sign service account JWT: {}", e)))?;
+
+        let response = self
+            .client
+            .post(token_uri)
+            .form(&[
+                ("grant_type", "urn:ietf:params:oauth:grant-type:jwt-bearer"),
+                ("assertion", assertion.as_str()),
+            ])
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(response.json::<TokenResponse>().await?)
+    }
+
+    /// Fetch an access token for the attached service account from the metadata server
+    async fn fetch_metadata_token(&self) -> Result<TokenResponse> {
+        let response = self
+            .client
+            .get(METADATA_TOKEN_URL)
+            .header("Metadata-Flavor", "Google")
+            .send()
+            .await?
+            .error_for_status()?;
+
+        Ok(response.json::<TokenResponse>().await?)
+    }
+}
+
+/// Build the Pub/Sub publish request body for a set of problems
+pub fn build_publish_request(problems: &[Problem], connector: &ConnectorConfig, problem_url_base: &str) -> Result<Value> {
+    let mut messages = Vec::with_capacity(problems.len());
+
+    for problem in problems {
+        let data = serde_json::to_vec(&payload::problem_payload(problem, connector, problem_url_base)?)?;
+        let management_zones = problem
+            .management_zones
+            .iter()
+            .map(|zone| zone.name.as_str())
+            .collect::<Vec<_>>()
+            .join(",");
+
+        messages.push(json!({
+            "data": BASE64.encode(data),
+            "attributes": {
+                "problemId": problem.problem_id,
+                "severity": problem.severity_level,
+                "status": problem.status.to_string(),
+                "managementZone": management_zones,
+            }
+        }));
+    }
+
+    Ok(json!({ "messages": messages }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dynatrace::models::ManagementZone;
+    use crate::dynatrace::ProblemStatus;
+
+    fn sample_problem() -> Problem {
+        Problem {
+            problem_id: "P-1".to_string(),
+            display_id: "P-1".to_string(),
+            title: "High CPU".to_string(),
+            impact_level: "INFRASTRUCTURE".to_string(),
+            severity_level: "RESOURCE_CONTENTION".to_string(),
+            status: ProblemStatus::Open,
+            affected_entities: vec![],
+            impacted_entities: vec![],
+            root_cause_entity: None,
+            management_zones: vec![
+                ManagementZone { id: "1".to_string(), name: "prod".to_string() },
+                ManagementZone { id: "2".to_string(), name: "web".to_string() },
+            ],
+            entity_tags: vec![],
+            problem_filters: vec![],
+            start_time: 0,
+            end_time: -1,
+            evidence_details: None,
+            recent_comments: None,
+            impact_analysis: None,
+            event: None,
+            security: None,
+            audit: None,
+            runbooks: vec![],
+            correlated_problems: vec![],
+            recurrence: None,
+            tenant: None,
+            tenant_url_base: None,
+            change: None,
+        }
+    }
+
+    #[test]
+    fn test_publish_request_attributes() {
+        let connector: ConnectorConfig = serde_yaml::from_str(
+            "name: gcp\ntype: pubsub\npubsub:\n  project_id: p\n  topic: t\n  auth: workload_identity\n",
+        )
+        .unwrap();
+        let body = build_publish_request(&[sample_problem()], &connector, "").unwrap();
+        let message = &body["messages"][0];
+
+        assert_eq!(message["attributes"]["severity"], "RESOURCE_CONTENTION");
+        assert_eq!(message["attributes"]["status"], "OPEN");
+        assert_eq!(message["attributes"]["managementZone"], "prod,web");
+
+        let data = BASE64.decode(message["data"].as_str().unwrap()).unwrap();
+        let decoded: Problem = serde_json::from_slice(&data).unwrap();
+        assert_eq!(decoded.problem_id, "P-1");
+    }
+}
@@ -0,0 +1,195 @@
+use crate::config::PushConfig;
+use crate::error::{ForwarderError, Result};
+use axum::body::Bytes;
+use axum::extract::{Path, State};
+use axum::http::{header, HeaderMap, StatusCode};
+use axum::routing::post;
+use axum::{Json, Router};
+use serde_json::{json, Value};
+use std::collections::HashSet;
+use std::future::Future;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::sync::{Mutex, Notify};
+use tracing::{debug, info, warn};
+
+/// A problem announced by a Dynatrace notification, waiting for the engine's next push cycle
+#[derive(Debug, Clone, PartialEq)]
+pub struct PushedProblem {
+    /// Environment the notification was posted for (the main one when not set)
+    pub environment: Option<String>,
+    pub problem_id: String,
+    /// Failed attempts to fetch the problem's details so far
+    pub fetch_failures: u32,
+}
+
+/// Notified problems shared by the receiver and the engine
+#[derive(Clone, Default)]
+pub struct PushQueue {
+    problems: Arc<Mutex<Vec<PushedProblem>>>,
+    notify: Arc<Notify>,
+}
+
+impl PushQueue {
+    /// Queue a problem and wake the engine; a problem already queued is not added twice
+    pub async fn push(&self, problem: PushedProblem) {
+        let mut problems = self.problems.lock().await;
+        if !problems
+            .iter()
+            .any(|queued| queued.problem_id == problem.problem_id && queued.environment == problem.environment)
+        {
+            problems.push(problem);
+        }
+        self.notify.notify_one();
+    }
+
+    /// Queue a problem again once `delay` has passed, e.g. after its details could not be fetched
+    pub fn push_after(&self, problem: PushedProblem, delay: Duration) {
+        let queue = self.clone();
+        tokio::spawn(async move {
+            tokio::time::sleep(delay).await;
+            queue.push(problem).await;
+        });
+    }
+
+    /// Take all queued problems, oldest first
+    pub async fn take(&self) -> Vec<PushedProblem> {
+        std::mem::take(&mut *self.problems.lock().await)
+    }
+
+    /// Wait until a problem is queued
+    pub async fn notified(&self) {
+        self.notify.notified().await
+    }
+}
+
+struct ReceiverState {
+    queue: PushQueue,
+    auth_token: Option<String>,
+    environments: HashSet<String>,
+}
+
+/// Listen on `push.listen` and return the receiver, which accepts Dynatrace problem
+/// notifications until it is dropped. Notifications for the main environment are
+/// posted to `push.path`, those for another environment to `<push.path>/<environment>`.
+pub fn bind(
+    config: &PushConfig,
+    queue: PushQueue,
+    environments: Vec<String>,
+) -> Result<impl Future<Output = Result<()>>> {
+    let addr: SocketAddr = config
+        .listen
+        .parse()
+        .map_err(|_| ForwarderError::Config(format!("Invalid push.listen address '{}'", config.listen)))?;
+
+    let state = Arc::new(ReceiverState {
+        queue,
+        auth_token: config.auth_token.clone(),
+        environments: environments.into_iter().collect(),
+    });
+
+    let app = Router::new()
+        .route(&config.path, post(receive_main))
+        .route(&format!("{}/:environment", config.path), post(receive_for_environment))
+        .with_state(state);
+
+    let server = axum::Server::try_bind(&addr)
+        .map_err(|e| ForwarderError::Config(format!("Failed to listen on {}: {}", addr, e)))?
+        .serve(app.into_make_service());
+
+    info!("Push receiver listening on http://{}{}", addr, config.path);
+
+    Ok(async move { server.await.map_err(|e| ForwarderError::Io(std::io::Error::other(e))) })
+}
+
+async fn receive_main(
+    State(state): State<Arc<ReceiverState>>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> (StatusCode, Json<Value>) {
+    receive(&state, None, &headers, &body).await
+}
+
+async fn receive_for_environment(
+    State(state): State<Arc<ReceiverState>>,
+    Path(environment): Path<String>,
+    headers: HeaderMap,
+    body: Bytes,
+) -> (StatusCode, Json<Value>) {
+    if !state.environments.contains(&environment) {
+        return reply(StatusCode::NOT_FOUND, format!("unknown environment '{}'", environment));
+    }
+
+    receive(&state, Some(environment), &headers, &body).await
+}
+
+async fn receive(
+    state: &ReceiverState,
+    environment: Option<String>,
+    headers: &HeaderMap,
+    body: &[u8],
+) -> (StatusCode, Json<Value>) {
+    if let Some(token) = &state.auth_token {
+        let authorization = headers.get(header::AUTHORIZATION).and_then(|value| value.to_str().ok());
+        if authorization != Some(format!("Bearer {}", token).as_str()) {
+            warn!("Rejected a problem notification without a valid bearer token");
+            return reply(StatusCode::UNAUTHORIZED, "missing or invalid bearer token");
+        }
+    }
+
+    let notification: Value = match serde_json::from_slice(body) {
+        Ok(notification) => notification,
+        Err(e) => return reply(StatusCode::BAD_REQUEST, format!("invalid JSON: {}", e)),
+    };
+
+    let Some(problem_id) = notified_problem_id(&notification) else {
+        return reply(StatusCode::BAD_REQUEST, "notification has no PID or problemId field");
+    };
+
+    debug!("Received notification for problem {}", problem_id);
+    state
+        .queue
+        .push(PushedProblem {
+            environment,
+            problem_id: problem_id.clone(),
+            fetch_failures: 0,
+        })
+        .await;
+
+    (StatusCode::ACCEPTED, Json(json!({ "status": "queued", "problemId": problem_id })))
+}
+
+fn reply(status: StatusCode, error: impl Into<String>) -> (StatusCode, Json<Value>) {
+    (status, Json(json!({ "error": error.into() })))
+}
+
+/// Problem ID of a notification: `PID` (the `{PID}` placeholder) or `problemId`
+pub fn notified_problem_id(notification: &Value) -> Option<String> {
+    ["PID", "problemId"].iter().find_map(|key| match notification.get(key)? {
+        Value::String(id) if !id.trim().is_empty() => Some(id.trim().to_string()),
+        Value::Number(id) => Some(id.to_string()),
+        _ => None,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_notified_problem_id() {
+        let pid = json!({"PID": "-3322108764589263413_1770700000000V2", "ProblemID": "P-42"});
+        assert_eq!(
+            notified_problem_id(&pid).as_deref(),
+            Some("-3322108764589263413_1770700000000V2")
+        );
+
+        let api = json!({"problemId": " 7716029110012849011_1770701000000V2 "});
+        assert_eq!(notified_problem_id(&api).as_deref(), Some("7716029110012849011_1770701000000V2"));
+
+        assert_eq!(notified_problem_id(&json!({"PID": 99999})).as_deref(), Some("99999"));
+        assert_eq!(notified_problem_id(&json!({"PID": "", "ProblemID": "P-42"})), None);
+        assert_eq!(notified_problem_id(&json!(["PID"])), None);
+    }
+}
@@ -0,0 +1,90 @@
+use regex::Regex;
+use crate::config::{RecurrenceKey, RecurrenceSamplingConfig};
+use crate::dynatrace::Problem;
+use crate::error::{ForwarderError, Result};
+
+/// Decides which problems count as occurrences of the same recurring issue
+/// for a connector's `recurrence_sampling`
+#[derive(Debug)]
+pub struct RecurrenceSampler {
+    pub every: u64,
+    pub reset_after_seconds: i64,
+    match_on: Vec<RecurrenceKey>,
+    title_regex: Option<Regex>,
+}
+
+impl RecurrenceSampler {
+    pub fn new(connector_name: &str, config: &RecurrenceSamplingConfig) -> Result<Self> {
+        let title_regex = config
+            .title_regex
+            .as_deref()
+            .map(Regex::new)
+            .transpose()
+            .map_err(|e| {
+                ForwarderError::Config(format!(
+                    "Connector '{}' recurrence_sampling.title_regex is invalid: {}",
+                    connector_name, e
+                ))
+            })?;
+
+        Ok(Self {
+            every: config.every,
+            reset_after_seconds: config.reset_after_hours as i64 * 3600,
+            match_on: config.match_on.clone(),
+            title_regex,
+        })
+    }
+
+    /// Key shared by occurrences of the same issue, or `None` when the problem
+    /// is not sampled
+    pub fn key(&self, problem: &Problem) -> Option<String> {
+        if self.title_regex.as_ref().is_some_and(|regex| !regex.is_match(&problem.title)) {
+            return None;
+        }
+
+        let parts: Vec<String> = self
+            .match_on
+            .iter()
+            .map(|key| match key {
+                RecurrenceKey::Title => format!("title={}", problem.title),
+                RecurrenceKey::Entity => format!("entity={}", entity_id(problem)),
+            })
+            .collect();
+        Some(parts.join("|"))
+    }
+}
+
+/// Root cause entity, or the first affected entity without one
+fn entity_id(problem: &Problem) -> &str {
+    match (&problem.root_cause_entity, problem.affected_entities.first()) {
+        (Some(root_cause), _) => &root_cause.entity_id.id,
+        (None, Some(affected)) => &affected.entity_id.id,
+        (None, None) => "",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dynatrace::ProblemFixture;
+    use std::path::Path;
+
+    #[test]
+    fn test_recurrence_key() {
+        let mut problem = ProblemFixture::load(Path::new("tests/fixtures/problems/open_problem.json"))
+            .unwrap()
+            .remove(0);
+        let sampler = |yaml: &str| RecurrenceSampler::new("test", &serde_yaml::from_str(yaml).unwrap()).unwrap();
+
+        let by_both = sampler("every: 3");
+        let key = by_both.key(&problem).unwrap();
+        assert_eq!(key, format!("title={}|entity=HOST-0123456789ABCDEF", problem.title));
+
+        problem.root_cause_entity = None;
+        let fallback = problem.affected_entities.first().map(|e| e.entity_id.id.clone()).unwrap_or_default();
+        assert_eq!(by_both.key(&problem).unwrap(), format!("title={}|entity={}", problem.title, fallback));
+
+        let by_title = sampler("{every: 3, match_on: [title], title_regex: '^Nothing matches this$'}");
+        assert_eq!(by_title.key(&problem), None);
+    }
+}
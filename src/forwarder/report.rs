@@ -0,0 +1,148 @@
+use chrono::{DateTime, Utc};
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use crate::dynatrace::ApiQuota;
+use crate::error::Result;
+
+/// Machine-readable summary of one poll cycle, written when `cycle_reports` is enabled
+#[derive(Debug, Clone, Serialize)]
+pub struct CycleReport {
+    pub cycle_id: String,
+    pub started_at: DateTime<Utc>,
+    pub completed_at: Option<DateTime<Utc>>,
+    /// Problems returned by Dynatrace
+    pub fetched: usize,
+    pub new_problems: usize,
+    pub status_changes: usize,
+    pub skipped: usize,
+    /// Notifications handed to connectors, after correlation
+    pub forwarded: usize,
+    /// Notifications no connector's management zones or tag filter cover
+    pub unrouted: usize,
+    /// Problems not forwarded because a Dynatrace maintenance window covers them
+    pub in_maintenance: usize,
+    /// Status changes withheld because the problem is flapping
+    pub flaps_suppressed: usize,
+    /// Reminders for problems still open after `renotify.after_minutes`
+    pub renotified: usize,
+    /// Problems sent again after an earlier delivery failed
+    pub redelivered: usize,
+    /// Problems not forwarded because they started before `polling.max_problem_age_hours`
+    pub too_old: usize,
+    /// Problems skipped because they are muted from the CLI
+    pub muted: usize,
+    pub anomaly: bool,
+    /// Every configured connector, including those without deliveries this cycle
+    pub connectors: BTreeMap<String, ConnectorOutcome>,
+    /// Dynatrace API quota and rate-limited calls per environment
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    pub dynatrace_api: BTreeMap<String, ApiQuota>,
+    /// Why the cycle failed before problems could be processed
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Delivery results of one connector within a cycle
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct ConnectorOutcome {
+    /// Requests made (a batch counts once)
+    pub deliveries: usize,
+    pub succeeded: usize,
+    pub failed: usize,
+    pub shadow_failed: usize,
+    /// Problems covered by successful deliveries
+    pub problems_delivered: usize,
+    /// Problems queued for a later digest or held until active hours
+    pub problems_queued: usize,
+    /// Problems discarded outside active hours
+    pub problems_suppressed: usize,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub errors: Vec<String>,
+}
+
+/// Result of one delivery attempt, as recorded in forward history
+#[derive(Debug, Clone)]
+pub struct DeliveryOutcome {
+    pub connector: String,
+    /// `success`, `failed` or `shadow_failed`
+    pub status: &'static str,
+    pub problems: usize,
+    pub error: Option<String>,
+    /// Problems of the delivery, including correlated ones (empty for digests)
+    pub problem_ids: Vec<String>,
+}
+
+impl CycleReport {
+    pub fn start<'a>(connectors: impl IntoIterator<Item = &'a str>) -> Self {
+        Self {
+            cycle_id: uuid::Uuid::new_v4().to_string(),
+            started_at: Utc::now(),
+            completed_at: None,
+            fetched: 0,
+            new_problems: 0,
+            status_changes: 0,
+            skipped: 0,
+            forwarded: 0,
+            unrouted: 0,
+            in_maintenance: 0,
+            flaps_suppressed: 0,
+            renotified: 0,
+            redelivered: 0,
+            too_old: 0,
+            muted: 0,
+            anomaly: false,
+            connectors: connectors
+                .into_iter()
+                .map(|name| (name.to_string(), ConnectorOutcome::default()))
+                .collect(),
+            dynatrace_api: BTreeMap::new(),
+            error: None,
+        }
+    }
+
+    pub fn record(&mut self, outcome: DeliveryOutcome) {
+        let entry = self.connectors.entry(outcome.connector).or_default();
+        entry.deliveries += 1;
+        match outcome.status {
+            "success" => {
+                entry.succeeded += 1;
+                entry.problems_delivered += outcome.problems;
+            }
+            "shadow_failed" => entry.shadow_failed += 1,
+            _ => entry.failed += 1,
+        }
+        entry.errors.extend(outcome.error);
+    }
+
+    pub fn record_queued(&mut self, connector: &str, problems: usize) {
+        self.connectors.entry(connector.to_string()).or_default().problems_queued += problems;
+    }
+
+    pub fn record_suppressed(&mut self, connector: &str, problems: usize) {
+        self.connectors.entry(connector.to_string()).or_default().problems_suppressed += problems;
+    }
+
+    /// File name sorting in cycle order, e.g. `cycle-20260210T053000.123Z-<id>.json`
+    fn file_name(&self) -> String {
+        format!(
+            "cycle-{}-{}.json",
+            self.started_at.format("%Y%m%dT%H%M%S%.3fZ"),
+            self.cycle_id
+        )
+    }
+
+    /// Write the report into `directory`. The file is written under a temporary
+    /// name and renamed, so readers never see a partial report.
+    pub async fn write(&self, directory: &Path) -> Result<PathBuf> {
+        tokio::fs::create_dir_all(directory).await?;
+
+        let path = directory.join(self.file_name());
+        let partial = directory.join(format!(".{}.tmp", self.file_name()));
+
+        tokio::fs::write(&partial, serde_json::to_vec_pretty(self)?).await?;
+        tokio::fs::rename(&partial, &path).await?;
+
+        Ok(path)
+    }
+}
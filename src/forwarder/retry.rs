@@ -1,21 +1,80 @@
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use tokio::time::sleep;
-use tracing::{debug, warn};
+use crate::error::ForwarderError;
+use tracing::{debug, info, warn};
 
-/// Retry an operation with exponential backoff
-pub async fn retry_with_backoff<F, T, E>(
+/// Errors that know whether the failed operation is worth attempting again
+pub trait Retryable {
+    fn is_retryable(&self) -> bool;
+}
+
+impl Retryable for ForwarderError {
+    fn is_retryable(&self) -> bool {
+        ForwarderError::is_retryable(self)
+    }
+}
+
+/// Bounds for one retried operation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RetryPolicy {
+    pub max_attempts: u32,
+    /// Delay before the first retry; doubled for every further retry
+    pub base_delay: Duration,
+    /// Upper bound for a single delay
+    pub max_delay: Duration,
+    /// Give up once this much time has passed since the first attempt
+    pub max_elapsed: Duration,
+}
+
+impl RetryPolicy {
+    pub fn new(max_attempts: u32, max_delay: Duration, max_elapsed: Duration) -> Self {
+        Self {
+            max_attempts,
+            base_delay: Duration::from_secs(1),
+            max_delay,
+            max_elapsed,
+        }
+    }
+
+    /// Delay after the given (1-based) failed attempt: base * 2^(attempt-1), capped
+    pub fn delay(&self, attempt: u32) -> Duration {
+        let factor = 2_u32.saturating_pow(attempt.saturating_sub(1));
+        self.base_delay.saturating_mul(factor).min(self.max_delay)
+    }
+}
+
+/// What retrying one operation cost
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct RetryMetrics {
+    pub attempts: u32,
+    /// Time spent waiting between attempts
+    pub total_delay: Duration,
+    /// The elapsed-time budget ran out before the attempts did
+    pub budget_exhausted: bool,
+}
+
+/// Retry an operation with capped exponential backoff. Errors that are not
+/// retryable (authentication, configuration, permanent rejections) are
+/// returned immediately, and no retry is started that would end after the
+/// policy's elapsed-time budget. Returns the result with the retry metrics,
+/// which are also logged when a retry happened.
+pub async fn retry_with_backoff<'a, F, T, E>(
     operation_name: &str,
-    max_attempts: u32,
+    policy: &RetryPolicy,
     mut operation: F,
-) -> Result<T, E>
+) -> (Result<T, E>, RetryMetrics)
 where
-    F: FnMut() -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<T, E>> + Send>>,
+    F: FnMut() -> std::pin::Pin<Box<dyn std::future::Future<Output = Result<T, E>> + Send + 'a>>,
+    E: Retryable,
 {
-    let mut attempt = 0;
+    let max_attempts = policy.max_attempts;
+    let started = Instant::now();
+    let mut metrics = RetryMetrics::default();
+
+    let result = loop {
+        metrics.attempts += 1;
+        let attempt = metrics.attempts;
 
-    loop {
-        attempt += 1;
-        
         match operation().await {
             Ok(result) => {
                 if attempt > 1 {
@@ -24,50 +83,104 @@ where
                         operation_name, attempt, max_attempts
                     );
                 }
-                return Ok(result);
+                break Ok(result);
             }
-            Err(_e) if attempt < max_attempts => {
-                let delay_secs = 2_u64.pow(attempt - 1);
+            Err(e) if !e.is_retryable() => {
+                warn!(
+                    "Operation '{}' failed with a non-retryable error (attempt {}/{})",
+                    operation_name, attempt, max_attempts
+                );
+                break Err(e);
+            }
+            Err(e) if attempt < max_attempts => {
+                let delay = policy.delay(attempt);
+                if started.elapsed() + delay > policy.max_elapsed {
+                    warn!(
+                        "Operation '{}' failed (attempt {}/{}), retry budget of {}s exhausted",
+                        operation_name,
+                        attempt,
+                        max_attempts,
+                        policy.max_elapsed.as_secs()
+                    );
+                    metrics.budget_exhausted = true;
+                    break Err(e);
+                }
+
                 warn!(
                     "Operation '{}' failed (attempt {}/{}), retrying in {}s...",
-                    operation_name, attempt, max_attempts, delay_secs
+                    operation_name,
+                    attempt,
+                    max_attempts,
+                    delay.as_secs_f64()
                 );
-                sleep(Duration::from_secs(delay_secs)).await;
+                sleep(delay).await;
+                metrics.total_delay += delay;
             }
             Err(e) => {
                 warn!(
                     "Operation '{}' failed after {} attempts",
                     operation_name, max_attempts
                 );
-                return Err(e);
+                break Err(e);
             }
         }
+    };
+
+    if metrics.attempts > 1 {
+        info!(
+            operation = operation_name,
+            attempts = metrics.attempts,
+            total_delay_ms = metrics.total_delay.as_millis() as u64,
+            budget_exhausted = metrics.budget_exhausted,
+            success = result.is_ok(),
+            "Retry metrics"
+        );
     }
+
+    (result, metrics)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use reqwest::StatusCode;
+
+    impl Retryable for &str {
+        fn is_retryable(&self) -> bool {
+            true
+        }
+    }
+
+    fn fast_policy(max_attempts: u32) -> RetryPolicy {
+        RetryPolicy {
+            max_attempts,
+            base_delay: Duration::from_millis(1),
+            max_delay: Duration::from_millis(10),
+            max_elapsed: Duration::from_secs(60),
+        }
+    }
 
     #[tokio::test]
     async fn test_retry_success_first_attempt() {
         let mut call_count = 0;
         
-        let result = retry_with_backoff("test", 3, || {
+        let (result, metrics) = retry_with_backoff("test", &fast_policy(3), || {
             call_count += 1;
-            Box::pin(async { Ok::<_, String>(42) })
+            Box::pin(async { Ok::<_, &str>(42) })
         })
         .await;
 
         assert_eq!(result, Ok(42));
         assert_eq!(call_count, 1);
+        assert_eq!(metrics.attempts, 1);
+        assert_eq!(metrics.total_delay, Duration::ZERO);
     }
 
     #[tokio::test]
     async fn test_retry_success_after_failures() {
         let mut call_count = 0;
         
-        let result = retry_with_backoff("test", 3, || {
+        let (result, metrics) = retry_with_backoff("test", &fast_policy(3), || {
             call_count += 1;
             Box::pin(async move {
                 if call_count < 3 {
@@ -81,13 +194,14 @@ mod tests {
 
         assert_eq!(result, Ok(42));
         assert_eq!(call_count, 3);
+        assert_eq!(metrics.total_delay, Duration::from_millis(3));
     }
 
     #[tokio::test]
     async fn test_retry_all_failures() {
         let mut call_count = 0;
         
-        let result = retry_with_backoff("test", 3, || {
+        let (result, metrics) = retry_with_backoff("test", &fast_policy(3), || {
             call_count += 1;
             Box::pin(async { Err::<i32, _>("permanent failure") })
         })
@@ -95,5 +209,53 @@ mod tests {
 
         assert_eq!(result, Err("permanent failure"));
         assert_eq!(call_count, 3);
+        assert!(!metrics.budget_exhausted);
+    }
+
+    #[tokio::test]
+    async fn test_retry_stops_on_permanent_error() {
+        let mut call_count = 0;
+
+        let (result, metrics) = retry_with_backoff("test", &fast_policy(3), || {
+            call_count += 1;
+            Box::pin(async {
+                Err::<i32, _>(ForwarderError::from_status("hook", StatusCode::BAD_REQUEST, "invalid"))
+            })
+        })
+        .await;
+
+        assert!(result.is_err());
+        assert_eq!(call_count, 1);
+        assert_eq!(metrics.attempts, 1);
+    }
+
+    #[test]
+    fn test_delay_is_capped() {
+        let policy = RetryPolicy::new(30, Duration::from_secs(60), Duration::from_secs(300));
+
+        assert_eq!(policy.delay(1), Duration::from_secs(1));
+        assert_eq!(policy.delay(4), Duration::from_secs(8));
+        assert_eq!(policy.delay(7), Duration::from_secs(60));
+        assert_eq!(policy.delay(40), Duration::from_secs(60));
+    }
+
+    #[tokio::test]
+    async fn test_retry_stops_when_budget_exhausted() {
+        let mut call_count = 0;
+        let policy = RetryPolicy {
+            max_elapsed: Duration::from_millis(5),
+            ..fast_policy(100)
+        };
+
+        let (result, metrics) = retry_with_backoff("test", &policy, || {
+            call_count += 1;
+            Box::pin(async { Err::<i32, _>("still failing") })
+        })
+        .await;
+
+        assert_eq!(result, Err("still failing"));
+        assert!(metrics.budget_exhausted);
+        assert!(call_count < 100);
+        assert_eq!(metrics.attempts, call_count);
     }
 }
@@ -0,0 +1,179 @@
+use regex::Regex;
+use std::collections::BTreeSet;
+use tracing::debug;
+use crate::config::{RoutingConfig, RoutingRule};
+use crate::dynatrace::Problem;
+use crate::error::{ForwarderError, Result};
+use crate::forwarder::tag_filter::TagFilter;
+
+/// Chooses the connectors for each problem from the `routing` rules
+pub struct Router {
+    rules: Vec<CompiledRule>,
+    default: Vec<String>,
+}
+
+struct CompiledRule {
+    rule: RoutingRule,
+    label: String,
+    title_regex: Option<Regex>,
+    root_cause_name_regex: Option<Regex>,
+    tag: Option<TagFilter>,
+}
+
+impl Router {
+    pub fn new(config: &RoutingConfig) -> Result<Self> {
+        let rules = config
+            .rules
+            .iter()
+            .enumerate()
+            .map(|(index, rule)| {
+                let label = rule.label(index);
+                let title_regex = rule
+                    .title_regex
+                    .as_deref()
+                    .map(Regex::new)
+                    .transpose()
+                    .map_err(|e| ForwarderError::Config(format!("Invalid title_regex in routing rule '{}': {}", label, e)))?;
+                let root_cause_name_regex = rule
+                    .root_cause_name_regex
+                    .as_deref()
+                    .map(Regex::new)
+                    .transpose()
+                    .map_err(|e| {
+                        ForwarderError::Config(format!("Invalid root_cause_name_regex in routing rule '{}': {}", label, e))
+                    })?;
+                let tag = rule
+                    .tag
+                    .as_deref()
+                    .map(TagFilter::parse)
+                    .transpose()
+                    .map_err(|e| ForwarderError::Config(format!("Invalid tag in routing rule '{}': {}", label, e)))?;
+
+                Ok(CompiledRule {
+                    rule: rule.clone(),
+                    label,
+                    title_regex,
+                    root_cause_name_regex,
+                    tag,
+                })
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self {
+            rules,
+            default: config.default.clone(),
+        })
+    }
+
+    /// Names of the connectors this problem is routed to: those of every
+    /// matching rule up to the first matching `stop` rule, or the defaults when
+    /// no rule matches
+    pub fn connectors(&self, problem: &Problem) -> BTreeSet<String> {
+        let mut connectors = BTreeSet::new();
+        let mut matched = false;
+
+        for compiled in &self.rules {
+            if !compiled.matches(problem) {
+                continue;
+            }
+            debug!("Problem {} matches routing rule '{}'", problem.problem_id, compiled.label);
+            matched = true;
+            connectors.extend(compiled.rule.connectors.iter().cloned());
+            if compiled.rule.stop {
+                break;
+            }
+        }
+
+        if !matched {
+            connectors.extend(self.default.iter().cloned());
+        }
+        connectors
+    }
+}
+
+impl CompiledRule {
+    fn matches(&self, problem: &Problem) -> bool {
+        let rule = &self.rule;
+        let listed = |list: &[String], value: &str| list.is_empty() || list.iter().any(|item| item == value);
+
+        listed(&rule.severity, &problem.severity_level)
+            && listed(&rule.impact_level, &problem.impact_level)
+            && (rule.management_zones.is_empty()
+                || problem
+                    .management_zones
+                    .iter()
+                    .any(|zone| rule.management_zones.iter().any(|wanted| *wanted == zone.name || *wanted == zone.id)))
+            && self.tag.as_ref().is_none_or(|tag| tag.matches(&problem.entity_tags))
+            && self.title_regex.as_ref().is_none_or(|regex| regex.is_match(&problem.title))
+            && self.matches_root_cause(problem)
+    }
+
+    /// Problems without a root cause entity never match root cause criteria
+    fn matches_root_cause(&self, problem: &Problem) -> bool {
+        if self.rule.root_cause_type.is_empty() && self.root_cause_name_regex.is_none() {
+            return true;
+        }
+
+        problem.root_cause_entity.as_ref().is_some_and(|entity| {
+            (self.rule.root_cause_type.is_empty()
+                || self.rule.root_cause_type.contains(&entity.entity_id.entity_type))
+                && self.root_cause_name_regex.as_ref().is_none_or(|regex| regex.is_match(&entity.name))
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dynatrace::ProblemFixture;
+    use std::path::Path;
+
+    #[test]
+    fn test_rules_in_order_with_stop_and_default() {
+        let problem = ProblemFixture::load(Path::new("tests/fixtures/problems/open_problem.json"))
+            .unwrap()
+            .remove(0);
+        let router = |yaml: &str| Router::new(&serde_yaml::from_str(yaml).unwrap()).unwrap();
+
+        let routed = router(&format!(
+            r#"
+rules:
+  - {{ severity: [AVAILABILITY], connectors: [pager] }}
+  - {{ impact_level: ["{impact}"], connectors: [team-a] }}
+  - {{ title_regex: ".", connectors: [team-b], stop: true }}
+  - {{ connectors: [never] }}
+default: [catch-all]
+"#,
+            impact = problem.impact_level
+        ))
+        .connectors(&problem);
+        assert_eq!(routed.into_iter().collect::<Vec<_>>(), ["team-a", "team-b"]);
+
+        let unmatched = router("{rules: [{severity: [NONE], connectors: [pager]}], default: [catch-all]}");
+        assert_eq!(unmatched.connectors(&problem).into_iter().collect::<Vec<_>>(), ["catch-all"]);
+    }
+
+    #[test]
+    fn test_root_cause_rules() {
+        let mut problem = ProblemFixture::load(Path::new("tests/fixtures/problems/open_problem.json"))
+            .unwrap()
+            .remove(0);
+        let router = Router::new(
+            &serde_yaml::from_str(
+                r#"
+rules:
+  - { root_cause_type: [PROCESS_GROUP_INSTANCE, DATABASE_CONNECTION_FAILURE], connectors: [dba] }
+  - { root_cause_type: [HOST], root_cause_name_regex: "\\.prod\\.", connectors: [infra] }
+default: [catch-all]
+"#,
+            )
+            .unwrap(),
+        )
+        .unwrap();
+
+        assert_eq!(router.connectors(&problem).into_iter().collect::<Vec<_>>(), ["infra"]);
+
+        problem.root_cause_entity = None;
+        assert_eq!(router.connectors(&problem).into_iter().collect::<Vec<_>>(), ["catch-all"]);
+    }
+}
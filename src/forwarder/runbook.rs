@@ -0,0 +1,97 @@
+use regex::Regex;
+use crate::config::RunbookRule;
+use crate::dynatrace::models::Runbook;
+use crate::dynatrace::Problem;
+use crate::error::{ForwarderError, Result};
+use crate::forwarder::tag_filter::tag_matches;
+
+/// Matches problems against the configured runbook rules
+pub struct RunbookMatcher {
+    rules: Vec<(RunbookRule, Option<Regex>)>,
+}
+
+impl RunbookMatcher {
+    pub fn new(rules: &[RunbookRule]) -> Result<Self> {
+        let rules = rules
+            .iter()
+            .map(|rule| {
+                let title_regex = rule
+                    .title_regex
+                    .as_deref()
+                    .map(Regex::new)
+                    .transpose()
+                    .map_err(|e| ForwarderError::Config(format!("Invalid runbook title_regex: {}", e)))?;
+                Ok((rule.clone(), title_regex))
+            })
+            .collect::<Result<_>>()?;
+
+        Ok(Self { rules })
+    }
+
+    /// Set the problem's runbooks to the links of all matching rules, in configuration order
+    pub fn attach(&self, problem: &mut Problem) {
+        problem.runbooks.clear();
+
+        for (rule, title_regex) in &self.rules {
+            if matches(rule, title_regex.as_ref(), problem)
+                && !problem.runbooks.iter().any(|runbook| runbook.url == rule.url)
+            {
+                problem.runbooks.push(Runbook {
+                    name: rule.name.clone(),
+                    url: rule.url.clone(),
+                });
+            }
+        }
+    }
+}
+
+fn matches(rule: &RunbookRule, title_regex: Option<&Regex>, problem: &Problem) -> bool {
+    let tag_matches = rule.tag.as_ref().is_none_or(|wanted| {
+        problem
+            .entity_tags
+            .iter()
+            .any(|tag| tag_matches(tag, wanted))
+    });
+
+    let zone_matches = rule
+        .management_zone
+        .as_ref()
+        .is_none_or(|wanted| problem.management_zones.iter().any(|zone| &zone.name == wanted));
+
+    let title_matches = title_regex.is_none_or(|regex| regex.is_match(&problem.title));
+
+    tag_matches && zone_matches && title_matches
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dynatrace::ProblemFixture;
+    use std::path::Path;
+
+    fn rule(yaml: &str) -> RunbookRule {
+        serde_yaml::from_str(yaml).unwrap()
+    }
+
+    #[test]
+    fn test_attach_matching_runbooks() {
+        let mut problem = ProblemFixture::load(Path::new("tests/fixtures/problems/open_problem.json"))
+            .unwrap()
+            .remove(0);
+
+        let matcher = RunbookMatcher::new(&[
+            rule("{url: 'https://wiki/disk', name: Disk, title_regex: '(?i)disk space'}"),
+            rule("{url: 'https://wiki/prod', management_zone: Production}"),
+            rule("{url: 'https://wiki/payments', tag: 'team:payments', title_regex: '^Low'}"),
+            rule("{url: 'https://wiki/other', management_zone: Staging}"),
+            rule("{url: 'https://wiki/disk', tag: missing}"),
+        ])
+        .unwrap();
+
+        matcher.attach(&mut problem);
+
+        let urls: Vec<&str> = problem.runbooks.iter().map(|r| r.url.as_str()).collect();
+        assert_eq!(urls, ["https://wiki/disk", "https://wiki/prod", "https://wiki/payments"]);
+        assert_eq!(problem.runbooks[0].name.as_deref(), Some("Disk"));
+    }
+}
@@ -0,0 +1,174 @@
+use crate::config::{ConnectorConfig, PayloadFormat};
+use crate::dynatrace::{Problem, ProblemStatus};
+use crate::forwarder::payload;
+
+const VENDOR: &str = "Dynatrace";
+const PRODUCT: &str = "Problem Forwarder";
+
+/// Render problems as SIEM lines (one per problem, newline separated), or
+/// `None` for the JSON and XML formats
+pub fn lines(problems: &[Problem], connector: &ConnectorConfig, problem_url_base: &str) -> Option<String> {
+    let render: fn(&Problem, &ConnectorConfig, &str) -> String = match connector.format {
+        PayloadFormat::Json | PayloadFormat::Xml | PayloadFormat::Form | PayloadFormat::Query => return None,
+        PayloadFormat::Cef => cef_line,
+        PayloadFormat::Leef => leef_line,
+    };
+
+    Some(
+        problems
+            .iter()
+            .map(|problem| render(problem, connector, problem_url_base))
+            .collect::<Vec<_>>()
+            .join("\n"),
+    )
+}
+
+/// ArcSight Common Event Format (CEF:0) line for one problem
+pub fn cef_line(problem: &Problem, connector: &ConnectorConfig, problem_url_base: &str) -> String {
+    let mut extension = vec![
+        ("externalId", problem.problem_id.clone()),
+        ("rt", problem.start_time.to_string()),
+        ("outcome", problem.status.to_string()),
+        ("cat", problem.impact_level.clone()),
+        ("request", problem.url(problem_url_base)),
+        ("cs1Label", "displayId".to_string()),
+        ("cs1", problem.display_id.clone()),
+    ];
+
+    if problem.end_time > 0 {
+        extension.push(("end", problem.end_time.to_string()));
+    }
+    if !problem.management_zones.is_empty() {
+        extension.push(("cs2Label", "managementZones".to_string()));
+        extension.push(("cs2", problem.management_zone_names().join(",")));
+    }
+    if let Some(root_cause) = &problem.root_cause_entity {
+        extension.push(("cs3Label", "rootCause".to_string()));
+        extension.push(("cs3", root_cause.name.clone()));
+    }
+    if let Some(labels) = connector.labels.as_ref().map(|config| payload::build_labels(problem, config)) {
+        if !labels.is_empty() {
+            let labels: Vec<String> = labels.iter().map(|(key, value)| format!("{}:{}", key, value)).collect();
+            extension.push(("cs4Label", "labels".to_string()));
+            extension.push(("cs4", labels.join(",")));
+        }
+    }
+    if !problem.correlated_problems.is_empty() {
+        extension.push(("cs5Label", "correlatedProblems".to_string()));
+        extension.push(("cs5", correlated_ids(problem)));
+    }
+
+    let extension: Vec<String> = extension
+        .into_iter()
+        .map(|(key, value)| format!("{}={}", key, cef_extension_escape(&value)))
+        .collect();
+
+    format!(
+        "CEF:0|{}|{}|{}|{}|{}|{}|{}",
+        VENDOR,
+        PRODUCT,
+        env!("CARGO_PKG_VERSION"),
+        cef_header_escape(&problem.severity_level),
+        cef_header_escape(&problem.title),
+        severity(problem),
+        extension.join(" ")
+    )
+}
+
+/// IBM QRadar Log Event Extended Format (LEEF:1.0, tab separated) line for one problem
+pub fn leef_line(problem: &Problem, connector: &ConnectorConfig, problem_url_base: &str) -> String {
+    let mut attributes = vec![
+        ("devTime".to_string(), problem.start_time.to_string()),
+        ("sev".to_string(), severity(problem).to_string()),
+        ("cat".to_string(), problem.impact_level.clone()),
+        ("problemId".to_string(), problem.problem_id.clone()),
+        ("displayId".to_string(), problem.display_id.clone()),
+        ("title".to_string(), problem.title.clone()),
+        ("status".to_string(), problem.status.to_string()),
+        ("url".to_string(), problem.url(problem_url_base)),
+    ];
+
+    if problem.end_time > 0 {
+        attributes.push(("endTime".to_string(), problem.end_time.to_string()));
+    }
+    if !problem.management_zones.is_empty() {
+        attributes.push(("managementZones".to_string(), problem.management_zone_names().join(",")));
+    }
+    if let Some(root_cause) = &problem.root_cause_entity {
+        attributes.push(("rootCause".to_string(), root_cause.name.clone()));
+    }
+    if !problem.correlated_problems.is_empty() {
+        attributes.push(("correlatedProblems".to_string(), correlated_ids(problem)));
+    }
+    if let Some(labels) = &connector.labels {
+        attributes.extend(payload::build_labels(problem, labels));
+    }
+
+    let attributes: Vec<String> = attributes
+        .into_iter()
+        .map(|(key, value)| format!("{}={}", key, leef_value_escape(&value)))
+        .collect();
+
+    format!(
+        "LEEF:1.0|{}|{}|{}|{}|{}",
+        VENDOR,
+        PRODUCT,
+        env!("CARGO_PKG_VERSION"),
+        cef_header_escape(&problem.severity_level),
+        attributes.join("\t")
+    )
+}
+
+fn correlated_ids(problem: &Problem) -> String {
+    problem
+        .correlated_problems
+        .iter()
+        .map(|child| child.display_id.as_str())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// 0-10 severity from the Dynatrace severity level; closed problems report the lowest
+fn severity(problem: &Problem) -> u8 {
+    if problem.status != ProblemStatus::Open {
+        return 1;
+    }
+
+    match problem.severity_level.as_str() {
+        "AVAILABILITY" => 10,
+        "ERROR" => 8,
+        "PERFORMANCE" | "RESOURCE_CONTENTION" => 6,
+        "CUSTOM_ALERT" => 5,
+        "MONITORING_UNAVAILABLE" => 4,
+        _ => 3,
+    }
+}
+
+fn cef_header_escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('|', "\\|").replace(['\r', '\n'], " ")
+}
+
+fn cef_extension_escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('=', "\\=")
+        .replace('\r', "\\r")
+        .replace('\n', "\\n")
+}
+
+/// LEEF has no escape sequences; the attribute delimiter and line breaks become spaces
+fn leef_value_escape(value: &str) -> String {
+    value.replace(['\t', '\r', '\n'], " ")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_cef_escaping() {
+        assert_eq!(cef_header_escape("a|b\\c"), "a\\|b\\\\c");
+        assert_eq!(cef_extension_escape("x=1\ny\\z"), "x\\=1\\ny\\\\z");
+        assert_eq!(leef_value_escape("a\tb\nc"), "a b c");
+    }
+}
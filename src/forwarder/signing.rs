@@ -0,0 +1,35 @@
+use hmac::{Hmac, Mac};
+use sha2::Sha256;
+use std::fmt::Write;
+use crate::error::{ForwarderError, Result};
+
+/// Header value for a request body: `sha256=` followed by the lowercase hex
+/// HMAC-SHA256 of the exact bytes sent
+pub fn signature(secret: &str, body: &[u8]) -> Result<String> {
+    let mut mac = Hmac::<Sha256>::new_from_slice(secret.as_bytes())
+        .map_err(|e| ForwarderError::Config(format!("Invalid signing secret: {}", e)))?;
+    mac.update(body);
+
+    Ok(mac
+        .finalize()
+        .into_bytes()
+        .iter()
+        .fold(String::from("sha256="), |mut hex, byte| {
+            let _ = write!(hex, "{:02x}", byte);
+            hex
+        }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_signature_matches_rfc4231_vector() {
+        // RFC 4231 test case 2
+        assert_eq!(
+            signature("Jefe", b"what do ya want for nothing?").unwrap(),
+            "sha256=5bdcc146bf60754e6a042426089575c75a003f089d2739839dec58b964ec3843"
+        );
+    }
+}
@@ -0,0 +1,202 @@
+use crate::dynatrace::models::EntityTag;
+
+/// Boolean expression over entity tags, e.g. `env:prod AND (team:payments OR team:checkout)`
+#[derive(Debug, Clone, PartialEq)]
+pub enum TagFilter {
+    /// `key` or `key:value`
+    Tag(String),
+    Not(Box<TagFilter>),
+    And(Box<TagFilter>, Box<TagFilter>),
+    Or(Box<TagFilter>, Box<TagFilter>),
+}
+
+impl TagFilter {
+    /// Parse an expression. `AND` binds tighter than `OR`; operators are case
+    /// insensitive and tags containing spaces can be double-quoted.
+    pub fn parse(expression: &str) -> Result<Self, String> {
+        let tokens = tokenize(expression)?;
+        let mut parser = Parser { tokens, position: 0 };
+        let filter = parser.or()?;
+
+        match parser.tokens.get(parser.position) {
+            None => Ok(filter),
+            Some(token) => Err(format!("unexpected '{}' in tag filter '{}'", token.text(), expression)),
+        }
+    }
+
+    /// Whether a problem with these tags matches
+    pub fn matches(&self, tags: &[EntityTag]) -> bool {
+        match self {
+            TagFilter::Tag(wanted) => tags.iter().any(|tag| tag_matches(tag, wanted)),
+            TagFilter::Not(inner) => !inner.matches(tags),
+            TagFilter::And(left, right) => left.matches(tags) && right.matches(tags),
+            TagFilter::Or(left, right) => left.matches(tags) || right.matches(tags),
+        }
+    }
+}
+
+/// `key` matches any value of that tag, `key:value` one value (ignoring the tag context)
+pub fn tag_matches(tag: &EntityTag, wanted: &str) -> bool {
+    match (wanted.split_once(':'), &tag.value) {
+        (None, _) => tag.key == wanted,
+        (Some((key, value)), Some(tag_value)) => tag.key == key && tag_value == value,
+        (Some(_), None) => false,
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Token {
+    Tag(String),
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+}
+
+impl Token {
+    fn text(&self) -> &str {
+        match self {
+            Token::Tag(tag) => tag,
+            Token::And => "AND",
+            Token::Or => "OR",
+            Token::Not => "NOT",
+            Token::Open => "(",
+            Token::Close => ")",
+        }
+    }
+}
+
+fn tokenize(expression: &str) -> Result<Vec<Token>, String> {
+    let mut tokens = Vec::new();
+    let mut chars = expression.chars().peekable();
+
+    while let Some(&c) = chars.peek() {
+        match c {
+            c if c.is_whitespace() => {
+                chars.next();
+            }
+            '(' | ')' => {
+                chars.next();
+                tokens.push(if c == '(' { Token::Open } else { Token::Close });
+            }
+            '"' => {
+                chars.next();
+                let quoted: String = chars.by_ref().take_while(|c| *c != '"').collect();
+                tokens.push(Token::Tag(quoted));
+            }
+            _ => {
+                let mut word = String::new();
+                while let Some(&c) = chars.peek() {
+                    if c.is_whitespace() || c == '(' || c == ')' {
+                        break;
+                    }
+                    word.push(c);
+                    chars.next();
+                }
+                tokens.push(match word.to_ascii_uppercase().as_str() {
+                    "AND" => Token::And,
+                    "OR" => Token::Or,
+                    "NOT" => Token::Not,
+                    _ => Token::Tag(word),
+                });
+            }
+        }
+    }
+
+    if tokens.is_empty() {
+        return Err("tag filter is empty".to_string());
+    }
+    Ok(tokens)
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    position: usize,
+}
+
+impl Parser {
+    fn next_if(&mut self, token: &Token) -> bool {
+        let found = self.tokens.get(self.position) == Some(token);
+        if found {
+            self.position += 1;
+        }
+        found
+    }
+
+    fn or(&mut self) -> Result<TagFilter, String> {
+        let mut filter = self.and()?;
+        while self.next_if(&Token::Or) {
+            filter = TagFilter::Or(Box::new(filter), Box::new(self.and()?));
+        }
+        Ok(filter)
+    }
+
+    fn and(&mut self) -> Result<TagFilter, String> {
+        let mut filter = self.not()?;
+        while self.next_if(&Token::And) {
+            filter = TagFilter::And(Box::new(filter), Box::new(self.not()?));
+        }
+        Ok(filter)
+    }
+
+    fn not(&mut self) -> Result<TagFilter, String> {
+        if self.next_if(&Token::Not) {
+            return Ok(TagFilter::Not(Box::new(self.not()?)));
+        }
+        self.primary()
+    }
+
+    fn primary(&mut self) -> Result<TagFilter, String> {
+        let token = self.tokens.get(self.position).cloned();
+        self.position += 1;
+
+        match token {
+            Some(Token::Tag(tag)) => Ok(TagFilter::Tag(tag)),
+            Some(Token::Open) => {
+                let filter = self.or()?;
+                if !self.next_if(&Token::Close) {
+                    return Err("missing ')' in tag filter".to_string());
+                }
+                Ok(filter)
+            }
+            Some(token) => Err(format!("expected a tag before '{}'", token.text())),
+            None => Err("tag filter ends after an operator".to_string()),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn tag(key: &str, value: Option<&str>) -> EntityTag {
+        EntityTag {
+            context: "CONTEXTLESS".to_string(),
+            key: key.to_string(),
+            value: value.map(str::to_string),
+            string_representation: match value {
+                Some(value) => format!("{}:{}", key, value),
+                None => key.to_string(),
+            },
+        }
+    }
+
+    #[test]
+    fn test_parse_and_match_expressions() {
+        let tags = [tag("env", Some("prod")), tag("team", Some("payments")), tag("critical", None)];
+        let matches = |expression: &str| TagFilter::parse(expression).unwrap().matches(&tags);
+
+        assert!(matches("env:prod AND team:payments"));
+        assert!(!matches("env:prod AND team:search"));
+        assert!(matches("team:search OR critical"));
+        assert!(matches("env:prod and not (team:search or team:checkout)"));
+        assert!(!matches("NOT env:prod OR team:search AND critical"));
+        assert!(matches("\"env:prod\""));
+
+        assert!(TagFilter::parse("env:prod AND").is_err());
+        assert!(TagFilter::parse("(env:prod").is_err());
+        assert!(TagFilter::parse("env:prod team:payments").is_err());
+        assert!(TagFilter::parse("  ").is_err());
+    }
+}
@@ -0,0 +1,237 @@
+use chrono::{SecondsFormat, TimeZone};
+use chrono_tz::Tz;
+use handlebars::{
+    handlebars_helper, Context, Handlebars, Helper, HelperResult, Output, RenderContext, RenderError,
+};
+use serde_json::{json, Value};
+use crate::config::ConnectorConfig;
+use crate::dynatrace::{Problem, ProblemStatus};
+use crate::error::{ForwarderError, Result};
+use crate::forwarder::payload;
+use crate::utils::time::humanize_duration;
+
+/// Render the webhook body from the connector's template for this mode (the
+/// `close` template for a problem that is no longer open, when set), or `None`
+/// when the connector has no template for it. The output must be JSON.
+pub fn render(
+    problems: &[Problem],
+    batch: bool,
+    config: &ConnectorConfig,
+    problem_url_base: &str,
+) -> Result<Option<Value>> {
+    let Some(templates) = &config.template else {
+        return Ok(None);
+    };
+    let closed = !batch && problems[0].status != ProblemStatus::Open;
+    let source = match (batch, closed) {
+        (true, _) => &templates.batch,
+        (false, true) if templates.close.is_some() => &templates.close,
+        (false, _) => &templates.single,
+    };
+    let Some(source) = source else {
+        return Ok(None);
+    };
+
+    let mut contexts = problems
+        .iter()
+        .map(|problem| problem_context(problem, config, problem_url_base))
+        .collect::<Result<Vec<_>>>()?;
+    let context = if batch {
+        json!({ "problems": contexts, "count": problems.len() })
+    } else {
+        contexts.remove(0)
+    };
+
+    let rendered = registry(config.timezone())
+        .render_template(source, &context)
+        .map_err(|e| template_error(config, e))?;
+
+    serde_json::from_str(&rendered).map(Some).map_err(|e| {
+        ForwarderError::Config(format!(
+            "Connector '{}' template did not render valid JSON: {}",
+            config.name, e
+        ))
+    })
+}
+
+/// Render a plain-text template for one problem, without JSON escaping (form
+/// and query field values)
+pub fn render_text(source: &str, problem: &Problem, config: &ConnectorConfig, problem_url_base: &str) -> Result<String> {
+    let context = problem_context(problem, config, problem_url_base)?;
+
+    let mut registry = registry(config.timezone());
+    registry.register_escape_fn(handlebars::no_escape);
+    registry
+        .render_template(source, &context)
+        .map_err(|e| template_error(config, e))
+}
+
+/// The problem JSON sent without a template, plus `problemUrl`
+fn problem_context(problem: &Problem, config: &ConnectorConfig, problem_url_base: &str) -> Result<Value> {
+    let mut context = payload::problem_payload(problem, config, problem_url_base)?;
+    if let Value::Object(map) = &mut context {
+        map.insert(
+            "problemUrl".to_string(),
+            Value::String(problem.url(problem_url_base)),
+        );
+    }
+    Ok(context)
+}
+
+/// Registry with JSON string escaping for `{{value}}` and the built-in helpers
+fn registry(tz: Tz) -> Handlebars<'static> {
+    let mut registry = Handlebars::new();
+    registry.set_strict_mode(false);
+    registry.register_escape_fn(json_escape);
+
+    registry.register_helper("json", Box::new(json_helper));
+    registry.register_helper("severity_emoji", Box::new(severity_emoji_helper));
+    registry.register_helper("severity_color", Box::new(severity_color_helper));
+    registry.register_helper("truncate", Box::new(truncate_helper));
+    registry.register_helper("markdown_escape", Box::new(markdown_escape_helper));
+    registry.register_helper("html_escape", Box::new(html_escape_helper));
+    registry.register_helper("duration", Box::new(duration_helper));
+    registry.register_helper(
+        "iso_time",
+        Box::new(
+            move |h: &Helper, _: &Handlebars, _: &Context, _: &mut RenderContext, out: &mut dyn Output| -> HelperResult {
+                if let Some(time) = helper_time(h, tz) {
+                    out.write(&time.to_rfc3339_opts(SecondsFormat::Millis, false))?;
+                }
+                Ok(())
+            },
+        ),
+    );
+    registry.register_helper(
+        "format_time",
+        Box::new(
+            move |h: &Helper, _: &Handlebars, _: &Context, _: &mut RenderContext, out: &mut dyn Output| -> HelperResult {
+                let format = h
+                    .param(1)
+                    .and_then(|param| param.value().as_str())
+                    .ok_or_else(|| RenderError::new("format_time requires a format string, e.g. \"%Y-%m-%d %H:%M\""))?;
+                if let Some(time) = helper_time(h, tz) {
+                    out.write(&time.format(format).to_string())?;
+                }
+                Ok(())
+            },
+        ),
+    );
+
+    registry
+}
+
+/// Epoch-millisecond first parameter in `tz`; `None` for -1 (still open) or non-numbers
+fn helper_time(h: &Helper, tz: Tz) -> Option<chrono::DateTime<Tz>> {
+    let millis = h.param(0)?.value().as_i64().filter(|millis| *millis >= 0)?;
+    tz.timestamp_millis_opt(millis).single()
+}
+
+/// `{{{json value}}}` embeds any value as JSON
+fn json_helper(h: &Helper, _: &Handlebars, _: &Context, _: &mut RenderContext, out: &mut dyn Output) -> HelperResult {
+    let value = h.param(0).map(|param| param.value().clone()).unwrap_or(Value::Null);
+    out.write(&value.to_string())?;
+    Ok(())
+}
+
+handlebars_helper!(severity_emoji_helper: |severity: str| severity_emoji(severity));
+handlebars_helper!(severity_color_helper: |severity: str| severity_color(severity));
+handlebars_helper!(truncate_helper: |text: str, max: u64| truncate(text, max as usize));
+handlebars_helper!(markdown_escape_helper: |text: str| markdown_escape(text));
+handlebars_helper!(html_escape_helper: |text: str| html_escape(text));
+
+// `{{duration startTime endTime}}`; an open problem (end -1 or omitted) is measured until now
+handlebars_helper!(duration_helper: |start: i64, *args| {
+    let end = args
+        .get(1)
+        .and_then(|end| end.as_i64())
+        .filter(|end| *end >= 0)
+        .unwrap_or_else(|| chrono::Utc::now().timestamp_millis());
+    humanize_duration((end - start).max(0) / 1000)
+});
+
+fn severity_emoji(severity: &str) -> &'static str {
+    match severity {
+        "AVAILABILITY" => "🔴",
+        "ERROR" => "🟠",
+        "PERFORMANCE" | "RESOURCE_CONTENTION" => "🟡",
+        "CUSTOM_ALERT" => "🔵",
+        "MONITORING_UNAVAILABLE" => "⚪",
+        _ => "⚫",
+    }
+}
+
+/// Hex color for chat attachments and cards
+fn severity_color(severity: &str) -> &'static str {
+    match severity {
+        "AVAILABILITY" => "#dc172a",
+        "ERROR" => "#f5a623",
+        "PERFORMANCE" | "RESOURCE_CONTENTION" => "#fcd53f",
+        "CUSTOM_ALERT" => "#1496ff",
+        "MONITORING_UNAVAILABLE" => "#b7b7b7",
+        _ => "#6d6d6d",
+    }
+}
+
+/// Shorten to at most `max` characters, ending in "…" when cut
+fn truncate(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let mut truncated: String = text.chars().take(max.saturating_sub(1)).collect();
+    truncated.push('…');
+    truncated
+}
+
+fn markdown_escape(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if "\\`*_{}[]()#+-.!|<>~".contains(c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+fn html_escape(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+        .replace('\'', "&#39;")
+}
+
+/// Escape for use inside a JSON string literal, so `"{{title}}"` is always valid
+fn json_escape(text: &str) -> String {
+    let quoted = Value::String(text.to_string()).to_string();
+    quoted[1..quoted.len() - 1].to_string()
+}
+
+fn template_error(config: &ConnectorConfig, error: RenderError) -> ForwarderError {
+    ForwarderError::Config(format!("Connector '{}' template failed to render: {}", config.name, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_json_escape_keeps_string_literals_valid() {
+        let rendered = format!("\"{}\"", json_escape("disk \"full\"\n\\ on <host>"));
+        let parsed: Value = serde_json::from_str(&rendered).unwrap();
+
+        assert_eq!(parsed, Value::String("disk \"full\"\n\\ on <host>".to_string()));
+    }
+
+    #[test]
+    fn test_text_helpers() {
+        assert_eq!(truncate("Response time degradation", 10), "Response …");
+        assert_eq!(truncate("short", 10), "short");
+        assert_eq!(markdown_escape("disk_usage *high* [web-01]"), "disk\\_usage \\*high\\* \\[web\\-01\\]");
+        assert_eq!(html_escape("<b>\"a\" & 'b'</b>"), "&lt;b&gt;&quot;a&quot; &amp; &#39;b&#39;&lt;/b&gt;");
+        assert_eq!(humanize_duration(45), "45s");
+        assert_eq!(humanize_duration(3_900), "1h 5m");
+        assert_eq!(humanize_duration(183_600), "2d 3h");
+    }
+}
@@ -0,0 +1,84 @@
+use std::sync::Arc;
+use tokio::sync::{Mutex, OwnedSemaphorePermit, Semaphore};
+use tokio::time::{sleep_until, Duration, Instant};
+use crate::config::OutboundConfig;
+
+/// Process-wide limit on outbound connector requests, shared by all connectors
+#[derive(Debug)]
+pub struct Throttle {
+    concurrency: Option<Arc<Semaphore>>,
+    /// Minimum spacing between request starts
+    interval: Option<Duration>,
+    next_start: Mutex<Instant>,
+}
+
+/// Held for the duration of one request; releases its concurrency slot on drop
+#[derive(Debug)]
+pub struct ThrottlePermit {
+    _permit: Option<OwnedSemaphorePermit>,
+}
+
+impl Throttle {
+    pub fn new(config: &OutboundConfig) -> Self {
+        Self {
+            concurrency: config.max_concurrent_requests.map(|max| Arc::new(Semaphore::new(max))),
+            interval: config
+                .max_requests_per_second
+                .map(|rate| Duration::from_secs_f64(1.0 / rate)),
+            next_start: Mutex::new(Instant::now()),
+        }
+    }
+
+    /// No limits, e.g. for load tests that pace themselves
+    pub fn unlimited() -> Self {
+        Self::new(&OutboundConfig::default())
+    }
+
+    /// Wait for a concurrency slot, then for the next start time the rate allows
+    pub async fn acquire(&self) -> ThrottlePermit {
+        let permit = match &self.concurrency {
+            Some(semaphore) => Some(
+                Arc::clone(semaphore)
+                    .acquire_owned()
+                    .await
+                    .expect("throttle semaphore is never closed"),
+            ),
+            None => None,
+        };
+
+        if let Some(interval) = self.interval {
+            let start = {
+                let mut next_start = self.next_start.lock().await;
+                let start = (*next_start).max(Instant::now());
+                *next_start = start + interval;
+                start
+            };
+            sleep_until(start).await;
+        }
+
+        ThrottlePermit { _permit: permit }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_rate_and_concurrency() {
+        let throttle = Throttle::new(&OutboundConfig {
+            max_concurrent_requests: Some(2),
+            max_requests_per_second: Some(50.0),
+        });
+
+        let started = Instant::now();
+        let first = throttle.acquire().await;
+        let _second = throttle.acquire().await;
+        assert_eq!(throttle.concurrency.as_ref().unwrap().available_permits(), 0);
+
+        drop(first);
+        let _third = throttle.acquire().await;
+        // Three starts 20ms apart
+        assert!(started.elapsed() >= Duration::from_millis(40));
+    }
+}
@@ -0,0 +1,69 @@
+use jaq_core::load::{Arena, File, Loader};
+use jaq_core::{Compiler, Ctx, Native, RcIter};
+use jaq_json::Val;
+use serde_json::Value;
+
+type Filter = jaq_core::Filter<Native<Val>>;
+
+/// Compile a jq expression with the jq standard library
+fn compile(expression: &str) -> Result<Filter, String> {
+    let program = File { code: expression, path: () };
+    let loader = Loader::new(jaq_std::defs().chain(jaq_json::defs()));
+    let arena = Arena::default();
+
+    let modules = loader
+        .load(&arena, program)
+        .map_err(|errors| format!("{:?}", errors.into_iter().map(|(_, e)| e).collect::<Vec<_>>()))?;
+
+    Compiler::default()
+        .with_funs(jaq_std::funs().chain(jaq_json::funs()))
+        .compile(modules)
+        .map_err(|errors| format!("{:?}", errors.into_iter().map(|(_, e)| e).collect::<Vec<_>>()))
+}
+
+/// Check that a connector's `transform` expression compiles
+pub fn validate(expression: &str) -> Result<(), String> {
+    compile(expression).map(|_| ())
+}
+
+/// Run a jq expression on one problem payload. The expression must produce
+/// exactly one value.
+pub fn apply(expression: &str, input: Value) -> Result<Value, String> {
+    let filter = compile(expression).map_err(|e| format!("invalid transform: {}", e))?;
+
+    let inputs = RcIter::new(core::iter::empty());
+    let mut outputs = filter.run((Ctx::new([], &inputs), Val::from(input)));
+
+    let output = match outputs.next() {
+        Some(Ok(value)) => Value::from(value),
+        Some(Err(e)) => return Err(format!("transform failed: {}", e)),
+        None => return Err("transform produced no output".to_string()),
+    };
+    if outputs.next().is_some() {
+        return Err("transform produced more than one value; wrap the expression in [...] to collect them".to_string());
+    }
+
+    Ok(output)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serde_json::json;
+
+    #[test]
+    fn test_apply_restructures_problem() {
+        let problem = json!({
+            "problemId": "P-1",
+            "title": "High CPU",
+            "managementZones": [{ "id": "1", "name": "prod" }, { "id": "2", "name": "eu" }]
+        });
+
+        let output = apply("{id: .problemId, zones: [.managementZones[].name] | join(\",\")}", problem.clone()).unwrap();
+        assert_eq!(output, json!({ "id": "P-1", "zones": "prod,eu" }));
+
+        assert!(apply(".managementZones[]", problem.clone()).is_err());
+        assert!(apply("empty", problem).is_err());
+        assert!(validate("{id: .problemId").is_err());
+    }
+}
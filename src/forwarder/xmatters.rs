@@ -0,0 +1,151 @@
+use reqwest::{Client, Response, StatusCode};
+use serde_json::{json, Value};
+use std::time::Duration;
+use crate::config::{ConnectorConfig, XMattersConfig};
+use crate::dynatrace::Problem;
+use crate::error::{ForwarderError, Result};
+use tracing::debug;
+
+/// Form property used to find the xMatters events of a problem
+pub const PROBLEM_ID_PROPERTY: &str = "problemId";
+
+/// Triggers an xMatters flow per problem and terminates its events on close
+pub struct XMattersNotifier {
+    client: Client,
+    config: Option<XMattersConfig>,
+    connector: ConnectorConfig,
+    problem_url_base: String,
+}
+
+impl XMattersNotifier {
+    /// Create a new notifier sharing the connector's HTTP client
+    pub fn new(
+        client: Client,
+        config: Option<XMattersConfig>,
+        connector: ConnectorConfig,
+        problem_url_base: String,
+    ) -> Self {
+        Self {
+            client,
+            config,
+            connector,
+            problem_url_base,
+        }
+    }
+
+    /// Trigger open problems and terminate closed ones, one problem at a time.
+    /// Without API credentials closed problems are sent to the trigger as well.
+    /// The connector passes a single problem, so a retry repeats only that one.
+    pub async fn notify(&self, problems: &[Problem], timeout: Duration) -> Result<StatusCode> {
+        let mut last_status = StatusCode::OK;
+
+        for problem in problems {
+            last_status = match &self.config {
+                Some(api) if !problem.is_open() => self.terminate(api, problem, timeout).await?,
+                _ => self.trigger(problem, timeout).await?,
+            };
+        }
+
+        Ok(last_status)
+    }
+
+    /// POST the problem to the flow's HTTP trigger / inbound integration URL
+    async fn trigger(&self, problem: &Problem, timeout: Duration) -> Result<StatusCode> {
+        debug!("Triggering xMatters for problem {}", problem.problem_id);
+
+        let response = self
+            .client
+            .post(&self.connector.url)
+            .timeout(timeout)
+            .json(&build_trigger_payload(problem, &self.problem_url_base))
+            .send()
+            .await?;
+
+        Ok(ensure_success(&self.connector.url, response).await?.status())
+    }
+
+    /// Terminate all active xMatters events raised for the problem
+    async fn terminate(&self, api: &XMattersConfig, problem: &Problem, timeout: Duration) -> Result<StatusCode> {
+        let events_url = format!("{}/api/xm/1/events", api.base_url.trim_end_matches('/'));
+
+        let response = self
+            .client
+            .get(&events_url)
+            .timeout(timeout)
+            .basic_auth(&api.username, Some(&api.password))
+            .query(&[
+                ("propertyName", PROBLEM_ID_PROPERTY),
+                ("propertyValue", problem.problem_id.as_str()),
+                ("status", "ACTIVE"),
+            ])
+            .send()
+            .await?;
+
+        let mut status = response.status();
+        let events: Value = ensure_success(&events_url, response).await?.json().await?;
+        let event_ids: Vec<&str> = events["data"]
+            .as_array()
+            .map(|data| data.iter().filter_map(|event| event["id"].as_str()).collect())
+            .unwrap_or_default();
+
+        if event_ids.is_empty() {
+            debug!("No active xMatters events for problem {}", problem.problem_id);
+        }
+
+        for event_id in event_ids {
+            debug!("Terminating xMatters event {} for problem {}", event_id, problem.problem_id);
+
+            let response = self
+                .client
+                .post(&events_url)
+                .timeout(timeout)
+                .basic_auth(&api.username, Some(&api.password))
+                .json(&json!({ "id": event_id, "status": "TERMINATED" }))
+                .send()
+                .await?;
+
+            status = ensure_success(&events_url, response).await?.status();
+        }
+
+        Ok(status)
+    }
+}
+
+/// Fail with the response body if the status is not a success
+async fn ensure_success(url: &str, response: Response) -> Result<Response> {
+    let status = response.status();
+
+    if !status.is_success() {
+        let error_text = response.text().await.unwrap_or_else(|_| "Unknown error".to_string());
+        return Err(ForwarderError::from_status(url, status, &error_text));
+    }
+
+    Ok(response)
+}
+
+/// Build the HTTP trigger body: problem details as flow properties plus a priority
+pub fn build_trigger_payload(problem: &Problem, problem_url_base: &str) -> Value {
+    json!({
+        "priority": priority(&problem.severity_level),
+        "properties": {
+            PROBLEM_ID_PROPERTY: problem.problem_id,
+            "displayId": problem.display_id,
+            "title": problem.title,
+            "status": problem.status.to_string(),
+            "severity": problem.severity_level,
+            "impact": problem.impact_level,
+            "managementZones": problem.management_zone_names().join(", "),
+            "rootCause": problem.root_cause_entity.as_ref().map(|entity| entity.name.as_str()),
+            "url": problem.url(problem_url_base),
+        }
+    })
+}
+
+/// Map Dynatrace severities to xMatters event priorities
+fn priority(severity: &str) -> &'static str {
+    match severity {
+        "AVAILABILITY" | "ERROR" => "HIGH",
+        "PERFORMANCE" | "RESOURCE_CONTENTION" => "MEDIUM",
+        _ => "LOW",
+    }
+}
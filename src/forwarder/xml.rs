@@ -0,0 +1,124 @@
+use serde_json::Value;
+use crate::config::{ConnectorConfig, XmlConfig};
+use crate::dynatrace::Problem;
+use crate::error::Result;
+use crate::forwarder::payload;
+
+const SOAP_ENVELOPE_NS: &str = "http://schemas.xmlsoap.org/soap/envelope/";
+
+/// Render problems as one XML document: the root element with one item element
+/// per problem, wrapped in a SOAP 1.1 envelope when `soap_action` is set
+pub fn document(problems: &[Problem], connector: &ConnectorConfig, problem_url_base: &str) -> Result<String> {
+    let config = connector.xml.clone().unwrap_or_default();
+    let soap = config.soap_action.is_some();
+    let depth = if soap { 2 } else { 0 };
+
+    let mut out = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    if soap {
+        out.push_str(&format!("<soapenv:Envelope xmlns:soapenv=\"{}\">\n", SOAP_ENVELOPE_NS));
+        out.push_str("  <soapenv:Body>\n");
+    }
+
+    open_tag(&mut out, &config.root_element, depth);
+    for problem in problems {
+        let payload = payload::problem_payload(problem, connector, problem_url_base)?;
+        write_problem(&mut out, &payload, &config, depth + 1);
+    }
+    close_tag(&mut out, &config.root_element, depth);
+
+    if soap {
+        out.push_str("  </soapenv:Body>\n");
+        out.push_str("</soapenv:Envelope>\n");
+    }
+
+    Ok(out)
+}
+
+/// Content-Type and, for SOAP, the `SOAPAction` header
+pub fn http_headers(config: Option<&XmlConfig>) -> Vec<(String, String)> {
+    match config.and_then(|config| config.soap_action.as_ref()) {
+        Some(action) => vec![
+            ("content-type".to_string(), "text/xml; charset=utf-8".to_string()),
+            ("soapaction".to_string(), format!("\"{}\"", action)),
+        ],
+        None => vec![("content-type".to_string(), "application/xml; charset=utf-8".to_string())],
+    }
+}
+
+fn write_problem(out: &mut String, payload: &Value, config: &XmlConfig, depth: usize) {
+    if config.fields.is_empty() {
+        write_value(out, &config.item_element, payload, depth);
+        return;
+    }
+
+    open_tag(out, &config.item_element, depth);
+    for field in &config.fields {
+        write_value(out, &field.element, payload.pointer(&field.pointer).unwrap_or(&Value::Null), depth + 1);
+    }
+    close_tag(out, &config.item_element, depth);
+}
+
+/// Objects become child elements, array entries repeated `item` elements and
+/// null or missing values empty elements
+fn write_value(out: &mut String, name: &str, value: &Value, depth: usize) {
+    let indent = "  ".repeat(depth);
+
+    match value {
+        Value::Null => out.push_str(&format!("{}<{}/>\n", indent, name)),
+        Value::Object(map) if map.is_empty() => out.push_str(&format!("{}<{}/>\n", indent, name)),
+        Value::Array(items) if items.is_empty() => out.push_str(&format!("{}<{}/>\n", indent, name)),
+        Value::Object(map) => {
+            open_tag(out, name, depth);
+            for (key, child) in map {
+                write_value(out, &element_name(key), child, depth + 1);
+            }
+            close_tag(out, name, depth);
+        }
+        Value::Array(items) => {
+            open_tag(out, name, depth);
+            for item in items {
+                write_value(out, "item", item, depth + 1);
+            }
+            close_tag(out, name, depth);
+        }
+        Value::String(text) => out.push_str(&format!("{}<{}>{}</{}>\n", indent, name, escape(text), name)),
+        scalar => out.push_str(&format!("{}<{}>{}</{}>\n", indent, name, scalar, name)),
+    }
+}
+
+fn open_tag(out: &mut String, name: &str, depth: usize) {
+    out.push_str(&format!("{}<{}>\n", "  ".repeat(depth), name));
+}
+
+fn close_tag(out: &mut String, name: &str, depth: usize) {
+    out.push_str(&format!("{}</{}>\n", "  ".repeat(depth), name));
+}
+
+/// JSON keys (e.g. label names) as element names, with invalid characters replaced by `_`
+fn element_name(key: &str) -> String {
+    let mut name: String = key
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || matches!(c, '_' | '-' | '.') { c } else { '_' })
+        .collect();
+    if !name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_') {
+        name.insert(0, '_');
+    }
+    name
+}
+
+fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_element_names_and_escaping() {
+        assert_eq!(element_name("problemId"), "problemId");
+        assert_eq!(element_name("team name"), "team_name");
+        assert_eq!(element_name("1st"), "_1st");
+        assert_eq!(escape("a < b && c > d"), "a &lt; b &amp;&amp; c &gt; d");
+    }
+}
@@ -1,10 +1,24 @@
 use clap::Parser;
 use dynatrace_problem_forwarder::{
-    cli::{Cli, Commands},
-    config::Settings,
-    forwarder::ForwardingEngine,
+    cli::{CacheAction, Cli, Commands, ConnectorAction, DbAction, ProblemAction},
+    config::{DatabaseDriver, PushConfig, Settings},
+    dynatrace::{DynatraceClient, ProblemFixture},
+    error::{ErrorCategory, ForwarderError},
+    forwarder::{
+        engine::CONNECTOR_HEALTH_KEY,
+        health::HealthSnapshot,
+        loadtest::{self, LoadTestPlan},
+        receiver::{self, PushQueue},
+        runbook::RunbookMatcher,
+        Connector,
+        ForwardingEngine,
+    },
+    storage::{postgres, snapshot::default_snapshot_path, CacheSnapshot, ConnectorStats, Database, DisabledConnector, Export, HistoryFilter, TimeBucket},
+    utils::{control, time::format_time},
 };
+use std::collections::BTreeMap;
 use std::io::{self, Write};
+use std::sync::Arc;
 use tracing::{info, error};
 use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 
@@ -12,12 +26,21 @@ use tracing_subscriber::{fmt, prelude::*, EnvFilter};
 async fn main() {
     // Parse CLI arguments
     let cli = Cli::parse();
+    let quiet = cli.command.is_quiet();
 
     match run(cli).await {
         Ok(_) => {}
         Err(e) => {
-            eprintln!("Error: {}", e);
-            std::process::exit(1);
+            let category = e.downcast_ref::<ForwarderError>().map(ForwarderError::category);
+
+            if quiet {
+                eprintln!("{}: {}", category.map(ErrorCategory::as_str).unwrap_or("other"), e);
+            } else {
+                eprintln!("Error: {}", e);
+            }
+
+            // Forwarder errors exit with their category's code, anything else with 1
+            std::process::exit(category.map(ErrorCategory::exit_code).unwrap_or(1));
         }
     }
 }
@@ -41,31 +64,100 @@ async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
             info!("Dynatrace Problem Forwarder v{}", env!("CARGO_PKG_VERSION"));
             info!("Configuration loaded from: {}", config.display());
 
+            let push = settings.push.clone();
+            let environments = environment_names(&settings);
+
             // Create forwarding engine
             let engine = ForwardingEngine::new(settings).await?;
 
             // Setup graceful shutdown
             let shutdown_handle = tokio::spawn(dynatrace_problem_forwarder::utils::setup_shutdown_handler());
 
-            // Run the engine in a separate task
-            let _engine_handle = tokio::spawn(async move {
-                if let Err(e) = engine.run().await {
-                    error!("Engine error: {}", e);
+            // Accept control commands (trigger-poll) from the CLI
+            let socket_path = control::get_socket_path(&config);
+            let control = control::serve(socket_path.clone(), engine.poll_trigger(), engine.subscribe());
+            let control_handle = tokio::spawn(async move {
+                if let Err(e) = control.await {
+                    error!("Control socket error: {}", e);
                 }
             });
 
+            // Accept Dynatrace problem notifications alongside polling
+            let push_handle = if push.enabled {
+                Some(spawn_push_receiver(&push, engine.push_queue(), environments)?)
+            } else {
+                None
+            };
+
+            // Run the engine in a separate task; it only returns when startup fails
+            let engine_handle = tokio::spawn(async move { engine.run().await });
+
             // Wait for shutdown signal
-            shutdown_handle.await?;
+            let engine_result = tokio::select! {
+                joined = shutdown_handle => joined.map(Ok)?,
+                joined = engine_handle => joined?,
+            };
+
+            control_handle.abort();
+            if let Some(push_handle) = push_handle {
+                push_handle.abort();
+            }
+            let _ = std::fs::remove_file(&socket_path);
+
+            if let Err(e) = engine_result {
+                error!("Engine error: {}", e);
+                return Err(e.into());
+            }
 
             info!("Shutdown complete");
         }
 
-        Commands::ClearCache { config, confirm } => {
+        Commands::Serve { config } => {
+            let settings = Settings::load(&config)?;
+            init_logging(&settings);
+
+            info!("Dynatrace Problem Forwarder v{} (push mode)", env!("CARGO_PKG_VERSION"));
+            info!("Configuration loaded from: {}", config.display());
+
+            let push = settings.push.clone();
+            let environments = environment_names(&settings);
+            let engine = ForwardingEngine::new(settings).await?.without_polling();
+
+            let push_handle = spawn_push_receiver(&push, engine.push_queue(), environments)?;
+            let shutdown_handle = tokio::spawn(dynatrace_problem_forwarder::utils::setup_shutdown_handler());
+
+            let engine_handle = tokio::spawn(async move { engine.run().await });
+
+            let engine_result = tokio::select! {
+                joined = shutdown_handle => joined.map(Ok)?,
+                joined = engine_handle => joined?,
+            };
+            push_handle.abort();
+
+            if let Err(e) = engine_result {
+                error!("Engine error: {}", e);
+                return Err(e.into());
+            }
+
+            info!("Shutdown complete");
+        }
+
+        Commands::ClearCache { config, confirm, no_snapshot } => {
             let settings = Settings::load(&config)?;
             init_logging(&settings);
 
             info!("Clear Cache Command");
 
+            let db_path = settings.database.path.clone();
+            let engine = ForwardingEngine::new(settings).await?;
+            let snapshot = CacheSnapshot::capture(engine.database()).await?;
+
+            println!(
+                "{} cached problems will be deleted; {} open problems will be re-forwarded on the next poll cycle.",
+                snapshot.problems.len(),
+                snapshot.open_count()
+            );
+
             // Confirm operation
             if !confirm {
                 print!("This will delete all cached problems. Are you sure? (y/N): ");
@@ -80,55 +172,202 @@ async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
                 }
             }
 
-            let engine = ForwardingEngine::new(settings).await?;
+            if !no_snapshot {
+                let snapshot_path = default_snapshot_path(&db_path);
+                snapshot.write(&snapshot_path)?;
+                println!("Snapshot written to {} (restore with 'dtpf cache import --file <path>')", snapshot_path.display());
+            }
+
             let count = engine.database().clear_all_problems().await?;
 
             info!("✓ Cleared {} problems from cache", count);
             println!("Cleared {} problems from cache", count);
         }
 
-        Commands::TestDynatrace { config } => {
+        Commands::Cache { action } => match action {
+            CacheAction::Export { config, output } => {
+                let settings = Settings::load(&config)?;
+                init_logging(&settings);
+
+                let output = output.unwrap_or_else(|| default_snapshot_path(&settings.database.path));
+                let engine = ForwardingEngine::new(settings).await?;
+                let snapshot = CacheSnapshot::capture(engine.database()).await?;
+                snapshot.write(&output)?;
+
+                println!("Exported {} problems to {}", snapshot.problems.len(), output.display());
+            }
+
+            CacheAction::Import { config, file } => {
+                let settings = Settings::load(&config)?;
+                init_logging(&settings);
+
+                let snapshot = CacheSnapshot::read(&file)?;
+                let engine = ForwardingEngine::new(settings).await?;
+                let count = snapshot.restore(engine.database()).await?;
+
+                println!("Imported {} problems from {}", count, file.display());
+            }
+        },
+
+        Commands::Db { action } => match action {
+            DbAction::MigrateTo { config, url } => {
+                let settings = Settings::load(&config)?;
+                init_logging(&settings);
+
+                let database = Database::new(&settings.database).await?;
+                let reports = postgres::migrate_to_postgres(&database, &url).await?;
+
+                println!("Copied {} to Postgres:", settings.database.path.display());
+                for report in &reports {
+                    println!("  {:<20} {:>8} rows  sha256 {}", report.table, report.rows, &report.checksum[..12]);
+                }
+                println!("✓ Row counts and checksums match");
+            }
+
+            DbAction::Migrations { config } => {
+                let settings = Settings::load(&config)?;
+                init_logging(&settings);
+
+                let database = Database::new(&settings.database).await?;
+                println!("{:<8} {:<28} APPLIED AT", "VERSION", "NAME");
+                for (version, name, applied_at) in database.applied_migrations().await? {
+                    println!("{:<8} {:<28} {}", version, name, format_time(applied_at));
+                }
+            }
+        },
+
+        Commands::TestDynatrace { config, quiet } => {
             let settings = Settings::load(&config)?;
-            init_logging(&settings);
+            if !quiet {
+                init_logging(&settings);
+            }
 
             info!("Testing Dynatrace API connectivity...");
 
             let engine = ForwardingEngine::new(settings).await?;
-            engine.dynatrace_client().test_connection().await?;
+            engine.verify_token_scopes().await?;
+            for client in engine.dynatrace_clients() {
+                client.test_connection().await?;
+            }
 
-            println!("✓ Dynatrace API connection successful");
+            if !quiet {
+                println!("✓ Dynatrace API connection successful");
+            }
         }
 
-        Commands::TestConnectors { config } => {
+        Commands::TestConnectors { config, quiet } => {
             let settings = Settings::load(&config)?;
-            init_logging(&settings);
+            if !quiet {
+                init_logging(&settings);
+            }
 
             info!("Testing connector configurations...");
 
             let engine = ForwardingEngine::new(settings).await?;
+            let mut first_failure = None;
 
             for connector in engine.connectors() {
                 match connector.test().await {
                     Ok(_) => {
-                        println!("✓ Connector '{}' test successful", connector.name());
+                        if !quiet {
+                            println!("✓ Connector '{}' test successful", connector.name());
+                        }
                     }
                     Err(e) => {
-                        println!("✗ Connector '{}' test failed: {}", connector.name(), e);
+                        if !quiet {
+                            println!("✗ Connector '{}' test failed: {}", connector.name(), e);
+                        }
+                        first_failure.get_or_insert(e);
                     }
                 }
             }
+
+            // The first failing connector decides the exit code
+            if let Some(e) = first_failure {
+                return Err(e.into());
+            }
+        }
+
+        Commands::Healthcheck { config, quiet } => {
+            let settings = Settings::load(&config)?;
+            if !quiet {
+                init_logging(&settings);
+            }
+
+            let engine = ForwardingEngine::new(settings).await?;
+            engine.database().get_stats(None, None).await?;
+            for client in engine.dynatrace_clients() {
+                client.test_connection().await?;
+            }
+
+            if !quiet {
+                println!("✓ Configuration, database and Dynatrace API are healthy");
+            }
         }
 
-        Commands::Stats { config } => {
+        Commands::Stats { config, management_zone, instance, by: Some(by), since } => {
+            let settings = Settings::load(&config)?;
+            init_logging(&settings);
+
+            let (bucket, default_since) = match by.as_str() {
+                "hour" => (TimeBucket::Hour, std::time::Duration::from_secs(24 * 3600)),
+                _ => (TimeBucket::Day, std::time::Duration::from_secs(7 * 86_400)),
+            };
+            let since = chrono::Utc::now().timestamp() - since.unwrap_or(default_since).as_secs() as i64;
+
+            let database = Database::new(&settings.database).await?;
+            let buckets = database
+                .get_bucket_stats(bucket, since, management_zone.as_deref(), instance.as_deref())
+                .await?;
+
+            println!(
+                "{:<16} {:>8} {:>8} {:>9} {:>10} {:>7}",
+                by.to_uppercase(), "NEW", "CHANGES", "FORWARDS", "SUCCESSFUL", "FAILED"
+            );
+            for entry in &buckets {
+                println!(
+                    "{:<16} {:>8} {:>8} {:>9} {:>10} {:>7}",
+                    entry.bucket,
+                    entry.new_problems,
+                    entry.status_changes,
+                    entry.total_forwards,
+                    entry.successful_forwards,
+                    entry.failed_forwards
+                );
+            }
+            println!(
+                "{:<16} {:>8} {:>8} {:>9} {:>10} {:>7}",
+                "TOTAL",
+                buckets.iter().map(|b| b.new_problems).sum::<i64>(),
+                buckets.iter().map(|b| b.status_changes).sum::<i64>(),
+                buckets.iter().map(|b| b.total_forwards).sum::<i64>(),
+                buckets.iter().map(|b| b.successful_forwards).sum::<i64>(),
+                buckets.iter().map(|b| b.failed_forwards).sum::<i64>()
+            );
+        }
+
+        Commands::Stats { config, management_zone, instance, .. } => {
             let settings = Settings::load(&config)?;
             init_logging(&settings);
 
             info!("Fetching database statistics...");
 
             let engine = ForwardingEngine::new(settings).await?;
-            let stats = engine.database().get_stats().await?;
-
-            println!("\n=== Database Statistics ===");
+            let stats = engine
+                .database()
+                .get_stats(management_zone.as_deref(), instance.as_deref())
+                .await?;
+
+            let scope: Vec<String> = management_zone
+                .iter()
+                .cloned()
+                .chain(instance.iter().map(|id| format!("instance {}", id)))
+                .collect();
+            if scope.is_empty() {
+                println!("\n=== Database Statistics ===");
+            } else {
+                println!("\n=== Database Statistics ({}) ===", scope.join(", "));
+            }
             println!("Total problems tracked:  {}", stats.total_problems);
             println!("  Open problems:         {}", stats.open_problems);
             println!("  Closed problems:       {}", stats.closed_problems);
@@ -136,9 +375,545 @@ async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
             println!("  Total forwards:        {}", stats.total_forwards);
             println!("  Successful:            {}", stats.successful_forwards);
             println!("  Failed:                {}", stats.failed_forwards);
+            println!("  Unrouted:              {}", stats.unrouted);
+            println!("  Suppressed:            {}", stats.suppressed);
+            println!("  Skipped (too old):     {}", stats.skipped);
+            println!("\nDelivery state (problem per connector):");
+            println!("  Delivered:             {}", stats.delivered_targets);
+            println!("  Failing:               {}", stats.failing_targets);
+            println!("\nPoll cycles:");
+            println!("  Volume anomalies:      {}", stats.volume_anomalies);
+            println!("  Flaps suppressed:      {}", stats.flaps_suppressed);
+
+            // Configured connectors that never made a delivery attempt are listed too
+            let mut connectors: BTreeMap<String, Option<ConnectorStats>> =
+                engine.connectors().iter().map(|c| (c.name().to_string(), None)).collect();
+            for entry in &stats.connectors {
+                connectors.insert(entry.connector_name.clone(), Some(entry.clone()));
+            }
+            if !connectors.is_empty() {
+                println!("\nBy connector:");
+                println!(
+                    "  {:<20} {:>8} {:>8} {:>12}  {:<20}  LAST ERROR",
+                    "CONNECTOR", "FORWARDS", "SUCCESS", "AVG DELIVERY", "LAST SUCCESS"
+                );
+                for (name, entry) in connectors {
+                    match entry {
+                        Some(entry) => println!(
+                            "  {:<20} {:>8} {:>7.1}% {:>12}  {:<20}  {}",
+                            name,
+                            entry.total_forwards,
+                            entry.success_rate(),
+                            entry.avg_delivery_ms.map(|ms| format!("{:.0} ms", ms)).unwrap_or_else(|| "-".to_string()),
+                            entry.last_success_at.map(format_time).unwrap_or_else(|| "never".to_string()),
+                            match (entry.last_error_at, entry.last_error) {
+                                (Some(at), error) => format!("{} {}", format_time(at), error.unwrap_or_default()),
+                                (None, _) => String::new(),
+                            }
+                        ),
+                        None => println!("  {:<20} {:>8} {:>8} {:>12}  never", name, 0, "-", "-"),
+                    }
+                }
+            }
+
+            if management_zone.is_none() {
+                let zones = engine.database().get_zone_stats().await?;
+
+                if !zones.is_empty() {
+                    println!("\nBy management zone:");
+                    println!(
+                        "  {:<32} {:>8} {:>6} {:>9} {:>7}",
+                        "ZONE", "PROBLEMS", "OPEN", "FORWARDS", "FAILED"
+                    );
+                    for zone in zones {
+                        println!(
+                            "  {:<32} {:>8} {:>6} {:>9} {:>7}",
+                            zone.management_zone,
+                            zone.total_problems,
+                            zone.open_problems,
+                            zone.total_forwards,
+                            zone.failed_forwards
+                        );
+                    }
+                }
+            }
+
+            if instance.is_none() {
+                let instances = engine.database().get_instance_stats().await?;
+
+                // Only interesting once more than one instance shares the database
+                if instances.len() > 1 {
+                    println!("\nBy instance:");
+                    println!(
+                        "  {:<32} {:>9} {:>10} {:>7}  LAST FORWARD",
+                        "INSTANCE", "FORWARDS", "SUCCESSFUL", "FAILED"
+                    );
+                    for entry in instances {
+                        let last_forward = chrono::DateTime::from_timestamp(entry.last_forwarded_at, 0)
+                            .map(|time| time.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+                            .unwrap_or_default();
+                        println!(
+                            "  {:<32} {:>9} {:>10} {:>7}  {}",
+                            entry.instance_id.as_deref().unwrap_or("(unknown)"),
+                            entry.total_forwards,
+                            entry.successful_forwards,
+                            entry.failed_forwards,
+                            last_forward
+                        );
+                    }
+                }
+            }
+            println!();
+        }
+
+        Commands::Render { config, problem, connector } => {
+            let settings = Settings::load(&config)?;
+
+            let connector_config = settings
+                .connectors
+                .iter()
+                .find(|c| c.name == connector)
+                .ok_or_else(|| format!("Connector '{}' not found in configuration", connector))?;
+
+            let mut problems = ProblemFixture::load(&problem)?;
+            let runbooks = RunbookMatcher::new(&settings.runbooks)?;
+            for problem in &mut problems {
+                runbooks.attach(problem);
+            }
+
+            let bodies = dynatrace_problem_forwarder::forwarder::payload::render_requests(
+                &problems,
+                connector_config,
+                &settings.get_problem_url_base(),
+            )?;
+
+            for body in bodies {
+                println!("{}", serde_json::to_string_pretty(&body)?);
+            }
+        }
+
+        Commands::Loadtest { config, connector, rate, duration, concurrency } => {
+            let settings = Settings::load(&config)?;
+            init_logging(&settings);
+
+            let connector_config = settings
+                .connectors
+                .iter()
+                .find(|c| c.name == connector)
+                .ok_or_else(|| format!("Connector '{}' not found in configuration", connector))?;
+
+            // Built directly rather than through the engine: load tests leave
+            // the database and forward history untouched
+            let connector = Arc::new(Connector::new(
+                connector_config.clone(),
+                settings.health.window,
+                settings.retry.clone(),
+                settings.get_problem_url_base(),
+            )?);
+
+            let plan = LoadTestPlan { rate, duration, concurrency };
+            let report = loadtest::run(connector.clone(), &plan).await;
+
+            let millis = |percentile: f64| {
+                report
+                    .percentile(percentile)
+                    .map(|latency| format!("{:.1} ms", latency.as_secs_f64() * 1000.0))
+                    .unwrap_or_else(|| "-".to_string())
+            };
+
+            println!("\n=== Load Test: {} ===", connector.name());
+            println!("Target rate:            {:.1}/s for {}s", rate, duration.as_secs());
+            println!("Elapsed:                {:.1}s", report.elapsed.as_secs_f64());
+            println!("Deliveries:             {}", report.sent());
+            println!("  Successful:           {}", report.succeeded);
+            println!("  Failed:               {}", report.failed);
+            println!("Throughput:             {:.1}/s", report.throughput());
+            println!("Error rate:             {:.2}%", report.error_rate() * 100.0);
+            println!("\nLatency (including retries):");
+            println!("  p50:                  {}", millis(50.0));
+            println!("  p90:                  {}", millis(90.0));
+            println!("  p95:                  {}", millis(95.0));
+            println!("  p99:                  {}", millis(99.0));
+            println!("  max:                  {}", millis(100.0));
+
+            if !report.errors.is_empty() {
+                println!("\nErrors:");
+                for (message, count) in &report.errors {
+                    println!("  {:>6}  {}", count, message);
+                }
+            }
             println!();
         }
 
+        Commands::Mute { config, problem_id, duration, reason } => {
+            let settings = Settings::load(&config)?;
+            init_logging(&settings);
+            require_shared_state(&settings, "mute")?;
+
+            let database = Database::new(&settings.database).await?;
+            let until = chrono::Utc::now() + chrono::Duration::from_std(duration)?;
+            database.mute_problem(&problem_id, until.timestamp(), reason.as_deref()).await?;
+
+            println!("✓ Muted {} until {}", problem_id, format_time(until.timestamp()));
+        }
+
+        Commands::CloseProblem { config, problem_id, message, environment } => {
+            let settings = Settings::load(&config)?;
+            init_logging(&settings);
+
+            let environments = settings.dynatrace_environments();
+            let target = match &environment {
+                Some(name) => environments.iter().find(|env| &env.name == name).ok_or_else(|| {
+                    ForwarderError::Config(format!("Unknown Dynatrace environment '{}'", name))
+                })?,
+                None => &environments[0],
+            };
+
+            let client = DynatraceClient::for_environment(&settings, target)?;
+            client.close_problem(&problem_id, &message).await?;
+
+            println!("✓ Closed {} in Dynatrace", problem_id);
+        }
+
+        Commands::Unmute { config, problem_id } => {
+            let settings = Settings::load(&config)?;
+            init_logging(&settings);
+            require_shared_state(&settings, "unmute")?;
+
+            let database = Database::new(&settings.database).await?;
+            if database.unmute_problem(&problem_id).await? {
+                println!("✓ Unmuted {}", problem_id);
+            } else {
+                println!("{} was not muted", problem_id);
+            }
+        }
+
+        Commands::History { config, problem_id, connector, status, since, limit, json } => {
+            let settings = Settings::load(&config)?;
+            init_logging(&settings);
+
+            let database = Database::new(&settings.database).await?;
+            let filter = HistoryFilter {
+                problem_id,
+                connector_name: connector,
+                status,
+                since: since.map(|since| chrono::Utc::now().timestamp() - since.as_secs() as i64),
+                until: None,
+                limit: Some(limit),
+            };
+            let history = database.get_forward_history(&filter).await?;
+
+            if json {
+                println!("{}", serde_json::to_string_pretty(&history)?);
+            } else if history.is_empty() {
+                println!("No forward history matches");
+            } else {
+                println!(
+                    "{:<22} {:<40} {:<10} {:<20} {:<10} {:>5} ERROR",
+                    "FORWARDED AT", "PROBLEM", "STATUS", "CONNECTOR", "RESULT", "CODE"
+                );
+                for row in history {
+                    let forwarded_at = format_time(row.forwarded_at);
+                    println!(
+                        "{:<22} {:<40} {:<10} {:<20} {:<10} {:>5} {}",
+                        forwarded_at,
+                        row.problem_id,
+                        row.problem_status.unwrap_or_default(),
+                        row.connector_name,
+                        row.status,
+                        row.response_code.map(|code| code.to_string()).unwrap_or_default(),
+                        row.error_message.unwrap_or_default()
+                    );
+                }
+            }
+        }
+
+        Commands::Problem { action } => match action {
+            ProblemAction::Show { config, problem_id, json } => {
+                let settings = Settings::load(&config)?;
+                init_logging(&settings);
+
+                let database = Database::new(&settings.database).await?;
+                let problem = database.get_problem(&problem_id).await?;
+                let snapshot = database.get_problem_snapshot(&problem_id).await?;
+                let status_changes = database.get_status_changes(&problem_id).await?;
+                let filter = HistoryFilter { problem_id: Some(problem_id.clone()), ..Default::default() };
+                let mut history = database.get_forward_history(&filter).await?;
+                history.reverse();
+                let deliveries = database.get_problem_deliveries(&problem_id).await?;
+
+                if problem.is_none() && status_changes.is_empty() && history.is_empty() {
+                    return Err(format!("Problem '{}' not found in the database", problem_id).into());
+                }
+
+                if json {
+                    let timeline = serde_json::json!({
+                        "problem": problem,
+                        "snapshot": snapshot,
+                        "status_changes": status_changes,
+                        "forward_history": history,
+                        "deliveries": deliveries,
+                    });
+                    println!("{}", serde_json::to_string_pretty(&timeline)?);
+                    return Ok(());
+                }
+
+
+                println!("\n=== Problem {} ===", problem_id);
+                if let Some(snapshot) = &snapshot {
+                    println!("Display ID:    {}", snapshot.display_id);
+                    println!("Impact:        {}", snapshot.impact_level);
+                }
+                if let Some(problem) = &problem {
+                    println!("Title:         {}", problem.title);
+                    println!("Status:        {}", problem.status);
+                    println!("Severity:      {}", problem.severity_level.as_deref().unwrap_or("-"));
+                    if !problem.management_zones.is_empty() {
+                        println!("Zones:         {}", problem.management_zones.join(", "));
+                    }
+                    if let Some(tenant) = &problem.tenant {
+                        println!("Environment:   {}", tenant);
+                    }
+                    println!("Forwards:      {}", problem.forward_count);
+                } else {
+                    println!("(no longer in the problem cache; timeline from history)");
+                }
+
+                // Status entries sort before forward attempts made in the same second
+                let mut timeline: Vec<(i64, String)> = Vec::new();
+                if let Some(snapshot) = &snapshot {
+                    timeline.push((snapshot.start_time / 1000, "started in Dynatrace".to_string()));
+                    if snapshot.end_time > 0 {
+                        timeline.push((snapshot.end_time / 1000, "ended in Dynatrace".to_string()));
+                    }
+                }
+                if let Some(problem) = &problem {
+                    if !status_changes.iter().any(|change| change.from_status.is_none()) {
+                        timeline.push((problem.first_seen_at, "first seen".to_string()));
+                    }
+                }
+                for change in &status_changes {
+                    let event = match &change.from_status {
+                        None => format!("first seen as {}", change.to_status),
+                        Some(from) => format!("status {} -> {}", from, change.to_status),
+                    };
+                    timeline.push((change.changed_at, event));
+                }
+                for row in &history {
+                    let mut event = match row.connector_name.as_str() {
+                        "" => row.status.clone(),
+                        connector => format!("{} -> '{}': {}", row.problem_status.as_deref().unwrap_or("?"), connector, row.status),
+                    };
+                    if let Some(code) = row.response_code {
+                        event.push_str(&format!(" ({})", code));
+                    }
+                    if let Some(error) = &row.error_message {
+                        event.push_str(&format!(" - {}", error));
+                    }
+                    timeline.push((row.forwarded_at, event));
+                }
+                timeline.sort_by_key(|(timestamp, _)| *timestamp);
+
+                println!("\nTimeline:");
+                for (timestamp, event) in timeline {
+                    println!("  {}  {}", format_time(timestamp), event);
+                }
+
+                if !deliveries.is_empty() {
+                    println!("\nDelivery state:");
+                    println!(
+                        "  {:<20} {:<10} {:<10} {:>8} {:>5}  {:<22} LAST ERROR",
+                        "CONNECTOR", "STATUS", "STATE", "ATTEMPTS", "CODE", "LAST ATTEMPT"
+                    );
+                    for delivery in deliveries {
+                        println!(
+                            "  {:<20} {:<10} {:<10} {:>8} {:>5}  {:<22} {}",
+                            delivery.connector_name,
+                            delivery.problem_status.unwrap_or_default(),
+                            delivery.state,
+                            delivery.attempts,
+                            delivery.last_response_code.map(|code| code.to_string()).unwrap_or_default(),
+                            format_time(delivery.last_attempt_at),
+                            delivery.last_error.unwrap_or_default()
+                        );
+                    }
+                }
+                println!();
+            }
+        },
+
+        Commands::Export { config, format, out, from, to } => {
+            let settings = Settings::load(&config)?;
+            init_logging(&settings);
+
+            let database = Database::new(&settings.database).await?;
+            let export = Export::capture(&database, from, to).await?;
+
+            if let Some(parent) = out.parent().filter(|parent| !parent.as_os_str().is_empty()) {
+                std::fs::create_dir_all(parent)?;
+            }
+            if format == "json" {
+                std::fs::write(&out, serde_json::to_string_pretty(&export)?)?;
+                println!(
+                    "✓ Exported {} problems and {} forwards to {}",
+                    export.problems.len(),
+                    export.history.len(),
+                    out.display()
+                );
+            } else {
+                // One CSV file per table: <out> for problems, <out stem>-history.csv for history
+                let history_out = out.with_file_name(format!(
+                    "{}-history.csv",
+                    out.file_stem().and_then(|stem| stem.to_str()).unwrap_or("export")
+                ));
+                std::fs::write(&out, export.problems_csv())?;
+                std::fs::write(&history_out, export.history_csv())?;
+                println!("✓ Exported {} problems to {}", export.problems.len(), out.display());
+                println!("✓ Exported {} forwards to {}", export.history.len(), history_out.display());
+            }
+        }
+
+        Commands::Replay { config, connector, id, all: _ } => {
+            let settings = Settings::load(&config)?;
+            init_logging(&settings);
+            require_shared_state(&settings, "replay")?;
+
+            let engine = ForwardingEngine::new(settings).await?;
+            let (delivered, failed) = engine.replay_dead_letters(&connector, id).await?;
+
+            if delivered + failed == 0 {
+                println!("No dead letters for '{}'", connector);
+            } else {
+                println!("✓ Replayed {} dead letter(s) to '{}', {} failed again", delivered, connector, failed);
+            }
+            if failed > 0 {
+                return Err(format!("{} dead letter(s) could not be delivered to '{}'", failed, connector).into());
+            }
+        }
+
+        Commands::Mutes { config } => {
+            let settings = Settings::load(&config)?;
+            init_logging(&settings);
+            require_shared_state(&settings, "mutes")?;
+
+            let database = Database::new(&settings.database).await?;
+            let mutes = database.get_active_mutes().await?;
+
+            if mutes.is_empty() {
+                println!("No active mutes");
+            } else {
+                println!("{:<40} {:<22} REASON", "PROBLEM", "MUTED UNTIL");
+                for mute in mutes {
+                    let until = format_time(mute.muted_until);
+                    println!("{:<40} {:<22} {}", mute.problem_id, until, mute.reason.unwrap_or_default());
+                }
+            }
+        }
+
+        Commands::Connector { action } => match action {
+            ConnectorAction::Disable { config, name, reason } => {
+                let settings = Settings::load(&config)?;
+                init_logging(&settings);
+                require_shared_state(&settings, "connector disable")?;
+
+                if !settings.connectors.iter().any(|c| c.name == name) {
+                    return Err(format!("Connector '{}' not found in configuration", name).into());
+                }
+
+                let database = Database::new(&settings.database).await?;
+                database.disable_connector(&name, reason.as_deref()).await?;
+                println!("✓ Disabled connector '{}'", name);
+            }
+
+            ConnectorAction::Enable { config, name } => {
+                let settings = Settings::load(&config)?;
+                init_logging(&settings);
+                require_shared_state(&settings, "connector enable")?;
+
+                let database = Database::new(&settings.database).await?;
+                if database.enable_connector(&name).await? {
+                    println!("✓ Enabled connector '{}'", name);
+                } else {
+                    println!("Connector '{}' was not disabled", name);
+                }
+            }
+
+            ConnectorAction::List { config } => {
+                let settings = Settings::load(&config)?;
+                init_logging(&settings);
+
+                let database = Database::new(&settings.database).await?;
+                let disabled: BTreeMap<String, DisabledConnector> = database
+                    .get_disabled_connectors()
+                    .await?
+                    .into_iter()
+                    .map(|connector| (connector.connector_name.clone(), connector))
+                    .collect();
+
+                println!("{:<24} {:<10} {:<22} REASON", "CONNECTOR", "STATE", "SINCE");
+                for connector in &settings.connectors {
+                    match disabled.get(&connector.name) {
+                        Some(entry) => {
+                            let since = format_time(entry.disabled_at);
+                            println!(
+                                "{:<24} {:<10} {:<22} {}",
+                                connector.name,
+                                "disabled",
+                                since,
+                                entry.reason.as_deref().unwrap_or_default()
+                            );
+                        }
+                        None => println!("{:<24} {:<10}", connector.name, "enabled"),
+                    }
+                }
+            }
+        },
+
+        Commands::Status { config } => {
+            let settings = Settings::load(&config)?;
+            init_logging(&settings);
+
+            let engine = ForwardingEngine::new(settings).await?;
+
+            println!("\n=== Connector Health ===");
+            match engine.database().get_app_state(CONNECTOR_HEALTH_KEY).await? {
+                Some((value, updated_at)) => {
+                    let snapshots: BTreeMap<String, HealthSnapshot> = serde_json::from_str(&value)?;
+                    println!("Last updated: {}\n", format_time(updated_at));
+                    println!(
+                        "{:<24} {:>6} {:>8} {:>9} {:>10} {:>10} {:>8} {:>10} {:>9}",
+                        "CONNECTOR", "SCORE", "SAMPLES", "SUCCESS", "AVG (ms)", "P95 (ms)",
+                        "RETRIES", "WAITED (s)", "GAVE UP"
+                    );
+                    for (name, health) in snapshots {
+                        println!(
+                            "{:<24} {:>6.1} {:>8} {:>8.1}% {:>10.0} {:>10.0} {:>8} {:>10.0} {:>9}",
+                            name,
+                            health.score,
+                            health.samples,
+                            health.success_rate * 100.0,
+                            health.avg_latency_ms,
+                            health.p95_latency_ms,
+                            health.retries,
+                            health.retry_delay_ms as f64 / 1000.0,
+                            health.retry_budget_exhausted
+                        );
+                    }
+                }
+                None => println!("No health data recorded yet (is the service running?)"),
+            }
+            println!();
+        }
+
+        Commands::TriggerPoll { config } => {
+            let response = control::send_command(&control::get_socket_path(&config), control::TRIGGER_POLL).await?;
+
+            match response.strip_prefix("ok: ") {
+                Some(message) => println!("✓ {}", message),
+                None => return Err(response.trim_start_matches("error: ").to_string().into()),
+            }
+        }
+
         Commands::Stop { config } => {
             dynatrace_problem_forwarder::utils::stop_background(&config)?;
         }
@@ -147,6 +922,46 @@ async fn run(cli: Cli) -> Result<(), Box<dyn std::error::Error>> {
     Ok(())
 }
 
+/// Fail for commands whose state the running forwarder would never see: with the
+/// memory and redis drivers, mutes, disabled connectors and dead letters are
+/// read from the forwarder process's own in-memory database
+fn require_shared_state(settings: &Settings, command: &str) -> Result<(), Box<dyn std::error::Error>> {
+    let driver = match settings.database.driver {
+        DatabaseDriver::Sqlite => return Ok(()),
+        DatabaseDriver::Memory => "memory",
+        DatabaseDriver::Redis => "redis",
+    };
+    Err(format!(
+        "`dtpf {}` needs database.driver sqlite: the {} driver keeps this state in the forwarder's memory only",
+        command, driver
+    )
+    .into())
+}
+
+/// Names of all configured Dynatrace environments, the main one first
+fn environment_names(settings: &Settings) -> Vec<String> {
+    settings
+        .dynatrace_environments()
+        .into_iter()
+        .map(|environment| environment.name)
+        .collect()
+}
+
+/// Start the push receiver in a separate task; fails if its address cannot be bound
+fn spawn_push_receiver(
+    push: &PushConfig,
+    queue: PushQueue,
+    environments: Vec<String>,
+) -> Result<tokio::task::JoinHandle<()>, Box<dyn std::error::Error>> {
+    let receiver = receiver::bind(push, queue, environments)?;
+
+    Ok(tokio::spawn(async move {
+        if let Err(e) = receiver.await {
+            error!("Push receiver error: {}", e);
+        }
+    }))
+}
+
 fn init_logging(settings: &Settings) {
     let log_level = settings.logging.level.as_str();
     let log_format = settings.logging.format.as_str();
@@ -1,25 +1,222 @@
-use sqlx::{sqlite::SqlitePool, Pool, Sqlite, Row};
-use std::path::Path;
-use crate::error::Result;
-use crate::storage::models::{ForwardedProblem, ForwardHistory, DatabaseStats};
+use sqlx::{ConnectOptions, sqlite::{SqliteConnectOptions, SqliteExecutor, SqliteJournalMode, SqlitePool, SqlitePoolOptions, SqliteRow, SqliteSynchronous}, Pool, Sqlite, Row};
+use std::collections::{HashMap, HashSet};
+use std::io::{Read, Write};
+use std::str::FromStr;
+use std::time::Duration;
+use flate2::read::GzDecoder;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use crate::config::{DatabaseConfig, DatabaseDriver, JournalMode, Synchronous};
+use crate::dynatrace::{EntityDetails, Problem};
+use crate::error::{ForwarderError, Result};
+use crate::storage::redis::RedisStore;
+use crate::storage::models::{ForwardedProblem, ForwardedSecurityProblem, ForwardHistory, BucketStats, ConnectorStats, DatabaseStats, DeadLetter, DisabledConnector, FlapState, HistoryFilter, InstanceStats, PollCycle, ProblemDelivery, ProblemMute, RecurrenceDecision, StatusChange, TimeBucket, UndeliveredProblem, ZoneStats};
 use chrono::Utc;
+use tracing::{info, warn};
+
+/// Matches problems in the management zone bound (twice) to the placeholders; NULL matches all
+const ZONE_FILTER: &str =
+    "(? IS NULL OR EXISTS (SELECT 1 FROM json_each(forwarded_problems.management_zones) WHERE value = ?))";
+
+/// Forward history rows that are delivery attempts, not routing or suppression records
+const DELIVERY_FILTER: &str = "status NOT IN ('unrouted', 'suppressed', 'skipped')";
+
+/// Forward history statuses that update a problem's delivery state at the connector
+const DELIVERY_STATUSES: &[&str] = &["success", "failed", "shadow_failed"];
+
+/// One schema change, applied once per database
+pub struct Migration {
+    pub version: i64,
+    pub name: &'static str,
+    /// `(table, column, definition)` added before `sql` runs, each unless the
+    /// table has it already: dtpf added some columns at startup before
+    /// versioned migrations, so older databases may have them
+    columns: &'static [(&'static str, &'static str, &'static str)],
+    sql: &'static str,
+}
+
+/// Columns of migration 15 that older versions added at startup
+const LEGACY_COLUMNS: &[(&str, &str, &str)] = &[
+    ("forwarded_problems", "management_zones", "TEXT NOT NULL DEFAULT '[]'"),
+    ("forwarded_problems", "tenant", "TEXT"),
+    // Gzipped JSON of the problem as last forwarded
+    ("forwarded_problems", "snapshot", "BLOB"),
+    ("forward_history", "problem_status", "TEXT"),
+    ("forward_history", "instance_id", "TEXT"),
+];
+
+/// All schema migrations in order. New schema changes go into a new file with
+/// the next version; applied migrations must not be edited.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "initial_schema",
+        columns: &[],
+        sql: include_str!("../../migrations/001_initial_schema.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "poll_cycles",
+        columns: &[],
+        sql: include_str!("../../migrations/002_poll_cycles.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "digest_queue",
+        columns: &[],
+        sql: include_str!("../../migrations/003_digest_queue.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "flap_suppressions",
+        columns: &[],
+        sql: include_str!("../../migrations/004_flap_suppressions.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "problem_mutes",
+        columns: &[],
+        sql: include_str!("../../migrations/005_problem_mutes.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "disabled_connectors",
+        columns: &[],
+        sql: include_str!("../../migrations/006_disabled_connectors.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "recurrence_sampling",
+        columns: &[],
+        sql: include_str!("../../migrations/007_recurrence_sampling.sql"),
+    },
+    Migration {
+        version: 8,
+        name: "entity_cache",
+        columns: &[],
+        sql: include_str!("../../migrations/008_entity_cache.sql"),
+    },
+    Migration {
+        version: 9,
+        name: "security_problems",
+        columns: &[],
+        sql: include_str!("../../migrations/009_security_problems.sql"),
+    },
+    Migration {
+        version: 10,
+        name: "dead_letter",
+        columns: &[],
+        sql: include_str!("../../migrations/010_dead_letter.sql"),
+    },
+    Migration {
+        version: 11,
+        name: "undelivered_problems",
+        columns: &[],
+        sql: include_str!("../../migrations/011_undelivered_problems.sql"),
+    },
+    Migration {
+        version: 12,
+        name: "problem_deliveries",
+        columns: &[],
+        sql: include_str!("../../migrations/012_problem_deliveries.sql"),
+    },
+    Migration {
+        version: 13,
+        name: "problem_status_changes",
+        columns: &[],
+        sql: include_str!("../../migrations/013_problem_status_changes.sql"),
+    },
+    Migration {
+        version: 14,
+        name: "forward_duration",
+        columns: &[],
+        sql: include_str!("../../migrations/014_forward_duration.sql"),
+    },
+    Migration {
+        version: 15,
+        name: "legacy_columns",
+        columns: LEGACY_COLUMNS,
+        sql: include_str!("../../migrations/015_legacy_columns.sql"),
+    },
+    Migration {
+        version: 16,
+        name: "forward_history_without_fk",
+        columns: &[],
+        sql: include_str!("../../migrations/016_forward_history_without_fk.sql"),
+    },
+    Migration {
+        version: 17,
+        name: "shadow_delivery_state",
+        columns: &[],
+        sql: include_str!("../../migrations/017_shadow_delivery_state.sql"),
+    },
+];
 
 pub struct Database {
     pool: Pool<Sqlite>,
+    /// Stamped on forward history rows that do not name an instance
+    instance_id: Option<String>,
+    /// Durable copy of problems, app state and history (`database.driver: redis`)
+    redis: Option<RedisStore>,
 }
 
 impl Database {
-    /// Create a new database connection
-    pub async fn new(db_path: &Path) -> Result<Self> {
+    /// Create a new database connection with the configured SQLite pragmas
+    pub async fn new(config: &DatabaseConfig) -> Result<Self> {
+        match config.driver {
+            DatabaseDriver::Sqlite => {}
+            DatabaseDriver::Memory => return Self::in_memory().await,
+            DatabaseDriver::Redis => {
+                let redis = config.redis.as_ref().ok_or_else(|| {
+                    ForwarderError::Config("database.redis is required for the redis driver".to_string())
+                })?;
+                return Self::with_redis(RedisStore::connect(redis).await?).await;
+            }
+        }
+
+        let db_path = &config.path;
         // Create parent directory if it doesn't exist
         if let Some(parent) = db_path.parent() {
             std::fs::create_dir_all(parent)?;
         }
 
-        let connection_string = format!("sqlite:{}?mode=rwc", db_path.display());
-        let pool = SqlitePool::connect(&connection_string).await?;
+        let options = SqliteConnectOptions::new()
+            .filename(db_path)
+            .create_if_missing(true)
+            .journal_mode(match config.journal_mode {
+                JournalMode::Delete => SqliteJournalMode::Delete,
+                JournalMode::Truncate => SqliteJournalMode::Truncate,
+                JournalMode::Persist => SqliteJournalMode::Persist,
+                JournalMode::Memory => SqliteJournalMode::Memory,
+                JournalMode::Wal => SqliteJournalMode::Wal,
+                JournalMode::Off => SqliteJournalMode::Off,
+            })
+            .busy_timeout(Duration::from_millis(config.busy_timeout_ms))
+            .synchronous(match config.synchronous {
+                Synchronous::Off => SqliteSynchronous::Off,
+                Synchronous::Normal => SqliteSynchronous::Normal,
+                Synchronous::Full => SqliteSynchronous::Full,
+                Synchronous::Extra => SqliteSynchronous::Extra,
+            });
+        // SQLCipher needs the key before anything else is read
+        let options = match &config.encryption {
+            Some(encryption) => {
+                Self::require_sqlcipher().await?;
+                options.pragma("key", format!("'{}'", encryption.key()?.replace('\'', "''")))
+            }
+            None => options,
+        };
+        let pool = match SqlitePool::connect_with(options).await {
+            Err(e) if config.encryption.is_some() => {
+                return Err(ForwarderError::Storage {
+                    target: db_path.display().to_string(),
+                    message: format!("cannot open the encrypted database (wrong key, or not encrypted): {}", e),
+                })
+            }
+            pool => pool?,
+        };
 
-        let db = Database { pool };
+        let db = Database { pool, instance_id: None, redis: None };
 
         // Run migrations
         db.run_migrations().await?;
@@ -27,17 +224,196 @@ impl Database {
         Ok(db)
     }
 
-    /// Run database migrations
+    /// Fail unless SQLite is SQLCipher, checked before the database file is
+    /// touched: plain SQLite ignores the key and would write it unencrypted
+    async fn require_sqlcipher() -> Result<()> {
+        let mut connection = SqliteConnectOptions::from_str("sqlite::memory:")?.connect().await?;
+        let cipher: Option<String> = sqlx::query_scalar("PRAGMA cipher_version")
+            .fetch_optional(&mut connection)
+            .await?;
+        if cipher.is_none() {
+            return Err(ForwarderError::Config(
+                "database.encryption needs dtpf built with the sqlcipher feature".to_string(),
+            ));
+        }
+        Ok(())
+    }
+
+    /// A database that lives only as long as this handle. Its single connection
+    /// is never closed, since the data would go with it.
+    pub async fn in_memory() -> Result<Self> {
+        let options = SqliteConnectOptions::from_str("sqlite::memory:")?;
+        let pool = SqlitePoolOptions::new()
+            .max_connections(1)
+            .idle_timeout(None)
+            .max_lifetime(None)
+            .connect_with(options)
+            .await?;
+
+        let db = Database { pool, instance_id: None, redis: None };
+        db.run_migrations().await?;
+
+        Ok(db)
+    }
+
+    /// An in-memory database loaded from Redis, writing tracked problems, app
+    /// state, forward history, dead letters and undelivered problems through
+    /// to it. Reloading the history also restores the delivery states derived
+    /// from it.
+    async fn with_redis(redis: RedisStore) -> Result<Self> {
+        let mut db = Self::in_memory().await?;
+
+        let problems = redis.load_problems().await?;
+        db.import_problems(&problems).await?;
+        for (key, value) in redis.load_state().await? {
+            db.set_app_state(&key, &value).await?;
+        }
+        let history = redis.load_history().await?;
+        for row in &history {
+            db.insert_forward_history(row).await?;
+        }
+        let dead_letters = redis.load_dead_letters().await?;
+        db.import_dead_letters(&dead_letters).await?;
+        for (connector_name, undelivered) in redis.load_undelivered().await? {
+            db.import_undelivered(&connector_name, &undelivered).await?;
+        }
+        info!(
+            "Loaded {} tracked problems, {} forward history rows and {} dead letters from Redis",
+            problems.len(),
+            history.len(),
+            dead_letters.len()
+        );
+
+        db.redis = Some(redis);
+        Ok(db)
+    }
+
+    /// Write a tracked problem's current row through to Redis. A failed write
+    /// is only logged: this process keeps working on its in-memory copy.
+    async fn mirror_problem(&self, problem_id: &str) {
+        let Some(redis) = &self.redis else {
+            return;
+        };
+        let result = match self.get_problem(problem_id).await {
+            Ok(Some(problem)) => redis.save_problem(&problem).await,
+            Ok(None) => Ok(()),
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            warn!("Failed to write problem {} to Redis: {}", problem_id, e);
+        }
+    }
+
+    /// Write a connector's dead letters through to Redis, like `mirror_problem`
+    async fn mirror_dead_letters(&self, connector_name: &str) {
+        let Some(redis) = &self.redis else {
+            return;
+        };
+        let result = match self.get_dead_letters(connector_name, None).await {
+            Ok(dead_letters) => redis.save_dead_letters(connector_name, &dead_letters).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            warn!("Failed to write dead letters of '{}' to Redis: {}", connector_name, e);
+        }
+    }
+
+    /// Write a connector's undelivered problems through to Redis, like `mirror_problem`
+    async fn mirror_undelivered(&self, connector_name: &str) {
+        let Some(redis) = &self.redis else {
+            return;
+        };
+        let result = match self.get_undelivered(connector_name).await {
+            Ok(undelivered) => redis.save_undelivered(connector_name, &undelivered).await,
+            Err(e) => Err(e),
+        };
+        if let Err(e) = result {
+            warn!("Failed to write undelivered problems of '{}' to Redis: {}", connector_name, e);
+        }
+    }
+
+    /// Record `instance_id` with every forward history row written through this handle
+    pub fn with_instance_id(mut self, instance_id: String) -> Self {
+        self.instance_id = Some(instance_id);
+        self
+    }
+
+    /// Apply the migrations this database has not run yet, each in its own
+    /// transaction, recording them in `schema_migrations`
     async fn run_migrations(&self) -> Result<()> {
-        let migration_sql = include_str!("../../migrations/001_initial_schema.sql");
-        sqlx::raw_sql(migration_sql).execute(&self.pool).await?;
+        sqlx::raw_sql(
+            "CREATE TABLE IF NOT EXISTS schema_migrations (
+                 version INTEGER PRIMARY KEY,
+                 name TEXT NOT NULL,
+                 applied_at INTEGER NOT NULL
+             )",
+        )
+        .execute(&self.pool)
+        .await?;
+
+        let applied: HashSet<i64> = sqlx::query_scalar("SELECT version FROM schema_migrations")
+            .fetch_all(&self.pool)
+            .await?
+            .into_iter()
+            .collect();
+        let latest = MIGRATIONS.last().map(|m| m.version).unwrap_or_default();
+        if let Some(newer) = applied.iter().copied().filter(|version| *version > latest).max() {
+            return Err(ForwarderError::Storage {
+                target: "database".to_string(),
+                message: format!(
+                    "schema version {} is newer than this dtpf supports ({}); upgrade dtpf",
+                    newer, latest
+                ),
+            });
+        }
+
+        // Databases from before versioning already have the early tables; their
+        // migrations only create what is missing, so they are recorded on the first run
+        for migration in MIGRATIONS.iter().filter(|m| !applied.contains(&m.version)) {
+            let mut tx = self.pool.begin().await?;
+            for (table, column, definition) in migration.columns {
+                let exists: bool =
+                    sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM pragma_table_info(?) WHERE name = ?)")
+                        .bind(table)
+                        .bind(column)
+                        .fetch_one(&mut *tx)
+                        .await?;
+                if !exists {
+                    sqlx::raw_sql(&format!("ALTER TABLE {} ADD COLUMN {} {}", table, column, definition))
+                        .execute(&mut *tx)
+                        .await?;
+                }
+            }
+            sqlx::raw_sql(migration.sql).execute(&mut *tx).await?;
+            sqlx::query("INSERT INTO schema_migrations (version, name, applied_at) VALUES (?, ?, ?)")
+                .bind(migration.version)
+                .bind(migration.name)
+                .bind(Utc::now().timestamp())
+                .execute(&mut *tx)
+                .await?;
+            tx.commit().await?;
+            info!("Applied database migration {:03}_{}", migration.version, migration.name);
+        }
+
         Ok(())
     }
 
+    /// Migrations recorded as applied, with when each was applied
+    pub async fn applied_migrations(&self) -> Result<Vec<(i64, String, i64)>> {
+        let rows = sqlx::query("SELECT version, name, applied_at FROM schema_migrations ORDER BY version")
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| (row.get("version"), row.get("name"), row.get("applied_at")))
+            .collect())
+    }
+
     /// Get a forwarded problem by problem_id
     pub async fn get_problem(&self, problem_id: &str) -> Result<Option<ForwardedProblem>> {
         let result = sqlx::query(
-            "SELECT id, problem_id, status, severity_level, title, first_seen_at, 
+            "SELECT id, problem_id, status, severity_level, title, management_zones, tenant, first_seen_at,
              last_forwarded_at, last_status_change_at, forward_count, created_at, updated_at
              FROM forwarded_problems WHERE problem_id = ?"
         )
@@ -45,36 +421,106 @@ impl Database {
         .fetch_optional(&self.pool)
         .await?;
 
-        match result {
-            Some(row) => Ok(Some(ForwardedProblem {
-                id: Some(row.get("id")),
-                problem_id: row.get("problem_id"),
-                status: row.get("status"),
-                severity_level: row.get("severity_level"),
-                title: row.get("title"),
-                first_seen_at: row.get("first_seen_at"),
-                last_forwarded_at: row.get("last_forwarded_at"),
-                last_status_change_at: row.get("last_status_change_at"),
-                forward_count: row.get("forward_count"),
-                created_at: row.get("created_at"),
-                updated_at: row.get("updated_at"),
-            })),
-            None => Ok(None),
+        Ok(result.map(|row| problem_from_row(&row)))
+    }
+
+    /// Get all forwarded problems (for cache export)
+    pub async fn get_all_problems(&self) -> Result<Vec<ForwardedProblem>> {
+        let rows = sqlx::query(
+            "SELECT id, problem_id, status, severity_level, title, management_zones, tenant, first_seen_at,
+             last_forwarded_at, last_status_change_at, forward_count, created_at, updated_at
+             FROM forwarded_problems ORDER BY id"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows.iter().map(problem_from_row).collect())
+    }
+
+    /// Insert or replace forwarded problems (for cache import)
+    pub async fn import_problems(&self, problems: &[ForwardedProblem]) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+        let mut imported = 0;
+
+        for problem in problems {
+            let result = sqlx::query(
+                "INSERT OR REPLACE INTO forwarded_problems
+                 (problem_id, status, severity_level, title, management_zones, tenant, first_seen_at,
+                  last_forwarded_at, last_status_change_at, forward_count, created_at, updated_at)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+            )
+            .bind(&problem.problem_id)
+            .bind(&problem.status)
+            .bind(&problem.severity_level)
+            .bind(&problem.title)
+            .bind(serde_json::to_string(&problem.management_zones)?)
+            .bind(&problem.tenant)
+            .bind(problem.first_seen_at)
+            .bind(problem.last_forwarded_at)
+            .bind(problem.last_status_change_at)
+            .bind(problem.forward_count)
+            .bind(problem.created_at)
+            .bind(problem.updated_at)
+            .execute(&mut *tx)
+            .await?;
+
+            imported += result.rows_affected().min(1);
+        }
+
+        tx.commit().await?;
+        for problem in problems {
+            self.mirror_problem(&problem.problem_id).await;
         }
+
+        Ok(imported)
+    }
+
+    /// Store the complete problem as forwarded (details, enrichment and
+    /// correlated problems included) with its tracked row, gzipped
+    pub async fn save_problem_snapshot(&self, problem: &Problem) -> Result<()> {
+        let mut encoder = GzEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(&serde_json::to_vec(problem)?)?;
+
+        sqlx::query("UPDATE forwarded_problems SET snapshot = ? WHERE problem_id = ?")
+            .bind(encoder.finish()?)
+            .bind(&problem.problem_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// The problem as last forwarded, if a snapshot was stored
+    pub async fn get_problem_snapshot(&self, problem_id: &str) -> Result<Option<Problem>> {
+        let snapshot: Option<Vec<u8>> =
+            sqlx::query_scalar("SELECT snapshot FROM forwarded_problems WHERE problem_id = ?")
+                .bind(problem_id)
+                .fetch_optional(&self.pool)
+                .await?
+                .flatten();
+        let Some(snapshot) = snapshot else {
+            return Ok(None);
+        };
+
+        let mut json = Vec::new();
+        GzDecoder::new(snapshot.as_slice()).read_to_end(&mut json)?;
+        Ok(Some(serde_json::from_slice(&json)?))
     }
 
     /// Insert a new forwarded problem
     pub async fn insert_problem(&self, problem: &ForwardedProblem) -> Result<i64> {
         let result = sqlx::query(
             "INSERT INTO forwarded_problems 
-             (problem_id, status, severity_level, title, first_seen_at, last_forwarded_at, 
-              last_status_change_at, forward_count, created_at, updated_at)
-             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
+             (problem_id, status, severity_level, title, management_zones, tenant, first_seen_at,
+              last_forwarded_at, last_status_change_at, forward_count, created_at, updated_at)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?, ?)"
         )
         .bind(&problem.problem_id)
         .bind(&problem.status)
         .bind(&problem.severity_level)
         .bind(&problem.title)
+        .bind(serde_json::to_string(&problem.management_zones)?)
+        .bind(&problem.tenant)
         .bind(problem.first_seen_at)
         .bind(problem.last_forwarded_at)
         .bind(problem.last_status_change_at)
@@ -83,27 +529,62 @@ impl Database {
         .bind(problem.updated_at)
         .execute(&self.pool)
         .await?;
+        sqlx::query(
+            "INSERT INTO problem_status_changes (problem_id, from_status, to_status, changed_at) VALUES (?, NULL, ?, ?)",
+        )
+        .bind(&problem.problem_id)
+        .bind(&problem.status)
+        .bind(problem.first_seen_at)
+        .execute(&self.pool)
+        .await?;
+        self.mirror_problem(&problem.problem_id).await;
 
         Ok(result.last_insert_rowid())
     }
 
-    /// Update a forwarded problem's status
-    pub async fn update_problem_status(&self, problem_id: &str, new_status: &str) -> Result<()> {
+    /// Update a forwarded problem's status (and its current management zones)
+    pub async fn update_problem_status(
+        &self,
+        problem_id: &str,
+        new_status: &str,
+        management_zones: &[String],
+    ) -> Result<()> {
         let now = Utc::now().timestamp();
-        
+        log_status_change(&self.pool, problem_id, new_status, now).await?;
+
         sqlx::query(
             "UPDATE forwarded_problems 
-             SET status = ?, last_forwarded_at = ?, last_status_change_at = ?, 
+             SET status = ?, management_zones = ?, last_forwarded_at = ?, last_status_change_at = ?, 
                  forward_count = forward_count + 1, updated_at = ?
              WHERE problem_id = ?"
         )
         .bind(new_status)
+        .bind(serde_json::to_string(management_zones)?)
         .bind(now)
         .bind(now)
         .bind(now)
         .bind(problem_id)
         .execute(&self.pool)
         .await?;
+        self.mirror_problem(problem_id).await;
+
+        Ok(())
+    }
+
+    /// Update a cached problem's status without counting a forward, for problems
+    /// that were never forwarded
+    pub async fn set_problem_status(&self, problem_id: &str, status: &str, management_zones: &[String]) -> Result<()> {
+        log_status_change(&self.pool, problem_id, status, Utc::now().timestamp()).await?;
+        sqlx::query(
+            "UPDATE forwarded_problems SET status = ?, management_zones = ?, updated_at = ? WHERE problem_id = ?",
+        )
+        .bind(status)
+        .bind(serde_json::to_string(management_zones)?)
+        .bind(Utc::now().timestamp())
+        .bind(problem_id)
+        .execute(&self.pool)
+        .await?;
+        self.mirror_problem(problem_id).await;
 
         Ok(())
     }
@@ -122,6 +603,7 @@ impl Database {
         .bind(problem_id)
         .execute(&self.pool)
         .await?;
+        self.mirror_problem(problem_id).await;
 
         Ok(())
     }
@@ -129,75 +611,1266 @@ impl Database {
     /// Insert a forward history record
     pub async fn insert_forward_history(&self, history: &ForwardHistory) -> Result<i64> {
         let result = sqlx::query(
-            "INSERT INTO forward_history 
-             (problem_id, connector_name, status, response_code, error_message, forwarded_at)
-             VALUES (?, ?, ?, ?, ?, ?)"
+            "INSERT INTO forward_history
+             (problem_id, problem_status, instance_id, connector_name, status, response_code, error_message,
+              forwarded_at, duration_ms)
+             VALUES (?, ?, ?, ?, ?, ?, ?, ?, ?)"
         )
         .bind(&history.problem_id)
+        .bind(&history.problem_status)
+        .bind(history.instance_id.as_ref().or(self.instance_id.as_ref()))
         .bind(&history.connector_name)
         .bind(&history.status)
         .bind(history.response_code)
         .bind(&history.error_message)
         .bind(history.forwarded_at)
+        .bind(history.duration_ms)
         .execute(&self.pool)
         .await?;
+        if DELIVERY_STATUSES.contains(&history.status.as_str()) {
+            self.record_delivery(history).await?;
+        }
+
+        if let Some(redis) = &self.redis {
+            let mut row = history.clone();
+            row.id = Some(result.last_insert_rowid());
+            row.instance_id = row.instance_id.or_else(|| self.instance_id.clone());
+            if let Err(e) = redis.append_history(&row).await {
+                warn!("Failed to write forward history of {} to Redis: {}", history.problem_id, e);
+            }
+        }
 
         Ok(result.last_insert_rowid())
     }
 
-    /// Clear all forwarded problems (for clear-cache command)
-    pub async fn clear_all_problems(&self) -> Result<u64> {
-        let result = sqlx::query("DELETE FROM forwarded_problems")
+    /// Update the delivery state of the problem at the connector from a delivery
+    /// attempt. Attempts restart when the problem status changes. Failures of
+    /// shadow connectors keep their own state, so they never count as failing.
+    async fn record_delivery(&self, history: &ForwardHistory) -> Result<()> {
+        let delivered = history.status == "success";
+        let state = match history.status.as_str() {
+            "success" => "delivered",
+            "shadow_failed" => "shadow_failed",
+            _ => "failed",
+        };
+        sqlx::query(
+            "INSERT INTO problem_deliveries
+             (problem_id, connector_name, problem_status, state, attempts, last_response_code, last_error,
+              last_attempt_at, delivered_at)
+             VALUES (?, ?, ?, ?, 1, ?, ?, ?, ?)
+             ON CONFLICT(problem_id, connector_name) DO UPDATE SET
+                 attempts = CASE WHEN problem_status IS excluded.problem_status THEN attempts + 1 ELSE 1 END,
+                 problem_status = excluded.problem_status,
+                 state = excluded.state,
+                 last_response_code = excluded.last_response_code,
+                 last_error = excluded.last_error,
+                 last_attempt_at = excluded.last_attempt_at,
+                 delivered_at = COALESCE(excluded.delivered_at, delivered_at)"
+        )
+        .bind(&history.problem_id)
+        .bind(&history.connector_name)
+        .bind(&history.problem_status)
+        .bind(state)
+        .bind(history.response_code)
+        .bind(&history.error_message)
+        .bind(history.forwarded_at)
+        .bind(delivered.then_some(history.forwarded_at))
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Statuses the problem was seen in, oldest first. Problems tracked before
+    /// status changes were logged miss the changes before that.
+    pub async fn get_status_changes(&self, problem_id: &str) -> Result<Vec<StatusChange>> {
+        let rows = sqlx::query(
+            "SELECT from_status, to_status, changed_at FROM problem_status_changes
+             WHERE problem_id = ? ORDER BY changed_at, id"
+        )
+        .bind(problem_id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| StatusChange {
+                from_status: row.get("from_status"),
+                to_status: row.get("to_status"),
+                changed_at: row.get("changed_at"),
+            })
+            .collect())
+    }
+
+    /// Delivery state of a problem at every connector it was sent to
+    pub async fn get_problem_deliveries(&self, problem_id: &str) -> Result<Vec<ProblemDelivery>> {
+        let rows = sqlx::query("SELECT * FROM problem_deliveries WHERE problem_id = ? ORDER BY connector_name")
+            .bind(problem_id)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.iter().map(problem_delivery_from_row).collect())
+    }
+
+    /// Forward history rows matching the filter, most recent first
+    pub async fn get_forward_history(&self, filter: &HistoryFilter) -> Result<Vec<ForwardHistory>> {
+        let mut query = sqlx::QueryBuilder::<Sqlite>::new(
+            "SELECT id, problem_id, problem_status, instance_id, connector_name, status, response_code,
+                    error_message, forwarded_at, duration_ms
+             FROM forward_history WHERE 1 = 1",
+        );
+        if let Some(problem_id) = &filter.problem_id {
+            query.push(" AND problem_id = ").push_bind(problem_id);
+        }
+        if let Some(connector_name) = &filter.connector_name {
+            query.push(" AND connector_name = ").push_bind(connector_name);
+        }
+        if let Some(status) = &filter.status {
+            query.push(" AND status = ").push_bind(status);
+        }
+        if let Some(since) = filter.since {
+            query.push(" AND forwarded_at >= ").push_bind(since);
+        }
+        if let Some(until) = filter.until {
+            query.push(" AND forwarded_at <= ").push_bind(until);
+        }
+        query.push(" ORDER BY forwarded_at DESC, id DESC");
+        if let Some(limit) = filter.limit {
+            query.push(" LIMIT ").push_bind(limit);
+        }
+
+        let rows = query.build().fetch_all(&self.pool).await?;
+        Ok(rows
+            .iter()
+            .map(|row| ForwardHistory {
+                id: row.get("id"),
+                problem_id: row.get("problem_id"),
+                problem_status: row.get("problem_status"),
+                instance_id: row.get("instance_id"),
+                connector_name: row.get("connector_name"),
+                status: row.get("status"),
+                response_code: row.get("response_code"),
+                error_message: row.get("error_message"),
+                forwarded_at: row.get("forwarded_at"),
+                duration_ms: row.get("duration_ms"),
+            })
+            .collect())
+    }
+
+    /// Whether forward history records a successful delivery of this problem
+    /// status to any of the connectors
+    pub async fn has_delivered(&self, problem_id: &str, problem_status: &str, connectors: &[&str]) -> Result<bool> {
+        let mut query = sqlx::QueryBuilder::<Sqlite>::new(
+            "SELECT EXISTS (SELECT 1 FROM forward_history WHERE status = 'success' AND problem_id = ",
+        );
+        query.push_bind(problem_id);
+        query.push(" AND problem_status = ");
+        query.push_bind(problem_status);
+        query.push(" AND connector_name IN (");
+        let mut names = query.separated(", ");
+        for connector in connectors {
+            names.push_bind(*connector);
+        }
+        names.push_unseparated("))");
+
+        let delivered: bool = query.build_query_scalar().fetch_one(&self.pool).await?;
+        Ok(delivered)
+    }
+
+    /// When this problem status was last delivered successfully to any connector
+    pub async fn last_delivered_at(&self, problem_id: &str, problem_status: &str) -> Result<Option<i64>> {
+        let delivered_at: Option<i64> = sqlx::query_scalar(
+            "SELECT MAX(forwarded_at) FROM forward_history
+             WHERE status = 'success' AND problem_id = ? AND problem_status = ?",
+        )
+        .bind(problem_id)
+        .bind(problem_status)
+        .fetch_one(&self.pool)
+        .await?;
+
+        Ok(delivered_at)
+    }
+
+    /// Flap state of every problem with a status change waiting to be forwarded
+    pub async fn get_pending_flaps(&self) -> Result<Vec<FlapState>> {
+        let rows = sqlx::query(
+            "SELECT problem_id, forwarded_status, pending_status, suppressed_count, last_suppressed_at
+             FROM flap_suppressions WHERE pending_status IS NOT NULL",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| FlapState {
+                problem_id: row.get("problem_id"),
+                forwarded_status: row.get("forwarded_status"),
+                pending_status: row.get("pending_status"),
+                suppressed_count: row.get("suppressed_count"),
+                last_suppressed_at: row.get("last_suppressed_at"),
+            })
+            .collect())
+    }
+
+    /// Record a status change withheld as flapping: the cached status follows the
+    /// problem while receivers keep `forwarded_status`. `pending` is false when the
+    /// problem flapped back to `forwarded_status`.
+    pub async fn record_flap(
+        &self,
+        problem_id: &str,
+        new_status: &str,
+        management_zones: &[String],
+        forwarded_status: &str,
+        pending: bool,
+    ) -> Result<()> {
+        let now = Utc::now().timestamp();
+        let mut tx = self.pool.begin().await?;
+        log_status_change(&mut *tx, problem_id, new_status, now).await?;
+
+        sqlx::query(
+            "UPDATE forwarded_problems SET status = ?, management_zones = ?, updated_at = ? WHERE problem_id = ?",
+        )
+        .bind(new_status)
+        .bind(serde_json::to_string(management_zones)?)
+        .bind(now)
+        .bind(problem_id)
+        .execute(&mut *tx)
+        .await?;
+
+        sqlx::query(
+            "INSERT INTO flap_suppressions
+             (problem_id, forwarded_status, pending_status, suppressed_count, last_suppressed_at)
+             VALUES (?, ?, ?, 1, ?)
+             ON CONFLICT(problem_id) DO UPDATE SET
+                 forwarded_status = excluded.forwarded_status,
+                 pending_status = excluded.pending_status,
+                 suppressed_count = suppressed_count + 1,
+                 last_suppressed_at = excluded.last_suppressed_at",
+        )
+        .bind(problem_id)
+        .bind(forwarded_status)
+        .bind(pending.then_some(new_status))
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+        tx.commit().await?;
+        self.mirror_problem(problem_id).await;
+        Ok(())
+    }
+
+    /// Forget a withheld status change once the problem's status is forwarded
+    pub async fn clear_pending_flap(&self, problem_id: &str) -> Result<()> {
+        sqlx::query("UPDATE flap_suppressions SET pending_status = NULL WHERE problem_id = ?")
+            .bind(problem_id)
             .execute(&self.pool)
             .await?;
 
-        Ok(result.rows_affected())
+        Ok(())
     }
 
-    /// Get database statistics
-    pub async fn get_stats(&self) -> Result<DatabaseStats> {
-        let total_problems: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM forwarded_problems")
-            .fetch_one(&self.pool)
+    /// Mute a problem until `muted_until`, replacing an existing mute
+    pub async fn mute_problem(&self, problem_id: &str, muted_until: i64, reason: Option<&str>) -> Result<()> {
+        sqlx::query(
+            "INSERT OR REPLACE INTO problem_mutes (problem_id, muted_until, reason, muted_at) VALUES (?, ?, ?, ?)",
+        )
+        .bind(problem_id)
+        .bind(muted_until)
+        .bind(reason)
+        .bind(Utc::now().timestamp())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Remove a problem's mute; false if it was not muted
+    pub async fn unmute_problem(&self, problem_id: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM problem_mutes WHERE problem_id = ?")
+            .bind(problem_id)
+            .execute(&self.pool)
             .await?;
 
-        let open_problems: i64 = sqlx::query_scalar(
-            "SELECT COUNT(*) FROM forwarded_problems WHERE status = 'OPEN'"
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// Mutes that have not expired, soonest expiry first. Expired mutes are deleted.
+    pub async fn get_active_mutes(&self) -> Result<Vec<ProblemMute>> {
+        let now = Utc::now().timestamp();
+        sqlx::query("DELETE FROM problem_mutes WHERE muted_until <= ?")
+            .bind(now)
+            .execute(&self.pool)
+            .await?;
+
+        let rows = sqlx::query(
+            "SELECT problem_id, muted_until, reason, muted_at FROM problem_mutes ORDER BY muted_until",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| ProblemMute {
+                problem_id: row.get("problem_id"),
+                muted_until: row.get("muted_until"),
+                reason: row.get("reason"),
+                muted_at: row.get("muted_at"),
+            })
+            .collect())
+    }
+
+    /// Disable a connector until it is enabled again, replacing an earlier reason
+    pub async fn disable_connector(&self, connector_name: &str, reason: Option<&str>) -> Result<()> {
+        sqlx::query("INSERT OR REPLACE INTO disabled_connectors (connector_name, reason, disabled_at) VALUES (?, ?, ?)")
+            .bind(connector_name)
+            .bind(reason)
+            .bind(Utc::now().timestamp())
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Enable a disabled connector; false if it was not disabled
+    pub async fn enable_connector(&self, connector_name: &str) -> Result<bool> {
+        let result = sqlx::query("DELETE FROM disabled_connectors WHERE connector_name = ?")
+            .bind(connector_name)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(result.rows_affected() > 0)
+    }
+
+    /// All disabled connectors, by name
+    pub async fn get_disabled_connectors(&self) -> Result<Vec<DisabledConnector>> {
+        let rows = sqlx::query(
+            "SELECT connector_name, reason, disabled_at FROM disabled_connectors ORDER BY connector_name",
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| DisabledConnector {
+                connector_name: row.get("connector_name"),
+                reason: row.get("reason"),
+                disabled_at: row.get("disabled_at"),
+            })
+            .collect())
+    }
+
+    /// Count an occurrence of a recurring problem for a connector that forwards
+    /// every `every`th one. The counter starts over once no occurrence was seen for
+    /// `reset_after_seconds`. Skipped problems are remembered so their later
+    /// updates can be skipped too.
+    pub async fn record_recurrence(
+        &self,
+        connector_name: &str,
+        recurrence_key: &str,
+        problem_id: &str,
+        every: u64,
+        reset_after_seconds: i64,
+    ) -> Result<RecurrenceDecision> {
+        let now = Utc::now().timestamp();
+        let mut tx = self.pool.begin().await?;
+
+        let row = sqlx::query(
+            "SELECT occurrences, skipped, last_seen_at FROM recurrence_counters
+             WHERE connector_name = ? AND recurrence_key = ?",
+        )
+        .bind(connector_name)
+        .bind(recurrence_key)
+        .fetch_optional(&mut *tx)
+        .await?;
+
+        let (previous, skipped) = match row {
+            Some(row) => {
+                let last_seen_at: i64 = row.get("last_seen_at");
+                let previous = if now - last_seen_at > reset_after_seconds { 0 } else { row.get("occurrences") };
+                (previous, row.get::<i64, _>("skipped"))
+            }
+            None => (0, 0),
+        };
+        let occurrences = previous + 1;
+        let forward = (occurrences - 1) % every as i64 == 0;
+
+        sqlx::query(
+            "INSERT OR REPLACE INTO recurrence_counters
+             (connector_name, recurrence_key, occurrences, skipped, last_seen_at)
+             VALUES (?, ?, ?, ?, ?)",
         )
+        .bind(connector_name)
+        .bind(recurrence_key)
+        .bind(occurrences)
+        .bind(if forward { 0 } else { skipped + 1 })
+        .bind(now)
+        .execute(&mut *tx)
+        .await?;
+
+        if !forward {
+            sqlx::query(
+                "INSERT OR REPLACE INTO recurrence_skipped (connector_name, problem_id, skipped_at) VALUES (?, ?, ?)",
+            )
+            .bind(connector_name)
+            .bind(problem_id)
+            .bind(now)
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        tx.commit().await?;
+        Ok(RecurrenceDecision { forward, occurrences, skipped })
+    }
+
+    /// IDs of the problems this connector skipped by recurrence sampling
+    pub async fn get_recurrence_skipped(&self, connector_name: &str) -> Result<HashSet<String>> {
+        let rows = sqlx::query("SELECT problem_id FROM recurrence_skipped WHERE connector_name = ?")
+            .bind(connector_name)
+            .fetch_all(&self.pool)
+            .await?;
+
+        Ok(rows.iter().map(|row| row.get("problem_id")).collect())
+    }
+
+    /// Forget a skipped problem once it is closed
+    pub async fn clear_recurrence_skipped(&self, connector_name: &str, problem_id: &str) -> Result<()> {
+        sqlx::query("DELETE FROM recurrence_skipped WHERE connector_name = ? AND problem_id = ?")
+            .bind(connector_name)
+            .bind(problem_id)
+            .execute(&self.pool)
+            .await?;
+
+        Ok(())
+    }
+
+    /// Cached entities of an environment fetched after `fetched_after` (unix seconds),
+    /// by entity ID. Expired entries are deleted.
+    pub async fn get_cached_entities(
+        &self,
+        environment: &str,
+        entity_ids: &[String],
+        fetched_after: i64,
+    ) -> Result<HashMap<String, EntityDetails>> {
+        sqlx::query("DELETE FROM entity_cache WHERE fetched_at <= ?")
+            .bind(fetched_after)
+            .execute(&self.pool)
+            .await?;
+
+        let mut entities = HashMap::new();
+        for entity_id in entity_ids {
+            let row = sqlx::query("SELECT details FROM entity_cache WHERE environment = ? AND entity_id = ?")
+                .bind(environment)
+                .bind(entity_id)
+                .fetch_optional(&self.pool)
+                .await?;
+
+            if let Some(row) = row {
+                let mut details: EntityDetails = serde_json::from_str(row.get("details"))?;
+                details.entity_id = entity_id.clone();
+                entities.insert(entity_id.clone(), details);
+            }
+        }
+
+        Ok(entities)
+    }
+
+    /// Status and risk level last forwarded for a security problem
+    pub async fn get_forwarded_security_problem(
+        &self,
+        security_problem_id: &str,
+    ) -> Result<Option<ForwardedSecurityProblem>> {
+        let row = sqlx::query(
+            "SELECT security_problem_id, display_id, status, risk_level, first_forwarded_at, last_forwarded_at
+             FROM forwarded_security_problems WHERE security_problem_id = ?",
+        )
+        .bind(security_problem_id)
+        .fetch_optional(&self.pool)
+        .await?;
+
+        Ok(row.map(|row| ForwardedSecurityProblem {
+            security_problem_id: row.get("security_problem_id"),
+            display_id: row.get("display_id"),
+            status: row.get("status"),
+            risk_level: row.get("risk_level"),
+            first_forwarded_at: row.get("first_forwarded_at"),
+            last_forwarded_at: row.get("last_forwarded_at"),
+        }))
+    }
+
+    /// Record the status and risk level forwarded for a security problem
+    pub async fn upsert_forwarded_security_problem(
+        &self,
+        security_problem_id: &str,
+        display_id: &str,
+        status: &str,
+        risk_level: Option<&str>,
+    ) -> Result<()> {
+        let now = Utc::now().timestamp();
+        sqlx::query(
+            "INSERT INTO forwarded_security_problems
+             (security_problem_id, display_id, status, risk_level, first_forwarded_at, last_forwarded_at)
+             VALUES (?, ?, ?, ?, ?, ?)
+             ON CONFLICT(security_problem_id) DO UPDATE SET
+                 display_id = excluded.display_id,
+                 status = excluded.status,
+                 risk_level = excluded.risk_level,
+                 last_forwarded_at = excluded.last_forwarded_at",
+        )
+        .bind(security_problem_id)
+        .bind(display_id)
+        .bind(status)
+        .bind(risk_level)
+        .bind(now)
+        .bind(now)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Cache fetched entities of an environment, replacing older copies
+    pub async fn cache_entities(&self, environment: &str, entities: &[EntityDetails]) -> Result<()> {
+        let now = Utc::now().timestamp();
+        for entity in entities {
+            sqlx::query(
+                "INSERT OR REPLACE INTO entity_cache (environment, entity_id, details, fetched_at) VALUES (?, ?, ?, ?)",
+            )
+            .bind(environment)
+            .bind(&entity.entity_id)
+            .bind(serde_json::to_string(entity)?)
+            .bind(now)
+            .execute(&self.pool)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Record the problem volume of a poll cycle
+    pub async fn insert_poll_cycle(&self, cycle: &PollCycle) -> Result<i64> {
+        let result = sqlx::query(
+            "INSERT INTO poll_cycles
+             (polled_at, fetched_count, new_count, status_change_count, baseline, anomaly)
+             VALUES (?, ?, ?, ?, ?, ?)"
+        )
+        .bind(cycle.polled_at)
+        .bind(cycle.fetched_count)
+        .bind(cycle.new_count)
+        .bind(cycle.status_change_count)
+        .bind(cycle.baseline)
+        .bind(cycle.anomaly)
+        .execute(&self.pool)
+        .await?;
+
+        Ok(result.last_insert_rowid())
+    }
+
+    /// Average new + status-change count over the last `cycles` non-anomalous cycles.
+    /// Returns the average and how many cycles it is based on.
+    pub async fn poll_volume_baseline(&self, cycles: u32) -> Result<(f64, i64)> {
+        let row = sqlx::query(
+            "SELECT COALESCE(AVG(volume), 0.0) AS average, COUNT(*) AS samples FROM (
+                 SELECT new_count + status_change_count AS volume FROM poll_cycles
+                 WHERE anomaly = 0 ORDER BY id DESC LIMIT ?
+             )"
+        )
+        .bind(cycles)
         .fetch_one(&self.pool)
         .await?;
 
-        let closed_problems: i64 = sqlx::query_scalar(
-            "SELECT COUNT(*) FROM forwarded_problems WHERE status != 'OPEN'"
+        Ok((row.get("average"), row.get("samples")))
+    }
+
+    /// Queue a problem for a connector's next digest
+    pub async fn enqueue_digest(&self, connector_name: &str, problem: &Problem) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO digest_queue (connector_name, problem_id, problem_json, queued_at)
+             VALUES (?, ?, ?, ?)"
+        )
+        .bind(connector_name)
+        .bind(&problem.problem_id)
+        .bind(serde_json::to_string(problem)?)
+        .bind(Utc::now().timestamp())
+        .execute(&self.pool)
+        .await?;
+
+        Ok(())
+    }
+
+    /// Timestamp of the oldest queued digest entry for a connector
+    pub async fn oldest_digest_entry(&self, connector_name: &str) -> Result<Option<i64>> {
+        let oldest: Option<i64> = sqlx::query_scalar(
+            "SELECT MIN(queued_at) FROM digest_queue WHERE connector_name = ?"
         )
+        .bind(connector_name)
         .fetch_one(&self.pool)
         .await?;
 
-        let total_forwards: i64 = sqlx::query_scalar("SELECT COUNT(*) FROM forward_history")
-            .fetch_one(&self.pool)
+        Ok(oldest)
+    }
+
+    /// Get queued problems for a connector, keeping only the latest state per problem.
+    /// Returns the problems and the highest queue id they cover.
+    pub async fn get_digest_queue(&self, connector_name: &str) -> Result<(Vec<Problem>, i64)> {
+        let rows = sqlx::query(
+            "SELECT id, problem_id, problem_json FROM digest_queue
+             WHERE connector_name = ? ORDER BY id"
+        )
+        .bind(connector_name)
+        .fetch_all(&self.pool)
+        .await?;
+
+        let mut max_id = 0;
+        let mut latest: Vec<Problem> = Vec::new();
+
+        for row in rows {
+            max_id = row.get("id");
+            let problem: Problem = serde_json::from_str(row.get("problem_json"))?;
+
+            match latest.iter_mut().find(|p| p.problem_id == problem.problem_id) {
+                Some(existing) => *existing = problem,
+                None => latest.push(problem),
+            }
+        }
+
+        Ok((latest, max_id))
+    }
+
+    /// Remove queued digest entries up to and including `max_id`
+    pub async fn delete_digest_entries(&self, connector_name: &str, max_id: i64) -> Result<u64> {
+        let result = sqlx::query("DELETE FROM digest_queue WHERE connector_name = ? AND id <= ?")
+            .bind(connector_name)
+            .bind(max_id)
+            .execute(&self.pool)
             .await?;
 
-        let successful_forwards: i64 = sqlx::query_scalar(
-            "SELECT COUNT(*) FROM forward_history WHERE status = 'success'"
+        Ok(result.rows_affected())
+    }
+
+    /// Keep a delivery that failed after all retries, with the problems as sent
+    pub async fn insert_dead_letter(&self, connector_name: &str, problems: &[Problem], error_message: &str) -> Result<i64> {
+        let problem_ids: Vec<&str> = problems.iter().map(|p| p.problem_id.as_str()).collect();
+        let result = sqlx::query(
+            "INSERT INTO dead_letter (connector_name, problem_ids, problems_json, error_message, failed_at)
+             VALUES (?, ?, ?, ?, ?)"
         )
+        .bind(connector_name)
+        .bind(serde_json::to_string(&problem_ids)?)
+        .bind(serde_json::to_string(problems)?)
+        .bind(error_message)
+        .bind(Utc::now().timestamp())
+        .execute(&self.pool)
+        .await?;
+
+        self.mirror_dead_letters(connector_name).await;
+        Ok(result.last_insert_rowid())
+    }
+
+    /// Restore dead letters with their IDs (for the redis driver)
+    async fn import_dead_letters(&self, dead_letters: &[DeadLetter]) -> Result<()> {
+        for dead_letter in dead_letters {
+            sqlx::query(
+                "INSERT OR REPLACE INTO dead_letter
+                 (id, connector_name, problem_ids, problems_json, error_message, failed_at, replay_count, last_replayed_at)
+                 VALUES (?, ?, ?, ?, ?, ?, ?, ?)"
+            )
+            .bind(dead_letter.id)
+            .bind(&dead_letter.connector_name)
+            .bind(serde_json::to_string(&dead_letter.problem_ids)?)
+            .bind(serde_json::to_string(&dead_letter.problems)?)
+            .bind(&dead_letter.error_message)
+            .bind(dead_letter.failed_at)
+            .bind(dead_letter.replay_count)
+            .bind(dead_letter.last_replayed_at)
+            .execute(&self.pool)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Connector a dead letter belongs to, when the redis driver needs to mirror it
+    async fn dead_letter_connector(&self, id: i64) -> Result<Option<String>> {
+        if self.redis.is_none() {
+            return Ok(None);
+        }
+        let row = sqlx::query("SELECT connector_name FROM dead_letter WHERE id = ?")
+            .bind(id)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| row.get("connector_name")))
+    }
+
+    /// Dead letters of a connector, oldest first; only the one with `id` when given
+    pub async fn get_dead_letters(&self, connector_name: &str, id: Option<i64>) -> Result<Vec<DeadLetter>> {
+        let rows = sqlx::query(
+            "SELECT id, connector_name, problem_ids, problems_json, error_message, failed_at, replay_count, last_replayed_at
+             FROM dead_letter WHERE connector_name = ? AND (? IS NULL OR id = ?) ORDER BY id"
+        )
+        .bind(connector_name)
+        .bind(id)
+        .bind(id)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(DeadLetter {
+                    id: row.get("id"),
+                    connector_name: row.get("connector_name"),
+                    problem_ids: serde_json::from_str(row.get("problem_ids"))?,
+                    problems: serde_json::from_str(row.get("problems_json"))?,
+                    error_message: row.get("error_message"),
+                    failed_at: row.get("failed_at"),
+                    replay_count: row.get("replay_count"),
+                    last_replayed_at: row.get("last_replayed_at"),
+                })
+            })
+            .collect()
+    }
+
+    /// Count a failed replay of a dead letter, keeping the latest error and
+    /// only the problems that are still undelivered
+    pub async fn record_dead_letter_replay(&self, id: i64, problems: &[Problem], error_message: &str) -> Result<()> {
+        let problem_ids: Vec<&str> = problems.iter().map(|p| p.problem_id.as_str()).collect();
+        sqlx::query(
+            "UPDATE dead_letter SET replay_count = replay_count + 1, last_replayed_at = ?, error_message = ?,
+             problem_ids = ?, problems_json = ?
+             WHERE id = ?"
+        )
+        .bind(Utc::now().timestamp())
+        .bind(error_message)
+        .bind(serde_json::to_string(&problem_ids)?)
+        .bind(serde_json::to_string(problems)?)
+        .bind(id)
+        .execute(&self.pool)
+        .await?;
+
+        if let Some(connector_name) = self.dead_letter_connector(id).await? {
+            self.mirror_dead_letters(&connector_name).await;
+        }
+        Ok(())
+    }
+
+    /// Remove a dead letter once it has been delivered
+    pub async fn delete_dead_letter(&self, id: i64) -> Result<()> {
+        let connector_name = self.dead_letter_connector(id).await?;
+        sqlx::query("DELETE FROM dead_letter WHERE id = ?")
+            .bind(id)
+            .execute(&self.pool)
+            .await?;
+
+        if let Some(connector_name) = connector_name {
+            self.mirror_dead_letters(&connector_name).await;
+        }
+        Ok(())
+    }
+
+    /// Drop problems that have since been delivered from a connector's dead
+    /// letters, removing dead letters with nothing left to replay
+    pub async fn remove_from_dead_letters(&self, connector_name: &str, problem_ids: &[&str]) -> Result<()> {
+        if problem_ids.is_empty() {
+            return Ok(());
+        }
+
+        for dead_letter in self.get_dead_letters(connector_name, None).await? {
+            if !dead_letter.problem_ids.iter().any(|id| problem_ids.contains(&id.as_str())) {
+                continue;
+            }
+
+            let remaining: Vec<Problem> = dead_letter
+                .problems
+                .into_iter()
+                .filter(|p| !problem_ids.contains(&p.problem_id.as_str()))
+                .collect();
+            if remaining.is_empty() {
+                self.delete_dead_letter(dead_letter.id).await?;
+                continue;
+            }
+
+            let remaining_ids: Vec<&str> = remaining.iter().map(|p| p.problem_id.as_str()).collect();
+            sqlx::query("UPDATE dead_letter SET problem_ids = ?, problems_json = ? WHERE id = ?")
+                .bind(serde_json::to_string(&remaining_ids)?)
+                .bind(serde_json::to_string(&remaining)?)
+                .bind(dead_letter.id)
+                .execute(&self.pool)
+                .await?;
+            self.mirror_dead_letters(connector_name).await;
+        }
+
+        Ok(())
+    }
+
+    /// Record problems a connector failed to receive, replacing older states of
+    /// the same problems while keeping when their first delivery failed
+    pub async fn mark_undelivered(&self, connector_name: &str, problems: &[Problem], error_message: &str) -> Result<()> {
+        let now = Utc::now().timestamp();
+        for problem in problems {
+            sqlx::query(
+                "INSERT INTO undelivered_problems
+                 (connector_name, problem_id, problem_status, problem_json, first_failed_at, attempts, last_error)
+                 VALUES (?, ?, ?, ?, ?, 1, ?)
+                 ON CONFLICT(connector_name, problem_id) DO UPDATE SET
+                     problem_status = excluded.problem_status,
+                     problem_json = excluded.problem_json,
+                     attempts = attempts + 1,
+                     last_error = excluded.last_error"
+            )
+            .bind(connector_name)
+            .bind(&problem.problem_id)
+            .bind(problem.status.to_string())
+            .bind(serde_json::to_string(problem)?)
+            .bind(now)
+            .bind(error_message)
+            .execute(&self.pool)
+            .await?;
+        }
+
+        self.mirror_undelivered(connector_name).await;
+        Ok(())
+    }
+
+    /// Restore a connector's undelivered problems (for the redis driver)
+    async fn import_undelivered(&self, connector_name: &str, undelivered: &[UndeliveredProblem]) -> Result<()> {
+        for entry in undelivered {
+            sqlx::query(
+                "INSERT OR REPLACE INTO undelivered_problems
+                 (connector_name, problem_id, problem_status, problem_json, first_failed_at, attempts)
+                 VALUES (?, ?, ?, ?, ?, ?)"
+            )
+            .bind(connector_name)
+            .bind(&entry.problem.problem_id)
+            .bind(entry.problem.status.to_string())
+            .bind(serde_json::to_string(&entry.problem)?)
+            .bind(entry.first_failed_at)
+            .bind(entry.attempts)
+            .execute(&self.pool)
+            .await?;
+        }
+
+        Ok(())
+    }
+
+    /// Forget undelivered states of problems a connector has now received
+    pub async fn clear_undelivered(&self, connector_name: &str, problem_ids: &[&str]) -> Result<()> {
+        let mut cleared = 0;
+        for problem_id in problem_ids {
+            cleared += sqlx::query("DELETE FROM undelivered_problems WHERE connector_name = ? AND problem_id = ?")
+                .bind(connector_name)
+                .bind(problem_id)
+                .execute(&self.pool)
+                .await?
+                .rows_affected();
+        }
+
+        if cleared > 0 {
+            self.mirror_undelivered(connector_name).await;
+        }
+        Ok(())
+    }
+
+    /// Problems a connector has not received yet, oldest failure first
+    pub async fn get_undelivered(&self, connector_name: &str) -> Result<Vec<UndeliveredProblem>> {
+        let rows = sqlx::query(
+            "SELECT problem_json, first_failed_at, attempts FROM undelivered_problems
+             WHERE connector_name = ? ORDER BY first_failed_at, problem_id"
+        )
+        .bind(connector_name)
+        .fetch_all(&self.pool)
+        .await?;
+
+        rows.iter()
+            .map(|row| {
+                Ok(UndeliveredProblem {
+                    problem: serde_json::from_str(row.get("problem_json"))?,
+                    first_failed_at: row.get("first_failed_at"),
+                    attempts: row.get("attempts"),
+                })
+            })
+            .collect()
+    }
+
+    /// Get a value from the app_state table
+    pub async fn get_app_state(&self, key: &str) -> Result<Option<(String, i64)>> {
+        let row = sqlx::query("SELECT value, updated_at FROM app_state WHERE key = ?")
+            .bind(key)
+            .fetch_optional(&self.pool)
+            .await?;
+
+        Ok(row.map(|row| (row.get("value"), row.get("updated_at"))))
+    }
+
+    /// Insert or update a value in the app_state table
+    pub async fn set_app_state(&self, key: &str, value: &str) -> Result<()> {
+        sqlx::query(
+            "INSERT INTO app_state (key, value, updated_at) VALUES (?, ?, ?)
+             ON CONFLICT(key) DO UPDATE SET value = excluded.value, updated_at = excluded.updated_at"
+        )
+        .bind(key)
+        .bind(value)
+        .bind(Utc::now().timestamp())
+        .execute(&self.pool)
+        .await?;
+
+        if let Some(redis) = &self.redis {
+            if let Err(e) = redis.save_state(key, value).await {
+                warn!("Failed to write app state '{}' to Redis: {}", key, e);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Clear all forwarded problems and their delivery states (for clear-cache
+    /// command). Forward history is an audit log and is kept.
+    pub async fn clear_all_problems(&self) -> Result<u64> {
+        let mut tx = self.pool.begin().await?;
+        let result = sqlx::query("DELETE FROM forwarded_problems").execute(&mut *tx).await?;
+        sqlx::query("DELETE FROM problem_deliveries").execute(&mut *tx).await?;
+        tx.commit().await?;
+        if let Some(redis) = &self.redis {
+            redis.clear_problems().await?;
+        }
+
+        Ok(result.rows_affected())
+    }
+
+    /// Get database statistics, optionally restricted to one management zone.
+    /// `instance_id` restricts the forward counts to one forwarder instance.
+    /// Volume anomalies are counted per cycle and are not zone specific.
+    pub async fn get_stats(&self, management_zone: Option<&str>, instance_id: Option<&str>) -> Result<DatabaseStats> {
+        let total_problems: i64 = sqlx::query_scalar(&format!(
+            "SELECT COUNT(*) FROM forwarded_problems WHERE {}",
+            ZONE_FILTER
+        ))
+        .bind(management_zone)
+        .bind(management_zone)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let open_problems: i64 = sqlx::query_scalar(&format!(
+            "SELECT COUNT(*) FROM forwarded_problems WHERE status = 'OPEN' AND {}",
+            ZONE_FILTER
+        ))
+        .bind(management_zone)
+        .bind(management_zone)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let closed_problems: i64 = sqlx::query_scalar(&format!(
+            "SELECT COUNT(*) FROM forwarded_problems WHERE status != 'OPEN' AND {}",
+            ZONE_FILTER
+        ))
+        .bind(management_zone)
+        .bind(management_zone)
         .fetch_one(&self.pool)
         .await?;
 
-        let failed_forwards: i64 = sqlx::query_scalar(
-            "SELECT COUNT(*) FROM forward_history WHERE status = 'failed'"
+        let history_filter = match management_zone {
+            Some(_) => format!(
+                "AND problem_id IN (SELECT problem_id FROM forwarded_problems WHERE {})",
+                ZONE_FILTER
+            ),
+            None => String::new(),
+        };
+
+        let mut forward_counts = Vec::with_capacity(6);
+        for status_clause in [
+            DELIVERY_FILTER,
+            "status = 'success'",
+            "status = 'failed'",
+            "status = 'unrouted'",
+            "status = 'suppressed'",
+            "status = 'skipped'",
+        ] {
+            let sql = format!(
+                "SELECT COUNT(*) FROM forward_history WHERE {} {} AND (? IS NULL OR instance_id = ?)",
+                status_clause, history_filter
+            );
+            let mut query = sqlx::query_scalar(&sql);
+            if management_zone.is_some() {
+                query = query.bind(management_zone).bind(management_zone);
+            }
+            let count: i64 = query
+                .bind(instance_id)
+                .bind(instance_id)
+                .fetch_one(&self.pool)
+                .await?;
+            forward_counts.push(count);
+        }
+
+        let volume_anomalies: i64 = sqlx::query_scalar(
+            "SELECT COUNT(*) FROM poll_cycles WHERE anomaly = 1"
         )
         .fetch_one(&self.pool)
         .await?;
 
+        let flaps_suppressed: i64 = sqlx::query_scalar(&format!(
+            "SELECT COALESCE(SUM(suppressed_count), 0) FROM flap_suppressions
+             WHERE ? IS NULL OR problem_id IN (SELECT problem_id FROM forwarded_problems WHERE {})",
+            ZONE_FILTER
+        ))
+        .bind(management_zone)
+        .bind(management_zone)
+        .bind(management_zone)
+        .fetch_one(&self.pool)
+        .await?;
+
+        let mut target_counts = Vec::with_capacity(2);
+        for state in ["delivered", "failed"] {
+            let count: i64 = sqlx::query_scalar(&format!(
+                "SELECT COUNT(*) FROM problem_deliveries WHERE state = ?
+                 AND (? IS NULL OR problem_id IN (SELECT problem_id FROM forwarded_problems WHERE {}))",
+                ZONE_FILTER
+            ))
+            .bind(state)
+            .bind(management_zone)
+            .bind(management_zone)
+            .bind(management_zone)
+            .fetch_one(&self.pool)
+            .await?;
+            target_counts.push(count);
+        }
+
+        let connectors = self.get_connector_stats(&history_filter, management_zone, instance_id).await?;
+
         Ok(DatabaseStats {
             total_problems,
             open_problems,
             closed_problems,
-            total_forwards,
-            successful_forwards,
-            failed_forwards,
+            total_forwards: forward_counts[0],
+            successful_forwards: forward_counts[1],
+            failed_forwards: forward_counts[2],
+            unrouted: forward_counts[3],
+            suppressed: forward_counts[4],
+            skipped: forward_counts[5],
+            volume_anomalies,
+            flaps_suppressed,
+            delivered_targets: target_counts[0],
+            failing_targets: target_counts[1],
+            connectors,
         })
     }
 
+    /// Delivery attempts per connector, restricted like the other forward counts
+    /// of `get_stats`. The latest error is the one of the connector's latest failure.
+    async fn get_connector_stats(
+        &self,
+        history_filter: &str,
+        management_zone: Option<&str>,
+        instance_id: Option<&str>,
+    ) -> Result<Vec<ConnectorStats>> {
+        let sql = format!(
+            "SELECT connector_name,
+                    COUNT(*) AS total_forwards,
+                    SUM(CASE WHEN status = 'success' THEN 1 ELSE 0 END) AS successful_forwards,
+                    MAX(CASE WHEN status = 'success' THEN forwarded_at END) AS last_success_at,
+                    AVG(duration_ms) AS avg_delivery_ms
+             FROM forward_history
+             WHERE {} {} AND (? IS NULL OR instance_id = ?)
+             GROUP BY connector_name
+             ORDER BY connector_name",
+            DELIVERY_FILTER, history_filter
+        );
+        let mut query = sqlx::query(&sql);
+        if management_zone.is_some() {
+            query = query.bind(management_zone).bind(management_zone);
+        }
+        let rows = query.bind(instance_id).bind(instance_id).fetch_all(&self.pool).await?;
+
+        // SQLite takes the other columns from the row holding the MAX
+        let sql = format!(
+            "SELECT connector_name, error_message, MAX(forwarded_at) AS failed_at
+             FROM forward_history
+             WHERE status IN ('failed', 'shadow_failed') {} AND (? IS NULL OR instance_id = ?)
+             GROUP BY connector_name",
+            history_filter
+        );
+        let mut query = sqlx::query(&sql);
+        if management_zone.is_some() {
+            query = query.bind(management_zone).bind(management_zone);
+        }
+        let last_errors: HashMap<String, (Option<String>, i64)> = query
+            .bind(instance_id)
+            .bind(instance_id)
+            .fetch_all(&self.pool)
+            .await?
+            .iter()
+            .map(|row| (row.get("connector_name"), (row.get("error_message"), row.get("failed_at"))))
+            .collect();
+
+        Ok(rows
+            .iter()
+            .map(|row| {
+                let connector_name: String = row.get("connector_name");
+                let total_forwards: i64 = row.get("total_forwards");
+                let successful_forwards: i64 = row.get("successful_forwards");
+                let last_error = last_errors.get(&connector_name).cloned();
+                ConnectorStats {
+                    total_forwards,
+                    successful_forwards,
+                    failed_forwards: total_forwards - successful_forwards,
+                    last_success_at: row.get("last_success_at"),
+                    last_error: last_error.as_ref().and_then(|(error, _)| error.clone()),
+                    last_error_at: last_error.map(|(_, failed_at)| failed_at),
+                    avg_delivery_ms: row.get("avg_delivery_ms"),
+                    connector_name,
+                }
+            })
+            .collect())
+    }
+
+    /// New problems, status changes and forwards per day or hour since `since`,
+    /// oldest bucket first and including empty buckets. The zone and instance
+    /// restrict the counts as in `get_stats`.
+    pub async fn get_bucket_stats(
+        &self,
+        bucket: TimeBucket,
+        since: i64,
+        management_zone: Option<&str>,
+        instance_id: Option<&str>,
+    ) -> Result<Vec<BucketStats>> {
+        let format = bucket.label_format();
+        let mut buckets: Vec<BucketStats> = Vec::new();
+        let now = Utc::now().timestamp();
+        let mut start = since - since.rem_euclid(bucket.seconds());
+        while start <= now {
+            buckets.push(BucketStats { bucket: bucket.label(start), ..Default::default() });
+            start += bucket.seconds();
+        }
+        let mut index: HashMap<String, usize> =
+            buckets.iter().enumerate().map(|(i, b)| (b.bucket.clone(), i)).collect();
+
+        let rows = sqlx::query(&format!(
+            "SELECT strftime(?, first_seen_at, 'unixepoch') AS bucket, COUNT(*) AS count
+             FROM forwarded_problems WHERE first_seen_at >= ? AND {}
+             GROUP BY bucket",
+            ZONE_FILTER
+        ))
+        .bind(format)
+        .bind(since)
+        .bind(management_zone)
+        .bind(management_zone)
+        .fetch_all(&self.pool)
+        .await?;
+        for row in &rows {
+            let i = bucket_index(&mut buckets, &mut index, row.get("bucket"));
+            buckets[i].new_problems = row.get("count");
+        }
+
+        let rows = sqlx::query(&format!(
+            "SELECT strftime(?, changed_at, 'unixepoch') AS bucket, COUNT(*) AS count
+             FROM problem_status_changes
+             WHERE from_status IS NOT NULL AND changed_at >= ?
+               AND (? IS NULL OR problem_id IN (SELECT problem_id FROM forwarded_problems WHERE {}))
+             GROUP BY bucket",
+            ZONE_FILTER
+        ))
+        .bind(format)
+        .bind(since)
+        .bind(management_zone)
+        .bind(management_zone)
+        .bind(management_zone)
+        .fetch_all(&self.pool)
+        .await?;
+        for row in &rows {
+            let i = bucket_index(&mut buckets, &mut index, row.get("bucket"));
+            buckets[i].status_changes = row.get("count");
+        }
+
+        let rows = sqlx::query(&format!(
+            "SELECT strftime(?, forwarded_at, 'unixepoch') AS bucket,
+                    COUNT(*) AS total,
+                    SUM(CASE WHEN status = 'success' THEN 1 ELSE 0 END) AS successful
+             FROM forward_history
+             WHERE {} AND forwarded_at >= ? AND (? IS NULL OR instance_id = ?)
+               AND (? IS NULL OR problem_id IN (SELECT problem_id FROM forwarded_problems WHERE {}))
+             GROUP BY bucket",
+            DELIVERY_FILTER, ZONE_FILTER
+        ))
+        .bind(format)
+        .bind(since)
+        .bind(instance_id)
+        .bind(instance_id)
+        .bind(management_zone)
+        .bind(management_zone)
+        .bind(management_zone)
+        .fetch_all(&self.pool)
+        .await?;
+        for row in &rows {
+            let i = bucket_index(&mut buckets, &mut index, row.get("bucket"));
+            let (total, successful): (i64, i64) = (row.get("total"), row.get("successful"));
+            buckets[i].total_forwards = total;
+            buckets[i].successful_forwards = successful;
+            buckets[i].failed_forwards = total - successful;
+        }
+
+        Ok(buckets)
+    }
+
+    /// Forward counts per forwarder instance, most recently active first
+    pub async fn get_instance_stats(&self) -> Result<Vec<InstanceStats>> {
+        let rows = sqlx::query(
+            "SELECT instance_id,
+                    COUNT(*) AS total_forwards,
+                    SUM(CASE WHEN status = 'success' THEN 1 ELSE 0 END) AS successful_forwards,
+                    SUM(CASE WHEN status = 'failed' THEN 1 ELSE 0 END) AS failed_forwards,
+                    MAX(forwarded_at) AS last_forwarded_at
+             FROM forward_history
+             WHERE status NOT IN ('unrouted', 'suppressed', 'skipped')
+             GROUP BY instance_id
+             ORDER BY last_forwarded_at DESC"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| InstanceStats {
+                instance_id: row.get("instance_id"),
+                total_forwards: row.get("total_forwards"),
+                successful_forwards: row.get("successful_forwards"),
+                failed_forwards: row.get("failed_forwards"),
+                last_forwarded_at: row.get("last_forwarded_at"),
+            })
+            .collect())
+    }
+
+    /// Problem and forward counts per management zone. A problem in several
+    /// zones counts towards each of them.
+    pub async fn get_zone_stats(&self) -> Result<Vec<ZoneStats>> {
+        let rows = sqlx::query(
+            "SELECT zone.value AS management_zone,
+                    COUNT(*) AS total_problems,
+                    SUM(CASE WHEN p.status = 'OPEN' THEN 1 ELSE 0 END) AS open_problems,
+                    COALESCE(SUM(h.total), 0) AS total_forwards,
+                    COALESCE(SUM(h.failed), 0) AS failed_forwards
+             FROM forwarded_problems p
+             JOIN json_each(p.management_zones) zone
+             LEFT JOIN (
+                 SELECT problem_id, COUNT(*) AS total,
+                        SUM(CASE WHEN status = 'failed' THEN 1 ELSE 0 END) AS failed
+                 FROM forward_history WHERE status NOT IN ('unrouted', 'suppressed', 'skipped') GROUP BY problem_id
+             ) h ON h.problem_id = p.problem_id
+             GROUP BY zone.value
+             ORDER BY zone.value"
+        )
+        .fetch_all(&self.pool)
+        .await?;
+
+        Ok(rows
+            .iter()
+            .map(|row| ZoneStats {
+                management_zone: row.get("management_zone"),
+                total_problems: row.get("total_problems"),
+                open_problems: row.get("open_problems"),
+                total_forwards: row.get("total_forwards"),
+                failed_forwards: row.get("failed_forwards"),
+            })
+            .collect())
+    }
+
     /// Get the connection pool (for testing or advanced usage)
     pub fn pool(&self) -> &Pool<Sqlite> {
         &self.pool
@@ -208,3 +1881,276 @@ impl Database {
         self.pool.close().await;
     }
 }
+
+fn problem_from_row(row: &SqliteRow) -> ForwardedProblem {
+    ForwardedProblem {
+        id: Some(row.get("id")),
+        problem_id: row.get("problem_id"),
+        status: row.get("status"),
+        severity_level: row.get("severity_level"),
+        title: row.get("title"),
+        management_zones: serde_json::from_str(row.get("management_zones")).unwrap_or_default(),
+        tenant: row.get("tenant"),
+        first_seen_at: row.get("first_seen_at"),
+        last_forwarded_at: row.get("last_forwarded_at"),
+        last_status_change_at: row.get("last_status_change_at"),
+        forward_count: row.get("forward_count"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+/// Log a tracked problem's status change, read from its row before the row is
+/// updated; nothing is logged when the status stays the same
+async fn log_status_change<'e>(executor: impl SqliteExecutor<'e>, problem_id: &str, to_status: &str, changed_at: i64) -> Result<()> {
+    sqlx::query(
+        "INSERT INTO problem_status_changes (problem_id, from_status, to_status, changed_at)
+         SELECT problem_id, status, ?, ? FROM forwarded_problems WHERE problem_id = ? AND status != ?",
+    )
+    .bind(to_status)
+    .bind(changed_at)
+    .bind(problem_id)
+    .bind(to_status)
+    .execute(executor)
+    .await?;
+
+    Ok(())
+}
+
+/// Position of the bucket with this label, appending it for rows dated after
+/// the buckets were laid out
+fn bucket_index(buckets: &mut Vec<BucketStats>, index: &mut HashMap<String, usize>, label: String) -> usize {
+    *index.entry(label.clone()).or_insert_with(|| {
+        buckets.push(BucketStats { bucket: label, ..Default::default() });
+        buckets.len() - 1
+    })
+}
+
+fn problem_delivery_from_row(row: &SqliteRow) -> ProblemDelivery {
+    ProblemDelivery {
+        problem_id: row.get("problem_id"),
+        connector_name: row.get("connector_name"),
+        problem_status: row.get("problem_status"),
+        state: row.get("state"),
+        attempts: row.get("attempts"),
+        last_response_code: row.get("last_response_code"),
+        last_error: row.get("last_error"),
+        last_attempt_at: row.get("last_attempt_at"),
+        delivered_at: row.get("delivered_at"),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_migration_versions_are_sequential() {
+        for (index, migration) in MIGRATIONS.iter().enumerate() {
+            assert_eq!(migration.version, index as i64 + 1, "migration {}", migration.name);
+        }
+    }
+
+    #[cfg(not(feature = "sqlcipher"))]
+    #[tokio::test]
+    async fn test_encryption_requires_sqlcipher() {
+        let path = std::env::temp_dir().join(format!("dtpf-encrypted-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let config: DatabaseConfig = serde_yaml::from_str(&format!(
+            "path: \"{}\"\nencryption:\n  key_env: DTPF_TEST_DATABASE_KEY",
+            path.display()
+        ))
+        .unwrap();
+
+        let error = Database::new(&config).await.err().unwrap();
+
+        assert!(matches!(error, ForwarderError::Config(_)), "{}", error);
+        assert!(error.to_string().contains("sqlcipher"), "{}", error);
+        // Nothing was written unencrypted
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_configured_pragmas_apply() {
+        let path = std::env::temp_dir().join(format!("dtpf-pragmas-{}.db", std::process::id()));
+        let _ = std::fs::remove_file(&path);
+        let config: DatabaseConfig = serde_yaml::from_str(&format!(
+            "path: \"{}\"\njournal_mode: truncate\nbusy_timeout_ms: 1234\nsynchronous: full",
+            path.display()
+        ))
+        .unwrap();
+
+        let database = Database::new(&config).await.unwrap();
+        let mut connection = database.pool().acquire().await.unwrap();
+        let journal_mode: String = sqlx::query_scalar("PRAGMA journal_mode").fetch_one(&mut *connection).await.unwrap();
+        let busy_timeout: i64 = sqlx::query_scalar("PRAGMA busy_timeout").fetch_one(&mut *connection).await.unwrap();
+        let synchronous: i64 = sqlx::query_scalar("PRAGMA synchronous").fetch_one(&mut *connection).await.unwrap();
+        drop(connection);
+        database.pool().close().await;
+        let _ = std::fs::remove_file(&path);
+
+        assert_eq!(journal_mode, "truncate");
+        assert_eq!(busy_timeout, 1234);
+        // FULL
+        assert_eq!(synchronous, 2);
+    }
+
+    #[tokio::test]
+    async fn test_average_delivery_time_includes_retries() {
+        let database = Database::in_memory().await.unwrap();
+        // First-try success, success after retries, failure after all retries,
+        // a row of an older version without a duration, and a skipped problem
+        for (status, duration_ms) in [
+            ("success", Some(100)),
+            ("success", Some(2_100)),
+            ("failed", Some(3_400)),
+            ("success", None),
+            ("skipped", None),
+        ] {
+            database
+                .insert_forward_history(&ForwardHistory {
+                    id: None,
+                    problem_id: "P-1".to_string(),
+                    problem_status: Some("OPEN".to_string()),
+                    instance_id: None,
+                    connector_name: "hook".to_string(),
+                    status: status.to_string(),
+                    response_code: None,
+                    error_message: None,
+                    forwarded_at: Utc::now().timestamp(),
+                    duration_ms,
+                })
+                .await
+                .unwrap();
+        }
+
+        let stats = database.get_stats(None, None).await.unwrap();
+        let hook = stats.connectors.iter().find(|c| c.connector_name == "hook").unwrap();
+
+        assert_eq!(hook.total_forwards, 4);
+        assert_eq!(hook.avg_delivery_ms, Some(1_866.6666666666667));
+    }
+
+    #[tokio::test]
+    async fn test_migrations_skip_existing_columns() {
+        let database = Database::in_memory().await.unwrap();
+        // A database from an older version that added all columns of migration
+        // 15 at startup but `tenant`
+        sqlx::raw_sql("ALTER TABLE forwarded_problems DROP COLUMN tenant; DELETE FROM schema_migrations WHERE version = 15")
+            .execute(database.pool())
+            .await
+            .unwrap();
+
+        database.run_migrations().await.unwrap();
+        let versions: Vec<i64> = database.applied_migrations().await.unwrap().iter().map(|m| m.0).collect();
+        assert!(versions.contains(&15));
+        let tenant: bool =
+            sqlx::query_scalar("SELECT EXISTS (SELECT 1 FROM pragma_table_info('forwarded_problems') WHERE name = 'tenant')")
+                .fetch_one(database.pool())
+                .await
+                .unwrap();
+        assert!(tenant);
+    }
+
+    #[tokio::test]
+    async fn test_delivery_state_per_connector() {
+        let database = Database::in_memory().await.unwrap();
+        let problem = ForwardedProblem::new("P-1".to_string(), "OPEN".to_string(), None, "CPU".to_string(), vec![]);
+        database.insert_problem(&problem).await.unwrap();
+
+        let attempt = |connector: &str, status: &str, problem_status: &str| {
+            ForwardHistory::new(
+                "P-1".to_string(),
+                problem_status.to_string(),
+                connector.to_string(),
+                status.to_string(),
+                None,
+                None,
+            )
+        };
+        for history in [
+            attempt("slack", "failed", "OPEN"),
+            attempt("slack", "failed", "OPEN"),
+            attempt("webhook", "success", "OPEN"),
+            attempt("webhook", "suppressed", "CLOSED"),
+            attempt("webhook", "failed", "CLOSED"),
+            attempt("canary", "shadow_failed", "OPEN"),
+        ] {
+            database.insert_forward_history(&history).await.unwrap();
+        }
+
+        let deliveries = database.get_problem_deliveries("P-1").await.unwrap();
+        assert_eq!(deliveries.len(), 3);
+        assert_eq!((deliveries[0].connector_name.as_str(), deliveries[0].state.as_str()), ("canary", "shadow_failed"));
+        assert_eq!(deliveries[1].connector_name, "slack");
+        assert_eq!((deliveries[1].state.as_str(), deliveries[1].attempts), ("failed", 2));
+        assert_eq!(deliveries[1].delivered_at, None);
+        assert_eq!(deliveries[2].problem_status.as_deref(), Some("CLOSED"));
+        assert_eq!((deliveries[2].state.as_str(), deliveries[2].attempts), ("failed", 1));
+        assert!(deliveries[2].delivered_at.is_some());
+
+        let stats = database.get_stats(None, None).await.unwrap();
+        assert_eq!((stats.delivered_targets, stats.failing_targets), (0, 2));
+    }
+
+    #[tokio::test]
+    async fn test_clear_keeps_forward_history() {
+        let database = Database::in_memory().await.unwrap();
+        let problem = ForwardedProblem::new("P-1".to_string(), "OPEN".to_string(), None, "CPU".to_string(), vec![]);
+        database.insert_problem(&problem).await.unwrap();
+        let history = ForwardHistory::new(
+            "P-1".to_string(),
+            "OPEN".to_string(),
+            "slack".to_string(),
+            "success".to_string(),
+            Some(200),
+            None,
+        );
+        database.insert_forward_history(&history).await.unwrap();
+
+        assert_eq!(database.clear_all_problems().await.unwrap(), 1);
+        assert!(database.get_problem("P-1").await.unwrap().is_none());
+        assert!(database.get_problem_deliveries("P-1").await.unwrap().is_empty());
+        let filter = HistoryFilter { problem_id: Some("P-1".to_string()), ..Default::default() };
+        assert_eq!(database.get_forward_history(&filter).await.unwrap().len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_bucket_stats_include_empty_days() {
+        let database = Database::in_memory().await.unwrap();
+        let problem = ForwardedProblem::new("P-1".to_string(), "OPEN".to_string(), None, "CPU".to_string(), vec![]);
+        database.insert_problem(&problem).await.unwrap();
+        for status in ["failed", "success", "unrouted"] {
+            let history = ForwardHistory::new(
+                "P-1".to_string(),
+                "OPEN".to_string(),
+                "slack".to_string(),
+                status.to_string(),
+                None,
+                None,
+            );
+            database.insert_forward_history(&history).await.unwrap();
+        }
+
+        let now = Utc::now().timestamp();
+        let buckets = database.get_bucket_stats(TimeBucket::Day, now - 2 * 86_400, None, None).await.unwrap();
+        assert_eq!(buckets.len(), 3);
+        assert_eq!(buckets[0].total_forwards, 0);
+        let today = &buckets[2];
+        assert_eq!(today.bucket, TimeBucket::Day.label(now));
+        assert_eq!((today.new_problems, today.total_forwards, today.failed_forwards), (1, 2, 1));
+    }
+
+    #[tokio::test]
+    async fn test_status_changes_are_logged_once() {
+        let database = Database::in_memory().await.unwrap();
+        let problem = ForwardedProblem::new("P-1".to_string(), "OPEN".to_string(), None, "CPU".to_string(), vec![]);
+        database.insert_problem(&problem).await.unwrap();
+        database.update_problem_status("P-1", "CLOSED", &[]).await.unwrap();
+        database.update_problem_status("P-1", "CLOSED", &[]).await.unwrap();
+
+        let changes = database.get_status_changes("P-1").await.unwrap();
+        let statuses: Vec<_> = changes.iter().map(|c| (c.from_status.as_deref(), c.to_status.as_str())).collect();
+        assert_eq!(statuses, vec![(None, "OPEN"), (Some("OPEN"), "CLOSED")]);
+    }
+}
@@ -0,0 +1,147 @@
+use chrono::{DateTime, NaiveDate, Utc};
+use serde::Serialize;
+use std::borrow::Cow;
+use crate::error::Result;
+use crate::storage::{Database, ForwardHistory, ForwardedProblem, HistoryFilter};
+use crate::utils::time::{format_time, parse_duration};
+
+/// Forwarded problems and forward history of a time range, for reporting and
+/// post-incident reviews (`dtpf export`)
+#[derive(Debug, Serialize)]
+pub struct Export {
+    pub exported_at: i64,
+    /// Start of the range (epoch seconds); unbounded when not set
+    pub from: Option<i64>,
+    /// End of the range (epoch seconds); unbounded when not set
+    pub to: Option<i64>,
+    /// Problems seen in the range: first seen before its end and last forwarded after its start
+    pub problems: Vec<ForwardedProblem>,
+    /// Deliveries made in the range, oldest first
+    pub history: Vec<ForwardHistory>,
+}
+
+impl Export {
+    pub async fn capture(database: &Database, from: Option<i64>, to: Option<i64>) -> Result<Self> {
+        let problems = database
+            .get_all_problems()
+            .await?
+            .into_iter()
+            .filter(|p| to.is_none_or(|to| p.first_seen_at <= to))
+            .filter(|p| from.is_none_or(|from| p.last_forwarded_at >= from))
+            .collect();
+
+        let mut history = database
+            .get_forward_history(&HistoryFilter {
+                since: from,
+                until: to,
+                ..HistoryFilter::default()
+            })
+            .await?;
+        history.reverse();
+
+        Ok(Self {
+            exported_at: Utc::now().timestamp(),
+            from,
+            to,
+            problems,
+            history,
+        })
+    }
+
+    /// Forwarded problems as CSV with a header row; times in RFC 3339
+    pub fn problems_csv(&self) -> String {
+        let mut csv = String::from(
+            "problem_id,status,severity_level,title,management_zones,tenant,first_seen_at,\
+             last_forwarded_at,last_status_change_at,forward_count\n",
+        );
+        for problem in &self.problems {
+            csv_row(
+                &mut csv,
+                &[
+                    &problem.problem_id,
+                    &problem.status,
+                    problem.severity_level.as_deref().unwrap_or_default(),
+                    &problem.title,
+                    &problem.management_zones.join(";"),
+                    problem.tenant.as_deref().unwrap_or_default(),
+                    &format_time(problem.first_seen_at),
+                    &format_time(problem.last_forwarded_at),
+                    &format_time(problem.last_status_change_at),
+                    &problem.forward_count.to_string(),
+                ],
+            );
+        }
+        csv
+    }
+
+    /// Forward history as CSV with a header row; times in RFC 3339
+    pub fn history_csv(&self) -> String {
+        let mut csv = String::from(
+            "forwarded_at,problem_id,problem_status,connector_name,status,response_code,error_message,instance_id\n",
+        );
+        for row in &self.history {
+            csv_row(
+                &mut csv,
+                &[
+                    &format_time(row.forwarded_at),
+                    &row.problem_id,
+                    row.problem_status.as_deref().unwrap_or_default(),
+                    &row.connector_name,
+                    &row.status,
+                    &row.response_code.map(|code| code.to_string()).unwrap_or_default(),
+                    row.error_message.as_deref().unwrap_or_default(),
+                    row.instance_id.as_deref().unwrap_or_default(),
+                ],
+            );
+        }
+        csv
+    }
+}
+
+/// Parse the bound of an export range: an RFC 3339 time, a UTC date such as
+/// `2026-02-10`, or a duration ago such as `24h` or `7d`
+pub fn parse_time(text: &str) -> std::result::Result<i64, String> {
+    if let Ok(time) = DateTime::parse_from_rfc3339(text) {
+        return Ok(time.timestamp());
+    }
+    if let Ok(date) = NaiveDate::parse_from_str(text, "%Y-%m-%d") {
+        return Ok(date.and_hms_opt(0, 0, 0).unwrap_or_default().and_utc().timestamp());
+    }
+    parse_duration(text)
+        .map(|ago| Utc::now().timestamp() - ago.as_secs() as i64)
+        .map_err(|_| format!("invalid time '{}', expected e.g. 2026-02-10, 2026-02-10T06:00:00Z or 24h", text))
+}
+
+/// Append one CSV record, quoting fields that contain separators, quotes or line breaks
+fn csv_row(csv: &mut String, fields: &[&str]) {
+    let fields: Vec<Cow<str>> = fields
+        .iter()
+        .map(|field| {
+            if field.contains([',', '"', '\n', '\r']) {
+                Cow::Owned(format!("\"{}\"", field.replace('"', "\"\"")))
+            } else {
+                Cow::Borrowed(*field)
+            }
+        })
+        .collect();
+    csv.push_str(&fields.join(","));
+    csv.push('\n');
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_csv_quoting_and_time_parsing() {
+        let mut csv = String::new();
+        csv_row(&mut csv, &["P-1", "High CPU, host \"db-1\"", "line\nbreak", ""]);
+        assert_eq!(csv, "P-1,\"High CPU, host \"\"db-1\"\"\",\"line\nbreak\",\n");
+
+        assert_eq!(parse_time("2026-02-10").unwrap(), 1_770_681_600);
+        assert_eq!(parse_time("2026-02-10T06:00:00+01:00").unwrap(), 1_770_699_600);
+        let day_ago = parse_time("1d").unwrap();
+        assert!((Utc::now().timestamp() - 86_400 - day_ago).abs() <= 1);
+        assert!(parse_time("yesterday").is_err());
+    }
+}
@@ -1,5 +1,11 @@
 pub mod database;
+pub mod export;
 pub mod models;
+pub mod postgres;
+pub mod redis;
+pub mod snapshot;
 
 pub use database::Database;
-pub use models::{ForwardedProblem, ForwardHistory, DatabaseStats};
+pub use export::Export;
+pub use models::{ForwardedProblem, ForwardedSecurityProblem, ForwardHistory, BucketStats, ConnectorStats, DatabaseStats, DeadLetter, DisabledConnector, FlapState, HistoryFilter, InstanceStats, PollCycle, ProblemDelivery, ProblemMute, RecurrenceDecision, StatusChange, TimeBucket, UndeliveredProblem, ZoneStats, SKIPPED, SUPPRESSED, UNROUTED};
+pub use snapshot::CacheSnapshot;
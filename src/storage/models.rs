@@ -1,12 +1,20 @@
 use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use crate::dynatrace::Problem;
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ForwardedProblem {
     pub id: Option<i64>,
     pub problem_id: String,
     pub status: String,
     pub severity_level: Option<String>,
     pub title: String,
+    /// Management zone names of the problem when it was last seen
+    #[serde(default)]
+    pub management_zones: Vec<String>,
+    /// Dynatrace environment name, when several are polled
+    #[serde(default)]
+    pub tenant: Option<String>,
     pub first_seen_at: i64,
     pub last_forwarded_at: i64,
     pub last_status_change_at: i64,
@@ -15,19 +23,46 @@ pub struct ForwardedProblem {
     pub updated_at: i64,
 }
 
-#[derive(Debug, Clone)]
+/// Forward history status of a problem no connector's management zones or tag
+/// filter cover; recorded without a connector name
+pub const UNROUTED: &str = "unrouted";
+
+/// Forward history status of a problem never forwarded because it was older than
+/// `polling.max_problem_age_hours` when first seen; recorded without a connector name
+pub const SKIPPED: &str = "skipped";
+
+/// Forward history status of a problem a connector deliberately did not
+/// receive, e.g. outside its active hours; `error_message` gives the reason
+pub const SUPPRESSED: &str = "suppressed";
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ForwardHistory {
     pub id: Option<i64>,
     pub problem_id: String,
+    /// Problem status (OPEN, CLOSED, ...) the delivery was for; unknown for rows
+    /// written by older versions
+    pub problem_status: Option<String>,
+    /// Forwarder instance that made the delivery; unknown for rows written by
+    /// older versions
+    pub instance_id: Option<String>,
     pub connector_name: String,
     pub status: String,
     pub response_code: Option<i32>,
     pub error_message: Option<String>,
     pub forwarded_at: i64,
+    /// How long the delivery took, retries included; unknown for rows that are
+    /// not delivery attempts and for rows written by older versions
+    pub duration_ms: Option<i64>,
 }
 
 impl ForwardedProblem {
-    pub fn new(problem_id: String, status: String, severity_level: Option<String>, title: String) -> Self {
+    pub fn new(
+        problem_id: String,
+        status: String,
+        severity_level: Option<String>,
+        title: String,
+        management_zones: Vec<String>,
+    ) -> Self {
         let now = Utc::now().timestamp();
         Self {
             id: None,
@@ -35,6 +70,8 @@ impl ForwardedProblem {
             status,
             severity_level,
             title,
+            management_zones,
+            tenant: None,
             first_seen_at: now,
             last_forwarded_at: now,
             last_status_change_at: now,
@@ -45,9 +82,25 @@ impl ForwardedProblem {
     }
 }
 
+/// Filters of a forward history query (`dtpf history`); unset filters match every row
+#[derive(Debug, Clone, Default)]
+pub struct HistoryFilter {
+    pub problem_id: Option<String>,
+    pub connector_name: Option<String>,
+    /// Delivery status, e.g. `success` or `failed`
+    pub status: Option<String>,
+    /// Only rows forwarded at or after this epoch second
+    pub since: Option<i64>,
+    /// Only rows forwarded at or before this epoch second
+    pub until: Option<i64>,
+    /// Most recent rows returned (default: all)
+    pub limit: Option<i64>,
+}
+
 impl ForwardHistory {
     pub fn new(
         problem_id: String,
+        problem_status: String,
         connector_name: String,
         status: String,
         response_code: Option<i32>,
@@ -56,15 +109,121 @@ impl ForwardHistory {
         Self {
             id: None,
             problem_id,
+            problem_status: Some(problem_status),
+            instance_id: None,
             connector_name,
             status,
             response_code,
             error_message,
             forwarded_at: Utc::now().timestamp(),
+            duration_ms: None,
         }
     }
 }
 
+/// Flap suppression state of a problem
+#[derive(Debug, Clone)]
+pub struct FlapState {
+    pub problem_id: String,
+    /// Status receivers last got
+    pub forwarded_status: String,
+    /// Current status that has not been forwarded yet
+    pub pending_status: Option<String>,
+    pub suppressed_count: i64,
+    pub last_suppressed_at: i64,
+}
+
+/// A problem muted from the CLI
+#[derive(Debug, Clone)]
+pub struct ProblemMute {
+    pub problem_id: String,
+    pub muted_until: i64,
+    pub reason: Option<String>,
+    pub muted_at: i64,
+}
+
+/// A connector disabled from the CLI
+#[derive(Debug, Clone)]
+pub struct DisabledConnector {
+    pub connector_name: String,
+    pub reason: Option<String>,
+    pub disabled_at: i64,
+}
+
+/// A security problem as last forwarded
+#[derive(Debug, Clone)]
+pub struct ForwardedSecurityProblem {
+    pub security_problem_id: String,
+    pub display_id: String,
+    /// `OPEN`, `RESOLVED` or `MUTED`
+    pub status: String,
+    pub risk_level: Option<String>,
+    pub first_forwarded_at: i64,
+    pub last_forwarded_at: i64,
+}
+
+/// A delivery that failed after all retries, kept until it is replayed
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct DeadLetter {
+    pub id: i64,
+    pub connector_name: String,
+    pub problem_ids: Vec<String>,
+    /// The problems as they were sent
+    pub problems: Vec<Problem>,
+    pub error_message: String,
+    pub failed_at: i64,
+    pub replay_count: i64,
+    pub last_replayed_at: Option<i64>,
+}
+
+/// The latest state of a problem a connector has not received yet
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct UndeliveredProblem {
+    pub problem: Problem,
+    pub first_failed_at: i64,
+    /// Failed deliveries of the problem so far, re-deliveries included
+    pub attempts: i64,
+}
+
+/// Latest delivery state of a problem at one connector
+#[derive(Debug, Clone, Serialize)]
+pub struct ProblemDelivery {
+    pub problem_id: String,
+    pub connector_name: String,
+    /// Problem status of the latest attempt; unknown for deliveries recorded
+    /// before delivery states were tracked
+    pub problem_status: Option<String>,
+    /// `delivered`, `failed`, or `shadow_failed` for a shadow connector's failure
+    pub state: String,
+    /// Delivery attempts of the current problem status
+    pub attempts: i64,
+    pub last_response_code: Option<i32>,
+    pub last_error: Option<String>,
+    pub last_attempt_at: i64,
+    /// When the connector last received any status of the problem
+    pub delivered_at: Option<i64>,
+}
+
+/// A status a tracked problem was seen in
+#[derive(Debug, Clone, Serialize)]
+pub struct StatusChange {
+    /// `None` when the problem was first seen
+    pub from_status: Option<String>,
+    pub to_status: String,
+    pub changed_at: i64,
+}
+
+/// Outcome of counting one occurrence of a recurring problem
+#[derive(Debug, Clone, Copy)]
+pub struct RecurrenceDecision {
+    /// Whether this occurrence is forwarded
+    pub forward: bool,
+    /// Occurrences since the counter was last reset, including this one
+    pub occurrences: i64,
+    /// Occurrences skipped before this one since the last forwarded one
+    pub skipped: i64,
+}
+
 #[derive(Debug)]
 pub struct DatabaseStats {
     pub total_problems: i64,
@@ -73,4 +232,121 @@ pub struct DatabaseStats {
     pub total_forwards: i64,
     pub successful_forwards: i64,
     pub failed_forwards: i64,
+    /// Problem updates no connector's management zones or tag filter covered
+    pub unrouted: i64,
+    /// Problem updates withheld from a connector
+    pub suppressed: i64,
+    /// Problem updates not forwarded because the problem was too old
+    pub skipped: i64,
+    pub volume_anomalies: i64,
+    /// Status changes withheld because the problem was flapping
+    pub flaps_suppressed: i64,
+    /// Problem and connector pairs whose latest delivery succeeded
+    pub delivered_targets: i64,
+    /// Problem and connector pairs whose latest delivery failed
+    pub failing_targets: i64,
+    /// Delivery attempts per connector, by connector name
+    pub connectors: Vec<ConnectorStats>,
+}
+
+/// Delivery attempts of one connector
+#[derive(Debug, Clone)]
+pub struct ConnectorStats {
+    pub connector_name: String,
+    pub total_forwards: i64,
+    pub successful_forwards: i64,
+    /// Failed deliveries, shadow connector failures included
+    pub failed_forwards: i64,
+    pub last_success_at: Option<i64>,
+    pub last_error: Option<String>,
+    pub last_error_at: Option<i64>,
+    /// Average delivery time, retries and their backoff included, of the
+    /// attempts with a recorded duration
+    pub avg_delivery_ms: Option<f64>,
+}
+
+impl ConnectorStats {
+    /// Share of delivery attempts that succeeded, in percent
+    pub fn success_rate(&self) -> f64 {
+        if self.total_forwards == 0 {
+            return 0.0;
+        }
+        100.0 * self.successful_forwards as f64 / self.total_forwards as f64
+    }
+}
+
+/// Forward counts of one forwarder instance
+#[derive(Debug, Clone)]
+pub struct InstanceStats {
+    /// `None` for history written before instance ids were recorded
+    pub instance_id: Option<String>,
+    pub total_forwards: i64,
+    pub successful_forwards: i64,
+    pub failed_forwards: i64,
+    pub last_forwarded_at: i64,
+}
+
+/// Problem and forward counts for one management zone
+#[derive(Debug, Clone)]
+pub struct ZoneStats {
+    pub management_zone: String,
+    pub total_problems: i64,
+    pub open_problems: i64,
+    pub total_forwards: i64,
+    pub failed_forwards: i64,
+}
+
+/// Width of the time buckets of `dtpf stats --by`, in UTC
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TimeBucket {
+    Day,
+    Hour,
+}
+
+impl TimeBucket {
+    pub fn seconds(self) -> i64 {
+        match self {
+            TimeBucket::Day => 86_400,
+            TimeBucket::Hour => 3_600,
+        }
+    }
+
+    /// strftime format of the bucket labels, the same for SQLite and chrono
+    pub fn label_format(self) -> &'static str {
+        match self {
+            TimeBucket::Day => "%Y-%m-%d",
+            TimeBucket::Hour => "%Y-%m-%d %H:00",
+        }
+    }
+
+    /// Label of the bucket holding this epoch-second timestamp
+    pub fn label(self, timestamp: i64) -> String {
+        chrono::DateTime::from_timestamp(timestamp, 0)
+            .map(|time| time.format(self.label_format()).to_string())
+            .unwrap_or_default()
+    }
+}
+
+/// Problem and forward counts of one time bucket
+#[derive(Debug, Clone, Default)]
+pub struct BucketStats {
+    /// Start of the bucket, e.g. `2026-02-10` or `2026-02-10 06:00`
+    pub bucket: String,
+    /// Problems first seen in the bucket
+    pub new_problems: i64,
+    pub status_changes: i64,
+    pub total_forwards: i64,
+    pub successful_forwards: i64,
+    /// Failed deliveries, shadow connector failures included
+    pub failed_forwards: i64,
+}
+
+#[derive(Debug, Clone)]
+pub struct PollCycle {
+    pub polled_at: i64,
+    pub fetched_count: i64,
+    pub new_count: i64,
+    pub status_change_count: i64,
+    pub baseline: Option<f64>,
+    pub anomaly: bool,
 }
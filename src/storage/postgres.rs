@@ -0,0 +1,493 @@
+use sha2::{Digest, Sha256};
+use sqlx::postgres::PgPoolOptions;
+use sqlx::{ColumnIndex, Decode, Postgres, QueryBuilder, Row, Type};
+use std::fmt::Write;
+use crate::error::{ForwarderError, Result};
+use crate::storage::Database;
+use tracing::{debug, info};
+
+/// Postgres counterpart of the SQLite migrations
+const SCHEMA: &str = include_str!("../../migrations/postgres/001_schema.sql");
+
+/// Rows per INSERT statement (well below the Postgres bind parameter limit)
+const INSERT_CHUNK: usize = 500;
+
+#[derive(Debug, Clone, Copy)]
+enum ColumnKind {
+    Int,
+    Real,
+    Text,
+    Blob,
+}
+
+struct TableSpec {
+    name: &'static str,
+    /// Column(s) the rows are copied in the order of
+    key: &'static str,
+    /// Whether `id` is a BIGSERIAL whose sequence must continue after the copied rows
+    serial: bool,
+    columns: &'static [(&'static str, ColumnKind)],
+}
+
+/// Every table of the SQLite schema, in copy order
+const TABLES: &[TableSpec] = &[
+    TableSpec {
+        name: "forwarded_problems",
+        key: "id",
+        serial: true,
+        columns: &[
+            ("id", ColumnKind::Int),
+            ("problem_id", ColumnKind::Text),
+            ("status", ColumnKind::Text),
+            ("severity_level", ColumnKind::Text),
+            ("title", ColumnKind::Text),
+            ("first_seen_at", ColumnKind::Int),
+            ("last_forwarded_at", ColumnKind::Int),
+            ("last_status_change_at", ColumnKind::Int),
+            ("forward_count", ColumnKind::Int),
+            ("created_at", ColumnKind::Int),
+            ("updated_at", ColumnKind::Int),
+            ("management_zones", ColumnKind::Text),
+            ("tenant", ColumnKind::Text),
+            ("snapshot", ColumnKind::Blob),
+        ],
+    },
+    TableSpec {
+        name: "forward_history",
+        key: "id",
+        serial: true,
+        columns: &[
+            ("id", ColumnKind::Int),
+            ("problem_id", ColumnKind::Text),
+            ("problem_status", ColumnKind::Text),
+            ("instance_id", ColumnKind::Text),
+            ("connector_name", ColumnKind::Text),
+            ("status", ColumnKind::Text),
+            ("response_code", ColumnKind::Int),
+            ("error_message", ColumnKind::Text),
+            ("forwarded_at", ColumnKind::Int),
+            ("duration_ms", ColumnKind::Int),
+        ],
+    },
+    TableSpec {
+        name: "app_state",
+        key: "key",
+        serial: false,
+        columns: &[
+            ("key", ColumnKind::Text),
+            ("value", ColumnKind::Text),
+            ("updated_at", ColumnKind::Int),
+        ],
+    },
+    TableSpec {
+        name: "poll_cycles",
+        key: "id",
+        serial: true,
+        columns: &[
+            ("id", ColumnKind::Int),
+            ("polled_at", ColumnKind::Int),
+            ("fetched_count", ColumnKind::Int),
+            ("new_count", ColumnKind::Int),
+            ("status_change_count", ColumnKind::Int),
+            ("baseline", ColumnKind::Real),
+            ("anomaly", ColumnKind::Int),
+        ],
+    },
+    TableSpec {
+        name: "digest_queue",
+        key: "id",
+        serial: true,
+        columns: &[
+            ("id", ColumnKind::Int),
+            ("connector_name", ColumnKind::Text),
+            ("problem_id", ColumnKind::Text),
+            ("problem_json", ColumnKind::Text),
+            ("queued_at", ColumnKind::Int),
+        ],
+    },
+    TableSpec {
+        name: "flap_suppressions",
+        key: "problem_id",
+        serial: false,
+        columns: &[
+            ("problem_id", ColumnKind::Text),
+            ("forwarded_status", ColumnKind::Text),
+            ("pending_status", ColumnKind::Text),
+            ("suppressed_count", ColumnKind::Int),
+            ("last_suppressed_at", ColumnKind::Int),
+        ],
+    },
+    TableSpec {
+        name: "problem_mutes",
+        key: "problem_id",
+        serial: false,
+        columns: &[
+            ("problem_id", ColumnKind::Text),
+            ("muted_until", ColumnKind::Int),
+            ("reason", ColumnKind::Text),
+            ("muted_at", ColumnKind::Int),
+        ],
+    },
+    TableSpec {
+        name: "disabled_connectors",
+        key: "connector_name",
+        serial: false,
+        columns: &[
+            ("connector_name", ColumnKind::Text),
+            ("reason", ColumnKind::Text),
+            ("disabled_at", ColumnKind::Int),
+        ],
+    },
+    TableSpec {
+        name: "recurrence_counters",
+        key: "connector_name, recurrence_key",
+        serial: false,
+        columns: &[
+            ("connector_name", ColumnKind::Text),
+            ("recurrence_key", ColumnKind::Text),
+            ("occurrences", ColumnKind::Int),
+            ("skipped", ColumnKind::Int),
+            ("last_seen_at", ColumnKind::Int),
+        ],
+    },
+    TableSpec {
+        name: "recurrence_skipped",
+        key: "connector_name, problem_id",
+        serial: false,
+        columns: &[
+            ("connector_name", ColumnKind::Text),
+            ("problem_id", ColumnKind::Text),
+            ("skipped_at", ColumnKind::Int),
+        ],
+    },
+    TableSpec {
+        name: "entity_cache",
+        key: "environment, entity_id",
+        serial: false,
+        columns: &[
+            ("environment", ColumnKind::Text),
+            ("entity_id", ColumnKind::Text),
+            ("details", ColumnKind::Text),
+            ("fetched_at", ColumnKind::Int),
+        ],
+    },
+    TableSpec {
+        name: "forwarded_security_problems",
+        key: "security_problem_id",
+        serial: false,
+        columns: &[
+            ("security_problem_id", ColumnKind::Text),
+            ("display_id", ColumnKind::Text),
+            ("status", ColumnKind::Text),
+            ("risk_level", ColumnKind::Text),
+            ("first_forwarded_at", ColumnKind::Int),
+            ("last_forwarded_at", ColumnKind::Int),
+        ],
+    },
+    TableSpec {
+        name: "dead_letter",
+        key: "id",
+        serial: true,
+        columns: &[
+            ("id", ColumnKind::Int),
+            ("connector_name", ColumnKind::Text),
+            ("problem_ids", ColumnKind::Text),
+            ("problems_json", ColumnKind::Text),
+            ("error_message", ColumnKind::Text),
+            ("failed_at", ColumnKind::Int),
+            ("replay_count", ColumnKind::Int),
+            ("last_replayed_at", ColumnKind::Int),
+        ],
+    },
+    TableSpec {
+        name: "undelivered_problems",
+        key: "connector_name, problem_id",
+        serial: false,
+        columns: &[
+            ("connector_name", ColumnKind::Text),
+            ("problem_id", ColumnKind::Text),
+            ("problem_status", ColumnKind::Text),
+            ("problem_json", ColumnKind::Text),
+            ("first_failed_at", ColumnKind::Int),
+            ("attempts", ColumnKind::Int),
+            ("last_error", ColumnKind::Text),
+        ],
+    },
+    TableSpec {
+        name: "problem_deliveries",
+        key: "problem_id, connector_name",
+        serial: false,
+        columns: &[
+            ("problem_id", ColumnKind::Text),
+            ("connector_name", ColumnKind::Text),
+            ("problem_status", ColumnKind::Text),
+            ("state", ColumnKind::Text),
+            ("attempts", ColumnKind::Int),
+            ("last_response_code", ColumnKind::Int),
+            ("last_error", ColumnKind::Text),
+            ("last_attempt_at", ColumnKind::Int),
+            ("delivered_at", ColumnKind::Int),
+        ],
+    },
+    TableSpec {
+        name: "problem_status_changes",
+        key: "id",
+        serial: true,
+        columns: &[
+            ("id", ColumnKind::Int),
+            ("problem_id", ColumnKind::Text),
+            ("from_status", ColumnKind::Text),
+            ("to_status", ColumnKind::Text),
+            ("changed_at", ColumnKind::Int),
+        ],
+    },
+];
+
+#[derive(Debug, Clone, PartialEq)]
+enum Cell {
+    Int(Option<i64>),
+    Real(Option<f64>),
+    Text(Option<String>),
+    Blob(Option<Vec<u8>>),
+}
+
+/// Result of copying one table
+#[derive(Debug, Clone)]
+pub struct TableReport {
+    pub table: &'static str,
+    pub rows: usize,
+    /// SHA-256 over all rows, identical on both sides after verification
+    pub checksum: String,
+}
+
+/// Copy every table of the SQLite database into an empty Postgres database.
+/// The copy runs in one Postgres transaction that is only committed when row
+/// counts and checksums read back from Postgres match the source.
+pub async fn migrate_to_postgres(database: &Database, url: &str) -> Result<Vec<TableReport>> {
+    let pool = PgPoolOptions::new().max_connections(1).connect(url).await?;
+
+    sqlx::raw_sql(SCHEMA)
+        .execute(&pool)
+        .await?;
+
+    let mut tx = pool.begin().await?;
+    let mut reports = Vec::new();
+
+    for table in TABLES {
+        let existing: i64 = sqlx::query_scalar(&format!("SELECT COUNT(*) FROM {}", table.name))
+            .fetch_one(&mut *tx)
+            .await?;
+        if existing > 0 {
+            return Err(ForwarderError::Config(format!(
+                "Postgres table '{}' already contains {} rows; migrate into an empty database",
+                table.name, existing
+            )));
+        }
+
+        let source_rows = sqlx::query(&select_sql(table))
+            .fetch_all(database.pool())
+            .await?
+            .iter()
+            .map(|row| decode_row(row, table))
+            .collect::<Result<Vec<_>>>()?;
+
+        for chunk in source_rows.chunks(INSERT_CHUNK) {
+            insert_rows(&mut tx, table, chunk).await?;
+        }
+
+        if table.serial {
+            // Continue ids after the copied rows instead of at 1
+            sqlx::query(&format!(
+                "SELECT setval(pg_get_serial_sequence('{0}', 'id'), COALESCE((SELECT MAX(id) FROM {0}), 0) + 1, false)",
+                table.name
+            ))
+            .execute(&mut *tx)
+            .await?;
+        }
+
+        let target_rows = sqlx::query(&select_sql(table))
+            .fetch_all(&mut *tx)
+            .await?
+            .iter()
+            .map(|row| decode_row(row, table))
+            .collect::<Result<Vec<_>>>()?;
+
+        let source_checksum = checksum(&source_rows);
+        let target_checksum = checksum(&target_rows);
+        if source_rows.len() != target_rows.len() || source_checksum != target_checksum {
+            return Err(ForwarderError::Storage {
+                target: table.name.to_string(),
+                message: format!(
+                    "Verification failed: SQLite has {} rows ({}), Postgres has {} rows ({}); nothing was committed",
+                    source_rows.len(),
+                    source_checksum,
+                    target_rows.len(),
+                    target_checksum
+                ),
+            });
+        }
+
+        debug!("Copied and verified {} rows of {}", source_rows.len(), table.name);
+        reports.push(TableReport {
+            table: table.name,
+            rows: source_rows.len(),
+            checksum: source_checksum,
+        });
+    }
+
+    tx.commit().await?;
+    pool.close().await;
+
+    info!("Migrated {} tables to Postgres", reports.len());
+
+    Ok(reports)
+}
+
+fn select_sql(table: &TableSpec) -> String {
+    let columns: Vec<&str> = table.columns.iter().map(|(name, _)| *name).collect();
+    format!("SELECT {} FROM {} ORDER BY {}", columns.join(", "), table.name, table.key)
+}
+
+/// Read a row from either database into backend-neutral cells
+fn decode_row<'r, R>(row: &'r R, table: &TableSpec) -> Result<Vec<Cell>>
+where
+    R: Row,
+    usize: ColumnIndex<R>,
+    Option<i64>: Decode<'r, R::Database> + Type<R::Database>,
+    Option<f64>: Decode<'r, R::Database> + Type<R::Database>,
+    Option<String>: Decode<'r, R::Database> + Type<R::Database>,
+    Option<Vec<u8>>: Decode<'r, R::Database> + Type<R::Database>,
+{
+    table
+        .columns
+        .iter()
+        .enumerate()
+        .map(|(index, (_, kind))| {
+            Ok(match kind {
+                ColumnKind::Int => Cell::Int(row.try_get(index)?),
+                ColumnKind::Real => Cell::Real(row.try_get(index)?),
+                ColumnKind::Text => Cell::Text(row.try_get(index)?),
+                ColumnKind::Blob => Cell::Blob(row.try_get(index)?),
+            })
+        })
+        .collect()
+}
+
+async fn insert_rows(
+    tx: &mut sqlx::Transaction<'_, Postgres>,
+    table: &TableSpec,
+    rows: &[Vec<Cell>],
+) -> Result<()> {
+    let columns: Vec<&str> = table.columns.iter().map(|(name, _)| *name).collect();
+    let mut query = QueryBuilder::<Postgres>::new(format!("INSERT INTO {} ({}) ", table.name, columns.join(", ")));
+
+    query.push_values(rows, |mut values, row| {
+        for cell in row {
+            match cell {
+                Cell::Int(value) => values.push_bind(*value),
+                Cell::Real(value) => values.push_bind(*value),
+                Cell::Text(value) => values.push_bind(value.clone()),
+                Cell::Blob(value) => values.push_bind(value.clone()),
+            };
+        }
+    });
+
+    query.build().execute(&mut **tx).await?;
+
+    Ok(())
+}
+
+/// SHA-256 over all cells of all rows. The rows are hashed in the byte order
+/// of their encoding rather than as read: SQLite sorts the TEXT keys of
+/// `ORDER BY` bytewise, Postgres by the database's collation.
+fn checksum(rows: &[Vec<Cell>]) -> String {
+    let mut encoded: Vec<String> = rows.iter().map(|row| format!("{:?}\n", row)).collect();
+    encoded.sort_unstable();
+
+    let mut hasher = Sha256::new();
+    for row in &encoded {
+        hasher.update(row.as_bytes());
+    }
+
+    hasher.finalize().iter().fold(String::new(), |mut hex, byte| {
+        let _ = write!(hex, "{:02x}", byte);
+        hex
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::storage::database::MIGRATIONS;
+    use std::collections::{BTreeMap, BTreeSet};
+
+    /// Columns of each table the Postgres schema creates or adds
+    fn postgres_columns() -> BTreeMap<String, BTreeSet<String>> {
+        let mut tables: BTreeMap<String, BTreeSet<String>> = BTreeMap::new();
+        let mut current = None;
+        for line in SCHEMA.lines().map(str::trim).filter(|line| !line.starts_with("--")) {
+            let words: Vec<&str> = line.split_whitespace().collect();
+            match words[..] {
+                ["CREATE", "TABLE", "IF", "NOT", "EXISTS", table, "("] => current = Some(table.to_string()),
+                ["ALTER", "TABLE", table, "ADD", "COLUMN", "IF", "NOT", "EXISTS", column, ..] => {
+                    tables.entry(table.to_string()).or_default().insert(column.to_string());
+                }
+                [");"] => current = None,
+                [column, ..] if column != "PRIMARY" => {
+                    if let Some(table) = &current {
+                        tables.entry(table.clone()).or_default().insert(column.to_string());
+                    }
+                }
+                _ => {}
+            }
+        }
+        tables
+    }
+
+    #[tokio::test]
+    async fn test_postgres_schema_matches_sqlite() {
+        let database = Database::in_memory().await.unwrap();
+        let names: Vec<String> = sqlx::query_scalar(
+            "SELECT name FROM sqlite_master WHERE type = 'table'
+             AND name NOT IN ('schema_migrations', 'sqlite_sequence') ORDER BY name",
+        )
+        .fetch_all(database.pool())
+        .await
+        .unwrap();
+
+        let mut sqlite = BTreeMap::new();
+        for name in names {
+            let columns: BTreeSet<String> = sqlx::query_scalar("SELECT name FROM pragma_table_info(?)")
+                .bind(&name)
+                .fetch_all(database.pool())
+                .await
+                .unwrap()
+                .into_iter()
+                .collect();
+            sqlite.insert(name, columns);
+        }
+
+        assert_eq!(postgres_columns(), sqlite);
+        for table in TABLES {
+            let columns: BTreeSet<String> = table.columns.iter().map(|(name, _)| name.to_string()).collect();
+            assert_eq!(Some(&columns), sqlite.get(table.name), "copied columns of {}", table.name);
+        }
+        assert_eq!(TABLES.len(), sqlite.len());
+
+        let latest = MIGRATIONS.last().unwrap();
+        let mirrored = format!("SQLite migration {:03}\n-- ({:03}_{})", latest.version, latest.version, latest.name);
+        assert!(SCHEMA.contains(&mirrored), "the Postgres schema header must name {}", mirrored);
+    }
+
+    #[test]
+    fn test_checksum_ignores_collation_order() {
+        let row = |key: &str| vec![Cell::Text(Some(key.to_string())), Cell::Int(Some(1))];
+        // Bytewise, as SQLite sorts them
+        let sqlite: Vec<Vec<Cell>> = ["-P-2", "Abc", "P-1", "abc"].into_iter().map(row).collect();
+        // As a linguistic collation such as en_US.UTF-8 sorts them
+        let postgres: Vec<Vec<Cell>> = ["abc", "Abc", "P-1", "-P-2"].into_iter().map(row).collect();
+
+        assert_eq!(checksum(&sqlite), checksum(&postgres));
+        assert_ne!(checksum(&sqlite), checksum(&sqlite[1..]));
+    }
+}
@@ -0,0 +1,178 @@
+use redis::aio::ConnectionManager;
+use redis::AsyncCommands;
+use std::collections::HashMap;
+use crate::config::RedisConfig;
+use crate::error::{ForwarderError, Result};
+use crate::storage::{DeadLetter, ForwardedProblem, ForwardHistory, UndeliveredProblem};
+
+/// Durable copy of the forwarder's state in Redis, for deployments without a
+/// persistent volume. The engine works on an in-memory SQLite database that is
+/// loaded from Redis at startup; every tracked problem, app state value,
+/// forward history row, dead letter and undelivered problem is also written
+/// here. Features with other state are rejected by config validation for this
+/// driver.
+///
+/// Keys (with the default prefix `dtpf`):
+/// - `dtpf:problems`: hash of problem ID to the tracked problem as JSON
+/// - `dtpf:state`: hash of app state values such as poll windows and checkpoints
+/// - `dtpf:history`: stream of forward history rows, trimmed to `history_max_len`
+/// - `dtpf:dead_letters`: hash of connector name to its dead letters as a JSON array
+/// - `dtpf:undelivered`: hash of connector name to its undelivered problems as a JSON array
+#[derive(Clone)]
+pub struct RedisStore {
+    connection: ConnectionManager,
+    prefix: String,
+    history_max_len: usize,
+}
+
+impl RedisStore {
+    pub async fn connect(config: &RedisConfig) -> Result<Self> {
+        let url = config.url()?;
+        let client = redis::Client::open(url.as_str()).map_err(|e| redis_error("connect", e))?;
+        let connection = ConnectionManager::new(client)
+            .await
+            .map_err(|e| redis_error("connect", e))?;
+
+        Ok(Self {
+            connection,
+            prefix: config.key_prefix.clone(),
+            history_max_len: config.history_max_len,
+        })
+    }
+
+    fn key(&self, name: &str) -> String {
+        format!("{}:{}", self.prefix, name)
+    }
+
+    pub async fn load_problems(&self) -> Result<Vec<ForwardedProblem>> {
+        let mut connection = self.connection.clone();
+        let stored: HashMap<String, String> = connection
+            .hgetall(self.key("problems"))
+            .await
+            .map_err(|e| redis_error("load problems", e))?;
+
+        stored.values().map(|json| Ok(serde_json::from_str(json)?)).collect()
+    }
+
+    pub async fn save_problem(&self, problem: &ForwardedProblem) -> Result<()> {
+        let mut connection = self.connection.clone();
+        connection
+            .hset::<_, _, _, ()>(self.key("problems"), &problem.problem_id, serde_json::to_string(problem)?)
+            .await
+            .map_err(|e| redis_error("save problem", e))
+    }
+
+    pub async fn clear_problems(&self) -> Result<()> {
+        let mut connection = self.connection.clone();
+        connection
+            .del::<_, ()>(self.key("problems"))
+            .await
+            .map_err(|e| redis_error("clear problems", e))
+    }
+
+    pub async fn load_state(&self) -> Result<HashMap<String, String>> {
+        let mut connection = self.connection.clone();
+        connection
+            .hgetall(self.key("state"))
+            .await
+            .map_err(|e| redis_error("load state", e))
+    }
+
+    pub async fn save_state(&self, key: &str, value: &str) -> Result<()> {
+        let mut connection = self.connection.clone();
+        connection
+            .hset::<_, _, _, ()>(self.key("state"), key, value)
+            .await
+            .map_err(|e| redis_error("save state", e))
+    }
+
+    /// Forward history rows still in the stream, oldest first
+    pub async fn load_history(&self) -> Result<Vec<ForwardHistory>> {
+        let mut connection = self.connection.clone();
+        let entries: Vec<(String, HashMap<String, String>)> = redis::cmd("XRANGE")
+            .arg(self.key("history"))
+            .arg("-")
+            .arg("+")
+            .query_async(&mut connection)
+            .await
+            .map_err(|e| redis_error("load history", e))?;
+
+        entries
+            .iter()
+            .filter_map(|(_, fields)| fields.get("row"))
+            .map(|json| Ok(serde_json::from_str(json)?))
+            .collect()
+    }
+
+    pub async fn append_history(&self, history: &ForwardHistory) -> Result<()> {
+        let mut connection = self.connection.clone();
+        redis::cmd("XADD")
+            .arg(self.key("history"))
+            .arg("MAXLEN")
+            .arg("~")
+            .arg(self.history_max_len)
+            .arg("*")
+            .arg("row")
+            .arg(serde_json::to_string(history)?)
+            .query_async::<_, String>(&mut connection)
+            .await
+            .map(|_| ())
+            .map_err(|e| redis_error("append history", e))
+    }
+
+    /// Dead letters of all connectors
+    pub async fn load_dead_letters(&self) -> Result<Vec<DeadLetter>> {
+        let mut dead_letters = Vec::new();
+        for (_, json) in self.load_lists("dead_letters").await? {
+            dead_letters.extend(serde_json::from_str::<Vec<DeadLetter>>(&json)?);
+        }
+        Ok(dead_letters)
+    }
+
+    /// Replace the dead letters kept for a connector
+    pub async fn save_dead_letters(&self, connector_name: &str, dead_letters: &[DeadLetter]) -> Result<()> {
+        self.save_list("dead_letters", connector_name, dead_letters.is_empty(), serde_json::to_string(dead_letters)?)
+            .await
+    }
+
+    /// Undelivered problems by connector name
+    pub async fn load_undelivered(&self) -> Result<HashMap<String, Vec<UndeliveredProblem>>> {
+        self.load_lists("undelivered")
+            .await?
+            .into_iter()
+            .map(|(connector_name, json)| Ok((connector_name, serde_json::from_str(&json)?)))
+            .collect()
+    }
+
+    /// Replace the undelivered problems kept for a connector
+    pub async fn save_undelivered(&self, connector_name: &str, undelivered: &[UndeliveredProblem]) -> Result<()> {
+        self.save_list("undelivered", connector_name, undelivered.is_empty(), serde_json::to_string(undelivered)?)
+            .await
+    }
+
+    async fn load_lists(&self, name: &str) -> Result<HashMap<String, String>> {
+        let mut connection = self.connection.clone();
+        connection
+            .hgetall(self.key(name))
+            .await
+            .map_err(|e| redis_error(&format!("load {}", name), e))
+    }
+
+    /// Write one connector's list, dropping the field once the list is empty
+    async fn save_list(&self, name: &str, connector_name: &str, empty: bool, json: String) -> Result<()> {
+        let mut connection = self.connection.clone();
+        let result = if empty {
+            connection.hdel::<_, _, ()>(self.key(name), connector_name).await
+        } else {
+            connection.hset::<_, _, _, ()>(self.key(name), connector_name, json).await
+        };
+        result.map_err(|e| redis_error(&format!("save {}", name), e))
+    }
+}
+
+fn redis_error(action: &str, e: redis::RedisError) -> ForwarderError {
+    ForwarderError::Storage {
+        target: "redis".to_string(),
+        message: format!("{}: {}", action, e),
+    }
+}
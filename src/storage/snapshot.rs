@@ -0,0 +1,56 @@
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use crate::error::Result;
+use crate::storage::{Database, ForwardedProblem};
+
+/// Point-in-time export of the forwarded problems cache
+#[derive(Debug, Serialize, Deserialize)]
+pub struct CacheSnapshot {
+    pub exported_at: i64,
+    pub problems: Vec<ForwardedProblem>,
+}
+
+impl CacheSnapshot {
+    /// Capture all cached problems from the database
+    pub async fn capture(database: &Database) -> Result<Self> {
+        Ok(Self {
+            exported_at: Utc::now().timestamp(),
+            problems: database.get_all_problems().await?,
+        })
+    }
+
+    /// Write the snapshot as pretty JSON, creating parent directories if needed
+    pub fn write(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+
+        std::fs::write(path, serde_json::to_string_pretty(self)?)?;
+        Ok(())
+    }
+
+    /// Read a snapshot previously written by `write`
+    pub fn read(path: &Path) -> Result<Self> {
+        let content = std::fs::read_to_string(path)?;
+        Ok(serde_json::from_str(&content)?)
+    }
+
+    /// Restore the snapshot into the database, replacing rows with the same problem_id
+    pub async fn restore(&self, database: &Database) -> Result<u64> {
+        database.import_problems(&self.problems).await
+    }
+
+    /// Number of open problems in the snapshot
+    pub fn open_count(&self) -> usize {
+        self.problems.iter().filter(|p| p.status == "OPEN").count()
+    }
+}
+
+/// Default location for automatic snapshots: `<db dir>/snapshots/cache-<timestamp>.json`
+pub fn default_snapshot_path(db_path: &Path) -> PathBuf {
+    let db_dir = db_path.parent().unwrap_or(Path::new("."));
+    db_dir
+        .join("snapshots")
+        .join(format!("cache-{}.json", Utc::now().format("%Y%m%dT%H%M%SZ")))
+}
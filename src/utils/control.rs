@@ -0,0 +1,131 @@
+use crate::error::{ForwarderError, Result};
+use crate::forwarder::EngineEvent;
+use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use tokio::sync::{broadcast, Notify};
+
+/// Control command that starts a poll cycle immediately
+pub const TRIGGER_POLL: &str = "trigger-poll";
+
+/// Get the control socket path based on config location
+pub fn get_socket_path(config_path: &Path) -> PathBuf {
+    let config_dir = config_path.parent().unwrap_or(Path::new("."));
+    config_dir.join("dtpf.sock")
+}
+
+/// Serve line-based control commands on a Unix socket until the task is aborted.
+/// Each connection sends one command and receives one `ok: ...` or `error: ...` line.
+#[cfg(unix)]
+pub async fn serve(
+    socket_path: PathBuf,
+    poll_trigger: Arc<Notify>,
+    events: broadcast::Receiver<EngineEvent>,
+) -> Result<()> {
+    use tokio::net::UnixListener;
+    use tracing::{info, warn};
+
+    // A socket left behind by a previous run would make bind fail
+    let _ = std::fs::remove_file(&socket_path);
+    let listener = UnixListener::bind(&socket_path)?;
+
+    info!("Control socket listening on {}", socket_path.display());
+
+    loop {
+        let (stream, _) = listener.accept().await?;
+        let poll_trigger = poll_trigger.clone();
+        let events = events.resubscribe();
+
+        tokio::spawn(async move {
+            if let Err(e) = handle_connection(stream, poll_trigger, events).await {
+                warn!("Control connection failed: {}", e);
+            }
+        });
+    }
+}
+
+#[cfg(not(unix))]
+pub async fn serve(
+    _socket_path: PathBuf,
+    _poll_trigger: Arc<Notify>,
+    _events: broadcast::Receiver<EngineEvent>,
+) -> Result<()> {
+    tracing::warn!("Control socket is only supported on Unix; trigger-poll is unavailable");
+    Ok(())
+}
+
+#[cfg(unix)]
+async fn handle_connection(
+    stream: tokio::net::UnixStream,
+    poll_trigger: Arc<Notify>,
+    mut events: broadcast::Receiver<EngineEvent>,
+) -> Result<()> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+    let (reader, mut writer) = stream.into_split();
+    let mut command = String::new();
+    BufReader::new(reader).read_line(&mut command).await?;
+
+    let response = match command.trim() {
+        TRIGGER_POLL => {
+            poll_trigger.notify_one();
+            wait_for_poll(&mut events).await
+        }
+        other => format!("error: unknown command '{}'", other),
+    };
+
+    writer.write_all(format!("{}\n", response).as_bytes()).await?;
+
+    Ok(())
+}
+
+/// Wait for the next poll cycle to start and report how it ended
+async fn wait_for_poll(events: &mut broadcast::Receiver<EngineEvent>) -> String {
+    let mut started = false;
+
+    loop {
+        match events.recv().await {
+            Ok(EngineEvent::PollStarted) => started = true,
+            Ok(EngineEvent::PollCompleted { new_problems, status_changes, skipped }) if started => {
+                return format!(
+                    "ok: poll completed ({} new, {} status changes, {} unchanged)",
+                    new_problems, status_changes, skipped
+                );
+            }
+            Ok(EngineEvent::PollFailed { error }) if started => {
+                return format!("error: poll failed: {}", error);
+            }
+            Ok(_) | Err(broadcast::error::RecvError::Lagged(_)) => {}
+            Err(broadcast::error::RecvError::Closed) => return "error: engine stopped".to_string(),
+        }
+    }
+}
+
+/// Send a control command to a running service and return its response line
+#[cfg(unix)]
+pub async fn send_command(socket_path: &Path, command: &str) -> Result<String> {
+    use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+    use tokio::net::UnixStream;
+
+    let stream = UnixStream::connect(socket_path).await.map_err(|e| {
+        ForwarderError::Config(format!(
+            "Failed to connect to control socket {}: {}. Is dtpf running?",
+            socket_path.display(),
+            e
+        ))
+    })?;
+
+    let (reader, mut writer) = stream.into_split();
+    writer.write_all(format!("{}\n", command).as_bytes()).await?;
+
+    let mut response = String::new();
+    BufReader::new(reader).read_line(&mut response).await?;
+
+    Ok(response.trim().to_string())
+}
+
+#[cfg(not(unix))]
+pub async fn send_command(_socket_path: &Path, _command: &str) -> Result<String> {
+    Err(ForwarderError::Config(
+        "The control socket is only supported on Unix".to_string()
+    ))
+}
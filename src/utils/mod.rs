@@ -1,5 +1,7 @@
+pub mod control;
 pub mod shutdown;
 pub mod process;
+pub mod time;
 
 pub use shutdown::setup_shutdown_handler;
 pub use process::{start_background, stop_background};
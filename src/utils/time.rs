@@ -0,0 +1,74 @@
+use chrono::{DateTime, SecondsFormat, TimeZone};
+use chrono_tz::Tz;
+use std::time::Duration;
+
+/// Render a Dynatrace epoch-millisecond timestamp as local wall-clock time,
+/// e.g. "2026-02-10 05:30 CET"
+pub fn format_local(millis: i64, tz: Tz) -> String {
+    match tz.timestamp_millis_opt(millis).single() {
+        Some(time) => time.format("%Y-%m-%d %H:%M %Z").to_string(),
+        None => millis.to_string(),
+    }
+}
+
+/// "45s", "12m", "1h 5m", "2d 3h"
+pub fn humanize_duration(seconds: i64) -> String {
+    let (days, hours, minutes) = (seconds / 86_400, seconds % 86_400 / 3_600, seconds % 3_600 / 60);
+    match (days, hours, minutes) {
+        (0, 0, 0) => format!("{}s", seconds),
+        (0, 0, m) => format!("{}m", m),
+        (0, h, 0) => format!("{}h", h),
+        (0, h, m) => format!("{}h {}m", h, m),
+        (d, 0, _) => format!("{}d", d),
+        (d, h, _) => format!("{}d {}h", d, h),
+    }
+}
+
+/// Render an epoch-second timestamp as RFC 3339 UTC, e.g. "2026-02-10T04:30:00Z"
+pub fn format_time(timestamp: i64) -> String {
+    DateTime::from_timestamp(timestamp, 0)
+        .map(|t| t.to_rfc3339_opts(SecondsFormat::Secs, true))
+        .unwrap_or_else(|| timestamp.to_string())
+}
+
+/// Parse a duration such as `60s`, `5m`, `1h` or `90` (seconds)
+pub fn parse_duration(text: &str) -> Result<Duration, String> {
+    let text = text.trim();
+    let split = text.find(|c: char| !c.is_ascii_digit()).unwrap_or(text.len());
+    let (value, unit) = text.split_at(split);
+    let value: u64 = value
+        .parse()
+        .map_err(|_| format!("invalid duration '{}', expected e.g. 60s, 5m, 1h or 2d", text))?;
+    let seconds = match unit {
+        "" | "s" => value,
+        "m" => value * 60,
+        "h" => value * 3600,
+        "d" => value * 86_400,
+        _ => return Err(format!("invalid duration unit in '{}', expected s, m, h or d", text)),
+    };
+
+    if seconds == 0 {
+        return Err(format!("duration '{}' must be greater than 0", text));
+    }
+    Ok(Duration::from_secs(seconds))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_duration() {
+        assert_eq!(parse_duration("60s").unwrap(), Duration::from_secs(60));
+        assert_eq!(parse_duration("5m").unwrap(), Duration::from_secs(300));
+        assert_eq!(parse_duration("90").unwrap(), Duration::from_secs(90));
+        assert_eq!(parse_duration("2d").unwrap(), Duration::from_secs(172_800));
+        assert!(parse_duration("1.5m").is_err());
+        assert!(parse_duration("0s").is_err());
+    }
+
+    #[test]
+    fn test_format_time() {
+        assert_eq!(format_time(1_770_697_800), "2026-02-10T04:30:00Z");
+    }
+}
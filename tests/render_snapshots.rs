@@ -0,0 +1,73 @@
+//! Golden-file snapshot tests for connector payload rendering.
+//!
+//! Every problem fixture in `tests/fixtures/problems` is rendered through every
+//! connector in `tests/fixtures/connectors` and compared against
+//! `tests/golden/<fixture>__<connector>.json`.
+//!
+//! Run with `UPDATE_GOLDEN=1 cargo test --test render_snapshots` to regenerate
+//! the golden files after an intentional payload change.
+
+use dynatrace_problem_forwarder::config::ConnectorConfig;
+use dynatrace_problem_forwarder::dynatrace::ProblemFixture;
+use dynatrace_problem_forwarder::forwarder::payload::render_requests;
+use std::fs;
+use std::path::{Path, PathBuf};
+
+/// Problem link prefix used by chat formats in golden files
+const PROBLEM_URL_BASE: &str = "https://dynatrace.example.com/e/abc12345/#problems/problemdetails;pid=";
+
+fn fixture_files(dir: &str, extension: &str) -> Vec<PathBuf> {
+    let dir = Path::new(env!("CARGO_MANIFEST_DIR")).join(dir);
+    let mut files: Vec<PathBuf> = fs::read_dir(&dir)
+        .unwrap_or_else(|e| panic!("Failed to read {}: {}", dir.display(), e))
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().and_then(|e| e.to_str()) == Some(extension))
+        .collect();
+    files.sort();
+    files
+}
+
+fn stem(path: &Path) -> String {
+    path.file_stem().unwrap().to_string_lossy().into_owned()
+}
+
+#[test]
+fn render_matches_golden_files() {
+    let update = std::env::var("UPDATE_GOLDEN").is_ok();
+    let golden_dir = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/golden");
+    let mut mismatches = Vec::new();
+
+    for connector_path in fixture_files("tests/fixtures/connectors", "yaml") {
+        let connector: ConnectorConfig =
+            serde_yaml::from_str(&fs::read_to_string(&connector_path).unwrap()).unwrap();
+
+        for problem_path in fixture_files("tests/fixtures/problems", "json") {
+            let problems = ProblemFixture::load(&problem_path).unwrap();
+            let rendered = render_requests(&problems, &connector, PROBLEM_URL_BASE).unwrap();
+            let actual = serde_json::to_string_pretty(&rendered).unwrap() + "\n";
+
+            let golden_path = golden_dir.join(format!(
+                "{}__{}.json",
+                stem(&problem_path),
+                stem(&connector_path)
+            ));
+
+            if update {
+                fs::write(&golden_path, &actual).unwrap();
+                continue;
+            }
+
+            match fs::read_to_string(&golden_path) {
+                Ok(expected) if expected == actual => {}
+                Ok(_) => mismatches.push(format!("{} differs", golden_path.display())),
+                Err(_) => mismatches.push(format!("{} is missing", golden_path.display())),
+            }
+        }
+    }
+
+    assert!(
+        mismatches.is_empty(),
+        "Rendered payloads do not match golden files (run with UPDATE_GOLDEN=1 to regenerate):\n  {}",
+        mismatches.join("\n  ")
+    );
+}